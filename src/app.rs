@@ -1,21 +1,58 @@
-use std::sync::Arc;
+use std::{borrow::Cow, sync::Arc};
 use winit::{
+    dpi::{PhysicalPosition, PhysicalSize},
     event::{self, ElementState, Event, MouseButton, WindowEvent},
     event_loop::{ControlFlow, EventLoop, EventLoopWindowTarget},
     keyboard,
-    window::{Window, WindowBuilder},
+    monitor::{MonitorHandle, VideoMode},
+    window::{Fullscreen, Window, WindowBuilder, WindowLevel},
 };
 
 use anyhow::Result;
 
 use crate::{
+    cursor::{Cursor, CursorState, CustomCursor},
+    frame_stats::FrameStats,
+    frame_timeline::FrameTimeline,
     input::{InputsState, SystemState, WinitEventHandler},
-    wgpu_utils::render_handles::{RenderInstance, SurfaceHandle},
+    math::{Letterbox, ProjectionJitter, Ray, Viewport, ViewportPolicy},
+    plugin::Plugin,
+    render_layers::{RenderLayerKind, RenderLayers},
+    resize_listeners::{ResizeEvent, ResizeListeners},
+    resources::Resources,
+    wgpu_utils::{
+        memory_tracker,
+        record_pass_label,
+        render_handles::{AdapterSelector, RenderInstance, SurfaceHandle},
+        uniform_buffer::UniformBufferWrapper,
+        AssetLoader,
+        Canvas,
+        CanvasDescriptor,
+        ComputeQueue,
+        DebugViewMode,
+        DepthConvention,
+        DiagnosticReporter,
+        ErrorPolicy,
+        ErrorScopes,
+        FrameUniforms,
+        FramesInFlight,
+        GpuScope,
+        GpuTimer,
+        PipelineCompiler,
+        RectFill,
+        RenderTargets,
+        UncapturedErrorHandler,
+    },
 };
 
+#[cfg(feature = "clipboard")]
+use crate::clipboard::{Clipboard, ClipboardImage};
+#[cfg(feature = "image")]
+use crate::screenshot::Screenshots;
 #[cfg(feature = "egui")]
 use crate::{
-    egui_wgpu_renderer::EguiRenderer,
+    egui_wgpu_renderer::{EguiConfig, EguiRenderer},
+    shader_error_overlay::ShaderErrorOverlay,
     wgpu_utils::render_handles::DeviceHandle,
 };
 
@@ -27,85 +64,633 @@ pub struct AppState {
 
     pub clear_color: wgpu::Color,
 
+    // Offscreen targets of the surface device, resized along with the surface
+    pub render_targets: RenderTargets,
+    // Frame index and slot of the per-frame resources, the frame slot is free on the GPU when `App::render` is called
+    pub frames_in_flight: FramesInFlight,
+    // Compute work submitted apart from the frame, queued jobs are submitted after `App::update`
+    pub compute_queue: ComputeQueue,
+    // GPU time of the render layers and of the app scopes (see `gpu_scope`), shown in the debug panel
+    pub gpu_timer: GpuTimer,
+    // Built-in uniforms (time, resolution, mouse...) updated before `App::render`, visible from all stages
+    pub frame_uniforms: UniformBufferWrapper<FrameUniforms>,
+    // Order and enable flags of the layers recorded in the frame encoder, including `App::render` and the egui pass
+    pub render_layers: RenderLayers,
+    // Sub-pixel jitter of the frames (e.g. for TAA), exposed in the frame uniforms and applied with `jitter_projection`
+    pub projection_jitter: Option<ProjectionJitter>,
+    // Depth convention of the app projections (see `math::perspective`), used to unproject the cursor in `cursor_ray`
+    pub depth_convention: DepthConvention,
+    // Debug visualization selected in the debug panel, the app swaps its pipelines or runs the `DebugViewRenderer` passes
+    pub debug_view: DebugViewMode,
+    // Placement of the app content in the app view (from `AppConfig::viewport_policy`), see `letterbox`
+    pub viewport_policy: ViewportPolicy,
+    // Color the bars around the letterboxed content are filled with after `App::render`, None to leave them to the app
+    pub letterbox_color: Option<wgpu::Color>,
+    // Captures of the presented frames, requested with `Screenshots::request` (e.g. by the `ScreenshotPlugin` key)
+    #[cfg(feature = "image")]
+    pub screenshots: Screenshots,
+
     #[cfg(feature = "egui")]
     pub egui_renderer: EguiRenderer,
+    // Shader compilation errors reported by the app (e.g. of the `PipelineRegistry` reloads), shown over the frame
+    #[cfg(feature = "egui")]
+    pub shader_errors: ShaderErrorOverlay,
 
     pub input_state: InputsState,
     pub system_state: SystemState,
-
+    pub frame_stats: FrameStats,
+    // Passes of the last frame (render layers and the passes the app records) with their CPU and GPU times
+    pub frame_timeline: FrameTimeline,
+
+    // Error scopes of the surface device, errors not polled by the app are reported after each frame
+    pub error_scopes: ErrorScopes,
+    // Diagnostic report of the surface device written when it is lost or on an uncaptured error, see `set_callback` to
+    // attach it to crash reports
+    pub diagnostics: DiagnosticReporter,
+    // Policy applied to the uncaptured errors of the surface device (from `RenderingConfig::error_policy`), can be changed
+    // with `set_policy`
+    pub error_handler: UncapturedErrorHandler,
+
+    // State shared by type between the app, its plugins and render layers, see `insert` and `get`
+    pub resources: Resources,
+    // Screen sized resources recreated on resize, see `ResizeListeners::add_resource`
+    pub resize_listeners: ResizeListeners,
+
+    // Background loading, completed loads are uploaded with the surface device before `App::update`
+    pub asset_loader: AssetLoader,
+    // Shaders and pipelines compiled in the background with the surface device, completed along with `asset_loader`
+    pub pipeline_compiler: PipelineCompiler,
+
+    // Poll redraws continuously, Wait/WaitUntil only redraw on input, egui repaints and `request_redraw` (and when the
+    // WaitUntil time is reached)
     pub control_flow: ControlFlow,
 
+    #[cfg(feature = "egui")]
+    pub suppress_inputs_captured_by_egui: bool,
+
+    start_time: std::time::Instant,
+    frame_pacing: FramePacing,
+    // Refresh interval of the monitor the window is on, used as frame target under vsync
+    refresh_interval: Option<std::time::Duration>,
     last_frame_time: std::time::Instant,
-    target_frame_duration: std::time::Duration,
+    // Time blocked acquiring the last surface texture
+    surface_wait_time: std::time::Duration,
+    // Time slept by the adaptive pacing before the last frame and smoothed idle time (surface wait + sleep) it is based on
+    adaptive_sleep_time: std::time::Duration,
+    adaptive_idle_time: std::time::Duration,
+    srgb_app_view: bool,
+    // Redraw requested by the app or an event, used when the control flow isn't Poll
+    redraw_requested: bool,
+    // Time step of `App::fixed_update` in seconds, None to not call it
+    pub fixed_timestep: Option<f64>,
+    fixed_time_accumulator: f64,
+    // Commands recorded by `App::update` and by the egui paint callbacks, submitted with the next frame before its encoder
+    update_command_buffers: Vec<wgpu::CommandBuffer>,
+    // Time egui asked to be repainted at
+    #[cfg(feature = "egui")]
+    egui_repaint_time: Option<std::time::Instant>,
+    plugins: Vec<Box<dyn Plugin>>,
+    cursor_state: CursorState,
+    // Created on the first frame with bars, for the format of the app view
+    letterbox_fill: Option<RectFill>,
+    // Fixed resolution target `App::render` renders to instead of the surface, see `set_canvas`
+    canvas: Option<Canvas>,
+    #[cfg(feature = "clipboard")]
+    clipboard: Clipboard,
+    // Window position and size before going fullscreen, restored by `set_windowed`
+    windowed_geometry: Option<(Option<PhysicalPosition<i32>>, PhysicalSize<u32>)>,
 }
 
 impl AppState {
-    pub fn set_fullscreen(&mut self) {
-        self.window
-            .set_fullscreen(Some(winit::window::Fullscreen::Borderless(self.window.primary_monitor())));
+    // Borderless fullscreen on the primary monitor
+    pub fn set_fullscreen(&mut self) { self.set_borderless_fullscreen(self.window.primary_monitor()); }
+
+    // Borderless fullscreen on the given monitor, the current one if None
+    pub fn set_borderless_fullscreen(&mut self, monitor: Option<MonitorHandle>) {
+        self.save_windowed_geometry();
+        self.window.set_fullscreen(Some(Fullscreen::Borderless(monitor)));
+    }
+
+    // Exclusive fullscreen changing the monitor video mode, see `MonitorInfo::find_video_mode`
+    pub fn set_exclusive_fullscreen(&mut self, video_mode: VideoMode) {
+        self.save_windowed_geometry();
+        self.window.set_fullscreen(Some(Fullscreen::Exclusive(video_mode)));
+    }
+
+    // Leave fullscreen, restoring the window position and size it had before
+    pub fn set_windowed(&mut self) {
+        if !self.is_fullscreen() {
+            return;
+        }
+        self.window.set_fullscreen(None);
+        if let Some((position, size)) = self.windowed_geometry.take() {
+            if let Some(position) = position {
+                self.window.set_outer_position(position);
+            }
+            let _ = self.window.request_inner_size(size);
+        }
+    }
+
+    // Toggle between windowed and borderless fullscreen on the current monitor
+    pub fn toggle_fullscreen(&mut self) {
+        if self.is_fullscreen() {
+            self.set_windowed();
+        } else {
+            self.set_borderless_fullscreen(self.window.current_monitor());
+        }
+    }
+
+    // Whole surface, in physical pixels
+    pub fn surface_viewport(&self) -> Viewport { Viewport::from_size(self.surface_handle.config.width, self.surface_handle.config.height) }
+
+    // Size of the view `App::render` renders to, the canvas or the surface. The surface sized `render_targets` follow it.
+    pub fn app_view_size(&self) -> (u32, u32) {
+        self.canvas
+            .as_ref()
+            .map_or((self.surface_handle.config.width, self.surface_handle.config.height), Canvas::size)
+    }
+
+    // Whole view `App::render` renders to, in its pixels
+    pub fn app_viewport(&self) -> Viewport {
+        let (width, height) = self.app_view_size();
+        Viewport::from_size(width, height)
+    }
+
+    pub fn canvas(&self) -> Option<&Canvas> { self.canvas.as_ref() }
+
+    fn notify_resize_listeners(&mut self) {
+        let (width, height) = self.app_view_size();
+        let event = ResizeEvent {
+            width,
+            height,
+            surface_width: self.surface_handle.config.width,
+            surface_height: self.surface_handle.config.height,
+            scale_factor: self.system_state.scale_factor,
+        };
+        let device = &self.render_instance.device_from_surface_handle(&self.surface_handle).device;
+        self.resize_listeners.notify(device, &mut self.resources, &event);
+    }
+
+    // Render the app to a canvas of a fixed resolution blitted to the window, or to the surface with None. The surface
+    // sized `render_targets` are resized to the new app view size.
+    pub fn set_canvas(&mut self, descriptor: Option<CanvasDescriptor>) {
+        let device = &self.render_instance.device_from_surface_handle(&self.surface_handle).device;
+        let (surface_width, surface_height) = (self.surface_handle.config.width, self.surface_handle.config.height);
+        self.canvas = descriptor.map(|descriptor| Canvas::new(device, descriptor, surface_width, surface_height, self.surface_view_format()));
+        let (width, height) = self.app_view_size();
+        self.render_targets.resize(device, width, height);
+        self.notify_resize_listeners();
+    }
+
+    // Position of the cursor in the pixels of the app view, mapped to the canvas (None outside of it) when there is one
+    pub fn cursor_position(&self) -> Option<glam::Vec2> {
+        let cursor_position = self.input_state.mouse.position;
+        match &self.canvas {
+            Some(canvas) => canvas
+                .target_to_canvas(cursor_position.to_array(), self.surface_handle.config.width, self.surface_handle.config.height)
+                .map(glam::Vec2::from_array),
+            None => Some(cursor_position),
+        }
+    }
+
+    // Rect left to the app by the gui (see `fit_viewport_to_gui_available_rect`), in physical pixels
+    #[cfg(feature = "egui")]
+    pub fn gui_viewport(&self) -> Viewport { self.egui_rect_to_viewport(self.egui_renderer.available_rect()) }
+
+    // Egui points are scaled by the pixels per point (window scale factor with the egui zoom), unlike the logical points
+    // of `SystemState` which only use the window scale factor
+    #[cfg(feature = "egui")]
+    pub fn pixels_per_point(&self) -> f32 { self.egui_renderer.context().pixels_per_point() }
+
+    // Position in physical pixels (e.g. `MouseState::position`) to an egui position, to paint over the 3D view
+    #[cfg(feature = "egui")]
+    pub fn pixels_to_egui_pos(&self, position: glam::Vec2) -> egui::Pos2 {
+        let position = position / self.pixels_per_point();
+        egui::pos2(position.x, position.y)
+    }
+
+    #[cfg(feature = "egui")]
+    pub fn egui_pos_to_pixels(&self, position: egui::Pos2) -> glam::Vec2 { glam::Vec2::new(position.x, position.y) * self.pixels_per_point() }
+
+    // Egui rect (e.g. of a panel or `Ui::max_rect`) to a viewport in physical pixels
+    #[cfg(feature = "egui")]
+    pub fn egui_rect_to_viewport(&self, rect: egui::Rect) -> Viewport {
+        Viewport::new(glam::Vec2::new(rect.min.x, rect.min.y), glam::Vec2::new(rect.width(), rect.height())).scaled(self.pixels_per_point())
+    }
+
+    #[cfg(feature = "egui")]
+    pub fn viewport_to_egui_rect(&self, viewport: &Viewport) -> egui::Rect {
+        let viewport = viewport.scaled(1.0 / self.pixels_per_point());
+        egui::Rect::from_min_size(egui::pos2(viewport.position.x, viewport.position.y), egui::vec2(viewport.size.x, viewport.size.y))
+    }
+
+    // Normalized device coordinates of the cursor in the viewport, None when the cursor is outside of it
+    pub fn cursor_ndc(&self, viewport: &Viewport) -> Option<glam::Vec2> {
+        let cursor_position = self.input_state.mouse.position;
+        viewport.contains(cursor_position).then(|| viewport.to_ndc(cursor_position))
+    }
+
+    // Placement of the app content in the app view by the viewport policy, for the current app view size. Render with
+    // `Letterbox::set_viewport` (and `Letterbox::crop_projection` for `FillCrop`).
+    pub fn letterbox(&self) -> Letterbox { self.viewport_policy.apply(&self.app_viewport()) }
+
+    // `cursor_ray` for the letterboxed content, None when the cursor is over the bars
+    pub fn letterbox_cursor_ray(&self, view_proj: &glam::Mat4) -> Option<Ray> {
+        let letterbox = self.letterbox();
+        let cursor_position = self.cursor_position()?;
+        letterbox
+            .viewport
+            .contains(cursor_position)
+            .then(|| Ray::from_screen_with_convention(cursor_position, &letterbox.content, view_proj, self.depth_convention))
+    }
+
+    // Ray under the cursor for a camera rendering to the viewport, None when the cursor is outside of it
+    pub fn cursor_ray(&self, viewport: &Viewport, view_proj: &glam::Mat4) -> Option<Ray> {
+        let cursor_position = self.input_state.mouse.position;
+        viewport
+            .contains(cursor_position)
+            .then(|| Ray::from_screen_with_convention(cursor_position, viewport, view_proj, self.depth_convention))
+    }
+
+    // Projection jittered by the offset of the frame in the frame uniforms, for a projection covering the surface
+    pub fn jitter_projection(&self, projection: &glam::Mat4) -> glam::Mat4 {
+        let frame_uniforms = self.frame_uniforms.content();
+        ProjectionJitter::apply_offset(projection, frame_uniforms.jitter.into(), frame_uniforms.resolution.into())
+    }
+
+    // Store a value shared by type (see `resources`), returns the replaced value of this type if any
+    pub fn insert<T: 'static>(&mut self, value: T) -> Option<T> { self.resources.insert(value) }
+
+    pub fn get<T: 'static>(&self) -> Option<&T> { self.resources.get() }
+
+    pub fn get_mut<T: 'static>(&mut self) -> Option<&mut T> { self.resources.get_mut() }
+
+    pub fn remove<T: 'static>(&mut self) -> Option<T> { self.resources.remove() }
+
+    // Exit at the end of the current event, unless `App::on_exit_requested` vetoes it
+    pub fn request_exit(&mut self) { self.system_state.exit_requested = true; }
+
+    // Redraw the next frame when the control flow is Wait or WaitUntil, e.g. after a change of the scene or while animating
+    pub fn request_redraw(&mut self) { self.redraw_requested = true; }
+
+    // Cursor shown over the window, a CursorIcon or a custom cursor (drawn by egui, see `CustomCursor`)
+    pub fn set_cursor(&mut self, cursor: impl Into<Cursor>) { self.cursor_state.set(&self.window, cursor.into()); }
+
+    pub fn cursor(&self) -> &Cursor { self.cursor_state.cursor() }
+
+    // Create a custom cursor from RGBA8 pixels, cached under its name (see `custom_cursor`)
+    pub fn create_custom_cursor(&mut self, name: &str, rgba: Vec<u8>, width: u32, height: u32, hotspot: (u32, u32)) -> Result<CustomCursor> {
+        self.cursor_state.create_custom_cursor(name, rgba, width, height, hotspot)
+    }
+
+    pub fn custom_cursor(&self, name: &str) -> Option<&CustomCursor> { self.cursor_state.custom_cursor(name) }
+
+    // Text goes through the egui clipboard when the egui feature is enabled
+    #[cfg(any(feature = "egui", feature = "clipboard"))]
+    pub fn clipboard_text(&mut self) -> Option<String> {
+        #[cfg(feature = "egui")]
+        return self.egui_renderer.clipboard_text();
+        #[cfg(not(feature = "egui"))]
+        return self.clipboard.text();
+    }
+
+    #[cfg(any(feature = "egui", feature = "clipboard"))]
+    pub fn set_clipboard_text(&mut self, text: impl Into<String>) {
+        #[cfg(feature = "egui")]
+        self.egui_renderer.set_clipboard_text(text.into());
+        #[cfg(not(feature = "egui"))]
+        self.clipboard.set_text(text.into());
+    }
+
+    #[cfg(feature = "clipboard")]
+    pub fn clipboard_image(&mut self) -> Option<ClipboardImage> { self.clipboard.image() }
+
+    #[cfg(feature = "clipboard")]
+    pub fn set_clipboard_image(&mut self, image: &ClipboardImage) { self.clipboard.set_image(image); }
+
+    pub fn set_window_level(&self, window_level: WindowLevel) { self.window.set_window_level(window_level); }
+
+    fn frame_context<'a>(&self, encoder: &'a mut wgpu::CommandEncoder, surface_view: Option<&'a wgpu::TextureView>) -> FrameContext<'a> {
+        FrameContext {
+            delta_time: self.system_state.delta_time,
+            alpha: self
+                .fixed_timestep
+                .map_or(1.0, |timestep| (self.fixed_time_accumulator / timestep).min(1.0)),
+            frame_index: self.frames_in_flight.frame_index(),
+            surface_format: self.app_view_format(),
+            encoder,
+            surface_view,
+        }
+    }
+
+    // Format of the view `App::render` renders to, the canvas or the surface
+    fn app_view_format(&self) -> wgpu::TextureFormat { self.canvas.as_ref().map_or(self.surface_view_format(), Canvas::format) }
+
+    fn surface_view_format(&self) -> wgpu::TextureFormat {
+        self.srgb_app_view
+            .then(|| self.surface_handle.srgb_format())
+            .flatten()
+            .unwrap_or(self.surface_handle.format())
+    }
+
+    #[inline]
+    pub fn frame_index(&self) -> u64 { self.frames_in_flight.frame_index() }
+
+    // GPU time of the commands recorded through the scope until it is dropped, e.g. `let mut scope =
+    // app_state.gpu_scope(frame.encoder, "shadows");` then the passes on `scope`. In `App::render` the scopes are nested in
+    // the scope of the render layer, see `GpuTimer::tree` for the results.
+    pub fn gpu_scope<'a>(&'a self, encoder: &'a mut wgpu::CommandEncoder, label: &str) -> GpuScope<'a> { self.gpu_timer.scope(encoder, label) }
+
+    pub fn is_fullscreen(&self) -> bool { self.window.fullscreen().is_some() }
+
+    pub fn monitors(&self) -> Vec<MonitorInfo> { self.window.available_monitors().map(MonitorInfo::new).collect() }
+
+    pub fn current_monitor(&self) -> Option<MonitorInfo> { self.window.current_monitor().map(MonitorInfo::new) }
+
+    fn save_windowed_geometry(&mut self) {
+        if !self.is_fullscreen() {
+            self.windowed_geometry = Some((self.window.outer_position().ok(), self.window.inner_size()));
+        }
+    }
+
+    pub fn set_target_fps(&mut self, fps: u32) { self.set_frame_pacing(FramePacing::CapFps(fps)); }
+
+    pub fn set_frame_pacing(&mut self, frame_pacing: FramePacing) {
+        self.frame_pacing = frame_pacing;
+        self.adaptive_sleep_time = std::time::Duration::ZERO;
+        self.adaptive_idle_time = std::time::Duration::ZERO;
+    }
+
+    pub fn frame_pacing(&self) -> FramePacing { self.frame_pacing }
+
+    // Frame duration the frame stats missed target count is based on
+    pub fn target_frame_duration(&self) -> Option<std::time::Duration> {
+        match self.frame_pacing {
+            FramePacing::CapFps(fps) => Some(FramePacing::frame_duration(fps)),
+            FramePacing::VSyncOnly | FramePacing::Adaptive => self.refresh_interval,
+            FramePacing::Unlimited => None,
+        }
+    }
+
+    fn update_refresh_interval(&mut self) {
+        self.refresh_interval = self
+            .window
+            .current_monitor()
+            .and_then(|monitor| monitor.refresh_rate_millihertz())
+            .map(|refresh_rate_millihertz| std::time::Duration::from_secs_f64(1000.0 / refresh_rate_millihertz as f64));
+    }
+}
+
+// Monitor description for fullscreen selection
+#[derive(Clone, Debug)]
+pub struct MonitorInfo {
+    pub handle: MonitorHandle,
+    pub name: Option<String>,
+    pub size: PhysicalSize<u32>,
+    pub position: PhysicalPosition<i32>,
+    pub scale_factor: f64,
+    pub refresh_rate_millihertz: Option<u32>,
+}
+
+impl MonitorInfo {
+    pub fn new(handle: MonitorHandle) -> Self {
+        Self {
+            name: handle.name(),
+            size: handle.size(),
+            position: handle.position(),
+            scale_factor: handle.scale_factor(),
+            refresh_rate_millihertz: handle.refresh_rate_millihertz(),
+            handle,
+        }
+    }
+
+    pub fn refresh_rate_hz(&self) -> Option<f32> { self.refresh_rate_millihertz.map(|refresh_rate| refresh_rate as f32 / 1000.0) }
+
+    pub fn video_modes(&self) -> impl Iterator<Item = VideoMode> { self.handle.video_modes() }
+
+    // Video mode of the given size with the refresh rate closest to the requested one (the highest if None) and the highest bit depth
+    pub fn find_video_mode(&self, width: u32, height: u32, refresh_rate_hz: Option<u32>) -> Option<VideoMode> {
+        self.video_modes()
+            .filter(|video_mode| video_mode.size() == PhysicalSize::new(width, height))
+            .min_by_key(|video_mode| {
+                let refresh_rate_millihertz = video_mode.refresh_rate_millihertz();
+                let refresh_rate_score = match refresh_rate_hz {
+                    Some(refresh_rate_hz) => refresh_rate_millihertz.abs_diff(refresh_rate_hz * 1000),
+                    None => u32::MAX - refresh_rate_millihertz,
+                };
+                (refresh_rate_score, std::cmp::Reverse(video_mode.bit_depth()))
+            })
     }
+}
+
+// How the application loop waits between frames
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize), serde(rename_all = "snake_case"))]
+pub enum FramePacing {
+    // No sleep, the frame rate is limited by the surface present mode blocking (Fifo)
+    #[cfg_attr(feature = "serde", serde(rename = "vsync_only"))]
+    VSyncOnly,
+    // Spin sleep to cap the frame rate, for non blocking present modes (Immediate, Mailbox)
+    CapFps(u32),
+    // No sleep nor cap
+    Unlimited,
+    // Sleep the time measured as blocked on the surface (minus a margin) before updating,
+    // releasing the CPU under Fifo and reducing the latency between inputs and presentation
+    Adaptive,
+}
+
+impl FramePacing {
+    // Margin kept by the adaptive pacing to not miss the next vblank because of sleep inaccuracy
+    pub const ADAPTIVE_MARGIN: std::time::Duration = std::time::Duration::from_millis(2);
 
-    pub fn set_target_fps(&mut self, fps: u32) { self.target_frame_duration = std::time::Duration::from_micros((1_000_000.0 / fps as f32) as u64); }
+    pub fn frame_duration(fps: u32) -> std::time::Duration { std::time::Duration::from_secs_f64(1.0 / fps.max(1) as f64) }
 }
 
-pub trait App {
-    fn create(_app_state: &mut AppState) -> Self;
+const MAX_FIXED_UPDATE_TIME: f64 = 0.25;
+
+// Data of the frame being processed, passed to `App::update`, `App::render` and `App::render_gui`
+pub struct FrameContext<'a> {
+    // Seconds since the previous loop iteration
+    pub delta_time: f64,
+    // Fraction of the fixed timestep accumulated since the last `App::fixed_update`, to interpolate the last two fixed
+    // states. 1 without fixed timestep.
+    pub alpha: f64,
+    pub frame_index: u64,
+    // Format of `surface_view`, the sRGB format with `RenderingConfig::srgb_app_view` or the canvas format with a canvas
+    pub surface_format: wgpu::TextureFormat,
+    // Commands of the frame, submitted once after the egui pass. The commands of `App::update` are submitted before the
+    // ones of the frame rendered after it (or at once if no frame is rendered).
+    pub encoder: &'a mut wgpu::CommandEncoder,
+    surface_view: Option<&'a wgpu::TextureView>,
+}
+
+impl<'a> FrameContext<'a> {
+    // View the app renders to (the canvas with `AppState::set_canvas`), None in `App::update` as the surface texture is
+    // only acquired after it
+    pub fn surface_view(&self) -> Option<&'a wgpu::TextureView> { self.surface_view }
+}
+
+pub trait App: Sized {
+    // An error is returned by `run_application` before the event loop starts
+    fn create(_app_state: &mut AppState) -> Result<Self>;
+
+    // Asynchronous initialization run after `create` and before the first frame (blocking the main thread), e.g. to
+    // await device requests or buffer mappings. The loads of `AppState::asset_loader` only complete once the loop runs.
+    fn init(&mut self, _app_state: &mut AppState) -> impl std::future::Future<Output = Result<()>> { async { Ok(()) } }
+
+    // Called once per `AppConfig::fixed_timestep` seconds elapsed, before `update`
+    fn fixed_update(&mut self, _app_state: &mut AppState, _timestep: f64) -> Result<()> { Ok(()) }
 
-    fn update(&mut self, _app_state: &mut AppState) -> Result<()> { Ok(()) }
+    fn update(&mut self, _app_state: &mut AppState, _frame: &mut FrameContext) -> Result<()> { Ok(()) }
 
     #[cfg(feature = "egui")]
-    fn render_gui(&mut self, _app_state: &mut AppState) -> Result<()> { Ok(()) }
+    fn render_gui(&mut self, _app_state: &mut AppState, _frame: &mut FrameContext) -> Result<()> { Ok(()) }
 
-    fn render(&mut self, _app_state: &mut AppState, _output_view: &wgpu::TextureView) -> Result<()> { Ok(()) }
+    fn render(&mut self, _app_state: &mut AppState, _frame: &mut FrameContext) -> Result<()> { Ok(()) }
     // fn called after queue submit
     fn post_render(&mut self, _app_state: &mut AppState) -> Result<()> { Ok(()) }
 
     fn cleanup(&mut self) -> Result<()> { Ok(()) }
 
+    // Called when the window is closed, Escape is pressed or `AppState::request_exit` is called. Return false to keep
+    // running, e.g. to ask to save the changes first.
+    fn on_exit_requested(&mut self, _app_state: &mut AppState) -> bool { true }
+
     fn on_mouse(&mut self, _app_state: &mut AppState, _button: &MouseButton, _button_state: &ElementState) -> Result<()> { Ok(()) }
     fn on_key(&mut self, _app_state: &mut AppState, _event: &event::KeyEvent) -> Result<()> { Ok(()) }
 
+    // Called once the surface, `AppState::render_targets` and `AppState::resize_listeners` are resized, not when the window is
+    // minimized
+    fn on_resize(&mut self, _app_state: &mut AppState, _size: PhysicalSize<u32>) -> Result<()> { Ok(()) }
+    // Called when the window moves to a monitor with another scale factor, `on_resize` follows with the new size
+    fn on_scale_factor_changed(&mut self, _app_state: &mut AppState, _scale_factor: f64) -> Result<()> { Ok(()) }
+
     fn handle_event<T: 'static>(&mut self, _app_state: &mut AppState, _event: &Event<T>) -> Result<()> { Ok(()) }
 }
 
+// Missing fields are set to their default when deserialized (serde feature)
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize), serde(default))]
 pub struct AppConfig {
     pub is_resizable: bool,
-    pub title: &'static str,
+    pub title: Cow<'static, str>,
+    // Transparent window background, the surface uses a compositing alpha mode when supported (see `AppState::clear_color`)
+    pub transparent: bool,
+    pub decorations: bool,
+    #[cfg_attr(feature = "serde", serde(with = "crate::config::window_level"))]
+    pub window_level: WindowLevel,
     #[cfg(feature = "icon")]
-    pub icon: Option<&'static str>,
+    pub icon: Option<Cow<'static, str>>,
+    #[cfg_attr(feature = "serde", serde(with = "crate::config::control_flow"))]
     pub control_flow: ControlFlow,
+    pub frame_pacing: FramePacing,
+    // Time step of `App::fixed_update` in seconds (e.g. 1/60 for the physics), None to not call it
+    pub fixed_timestep: Option<f64>,
+    #[cfg(feature = "image")]
+    pub screenshot_directory: String,
+    // Directory of the GPU diagnostic reports, None to not write them
+    pub diagnostics_directory: Option<String>,
+    #[cfg(feature = "egui")]
+    #[cfg_attr(feature = "serde", serde(skip))]
+    pub egui: EguiConfig,
+    // Skip `App::on_mouse`/`App::on_key` when egui uses the pointer/keyboard
+    #[cfg(feature = "egui")]
+    pub suppress_inputs_captured_by_egui: bool,
+    // Locked aspect ratio of the app content, see `AppState::letterbox`
+    pub viewport_policy: ViewportPolicy,
+    // Fixed resolution the app renders at, upscaled to the window, see `AppState::set_canvas`
+    #[cfg_attr(feature = "serde", serde(skip))]
+    pub canvas: Option<CanvasDescriptor>,
+    // See `with_plugin`
+    #[cfg_attr(feature = "serde", serde(skip))]
+    pub plugins: Vec<Box<dyn Plugin>>,
 }
 
 impl Default for AppConfig {
     fn default() -> Self {
         Self {
             is_resizable: false,
-            title: "Application",
+            title: "Application".into(),
+            transparent: false,
+            decorations: true,
+            window_level: WindowLevel::Normal,
             #[cfg(feature = "icon")]
             icon: None,
             control_flow: ControlFlow::Poll,
+            frame_pacing: FramePacing::VSyncOnly,
+            fixed_timestep: None,
+            #[cfg(feature = "image")]
+            screenshot_directory: "screenshots".to_string(),
+            diagnostics_directory: Some("diagnostics".to_string()),
+            #[cfg(feature = "egui")]
+            egui: EguiConfig::default(),
+            #[cfg(feature = "egui")]
+            suppress_inputs_captured_by_egui: false,
+            viewport_policy: ViewportPolicy::default(),
+            canvas: None,
+            plugins: Vec::new(),
         }
     }
 }
 
+impl AppConfig {
+    // Plugins are run in the order they are added
+    pub fn with_plugin(mut self, plugin: impl Plugin + 'static) -> Self {
+        self.plugins.push(Box::new(plugin));
+        self
+    }
+}
+
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize), serde(default))]
 pub struct RenderingConfig {
+    #[cfg_attr(feature = "serde", serde(with = "crate::config::power_preference"))]
     pub power_preference: wgpu::PowerPreference,
+    // Forces the adapter choice (e.g. the discrete GPU) instead of the power preference
+    #[cfg_attr(feature = "serde", serde(skip))]
+    pub adapter_selector: Option<AdapterSelector>,
+    #[cfg_attr(feature = "serde", serde(with = "crate::config::features"))]
     pub device_features: wgpu::Features,
+    #[cfg_attr(feature = "serde", serde(with = "crate::config::limits"))]
     pub device_limits: wgpu::Limits,
+    #[cfg_attr(feature = "serde", serde(with = "crate::config::backends"))]
     pub backend: wgpu::Backends,
+    #[cfg_attr(feature = "serde", serde(with = "crate::config::present_mode"))]
     pub window_surface_present_mode: wgpu::PresentMode,
+    // Frames recorded ahead of the GPU, see `AppState::frames_in_flight`
+    pub frames_in_flight: usize,
+    // Give `App::render` an sRGB view of the surface (see `SurfaceHandle::srgb_view`), egui still renders to the base format
+    pub srgb_app_view: bool,
+    // Ignore, log or break on the uncaptured wgpu errors, breaks on all of them by default
+    pub error_policy: ErrorPolicy,
+}
+
+#[cfg(feature = "serde")]
+impl AppConfig {
+    pub fn from_toml_path(path: impl AsRef<std::path::Path>) -> Result<Self> { crate::config::load_toml(path) }
+
+    pub fn from_ron_path(path: impl AsRef<std::path::Path>) -> Result<Self> { crate::config::load_ron(path) }
+}
+
+#[cfg(feature = "serde")]
+impl RenderingConfig {
+    pub fn from_toml_path(path: impl AsRef<std::path::Path>) -> Result<Self> { crate::config::load_toml(path) }
+
+    pub fn from_ron_path(path: impl AsRef<std::path::Path>) -> Result<Self> { crate::config::load_ron(path) }
 }
 
 impl Default for RenderingConfig {
     fn default() -> Self {
         Self {
             power_preference: wgpu::PowerPreference::default(),
+            adapter_selector: None,
             device_features: wgpu::Features::default(),
             device_limits: wgpu::Limits::default(),
             backend: wgpu::Backends::PRIMARY,
             // FIFO, will cap the display rate at the displays framerate. This is essentially VSync.
             // https://docs.rs/wgpu/0.10.1/wgpu/enum.PresentMode.html
             window_surface_present_mode: wgpu::PresentMode::Fifo,
+            frames_in_flight: 2,
+            srgb_app_view: false,
+            error_policy: ErrorPolicy::default(),
         }
     }
 }
@@ -116,14 +701,15 @@ pub fn run_application<T: App + 'static>(app_config: AppConfig, rendering_config
 
     #[allow(unused_mut)]
     let mut window_builder: WindowBuilder = WindowBuilder::new()
-        .with_decorations(true)
+        .with_decorations(app_config.decorations)
         .with_resizable(app_config.is_resizable)
-        .with_transparent(false)
-        .with_title(app_config.title);
+        .with_transparent(app_config.transparent)
+        .with_window_level(app_config.window_level)
+        .with_title(app_config.title.as_ref());
 
     #[cfg(feature = "icon")]
-    if let Some(icon_path) = app_config.icon {
-        let image = image::io::Reader::open(icon_path)?.decode()?.into_rgba8();
+    if let Some(icon_path) = &app_config.icon {
+        let image = image::ImageReader::open(&**icon_path)?.decode()?.into_rgba8();
         let (width, height) = image.dimensions();
         let icon = winit::window::Icon::from_rgba(image.into_raw(), width, height)?;
         window_builder = window_builder.with_window_icon(Some(icon));
@@ -137,8 +723,10 @@ pub fn run_application<T: App + 'static>(app_config: AppConfig, rendering_config
     let window = Arc::new(window_builder.build(&event_loop)?);
 
     let window_dimensions = window.inner_size();
+    let scale_factor = window.scale_factor();
 
     let mut render_instance = RenderInstance::new(Some(rendering_config.backend), None);
+    render_instance.set_adapter_selector(rendering_config.adapter_selector);
     let mut surface_handle = pollster::block_on(render_instance.create_render_surface(
         window.clone(),
         window_dimensions.width,
@@ -148,11 +736,31 @@ pub fn run_application<T: App + 'static>(app_config: AppConfig, rendering_config
     ))?;
 
     let surface_device_handle = &render_instance.devices[surface_handle.device_handle_id];
-    
+
     surface_handle.set_present_mode(&surface_device_handle.device, rendering_config.window_surface_present_mode);
+    if app_config.transparent && !surface_handle.enable_transparency(surface_device_handle) {
+        #[cfg(feature = "log")]
+        log::warn!("The window surface doesn't support transparency");
+    }
+
+    let frame_uniforms = UniformBufferWrapper::new(
+        &surface_device_handle.device,
+        FrameUniforms::default(),
+        wgpu::ShaderStages::VERTEX_FRAGMENT | wgpu::ShaderStages::COMPUTE,
+    );
+    let gpu_timer = GpuTimer::new(&surface_device_handle.device, &surface_device_handle.queue, 256, Some("App"));
+    let render_targets = RenderTargets::new(&surface_device_handle.device, window_dimensions.width, window_dimensions.height);
+    let diagnostics = DiagnosticReporter::new(surface_device_handle.adapter().get_info(), &surface_device_handle.device)
+        .with_directory(app_config.diagnostics_directory.as_ref());
+    diagnostics.install_device_lost_handler(&surface_device_handle.device);
+    // Installed before `App::create` so its errors follow the policy too
+    let error_handler = UncapturedErrorHandler::new(rendering_config.error_policy, Some(diagnostics.clone()));
+    error_handler.install(&surface_device_handle.device);
 
     #[cfg(feature = "egui")]
-    let egui_renderer = EguiRenderer::new(&surface_device_handle.device, surface_handle.format(), None, 1, &window);
+    let egui_renderer = EguiRenderer::new_with_config(&surface_device_handle.device, surface_handle.format(), None, 1, &window, app_config.egui);
+
+    let pipeline_compiler = PipelineCompiler::new(surface_device_handle.device.clone());
 
     let mut app_state = AppState {
         window,
@@ -160,67 +768,154 @@ pub fn run_application<T: App + 'static>(app_config: AppConfig, rendering_config
         render_instance,
         surface_handle,
 
-        clear_color: wgpu::Color { r: 0.1, g: 0.2, b: 0.3, a: 1.0 },
+        clear_color: if app_config.transparent {
+            wgpu::Color::TRANSPARENT
+        } else {
+            wgpu::Color { r: 0.1, g: 0.2, b: 0.3, a: 1.0 }
+        },
+
+        render_targets,
+        frames_in_flight: FramesInFlight::new(rendering_config.frames_in_flight),
+        compute_queue: ComputeQueue::new("App compute"),
+        gpu_timer,
+        frame_uniforms,
+        render_layers: RenderLayers::default(),
+        projection_jitter: None,
+        depth_convention: DepthConvention::default(),
+        debug_view: DebugViewMode::default(),
+        viewport_policy: app_config.viewport_policy,
+        letterbox_color: Some(wgpu::Color::BLACK),
+        #[cfg(feature = "image")]
+        screenshots: Screenshots::new(&app_config.screenshot_directory, 3).with_keep_alpha(app_config.transparent),
 
         #[cfg(feature = "egui")]
         egui_renderer,
+        #[cfg(feature = "egui")]
+        shader_errors: ShaderErrorOverlay::default(),
 
         input_state: InputsState::default(),
-        system_state: SystemState::new(window_dimensions),
+        system_state: SystemState::new(window_dimensions).with_scale_factor(scale_factor),
+        frame_stats: FrameStats::default(),
+        frame_timeline: FrameTimeline::default(),
+
+        error_scopes: ErrorScopes::new(),
+        diagnostics,
+        error_handler,
+
+        resources: Resources::new(),
+        resize_listeners: ResizeListeners::new(),
+        asset_loader: AssetLoader::default(),
+        pipeline_compiler,
 
         control_flow: app_config.control_flow,
 
+        #[cfg(feature = "egui")]
+        suppress_inputs_captured_by_egui: app_config.suppress_inputs_captured_by_egui,
+
+        start_time: std::time::Instant::now(),
+        frame_pacing: app_config.frame_pacing,
+        refresh_interval: None,
         last_frame_time: std::time::Instant::now(),
-        target_frame_duration: std::time::Duration::from_micros(16_666),
+        surface_wait_time: std::time::Duration::ZERO,
+        adaptive_sleep_time: std::time::Duration::ZERO,
+        adaptive_idle_time: std::time::Duration::ZERO,
+        srgb_app_view: rendering_config.srgb_app_view,
+        redraw_requested: true,
+        fixed_timestep: app_config.fixed_timestep,
+        fixed_time_accumulator: 0.0,
+        update_command_buffers: Vec::new(),
+        #[cfg(feature = "egui")]
+        egui_repaint_time: None,
+        plugins: app_config.plugins,
+        cursor_state: CursorState::default(),
+        letterbox_fill: None,
+        canvas: None,
+        #[cfg(feature = "clipboard")]
+        clipboard: Clipboard::default(),
+        windowed_geometry: None,
     };
+    app_state.update_refresh_interval();
+    app_state.set_canvas(app_config.canvas);
 
-    let (tx, rx) = std::sync::mpsc::channel::<wgpu::Error>();
-    app_state.render_instance.device_from_surface_handle(&app_state.surface_handle).device.on_uncaptured_error(Box::new(move |e: wgpu::Error| {
-        tx.send(e).expect("sending error failed");
-    }));
+    // Wake the event loop (with an empty user event) when an asset is loaded so it is processed under ControlFlow::Wait
+    let event_loop_proxy = event_loop.create_proxy();
+    app_state.asset_loader.set_waker(move || {
+        let _ = event_loop_proxy.send_event(());
+    });
 
-    let mut app = T::create(&mut app_state);
-
-    app_state.render_instance.device_from_surface_handle(&app_state.surface_handle).device.on_uncaptured_error(Box::new(|err| panic!("{}", err)));
-
-    if let Ok(err) = rx.try_recv() {
-        panic!("{}", err);
-    }
+    for_each_plugin(&mut app_state, |plugin, app_state| plugin.setup(app_state))?;
+    let mut app = T::create(&mut app_state)?;
+    pollster::block_on(app.init(&mut app_state))?;
 
     // Run
     event_loop.run(move |event, elwt| {
         if let Err(error) = run_loop(&mut app, &mut app_state, event, elwt) {
             eprintln!("Application Error: {}", error);
         }
+        // Checked after each event as the exit can be requested from any callback
+        handle_exit_request(&mut app, &mut app_state, elwt);
     })?;
 
     Ok(())
 }
 
-fn run_loop<T: 'static>(app: &mut impl App, app_state: &mut AppState, event: Event<T>, elwt: &EventLoopWindowTarget<T>) -> Result<()> {
-    app_state.input_state.handle_event(&event);
-    app_state.system_state.handle_event(&event);
+// Call `f` on the plugins, taken out of the state as they are called with it
+fn for_each_plugin(app_state: &mut AppState, mut f: impl FnMut(&mut dyn Plugin, &mut AppState) -> Result<()>) -> Result<()> {
+    let mut plugins = std::mem::take(&mut app_state.plugins);
+    let result = plugins.iter_mut().try_for_each(|plugin| f(plugin.as_mut(), app_state));
+    app_state.plugins = plugins;
+    result
+}
 
+fn run_loop(app: &mut impl App, app_state: &mut AppState, event: Event<()>, elwt: &EventLoopWindowTarget<()>) -> Result<()> {
     #[cfg(feature = "egui")]
     if let Event::WindowEvent { event: window_event, .. } = &event {
-        let _ = app_state.egui_renderer.handle_window_event(&app_state.window, window_event);
+        let event_response = app_state.egui_renderer.handle_window_event(&app_state.window, window_event);
+        let egui_context = app_state.egui_renderer.context();
+        app_state.input_state.egui_consumed_event = event_response.consumed;
+        app_state.input_state.egui_wants_pointer_input = egui_context.wants_pointer_input();
+        app_state.input_state.egui_wants_keyboard_input = egui_context.wants_keyboard_input();
     }
 
+    app_state.input_state.handle_event(&event);
+    app_state.system_state.handle_event(&event);
+
+    for_each_plugin(app_state, |plugin, app_state| plugin.handle_event(app_state, &event))?;
     app.handle_event(app_state, &event)?;
 
+    match &event {
+        // Inputs and window changes redraw, as well as the loaded assets waking the loop
+        Event::WindowEvent { event, .. } if !matches!(event, WindowEvent::RedrawRequested) => app_state.redraw_requested = true,
+        Event::UserEvent(_) => app_state.redraw_requested = true,
+        _ => (),
+    }
+
     match event {
         Event::WindowEvent { ref event, .. } => match event {
-            WindowEvent::Resized(physical_size) => {
-                // Resize with 0 width and height is used by winit to signal a minimize event on Windows.
-                // See: https://github.com/rust-windowing/winit/issues/208
-                // This solves an issue where the app would panic when minimizing on Windows.
-                if physical_size.width > 0 && physical_size.height > 0 {
-                    let surface_device = &app_state.render_instance.device_from_surface_handle(&app_state.surface_handle).device;
-                    app_state.surface_handle.resize(surface_device, physical_size.width, physical_size.height)?;
-                    // On macos the window needs to be redrawn manually after resizing
-                    app_state.window.request_redraw();
+            // Resize with 0 width and height is used by winit to signal a minimize event on Windows.
+            // See: https://github.com/rust-windowing/winit/issues/208
+            // This solves an issue where the app would panic when minimizing on Windows.
+            WindowEvent::Resized(physical_size) if physical_size.width > 0 && physical_size.height > 0 => {
+                let surface_device = &app_state.render_instance.device_from_surface_handle(&app_state.surface_handle).device;
+                app_state
+                    .surface_handle
+                    .resize(surface_device, physical_size.width, physical_size.height)?;
+                if let Some(canvas) = &mut app_state.canvas {
+                    canvas.resize(surface_device, physical_size.width, physical_size.height);
                 }
+                let (width, height) = app_state.app_view_size();
+                app_state.render_targets.resize(surface_device, width, height);
+                app_state.notify_resize_listeners();
+                // On macos the window needs to be redrawn manually after resizing
+                app_state.window.request_redraw();
+                app.on_resize(app_state, *physical_size)?;
+            },
+            WindowEvent::ScaleFactorChanged { scale_factor, .. } => {
+                app_state.notify_resize_listeners();
+                app.on_scale_factor_changed(app_state, *scale_factor)?;
             },
+            // The window may have moved to a monitor with another refresh rate
+            WindowEvent::Moved(_) => app_state.update_refresh_interval(),
             WindowEvent::CloseRequested
             | WindowEvent::KeyboardInput {
                 event:
@@ -230,15 +925,28 @@ fn run_loop<T: 'static>(app: &mut impl App, app_state: &mut AppState, event: Eve
                         ..
                     },
                 ..
-            } => {
-                elwt.exit();
+            } => app_state.request_exit(),
+            WindowEvent::MouseInput { button, state, .. } => {
+                #[cfg(feature = "egui")]
+                if app_state.suppress_inputs_captured_by_egui && app_state.input_state.is_pointer_captured_by_egui() {
+                    return Ok(());
+                }
+                app.on_mouse(app_state, button, state)?;
             },
-            WindowEvent::MouseInput { button, state, .. } => app.on_mouse(app_state, button, state)?,
             WindowEvent::KeyboardInput { event, .. } => {
+                #[cfg(feature = "egui")]
+                if app_state.suppress_inputs_captured_by_egui && app_state.input_state.is_keyboard_captured_by_egui() {
+                    return Ok(());
+                }
                 app.on_key(app_state, event)?;
             },
             WindowEvent::RedrawRequested => {
-                match app_state.surface_handle.get_current_texture() {
+                trace_scope!("frame");
+                app_state.redraw_requested = false;
+                let surface_wait_start = std::time::Instant::now();
+                let current_texture = app_state.surface_handle.get_current_texture();
+                app_state.surface_wait_time = surface_wait_start.elapsed();
+                match current_texture {
                     Ok(output) => {
                         render_app(app, app_state, output)?;
                     },
@@ -250,32 +958,123 @@ fn run_loop<T: 'static>(app: &mut impl App, app_state: &mut AppState, event: Eve
                     Err(e) => eprintln!("{:?}", e),
                 }
 
+                trace_scope!("post_render");
                 app.post_render(app_state)?;
+                for_each_plugin(app_state, |plugin, app_state| plugin.post_render(app_state))?;
+
+                for scope_error in app_state.error_scopes.poll() {
+                    report_error!("{}", scope_error);
+                }
             },
             _ => (),
         },
         Event::AboutToWait => {
-            app.update(app_state)?;
+            {
+                trace_scope!("asset_loading");
+                let surface_device_handle = app_state.render_instance.device_from_surface_handle(&app_state.surface_handle);
+                app_state
+                    .asset_loader
+                    .process_completed(&surface_device_handle.device, &surface_device_handle.queue);
+            }
+            if let Some(timestep) = app_state.fixed_timestep {
+                trace_scope!("fixed_update");
+                // Clamped so a long frame doesn't spiral into more and more fixed updates
+                app_state.fixed_time_accumulator += app_state.system_state.delta_time.min(MAX_FIXED_UPDATE_TIME);
+                while app_state.fixed_time_accumulator >= timestep {
+                    app.fixed_update(app_state, timestep)?;
+                    app_state.fixed_time_accumulator -= timestep;
+                }
+            }
+            {
+                trace_scope!("update");
+                let surface_device = &app_state.render_instance.device_from_surface_handle(&app_state.surface_handle).device;
+                let mut encoder = surface_device.create_command_encoder(&wgpu::CommandEncoderDescriptor { label: Some("Update Encoder") });
+                for_each_plugin(app_state, |plugin, app_state| {
+                    plugin.pre_update(app_state, &mut app_state.frame_context(&mut encoder, None))
+                })?;
+                app.update(app_state, &mut app_state.frame_context(&mut encoder, None))?;
+                app_state.update_command_buffers.push(encoder.finish());
+            }
+            {
+                trace_scope!("compute_submit");
+                let surface_device_handle = app_state.render_instance.device_from_surface_handle(&app_state.surface_handle);
+                app_state.compute_queue.pump(&surface_device_handle.device, &surface_device_handle.queue);
+            }
 
             let now = std::time::Instant::now();
-            let next_frame_time = app_state.last_frame_time + app_state.target_frame_duration;
-            if now > next_frame_time {
-                #[cfg(feature = "log")]
-                log::warn!(
-                    "We are running behind the target frame rate of {:.0} fps (current frame took {:?} (~ {:.0} fps ))",
-                    1.0 / app_state.target_frame_duration.as_secs_f32(),
-                    now - app_state.last_frame_time,
-                    1.0 / (now - app_state.last_frame_time).as_secs_f32()
-                );
-                
-            } else {
-                spin_sleep::sleep(next_frame_time.duration_since(now));
+            #[cfg(feature = "egui")]
+            let egui_repaint_due = app_state.egui_repaint_time.is_some_and(|time| time <= now);
+            #[cfg(not(feature = "egui"))]
+            let egui_repaint_due = false;
+            let redraw = match app_state.control_flow {
+                ControlFlow::Poll => true,
+                ControlFlow::Wait => app_state.redraw_requested || egui_repaint_due,
+                ControlFlow::WaitUntil(time) => app_state.redraw_requested || egui_repaint_due || time <= now,
+            };
+
+            // Wake up for the egui repaints while waiting
+            #[allow(unused_mut)]
+            let mut control_flow = app_state.control_flow;
+            #[cfg(feature = "egui")]
+            {
+                if egui_repaint_due {
+                    app_state.egui_repaint_time = None;
+                }
+                if let Some(repaint_time) = app_state.egui_repaint_time {
+                    control_flow = match control_flow {
+                        ControlFlow::Poll => ControlFlow::Poll,
+                        ControlFlow::Wait => ControlFlow::WaitUntil(repaint_time),
+                        ControlFlow::WaitUntil(time) => ControlFlow::WaitUntil(time.min(repaint_time)),
+                    };
+                }
+            }
+            elwt.set_control_flow(control_flow);
+
+            if !redraw {
+                let surface_queue = &app_state.render_instance.device_from_surface_handle(&app_state.surface_handle).queue;
+                surface_queue.submit(app_state.update_command_buffers.drain(..));
+                // The idle time isn't part of the next frame time
+                app_state.last_frame_time = now;
+                return Ok(());
+            }
+
+            // The surface wait is part of the pacing, not of the frame work
+            let frame_time = (now - app_state.last_frame_time).saturating_sub(app_state.surface_wait_time);
+            app_state.frame_stats.record(frame_time, app_state.target_frame_duration());
+            match app_state.frame_pacing {
+                FramePacing::CapFps(fps) => {
+                    let target_frame_duration = FramePacing::frame_duration(fps);
+                    let next_frame_time = app_state.last_frame_time + target_frame_duration;
+                    if now > next_frame_time {
+                        #[cfg(feature = "log")]
+                        log::warn!(
+                            "We are running behind the target frame rate of {} fps (current frame took {:?} (~ {:.0} fps ))",
+                            fps,
+                            now - app_state.last_frame_time,
+                            1.0 / (now - app_state.last_frame_time).as_secs_f32()
+                        );
+                    } else {
+                        spin_sleep::sleep(next_frame_time.duration_since(now));
+                    }
+                },
+                FramePacing::Adaptive => {
+                    // The idle time includes the previous sleep so the estimate doesn't shrink once the wait is slept
+                    let idle_time = app_state.surface_wait_time + app_state.adaptive_sleep_time;
+                    app_state.adaptive_idle_time = app_state.adaptive_idle_time.mul_f64(0.9) + idle_time.mul_f64(0.1);
+                    app_state.adaptive_sleep_time = app_state.adaptive_idle_time.saturating_sub(FramePacing::ADAPTIVE_MARGIN);
+                    if !app_state.adaptive_sleep_time.is_zero() {
+                        std::thread::sleep(app_state.adaptive_sleep_time);
+                    }
+                },
+                FramePacing::VSyncOnly | FramePacing::Unlimited => (),
             }
             app_state.last_frame_time = std::time::Instant::now();
 
             app_state.window.request_redraw();
         },
         Event::LoopExiting => {
+            #[cfg(feature = "persistence")]
+            app_state.egui_renderer.save_memory()?;
             app.cleanup()?;
         },
         _ => (),
@@ -284,59 +1083,243 @@ fn run_loop<T: 'static>(app: &mut impl App, app_state: &mut AppState, event: Eve
     Ok(())
 }
 
+fn handle_exit_request<T: 'static>(app: &mut impl App, app_state: &mut AppState, elwt: &EventLoopWindowTarget<T>) {
+    if app_state.system_state.exit_requested {
+        app_state.system_state.exit_requested = false;
+        if app.on_exit_requested(app_state) {
+            elwt.exit();
+        }
+    }
+}
+
 pub fn render_app(app: &mut impl App, app_state: &mut AppState, output: wgpu::SurfaceTexture) -> Result<()> {
-    let view = output.texture.create_view(&wgpu::TextureViewDescriptor::default());
+    let view = output.texture.create_view(&wgpu::TextureViewDescriptor {
+        label: Some("Surface view"),
+        ..Default::default()
+    });
+    let srgb_view = app_state.srgb_app_view.then(|| app_state.surface_handle.srgb_view(&output));
+
+    update_frame_uniforms(app_state);
+    let surface_device = &app_state.render_instance.device_from_surface_handle(&app_state.surface_handle).device;
+    app_state.gpu_timer.poll(surface_device);
+    app_state.frame_timeline.begin_frame();
+    {
+        trace_scope!("frame_wait");
+        app_state.frames_in_flight.begin_frame(surface_device);
+    }
+    let mut frame_encoder = surface_device.create_command_encoder(&wgpu::CommandEncoderDescriptor { label: Some("Frame Encoder") });
+    record_pass_label(format!("Frame Encoder: frame {}", app_state.frames_in_flight.frame_index()));
+    let surface_app_view = srgb_view.as_ref().unwrap_or(&view);
+    // The app renders to the canvas when there is one, blitted to the surface before the egui pass
+    let canvas_view = app_state.canvas.as_ref().map(|canvas| {
+        canvas.texture().create_view(&wgpu::TextureViewDescriptor {
+            label: Some("Canvas: frame view"),
+            ..Default::default()
+        })
+    });
+    let app_view = canvas_view.as_ref().unwrap_or(surface_app_view);
+    #[allow(unused_mut)]
+    let mut canvas_blitted = canvas_view.is_none();
 
-    app.render(app_state, &view)?;
+    {
+        trace_scope!("render_layers");
+        // Taken out of the state as the layers are rendered with it
+        let mut render_layers = std::mem::replace(&mut app_state.render_layers, RenderLayers::empty());
+        let result = render_layers.enabled_layers_mut().try_for_each(|layer| {
+            let layer_scope = app_state.gpu_timer.begin_scope(&mut frame_encoder, &layer.name);
+            app_state.frame_timeline.begin_pass(&layer.name);
+            let (target_label, target_format, (target_width, target_height)) = match layer.kind {
+                #[cfg(feature = "egui")]
+                RenderLayerKind::Egui => {
+                    let config = &app_state.surface_handle.config;
+                    ("surface", app_state.surface_view_format(), (config.width, config.height))
+                },
+                _ if app_state.canvas.is_some() => ("canvas", app_state.app_view_format(), app_state.app_view_size()),
+                _ => ("surface", app_state.app_view_format(), app_state.app_view_size()),
+            };
+            app_state
+                .frame_timeline
+                .add_attachment(target_label, target_format, target_width, target_height);
+            let result = match &mut layer.kind {
+                RenderLayerKind::App => {
+                    trace_scope!("app_render");
+                    app.render(app_state, &mut app_state.frame_context(&mut frame_encoder, Some(app_view)))
+                        .map(|()| fill_letterbox_bars(app_state, &mut frame_encoder, app_view))
+                },
+                #[cfg(feature = "egui")]
+                RenderLayerKind::Egui => {
+                    if !canvas_blitted {
+                        blit_canvas(app_state, &mut frame_encoder, surface_app_view);
+                        canvas_blitted = true;
+                    }
+                    render_egui(app, app_state, &mut frame_encoder, surface_app_view, &view, output.texture.size())
+                },
+                RenderLayerKind::Custom(render) => {
+                    record_pass_label(format!("Render layer: {}", layer.name));
+                    render(app_state, &mut app_state.frame_context(&mut frame_encoder, Some(app_view)))
+                },
+            };
+            app_state.frame_timeline.end_pass();
+            if let Some(layer_scope) = layer_scope {
+                app_state.gpu_timer.end_scope(&mut frame_encoder, layer_scope);
+            }
+            result
+        });
+        app_state.render_layers = render_layers;
+        result?;
+        if !canvas_blitted {
+            blit_canvas(app_state, &mut frame_encoder, surface_app_view);
+        }
+    }
 
-    // draw UI
-    #[cfg(feature = "egui")]
     {
-        app_state.egui_renderer.begin_frame(&app_state.window);
-        app.render_gui(app_state)?;
-        let egui_output = app_state.egui_renderer.end_frame();
+        trace_scope!("frame_submit");
+        let surface_device_handle = app_state.render_instance.device_from_surface_handle(&app_state.surface_handle);
+        #[cfg(feature = "image")]
+        app_state
+            .screenshots
+            .encode_capture(&surface_device_handle.device, &mut frame_encoder, &output.texture);
+        app_state.gpu_timer.encode_resolve(&surface_device_handle.device, &mut frame_encoder);
+        let command_buffers = app_state.update_command_buffers.drain(..).chain(Some(frame_encoder.finish()));
+        let submission_index = surface_device_handle.queue.submit(command_buffers);
+        app_state.gpu_timer.after_submit();
+        app_state.frame_timeline.end_frame();
+        memory_tracker::end_frame();
+        app_state.frames_in_flight.end_frame(&surface_device_handle.queue, submission_index);
+        #[cfg(feature = "image")]
+        {
+            app_state.screenshots.after_submit();
+            app_state.screenshots.process(&surface_device_handle.device);
+        }
+    }
 
-        let output_size = output.texture.size();
+    trace_scope!("present");
+    output.present();
 
-        let screen_descriptor = egui_wgpu::ScreenDescriptor {
-            size_in_pixels: [output_size.width, output_size.height],
-            pixels_per_point: app_state.window.scale_factor() as f32,
-        };
+    Ok(())
+}
 
-        let DeviceHandle { device: surface_device, queue: surface_queue, .. } = app_state.render_instance.device_from_surface_handle(&app_state.surface_handle);
+fn blit_canvas(app_state: &AppState, frame_encoder: &mut wgpu::CommandEncoder, surface_app_view: &wgpu::TextureView) {
+    if let Some(canvas) = &app_state.canvas {
+        let bar_color = app_state.letterbox_color.unwrap_or(wgpu::Color::BLACK);
+        let config = &app_state.surface_handle.config;
+        canvas.encode_blit(frame_encoder, surface_app_view, config.width, config.height, bar_color);
+    }
+}
 
-        let mut egui_encoder = surface_device.create_command_encoder(&wgpu::CommandEncoderDescriptor { label: Some("Render UI Encoder") });
-        app_state.egui_renderer.draw_output(
-            egui_output,
-            surface_device,
-            surface_queue,
-            &mut egui_encoder,
-            &app_state.window,
-            &view,
-            screen_descriptor,
-        );
-        surface_queue.submit(Some(egui_encoder.finish()));
+// Fill the bars around the letterboxed content over what the app rendered there
+fn fill_letterbox_bars(app_state: &mut AppState, frame_encoder: &mut wgpu::CommandEncoder, app_view: &wgpu::TextureView) {
+    let Some(color) = app_state.letterbox_color else {
+        return;
+    };
+    let rects = app_state
+        .letterbox()
+        .bars()
+        .iter()
+        .map(|bar| {
+            let (min, max) = (bar.position.round(), (bar.position + bar.size).round());
+            [min.x as u32, min.y as u32, (max.x - min.x) as u32, (max.y - min.y) as u32]
+        })
+        .collect::<Vec<_>>();
+    if rects.is_empty() {
+        return;
     }
+    let format = app_state.app_view_format();
+    let device = &app_state.render_instance.device_from_surface_handle(&app_state.surface_handle).device;
+    let fill = match app_state.letterbox_fill.take() {
+        Some(fill) if fill.format() == format => fill,
+        _ => RectFill::new(device, format),
+    };
+    fill.encode(frame_encoder, app_view, &rects, color);
+    app_state.letterbox_fill = Some(fill);
+}
 
-    output.present();
+// Run the gui callbacks and record the egui pass on the surface view (`view`, not the sRGB `app_view`)
+#[cfg(feature = "egui")]
+fn render_egui(
+    app: &mut impl App,
+    app_state: &mut AppState,
+    frame_encoder: &mut wgpu::CommandEncoder,
+    app_view: &wgpu::TextureView,
+    view: &wgpu::TextureView,
+    output_size: wgpu::Extent3d,
+) -> Result<()> {
+    trace_scope!("egui");
+    let egui_output = {
+        trace_scope!("egui_gui");
+        app_state.egui_renderer.begin_frame(&app_state.window);
+        let mut frame = app_state.frame_context(frame_encoder, Some(app_view));
+        // The gui renders to the surface even with a canvas
+        frame.surface_format = app_state.surface_view_format();
+        app.render_gui(app_state, &mut frame)?;
+        for_each_plugin(app_state, |plugin, app_state| plugin.gui(app_state))?;
+        app_state.shader_errors.show(app_state.egui_renderer.context());
+        app_state.cursor_state.update_egui(app_state.egui_renderer.context());
+        app_state.egui_renderer.end_frame()
+    };
+    app_state.egui_repaint_time = egui_output
+        .viewport_output
+        .get(&egui::ViewportId::ROOT)
+        .and_then(|viewport_output| std::time::Instant::now().checked_add(viewport_output.repaint_delay));
+
+    let screen_descriptor = egui_wgpu::ScreenDescriptor {
+        size_in_pixels: [output_size.width, output_size.height],
+        pixels_per_point: egui_output.pixels_per_point,
+    };
+
+    let DeviceHandle {
+        device: surface_device,
+        queue: surface_queue,
+        ..
+    } = app_state.render_instance.device_from_surface_handle(&app_state.surface_handle);
+
+    let callback_command_buffers = app_state.egui_renderer.draw_output(
+        egui_output,
+        surface_device,
+        surface_queue,
+        frame_encoder,
+        &app_state.window,
+        view,
+        screen_descriptor,
+    );
+    app_state.update_command_buffers.extend(callback_command_buffers);
+    app_state.cursor_state.restore_after_egui(&app_state.window);
 
     Ok(())
 }
 
+fn update_frame_uniforms(app_state: &mut AppState) {
+    let mouse = &app_state.input_state.mouse;
+    let mouse_buttons = mouse.is_left_clicked as u32 | (mouse.is_right_clicked as u32) << 1 | (mouse.is_middle_clicked as u32) << 2;
+    let frame_index = app_state.frames_in_flight.frame_index();
+    let jitter = |frame_index: u64| {
+        app_state
+            .projection_jitter
+            .map_or([0.0; 2], |jitter| jitter.offset(frame_index).to_array())
+    };
+    let frame_uniforms = FrameUniforms {
+        time: app_state.start_time.elapsed().as_secs_f32(),
+        delta_time: app_state.system_state.delta_time as f32,
+        frame_index: frame_index as u32,
+        mouse_buttons,
+        resolution: [
+            app_state.surface_handle.config.width as f32,
+            app_state.surface_handle.config.height as f32,
+        ],
+        mouse: mouse.position.to_array(),
+        jitter: jitter(frame_index),
+        previous_jitter: jitter(frame_index.wrapping_sub(1)),
+    };
+    *app_state.frame_uniforms.content_mut() = frame_uniforms;
+    let surface_queue = &app_state.render_instance.device_from_surface_handle(&app_state.surface_handle).queue;
+    app_state.frame_uniforms.update_content(surface_queue);
+}
+
 #[cfg(feature = "egui")]
 // Update the viewport of the render pass to match the available rect of the gui
 pub fn fit_viewport_to_gui_available_rect(render_pass: &mut wgpu::RenderPass, _app_state: &AppState) {
-    let window_scale_factor = _app_state.window.scale_factor() as f32;
-    // // It must be multiplied by window scale factor as render pass use physical pixels screen size
-    let available_rect = _app_state.egui_renderer.context().available_rect();
-    let available_rect_size = available_rect.size();
-
-    render_pass.set_viewport(
-        available_rect.min.x * window_scale_factor,
-        available_rect.min.y * window_scale_factor,
-        available_rect_size.x * window_scale_factor,
-        available_rect_size.y * window_scale_factor,
-        0.0,
-        1.0,
-    );
+    // Render passes use physical pixels, see `AppState::gui_viewport`
+    let viewport = _app_state.gui_viewport();
+
+    render_pass.set_viewport(viewport.position.x, viewport.position.y, viewport.size.x, viewport.size.y, 0.0, 1.0);
 }
@@ -0,0 +1,53 @@
+// System clipboard through arboard, used for images and for text when egui (and its clipboard) is not enabled
+pub struct ClipboardImage {
+    pub width: usize,
+    pub height: usize,
+    pub rgba: Vec<u8>,
+}
+
+// The arboard clipboard is created on first use, as it can fail (e.g. without a display server)
+#[derive(Default)]
+pub struct Clipboard {
+    arboard: Option<arboard::Clipboard>,
+}
+
+impl Clipboard {
+    fn arboard(&mut self) -> Option<&mut arboard::Clipboard> {
+        if self.arboard.is_none() {
+            match arboard::Clipboard::new() {
+                Ok(arboard) => self.arboard = Some(arboard),
+                Err(error) => {
+                    report_warn!("Failed to access the clipboard: {}", error);
+                },
+            }
+        }
+        self.arboard.as_mut()
+    }
+
+    pub fn text(&mut self) -> Option<String> { self.arboard()?.get_text().ok() }
+
+    pub fn set_text(&mut self, text: String) {
+        if let Some(arboard) = self.arboard() {
+            let _ = arboard.set_text(text);
+        }
+    }
+
+    pub fn image(&mut self) -> Option<ClipboardImage> {
+        let image = self.arboard()?.get_image().ok()?;
+        Some(ClipboardImage {
+            width: image.width,
+            height: image.height,
+            rgba: image.bytes.into_owned(),
+        })
+    }
+
+    pub fn set_image(&mut self, image: &ClipboardImage) {
+        if let Some(arboard) = self.arboard() {
+            let _ = arboard.set_image(arboard::ImageData {
+                width: image.width,
+                height: image.height,
+                bytes: std::borrow::Cow::Borrowed(&image.rgba),
+            });
+        }
+    }
+}
@@ -0,0 +1,111 @@
+// Loading of the AppConfig/RenderingConfig from toml or ron files, with string forms of the wgpu and winit types they use
+// (e.g. `window_surface_present_mode = "mailbox"`, `backend = ["vulkan", "metal"]`) to use with `#[serde(with = "...")]`
+use std::path::Path;
+
+use anyhow::{Context, Result};
+use serde::{de::DeserializeOwned, Deserialize, Deserializer, Serializer};
+
+pub fn load_toml<T: DeserializeOwned>(path: impl AsRef<Path>) -> Result<T> {
+    let path = path.as_ref();
+    let source = std::fs::read_to_string(path).with_context(|| format!("Failed to read config {}", path.display()))?;
+    toml::from_str(&source).with_context(|| format!("Failed to parse config {}", path.display()))
+}
+
+pub fn load_ron<T: DeserializeOwned>(path: impl AsRef<Path>) -> Result<T> {
+    let path = path.as_ref();
+    let source = std::fs::read_to_string(path).with_context(|| format!("Failed to read config {}", path.display()))?;
+    ron::from_str(&source).with_context(|| format!("Failed to parse config {}", path.display()))
+}
+
+// Enums written as one of their lower snake case variant names
+macro_rules! string_enum {
+    ($module:ident, $type:ty, [$($variant:expr => $name:literal),* $(,)?]) => {
+        pub mod $module {
+            use super::*;
+
+            pub const NAMES: &[&str] = &[$($name),*];
+
+            pub fn to_name(value: &$type) -> Option<&'static str> {
+                $(if *value == $variant { return Some($name); })*
+                None
+            }
+
+            pub fn from_name(name: &str) -> Option<$type> {
+                match name {
+                    $($name => Some($variant),)*
+                    _ => None,
+                }
+            }
+
+            pub fn serialize<S: Serializer>(value: &$type, serializer: S) -> Result<S::Ok, S::Error> {
+                let name = to_name(value).ok_or_else(|| serde::ser::Error::custom(format!("{:?} has no string form", value)))?;
+                serializer.serialize_str(name)
+            }
+
+            pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<$type, D::Error> {
+                let name = String::deserialize(deserializer)?;
+                from_name(&name).ok_or_else(|| serde::de::Error::unknown_variant(&name, NAMES))
+            }
+        }
+    };
+}
+
+// Bitflags written as a list of their lower case flag names
+macro_rules! string_flags {
+    ($module:ident, $type:ty) => {
+        pub mod $module {
+            use super::*;
+
+            pub fn serialize<S: Serializer>(value: &$type, serializer: S) -> Result<S::Ok, S::Error> {
+                serializer.collect_seq(value.iter_names().map(|(name, _)| name.to_lowercase()))
+            }
+
+            pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<$type, D::Error> {
+                Vec::<String>::deserialize(deserializer)?
+                    .into_iter()
+                    .try_fold(<$type>::empty(), |flags, name| {
+                        <$type>::from_name(&name.to_uppercase())
+                            .map(|flag| flags | flag)
+                            .ok_or_else(|| serde::de::Error::custom(format!("Unknown {} flag \"{}\"", stringify!($type), name)))
+                    })
+            }
+        }
+    };
+}
+
+string_enum!(present_mode, wgpu::PresentMode, [
+    wgpu::PresentMode::AutoVsync => "auto_vsync",
+    wgpu::PresentMode::AutoNoVsync => "auto_no_vsync",
+    wgpu::PresentMode::Fifo => "fifo",
+    wgpu::PresentMode::FifoRelaxed => "fifo_relaxed",
+    wgpu::PresentMode::Immediate => "immediate",
+    wgpu::PresentMode::Mailbox => "mailbox",
+]);
+
+string_enum!(power_preference, wgpu::PowerPreference, [
+    wgpu::PowerPreference::None => "none",
+    wgpu::PowerPreference::LowPower => "low_power",
+    wgpu::PowerPreference::HighPerformance => "high_performance",
+]);
+
+// Only the wgpu presets, custom limits can't be written
+string_enum!(limits, wgpu::Limits, [
+    wgpu::Limits::default() => "default",
+    wgpu::Limits::downlevel_defaults() => "downlevel_defaults",
+    wgpu::Limits::downlevel_webgl2_defaults() => "downlevel_webgl2_defaults",
+]);
+
+// `ControlFlow::WaitUntil` has no string form as it is an instant
+string_enum!(control_flow, winit::event_loop::ControlFlow, [
+    winit::event_loop::ControlFlow::Poll => "poll",
+    winit::event_loop::ControlFlow::Wait => "wait",
+]);
+
+string_enum!(window_level, winit::window::WindowLevel, [
+    winit::window::WindowLevel::AlwaysOnBottom => "always_on_bottom",
+    winit::window::WindowLevel::Normal => "normal",
+    winit::window::WindowLevel::AlwaysOnTop => "always_on_top",
+]);
+
+string_flags!(backends, wgpu::Backends);
+string_flags!(features, wgpu::Features);
@@ -0,0 +1,202 @@
+use std::{collections::HashMap, sync::Arc};
+
+use anyhow::{anyhow, Result};
+use winit::window::{CursorIcon, Window};
+
+// RGBA cursor image with its hotspot (the pixel at the pointer position).
+// winit 0.29 has no custom OS cursors so they are drawn by egui on top of the ui, hiding the OS cursor,
+// without egui the fallback icon is used instead.
+#[derive(Clone)]
+pub struct CustomCursor(Arc<CustomCursorImage>);
+
+#[derive(Clone)]
+struct CustomCursorImage {
+    name: String,
+    rgba: Vec<u8>,
+    width: u32,
+    height: u32,
+    hotspot: (u32, u32),
+    fallback_icon: CursorIcon,
+}
+
+impl CustomCursor {
+    pub fn new(name: impl Into<String>, rgba: Vec<u8>, width: u32, height: u32, hotspot: (u32, u32)) -> Result<Self> {
+        if width == 0 || height == 0 || rgba.len() != (width * height * 4) as usize {
+            return Err(anyhow!("Custom cursor image must be {}x{} RGBA8, got {} bytes", width, height, rgba.len()));
+        }
+        if hotspot.0 >= width || hotspot.1 >= height {
+            return Err(anyhow!("Custom cursor hotspot {:?} outside of the {}x{} image", hotspot, width, height));
+        }
+        Ok(Self(Arc::new(CustomCursorImage {
+            name: name.into(),
+            rgba,
+            width,
+            height,
+            hotspot,
+            fallback_icon: CursorIcon::Default,
+        })))
+    }
+
+    #[cfg(feature = "image")]
+    pub fn from_image(name: impl Into<String>, image: &image::DynamicImage, hotspot: (u32, u32)) -> Result<Self> {
+        let image = image.to_rgba8();
+        let (width, height) = image.dimensions();
+        Self::new(name, image.into_raw(), width, height, hotspot)
+    }
+
+    // Icon shown when the custom cursor can't be drawn
+    pub fn with_fallback_icon(self, fallback_icon: CursorIcon) -> Self {
+        let mut image = Arc::unwrap_or_clone(self.0);
+        image.fallback_icon = fallback_icon;
+        Self(Arc::new(image))
+    }
+
+    pub fn name(&self) -> &str { &self.0.name }
+
+    pub fn rgba(&self) -> &[u8] { &self.0.rgba }
+
+    pub fn size(&self) -> (u32, u32) { (self.0.width, self.0.height) }
+
+    pub fn hotspot(&self) -> (u32, u32) { self.0.hotspot }
+}
+
+#[derive(Clone)]
+pub enum Cursor {
+    Icon(CursorIcon),
+    Custom(CustomCursor),
+    Hidden,
+}
+
+impl From<CursorIcon> for Cursor {
+    fn from(icon: CursorIcon) -> Self { Cursor::Icon(icon) }
+}
+
+impl From<CustomCursor> for Cursor {
+    fn from(custom_cursor: CustomCursor) -> Self { Cursor::Custom(custom_cursor) }
+}
+
+// Cursor requested by the app along with the created custom cursors (and their egui textures) cached by name
+pub struct CursorState {
+    cursor: Cursor,
+    custom_cursors: HashMap<String, CustomCursor>,
+    #[cfg(feature = "egui")]
+    textures: HashMap<String, egui::TextureHandle>,
+    // Icon last set on the window, None when egui may have changed it
+    #[cfg(feature = "egui")]
+    applied_icon: Option<CursorIcon>,
+    // Cursor of the last egui frame, egui sets it on the window when it changes
+    #[cfg(feature = "egui")]
+    egui_icon: egui::CursorIcon,
+}
+
+impl Default for CursorState {
+    fn default() -> Self {
+        Self {
+            cursor: Cursor::Icon(CursorIcon::Default),
+            custom_cursors: HashMap::new(),
+            #[cfg(feature = "egui")]
+            textures: HashMap::new(),
+            #[cfg(feature = "egui")]
+            applied_icon: None,
+            #[cfg(feature = "egui")]
+            egui_icon: egui::CursorIcon::Default,
+        }
+    }
+}
+
+impl CursorState {
+    pub fn cursor(&self) -> &Cursor { &self.cursor }
+
+    pub fn set(&mut self, window: &Window, cursor: Cursor) {
+        match &cursor {
+            Cursor::Icon(icon) => Self::set_window_icon(window, *icon),
+            Cursor::Custom(custom_cursor) if cfg!(not(feature = "egui")) => Self::set_window_icon(window, custom_cursor.0.fallback_icon),
+            _ => window.set_cursor_visible(false),
+        }
+        #[cfg(feature = "egui")]
+        {
+            self.applied_icon = match &cursor {
+                Cursor::Icon(icon) => Some(*icon),
+                _ => None,
+            };
+        }
+        self.cursor = cursor;
+    }
+
+    // Create a custom cursor and keep it under its name, replacing the previous one with the same name
+    pub fn create_custom_cursor(&mut self, name: &str, rgba: Vec<u8>, width: u32, height: u32, hotspot: (u32, u32)) -> Result<CustomCursor> {
+        let custom_cursor = CustomCursor::new(name, rgba, width, height, hotspot)?;
+        self.insert_custom_cursor(custom_cursor.clone());
+        Ok(custom_cursor)
+    }
+
+    pub fn insert_custom_cursor(&mut self, custom_cursor: CustomCursor) {
+        #[cfg(feature = "egui")]
+        self.textures.remove(custom_cursor.name());
+        self.custom_cursors.insert(custom_cursor.name().to_string(), custom_cursor);
+    }
+
+    pub fn custom_cursor(&self, name: &str) -> Option<&CustomCursor> { self.custom_cursors.get(name) }
+
+    fn set_window_icon(window: &Window, icon: CursorIcon) {
+        window.set_cursor_visible(true);
+        window.set_cursor_icon(icon);
+    }
+
+    // To call before the egui frame ends: draws the custom cursor and hides the OS one, unless egui shows its own cursor
+    #[cfg(feature = "egui")]
+    pub fn update_egui(&mut self, context: &egui::Context) {
+        if context.output(|output| output.cursor_icon) == egui::CursorIcon::Default {
+            self.draw_egui(context);
+        }
+
+        let egui_icon = context.output(|output| output.cursor_icon);
+        if egui_icon != self.egui_icon || egui_icon != egui::CursorIcon::Default {
+            self.applied_icon = None;
+        }
+        self.egui_icon = egui_icon;
+    }
+
+    #[cfg(feature = "egui")]
+    fn draw_egui(&mut self, context: &egui::Context) {
+        match &self.cursor {
+            Cursor::Icon(_) => (),
+            Cursor::Hidden => context.set_cursor_icon(egui::CursorIcon::None),
+            Cursor::Custom(custom_cursor) => {
+                context.set_cursor_icon(egui::CursorIcon::None);
+                let Some(pointer_position) = context.input(|input| input.pointer.hover_pos()) else {
+                    return;
+                };
+                let texture = self.textures.entry(custom_cursor.name().to_string()).or_insert_with(|| {
+                    let image = &custom_cursor.0;
+                    let color_image = egui::ColorImage::from_rgba_unmultiplied([image.width as usize, image.height as usize], &image.rgba);
+                    context.load_texture(format!("oxyde cursor {}", image.name), color_image, egui::TextureOptions::NEAREST)
+                });
+                // The image is drawn at its pixel size
+                let pixels_per_point = context.pixels_per_point();
+                let (width, height) = custom_cursor.size();
+                let (hotspot_x, hotspot_y) = custom_cursor.hotspot();
+                let min = pointer_position - egui::vec2(hotspot_x as f32, hotspot_y as f32) / pixels_per_point;
+                let rect = egui::Rect::from_min_size(min, egui::vec2(width as f32, height as f32) / pixels_per_point);
+                let painter = context.layer_painter(egui::LayerId::new(egui::Order::Tooltip, egui::Id::new("oxyde custom cursor")));
+                painter.image(
+                    texture.id(),
+                    rect,
+                    egui::Rect::from_min_max(egui::pos2(0.0, 0.0), egui::pos2(1.0, 1.0)),
+                    egui::Color32::WHITE,
+                );
+            },
+        }
+    }
+
+    // To call after the egui platform output is handled: restores the requested icon egui may have replaced
+    #[cfg(feature = "egui")]
+    pub fn restore_after_egui(&mut self, window: &Window) {
+        if let Cursor::Icon(icon) = self.cursor {
+            if self.egui_icon == egui::CursorIcon::Default && self.applied_icon != Some(icon) {
+                Self::set_window_icon(window, icon);
+                self.applied_icon = Some(icon);
+            }
+        }
+    }
+}
@@ -0,0 +1,273 @@
+use anyhow::Result;
+
+use crate::{
+    app::AppState,
+    plugin::Plugin,
+    wgpu_utils::{
+        memory_tracker::{self, AllocationFilter, MemoryCategory},
+        DebugViewMode,
+        GpuTimingNode,
+    },
+};
+
+// Toggleable egui window (performance overlay) showing adapter, surface, window, frame stats and device informations along with live wgpu resources counts.
+// Useful to triage bug reports on varied hardware.
+// Added to an app as a plugin with `AppConfig::with_plugin(DebugPanel::default())`.
+pub struct DebugPanel {
+    pub open: bool,
+    // Key toggling the panel, handled through the egui inputs
+    pub toggle_key: Option<egui::Key>,
+    allocation_filter: AllocationFilter,
+}
+
+impl Default for DebugPanel {
+    fn default() -> Self {
+        Self {
+            open: false,
+            toggle_key: Some(egui::Key::F12),
+            allocation_filter: AllocationFilter::default(),
+        }
+    }
+}
+
+impl DebugPanel {
+    pub fn toggle(&mut self) { self.open = !self.open; }
+
+    pub fn show(&mut self, ctx: &egui::Context, app_state: &mut AppState) {
+        if let Some(toggle_key) = self.toggle_key {
+            if ctx.input(|input| input.key_pressed(toggle_key)) {
+                self.toggle();
+            }
+        }
+
+        let mut open = self.open;
+        egui::Window::new("Debug info").open(&mut open).default_width(320.0).show(ctx, |ui| {
+            egui::ScrollArea::vertical().show(ui, |ui| self.ui(ui, app_state));
+        });
+        self.open = open;
+    }
+
+    fn ui(&mut self, ui: &mut egui::Ui, app_state: &mut AppState) {
+        egui::CollapsingHeader::new("Debug view").default_open(true).show(ui, |ui| {
+            let features = app_state
+                .render_instance
+                .device_from_surface_handle(&app_state.surface_handle)
+                .device
+                .features();
+            egui::ComboBox::from_id_source("debug_panel_debug_view")
+                .selected_text(app_state.debug_view.name())
+                .show_ui(ui, |ui| {
+                    for mode in DebugViewMode::ALL {
+                        ui.add_enabled_ui(mode.is_supported(features), |ui| {
+                            ui.selectable_value(&mut app_state.debug_view, mode, mode.name())
+                                .on_disabled_hover_text("Not supported by the device");
+                        });
+                    }
+                });
+        });
+
+        let device_handle = app_state.render_instance.device_from_surface_handle(&app_state.surface_handle);
+        let adapter_info = device_handle.adapter().get_info();
+        let surface_config = &app_state.surface_handle.config;
+
+        egui::CollapsingHeader::new("Adapter").default_open(true).show(ui, |ui| {
+            egui::Grid::new("debug_panel_adapter").num_columns(2).show(ui, |ui| {
+                Self::row(ui, "Name", &adapter_info.name);
+                Self::row(ui, "Backend", &format!("{:?}", adapter_info.backend));
+                Self::row(ui, "Type", &format!("{:?}", adapter_info.device_type));
+                Self::row(ui, "Vendor / device", &format!("{:#06x} / {:#06x}", adapter_info.vendor, adapter_info.device));
+                Self::row(ui, "Driver", &format!("{} {}", adapter_info.driver, adapter_info.driver_info));
+            });
+        });
+
+        egui::CollapsingHeader::new("Surface & window").default_open(true).show(ui, |ui| {
+            egui::Grid::new("debug_panel_surface").num_columns(2).show(ui, |ui| {
+                Self::row(ui, "Surface format", &format!("{:?}", surface_config.format));
+                Self::row(ui, "Present mode", &format!("{:?}", surface_config.present_mode));
+                Self::row(ui, "Alpha mode", &format!("{:?}", surface_config.alpha_mode));
+                Self::row(ui, "Surface size", &format!("{} x {}", surface_config.width, surface_config.height));
+                let window_size = app_state.window.inner_size();
+                Self::row(ui, "Window size", &format!("{} x {}", window_size.width, window_size.height));
+                Self::row(ui, "Scale factor", &format!("{:.2}", app_state.window.scale_factor()));
+                Self::row(ui, "Egui pixels per point", &format!("{:.2}", ui.ctx().pixels_per_point()));
+                let delta_time = app_state.system_state.delta_time;
+                Self::row(
+                    ui,
+                    "Frame time",
+                    &format!("{:.2} ms ({:.0} fps)", delta_time * 1000.0, 1.0 / delta_time.max(f64::EPSILON)),
+                );
+            });
+        });
+
+        egui::CollapsingHeader::new("Frame stats").default_open(true).show(ui, |ui| {
+            let frame_stats = &app_state.frame_stats;
+            let format_ms = |duration: Option<std::time::Duration>| {
+                duration.map_or("-".to_string(), |duration| format!("{:.2} ms", duration.as_secs_f64() * 1000.0))
+            };
+            egui::Grid::new("debug_panel_frame_stats").num_columns(2).show(ui, |ui| {
+                Self::row(ui, "Frame pacing", &format!("{:?}", app_state.frame_pacing()));
+                Self::row(
+                    ui,
+                    "Average",
+                    &format!("{} ({:.0} fps)", format_ms(frame_stats.average()), frame_stats.average_fps().unwrap_or(0.0)),
+                );
+                Self::row(ui, "p95 / p99", &format!("{} / {}", format_ms(frame_stats.p95()), format_ms(frame_stats.p99())));
+                Self::row(ui, "Worst", &format_ms(Some(frame_stats.worst())));
+                Self::row(
+                    ui,
+                    "Missed target",
+                    &format!("{} / {}", frame_stats.missed_target_count(), frame_stats.frame_count()),
+                );
+            });
+        });
+
+        egui::CollapsingHeader::new("GPU timings").show(ui, |ui| {
+            if !app_state.gpu_timer.is_supported() {
+                ui.label("Timestamp queries not supported by the device");
+            }
+            for node in app_state.gpu_timer.tree() {
+                Self::gpu_timing_ui(ui, &node);
+            }
+        });
+
+        egui::CollapsingHeader::new("Frame timeline").show(ui, |ui| {
+            app_state.frame_timeline.ui(ui, &app_state.gpu_timer);
+        });
+
+        egui::CollapsingHeader::new("Resources").default_open(true).show(ui, |ui| {
+            egui::Grid::new("debug_panel_resources").num_columns(2).show(ui, |ui| {
+                Self::row(ui, "Devices", &app_state.render_instance.devices.len().to_string());
+                Self::resource_rows(ui, app_state.render_instance.instance(), adapter_info.backend);
+            });
+        });
+
+        egui::CollapsingHeader::new("GPU memory (oxyde helpers)").show(ui, |ui| {
+            let memory_report = memory_tracker::memory_report();
+            egui::Grid::new("debug_panel_memory").num_columns(2).show(ui, |ui| {
+                for usage in memory_report.categories.iter().filter(|usage| usage.count > 0) {
+                    Self::row(ui, &format!("{:?}", usage.category), &format!("{} ({})", format_bytes(usage.size), usage.count));
+                }
+                Self::row(ui, "Total", &format!("{} ({})", format_bytes(memory_report.total_size), memory_report.total_count));
+            });
+            egui::CollapsingHeader::new("Allocations").show(ui, |ui| self.allocations_ui(ui));
+            let warnings = memory_tracker::resource_warnings();
+            egui::CollapsingHeader::new(format!("Warnings ({})", warnings.len())).show(ui, |ui| {
+                for warning in &warnings {
+                    ui.colored_label(ui.visuals().warn_fg_color, warning.to_string());
+                }
+            });
+        });
+
+        egui::CollapsingHeader::new("Device features").show(ui, |ui| {
+            for (name, _) in device_handle.device.features().iter_names() {
+                ui.monospace(name);
+            }
+        });
+
+        egui::CollapsingHeader::new("Device limits").show(ui, |ui| {
+            ui.monospace(format!("{:#?}", device_handle.device.limits()));
+        });
+    }
+
+    // Biggest tracked allocations matching the filter, with their creation site and usage on hover
+    fn allocations_ui(&mut self, ui: &mut egui::Ui) {
+        let filter = &mut self.allocation_filter;
+        ui.horizontal(|ui| {
+            ui.add(
+                egui::TextEdit::singleline(&mut filter.text)
+                    .hint_text("Label or file")
+                    .desired_width(120.0),
+            );
+            egui::ComboBox::from_id_source("debug_panel_allocation_category")
+                .selected_text(filter.category.map_or("All".to_string(), |category| format!("{:?}", category)))
+                .show_ui(ui, |ui| {
+                    ui.selectable_value(&mut filter.category, None, "All");
+                    for category in MemoryCategory::ALL {
+                        ui.selectable_value(&mut filter.category, Some(category), format!("{:?}", category));
+                    }
+                });
+            let mut unused = filter.unused_for.is_some();
+            if ui.checkbox(&mut unused, "Unused").on_hover_text("Not used for 60 frames").changed() {
+                filter.unused_for = unused.then_some(60);
+            }
+        });
+        let allocations = memory_tracker::filtered_allocations(filter);
+        ui.label(format!("{} allocations", allocations.len()));
+        for allocation in allocations.iter().take(50) {
+            ui.monospace(format!("{:>10} {}", format_bytes(allocation.size), allocation.label))
+                .on_hover_text(format!(
+                    "{:?} created at {} {} frames ago, {}",
+                    allocation.category,
+                    allocation.location,
+                    allocation.age(),
+                    match (allocation.tracks_usage, allocation.last_used_frame) {
+                        (false, _) => "usage not tracked".to_string(),
+                        (true, None) => "never used".to_string(),
+                        (true, Some(frame)) => format!("last used {} frames ago", memory_tracker::current_frame().saturating_sub(frame)),
+                    }
+                ));
+        }
+    }
+
+    fn row(ui: &mut egui::Ui, label: &str, value: &str) {
+        ui.label(label);
+        ui.monospace(value);
+        ui.end_row();
+    }
+
+    // Average time of the scope, with its nested scopes below it
+    fn gpu_timing_ui(ui: &mut egui::Ui, node: &GpuTimingNode) {
+        let text = format!("{}: {:.3} ms", node.label, node.stats.average_ms);
+        if node.children.is_empty() {
+            ui.monospace(text);
+        } else {
+            egui::CollapsingHeader::new(egui::RichText::new(text).monospace())
+                .id_source(&node.path)
+                .default_open(true)
+                .show(ui, |ui| node.children.iter().for_each(|child| Self::gpu_timing_ui(ui, child)));
+        }
+    }
+
+    // Live resources (created and not dropped by the user) of the backend hub, only available for native backends
+    fn resource_rows(ui: &mut egui::Ui, instance: &wgpu::Instance, backend: wgpu::Backend) {
+        let Some(report) = instance.generate_report() else {
+            return;
+        };
+        if !matches!(backend, wgpu::Backend::Vulkan | wgpu::Backend::Metal | wgpu::Backend::Dx12 | wgpu::Backend::Gl) {
+            return;
+        }
+
+        let hub = report.hub_report(backend);
+        for (label, registry) in [
+            ("Buffers", &hub.buffers),
+            ("Textures", &hub.textures),
+            ("Texture views", &hub.texture_views),
+            ("Samplers", &hub.samplers),
+            ("Bind groups", &hub.bind_groups),
+            ("Bind group layouts", &hub.bind_group_layouts),
+            ("Pipeline layouts", &hub.pipeline_layouts),
+            ("Render pipelines", &hub.render_pipelines),
+            ("Compute pipelines", &hub.compute_pipelines),
+            ("Shader modules", &hub.shader_modules),
+            ("Query sets", &hub.query_sets),
+        ] {
+            Self::row(ui, label, &registry.num_kept_from_user.to_string());
+        }
+    }
+}
+
+impl Plugin for DebugPanel {
+    fn gui(&mut self, app_state: &mut AppState) -> Result<()> {
+        self.show(&app_state.egui_renderer.context().clone(), app_state);
+        Ok(())
+    }
+}
+
+fn format_bytes(size: u64) -> String {
+    match size {
+        size if size >= 1 << 30 => format!("{:.2} GiB", size as f64 / (1u64 << 30) as f64),
+        size if size >= 1 << 20 => format!("{:.2} MiB", size as f64 / (1u64 << 20) as f64),
+        size if size >= 1 << 10 => format!("{:.2} KiB", size as f64 / (1u64 << 10) as f64),
+        size => format!("{} B", size),
+    }
+}
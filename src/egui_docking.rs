@@ -0,0 +1,208 @@
+use egui::{Context, Id, Rect, Ui, WidgetText};
+use egui_dock::{DockState, TabViewer};
+
+// Side of the central viewport a panel is docked to when it first appears in the layout
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum DockSide {
+    Left,
+    Right,
+    Bottom,
+}
+
+impl DockSide {
+    pub const ALL: [DockSide; 3] = [DockSide::Left, DockSide::Right, DockSide::Bottom];
+}
+
+// Panel docked as a tab, it can then be moved, stacked with other tabs or split by dragging it
+// `C` is the state given to the panels when drawing them (e.g. the application itself)
+pub trait DockablePanel<C> {
+    // Unique title, used as tab name and to persist the layout
+    fn title(&self) -> &str;
+
+    fn default_side(&self) -> DockSide { DockSide::Left }
+
+    fn ui(&mut self, ui: &mut Ui, context: &mut C);
+}
+
+// Tab of the dock tree: the central 3D viewport or a panel, identified by its title
+#[derive(Clone, PartialEq, Eq, Debug)]
+#[cfg_attr(feature = "persistence", derive(serde::Serialize, serde::Deserialize))]
+pub enum DockTab {
+    Viewport,
+    Panel(String),
+}
+
+// Fraction of the viewport node kept by the viewport when a side is split for new panels
+fn viewport_fraction(side: DockSide) -> f32 {
+    match side {
+        DockSide::Left | DockSide::Right => 0.8,
+        DockSide::Bottom => 0.75,
+    }
+}
+
+// Layout always containing the viewport tab and one tab per panel: tabs of removed panels are dropped and new panels
+// are added in a split of the viewport node on their default side
+fn sync_layout<'a>(layout: &mut DockState<DockTab>, panels: impl Iterator<Item = (&'a str, DockSide)> + Clone) {
+    layout.retain_tabs(|tab| match tab {
+        DockTab::Viewport => true,
+        DockTab::Panel(title) => panels.clone().any(|(panel_title, _)| panel_title == title),
+    });
+    if layout.find_main_surface_tab(&DockTab::Viewport).is_none() {
+        *layout = DockState::new(vec![DockTab::Viewport]);
+    }
+
+    for side in DockSide::ALL {
+        let new_tabs: Vec<DockTab> = panels
+            .clone()
+            .filter(|(title, default_side)| *default_side == side && layout.find_tab(&DockTab::Panel(title.to_string())).is_none())
+            .map(|(title, _)| DockTab::Panel(title.to_string()))
+            .collect();
+        if new_tabs.is_empty() {
+            continue;
+        }
+        let Some((viewport_node, _)) = layout.find_main_surface_tab(&DockTab::Viewport) else {
+            continue;
+        };
+        let surface = layout.main_surface_mut();
+        let fraction = viewport_fraction(side);
+        match side {
+            DockSide::Left => surface.split_left(viewport_node, fraction, new_tabs),
+            DockSide::Right => surface.split_right(viewport_node, fraction, new_tabs),
+            DockSide::Bottom => surface.split_below(viewport_node, fraction, new_tabs),
+        };
+    }
+}
+
+fn viewport_rect_id() -> Id { Id::new("oxyde dock viewport rect") }
+
+// Rect (in points) of the viewport tab of the last shown `Dock`, None if no dock was shown during the last frame or if its
+// viewport tab was hidden behind another tab.
+// `EguiRenderer::available_rect` returns it, so `fit_viewport_to_gui_available_rect` renders into the central node.
+pub fn reserved_viewport_rect(ctx: &Context) -> Option<Rect> {
+    let (frame, rect) = ctx.data(|data| data.get_temp::<(u64, Rect)>(viewport_rect_id()))?;
+    // The frame number is incremented by the end of the frame the rect was reserved in
+    (frame + 1 >= ctx.frame_nr() && rect.is_positive()).then_some(rect)
+}
+
+struct DockTabViewer<'a, C> {
+    panels: &'a mut [Box<dyn DockablePanel<C>>],
+    context: &'a mut C,
+    viewport_rect: Option<Rect>,
+}
+
+impl<C> TabViewer for DockTabViewer<'_, C> {
+    type Tab = DockTab;
+
+    fn title(&mut self, tab: &mut DockTab) -> WidgetText {
+        match tab {
+            DockTab::Viewport => "Viewport".into(),
+            DockTab::Panel(title) => title.as_str().into(),
+        }
+    }
+
+    fn ui(&mut self, ui: &mut Ui, tab: &mut DockTab) {
+        match tab {
+            DockTab::Viewport => {
+                let rect = ui.available_rect_before_wrap();
+                ui.allocate_rect(rect, egui::Sense::hover());
+                self.viewport_rect = Some(rect);
+            },
+            DockTab::Panel(title) => {
+                if let Some(panel) = self.panels.iter_mut().find(|panel| panel.title() == title) {
+                    panel.ui(ui, self.context);
+                }
+            },
+        }
+    }
+
+    // Panels always stay in the layout, the viewport in the main surface
+    fn closeable(&mut self, _tab: &mut DockTab) -> bool { false }
+
+    fn allowed_in_windows(&self, tab: &mut DockTab) -> bool { *tab != DockTab::Viewport }
+
+    // The 3D view is rendered under the viewport tab
+    fn clear_background(&self, tab: &DockTab) -> bool { *tab != DockTab::Viewport }
+
+    fn scroll_bars(&self, tab: &DockTab) -> [bool; 2] {
+        match tab {
+            DockTab::Viewport => [false, false],
+            DockTab::Panel(_) => [true, true],
+        }
+    }
+}
+
+// Docking layer (egui_dock) showing the panels as tabs around a central viewport tab, which can be split, stacked and
+// dragged into floating windows. The rect of the viewport tab is reserved for the 3D view (see `reserved_viewport_rect`).
+// The layout is kept in the egui memory so it follows the egui persistence.
+pub struct Dock<C> {
+    id: Id,
+    panels: Vec<Box<dyn DockablePanel<C>>>,
+    layout: Option<DockState<DockTab>>,
+    style: Option<egui_dock::Style>,
+    viewport_rect: Rect,
+}
+
+impl<C> Dock<C> {
+    pub fn new(id_source: impl std::hash::Hash) -> Self {
+        Self {
+            id: Id::new(id_source),
+            panels: Vec::new(),
+            layout: None,
+            style: None,
+            viewport_rect: Rect::NOTHING,
+        }
+    }
+
+    pub fn with_panel(mut self, panel: impl DockablePanel<C> + 'static) -> Self {
+        self.add_panel(Box::new(panel));
+        self
+    }
+
+    // Style of the dock, derived from the egui style unless set
+    pub fn with_style(mut self, style: egui_dock::Style) -> Self {
+        self.style = Some(style);
+        self
+    }
+
+    pub fn add_panel(&mut self, panel: Box<dyn DockablePanel<C>>) {
+        debug_assert!(self.panels.iter().all(|other| other.title() != panel.title()), "Dock panel titles must be unique");
+        self.panels.push(panel);
+    }
+
+    // Layout of the last `show`, None before the first one
+    pub fn layout_mut(&mut self) -> Option<&mut DockState<DockTab>> { self.layout.as_mut() }
+
+    // Rect (in points) of the viewport tab during the last `show`
+    pub fn viewport_rect(&self) -> Rect { self.viewport_rect }
+
+    // Show the dock over the whole central area, must be called after the other egui panels and instead of a central
+    // panel. Returns the rect of the viewport tab.
+    pub fn show(&mut self, ctx: &Context, context: &mut C) -> Rect {
+        let id = self.id;
+        let mut layout = self
+            .layout
+            .take()
+            .or_else(|| ctx.data_mut(|data| data.get_persisted::<DockState<DockTab>>(id)))
+            .unwrap_or_else(|| DockState::new(vec![DockTab::Viewport]));
+        sync_layout(&mut layout, self.panels.iter().map(|panel| (panel.title(), panel.default_side())));
+
+        let mut tab_viewer = DockTabViewer {
+            panels: &mut self.panels,
+            context,
+            viewport_rect: None,
+        };
+        egui_dock::DockArea::new(&mut layout)
+            .id(id.with("area"))
+            .style(self.style.clone().unwrap_or_else(|| egui_dock::Style::from_egui(&ctx.style())))
+            .show(ctx, &mut tab_viewer);
+
+        // The viewport tab isn't shown while another tab of its node is selected
+        self.viewport_rect = tab_viewer.viewport_rect.unwrap_or(Rect::NOTHING);
+        ctx.data_mut(|data| {
+            data.insert_temp(viewport_rect_id(), (ctx.frame_nr(), self.viewport_rect));
+            data.insert_persisted(id, layout.clone());
+        });
+        self.layout = Some(layout);
+        self.viewport_rect
+    }
+}
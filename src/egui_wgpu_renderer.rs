@@ -1,12 +1,106 @@
-use egui::Context;
-use egui_wgpu::{Renderer, ScreenDescriptor};
+use egui::{Context, PaintCallbackInfo};
+use egui_wgpu::{CallbackResources, Renderer, ScreenDescriptor};
 use egui_winit::{EventResponse, State};
-use wgpu::{CommandEncoder, Device, Queue, TextureFormat, TextureView};
+use wgpu::{CommandEncoder, Device, Queue, RenderPass, TextureFormat, TextureView};
 use winit::{event::WindowEvent, window::Window};
 
+// Custom wgpu rendering inside an egui widget rect, drawn in the egui render pass with the viewport set to the callback rect.
+// Pipelines used in `paint` must match the EguiRenderer output color/depth formats and msaa samples.
+// Resources (pipelines, bind groups...) are stored once in the renderer with `EguiRenderer::insert_paint_callback_resources`.
+pub trait EguiPaintCallback: Send + Sync + 'static {
+    type Resources: Send + Sync + 'static;
+
+    // Called before the egui render pass, e.g. to update buffers or record commands in the egui encoder
+    fn prepare(&self, _device: &Device, _queue: &Queue, _encoder: &mut CommandEncoder, _resources: &mut Self::Resources) {}
+
+    fn paint<'a>(&'a self, info: PaintCallbackInfo, render_pass: &mut RenderPass<'a>, resources: &'a Self::Resources);
+}
+
+struct PaintCallbackAdapter<T>(T);
+
+impl<T: EguiPaintCallback> PaintCallbackAdapter<T> {
+    // The callback is skipped when its resources are missing
+    fn report_missing_resources() {
+        report_warn!(
+            "Missing resources of the egui paint callback {}, they must be given with EguiRenderer::insert_paint_callback_resources",
+            std::any::type_name::<T>()
+        );
+    }
+}
+
+impl<T: EguiPaintCallback> egui_wgpu::CallbackTrait for PaintCallbackAdapter<T> {
+    fn prepare(
+        &self,
+        device: &Device,
+        queue: &Queue,
+        _screen_descriptor: &ScreenDescriptor,
+        encoder: &mut CommandEncoder,
+        callback_resources: &mut CallbackResources,
+    ) -> Vec<wgpu::CommandBuffer> {
+        match callback_resources.get_mut::<T::Resources>() {
+            Some(resources) => self.0.prepare(device, queue, encoder, resources),
+            None => Self::report_missing_resources(),
+        }
+        Vec::new()
+    }
+
+    fn paint<'a>(&'a self, info: PaintCallbackInfo, render_pass: &mut RenderPass<'a>, callback_resources: &'a CallbackResources) {
+        // Already reported by `prepare`
+        if let Some(resources) = callback_resources.get::<T::Resources>() {
+            self.0.paint(info, render_pass, resources);
+        }
+    }
+}
+
+// Shape to add to an egui painter (`ui.painter().add(...)`) to run the callback in the given rect
+pub fn paint_callback<T: EguiPaintCallback>(rect: egui::Rect, callback: T) -> egui::Shape {
+    egui::Shape::Callback(egui_wgpu::Callback::new_paint_callback(rect, PaintCallbackAdapter(callback)))
+}
+
+pub type EguiSetupFn = Box<dyn FnOnce(&Context)>;
+
+// Egui setup applied to the context when the EguiRenderer is created
+#[derive(Default)]
+pub struct EguiConfig {
+    pub fonts: Option<egui::FontDefinitions>,
+    pub style: Option<egui::Style>,
+    // Applied after the style
+    pub visuals: Option<egui::Visuals>,
+    // Override of the window scale factor, applied as an egui zoom factor
+    pub pixels_per_point: Option<f32>,
+    // Custom setup called last (e.g. to install image loaders)
+    pub setup: Option<EguiSetupFn>,
+    // File where the egui memory (windows positions, collapsing headers...) is restored from at startup and saved to on exit
+    #[cfg(feature = "persistence")]
+    pub memory_path: Option<std::path::PathBuf>,
+}
+
+impl EguiConfig {
+    // The memory path is not used here as it is kept by the EguiRenderer to save the memory
+    pub fn apply(self, context: &Context, window: &Window) {
+        if let Some(fonts) = self.fonts {
+            context.set_fonts(fonts);
+        }
+        if let Some(style) = self.style {
+            context.set_style(style);
+        }
+        if let Some(visuals) = self.visuals {
+            context.set_visuals(visuals);
+        }
+        if let Some(pixels_per_point) = self.pixels_per_point {
+            context.set_zoom_factor(pixels_per_point / window.scale_factor() as f32);
+        }
+        if let Some(setup) = self.setup {
+            setup(context);
+        }
+    }
+}
+
 pub struct EguiRenderer {
     state: State,
     renderer: Renderer,
+    #[cfg(feature = "persistence")]
+    memory_path: Option<std::path::PathBuf>,
 }
 
 impl EguiRenderer {
@@ -16,8 +110,31 @@ impl EguiRenderer {
         output_depth_format: Option<TextureFormat>,
         msaa_samples: u32,
         window: &Window,
+    ) -> EguiRenderer {
+        Self::new_with_config(device, output_color_format, output_depth_format, msaa_samples, window, EguiConfig::default())
+    }
+
+    pub fn new_with_config(
+        device: &Device,
+        output_color_format: TextureFormat,
+        output_depth_format: Option<TextureFormat>,
+        msaa_samples: u32,
+        window: &Window,
+        config: EguiConfig,
     ) -> EguiRenderer {
         let egui_context = Context::default();
+
+        // Memory is restored first so the config takes precedence over the persisted options
+        #[cfg(feature = "persistence")]
+        let memory_path = config.memory_path.clone();
+        #[cfg(feature = "persistence")]
+        if let Some(memory_path) = &memory_path {
+            if let Err(error) = load_memory(&egui_context, memory_path) {
+                report_warn!("Failed to restore egui memory from {}: {}", memory_path.display(), error);
+            }
+        }
+
+        config.apply(&egui_context, window);
         let viewport_id = egui_context.viewport_id();
         let egui_state = egui_winit::State::new(egui_context, viewport_id, &window, Some(window.scale_factor() as f32), None);
         let egui_renderer = Renderer::new(device, output_color_format, output_depth_format, msaa_samples);
@@ -25,6 +142,17 @@ impl EguiRenderer {
         EguiRenderer {
             state: egui_state,
             renderer: egui_renderer,
+            #[cfg(feature = "persistence")]
+            memory_path,
+        }
+    }
+
+    // Save the egui memory to the memory path of the config if any
+    #[cfg(feature = "persistence")]
+    pub fn save_memory(&self) -> anyhow::Result<()> {
+        match &self.memory_path {
+            Some(memory_path) => save_memory(self.context(), memory_path),
+            None => Ok(()),
         }
     }
 
@@ -32,7 +160,29 @@ impl EguiRenderer {
 
     pub fn context(&self) -> &Context { self.state.egui_ctx() }
 
+    // Clipboard used by egui (shared so the app doesn't need another clipboard)
+    pub fn clipboard_text(&mut self) -> Option<String> { self.state.clipboard_text() }
+
+    pub fn set_clipboard_text(&mut self, text: String) { self.state.set_clipboard_text(text) }
+
+    // Rect (in points) left after the egui panels of the current frame, typically used as the 3D viewport. The viewport
+    // tab of a `Dock` when one is shown.
+    pub fn available_rect(&self) -> egui::Rect {
+        crate::egui_docking::reserved_viewport_rect(self.context()).unwrap_or_else(|| self.context().available_rect())
+    }
+
+    // Store the resources used by the paint callbacks of type T, replacing the previous ones
+    pub fn insert_paint_callback_resources<T: EguiPaintCallback>(&mut self, resources: T::Resources) {
+        self.renderer.callback_resources.insert(resources);
+    }
+
+    pub fn paint_callback_resources_mut<T: EguiPaintCallback>(&mut self) -> Option<&mut T::Resources> {
+        self.renderer.callback_resources.get_mut::<T::Resources>()
+    }
+
+    // Returns the command buffers of the paint callbacks, to submit before the encoder (see `draw_output`)
     #[allow(clippy::too_many_arguments)]
+    #[must_use]
     pub fn draw_ui(
         &mut self,
         device: &Device,
@@ -42,13 +192,13 @@ impl EguiRenderer {
         window_surface_view: &TextureView,
         screen_descriptor: ScreenDescriptor,
         run_ui: impl FnOnce(&Context),
-    ) {
+    ) -> Vec<wgpu::CommandBuffer> {
         let raw_input = self.state.take_egui_input(window);
         let full_output = self.context().run(raw_input, |ui| {
             run_ui(ui);
         });
 
-        self.draw_output(full_output, device, queue, encoder, window, window_surface_view, screen_descriptor);
+        self.draw_output(full_output, device, queue, encoder, window, window_surface_view, screen_descriptor)
     }
 
     pub fn begin_frame(&mut self, window: &Window) {
@@ -58,7 +208,10 @@ impl EguiRenderer {
 
     pub fn end_frame(&mut self) -> egui::FullOutput { self.context().end_frame() }
 
+    // Record the egui render pass in the encoder. Returns the command buffers of the paint callbacks, they must be
+    // submitted along with the encoder and before it.
     #[allow(clippy::too_many_arguments)]
+    #[must_use]
     pub fn draw_output(
         &mut self,
         full_output: egui::FullOutput,
@@ -68,14 +221,17 @@ impl EguiRenderer {
         window: &Window,
         window_surface_view: &TextureView,
         screen_descriptor: ScreenDescriptor,
-    ) {
+    ) -> Vec<wgpu::CommandBuffer> {
         self.state.handle_platform_output(window, full_output.platform_output);
 
-        let tris = self.context().tessellate(full_output.shapes, full_output.pixels_per_point);
+        let tris = {
+            trace_scope!("egui_tessellate");
+            self.context().tessellate(full_output.shapes, full_output.pixels_per_point)
+        };
         for (id, image_delta) in &full_output.textures_delta.set {
             self.renderer.update_texture(device, queue, *id, image_delta);
         }
-        self.renderer.update_buffers(device, queue, encoder, &tris, &screen_descriptor);
+        let callback_command_buffers = self.renderer.update_buffers(device, queue, encoder, &tris, &screen_descriptor);
         {
             let mut render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
                 label: Some("egui main render pass"),
@@ -94,5 +250,27 @@ impl EguiRenderer {
         for x in &full_output.textures_delta.free {
             self.renderer.free_texture(x)
         }
+        callback_command_buffers
+    }
+}
+
+// Restore the egui memory from a ron file, a missing file is not an error (first run)
+#[cfg(feature = "persistence")]
+pub fn load_memory(context: &Context, path: &std::path::Path) -> anyhow::Result<()> {
+    if !path.exists() {
+        return Ok(());
+    }
+    let memory: egui::Memory = ron::from_str(&std::fs::read_to_string(path)?)?;
+    context.memory_mut(|context_memory| *context_memory = memory);
+    Ok(())
+}
+
+#[cfg(feature = "persistence")]
+pub fn save_memory(context: &Context, path: &std::path::Path) -> anyhow::Result<()> {
+    let memory = context.memory(ron::ser::to_string)?;
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)?;
     }
+    std::fs::write(path, memory)?;
+    Ok(())
 }
@@ -0,0 +1,87 @@
+use std::{collections::VecDeque, time::Duration};
+
+// Statistics over the last frame times (ring buffer) with worst frame and missed target tracking since the last reset
+pub struct FrameStats {
+    capacity: usize,
+    frame_times: VecDeque<Duration>,
+    worst_frame_time: Duration,
+    missed_target_count: u64,
+    frame_count: u64,
+}
+
+impl Default for FrameStats {
+    fn default() -> Self { Self::new(240) }
+}
+
+impl FrameStats {
+    // capacity is the number of frames the average and percentiles are computed on
+    pub fn new(capacity: usize) -> Self {
+        assert!(capacity > 0, "FrameStats capacity must be greater than 0");
+        Self {
+            capacity,
+            frame_times: VecDeque::with_capacity(capacity),
+            worst_frame_time: Duration::ZERO,
+            missed_target_count: 0,
+            frame_count: 0,
+        }
+    }
+
+    pub fn record(&mut self, frame_time: Duration, target_frame_time: Option<Duration>) {
+        if self.frame_times.len() == self.capacity {
+            self.frame_times.pop_front();
+        }
+        self.frame_times.push_back(frame_time);
+        self.worst_frame_time = self.worst_frame_time.max(frame_time);
+        if target_frame_time.is_some_and(|target_frame_time| frame_time > target_frame_time) {
+            self.missed_target_count += 1;
+        }
+        self.frame_count += 1;
+    }
+
+    pub fn reset(&mut self) {
+        self.frame_times.clear();
+        self.worst_frame_time = Duration::ZERO;
+        self.missed_target_count = 0;
+        self.frame_count = 0;
+    }
+
+    // Frame times of the ring buffer, oldest first
+    pub fn frame_times(&self) -> impl Iterator<Item = Duration> + '_ { self.frame_times.iter().copied() }
+
+    pub fn last(&self) -> Option<Duration> { self.frame_times.back().copied() }
+
+    pub fn average(&self) -> Option<Duration> {
+        if self.frame_times.is_empty() {
+            return None;
+        }
+        Some(self.frame_times.iter().sum::<Duration>() / self.frame_times.len() as u32)
+    }
+
+    pub fn average_fps(&self) -> Option<f64> { self.average().map(|average| 1.0 / average.as_secs_f64().max(f64::EPSILON)) }
+
+    // Frame time under which `percentile` (in [0, 100]) of the frames of the ring buffer are (nearest rank)
+    pub fn percentile(&self, percentile: f64) -> Option<Duration> {
+        if self.frame_times.is_empty() {
+            return None;
+        }
+        let mut sorted_frame_times: Vec<Duration> = self.frame_times.iter().copied().collect();
+        sorted_frame_times.sort_unstable();
+        let rank = ((percentile.clamp(0.0, 100.0) / 100.0) * sorted_frame_times.len() as f64).ceil() as usize;
+        Some(sorted_frame_times[rank.clamp(1, sorted_frame_times.len()) - 1])
+    }
+
+    pub fn p95(&self) -> Option<Duration> { self.percentile(95.0) }
+
+    pub fn p99(&self) -> Option<Duration> { self.percentile(99.0) }
+
+    // Worst frame time since the last reset
+    #[inline]
+    pub fn worst(&self) -> Duration { self.worst_frame_time }
+
+    // Frames longer than their target since the last reset
+    #[inline]
+    pub fn missed_target_count(&self) -> u64 { self.missed_target_count }
+
+    #[inline]
+    pub fn frame_count(&self) -> u64 { self.frame_count }
+}
@@ -0,0 +1,238 @@
+use std::time::{Duration, Instant};
+
+#[cfg(feature = "egui")]
+use crate::wgpu_utils::{GpuTimer, GpuTimingNode};
+
+// Texture a pass renders to, see `FrameTimeline::add_attachment`
+#[derive(Clone, Debug, PartialEq)]
+pub struct PassAttachment {
+    pub label: String,
+    pub format: wgpu::TextureFormat,
+    pub width: u32,
+    pub height: u32,
+}
+
+// Pass recorded in the frame encoder, in the order the passes were recorded (parents before the passes nested in them)
+#[derive(Clone, Debug)]
+pub struct PassRecord {
+    pub name: String,
+    // Names of the parent passes and its own joined with '/', the path of the GPU scope of the pass (see `GpuTimer::stats`)
+    pub path: String,
+    pub depth: usize,
+    // CPU time from the start of the frame to the start of the pass and CPU time recording the pass
+    pub cpu_start: Duration,
+    pub cpu_time: Duration,
+    pub attachments: Vec<PassAttachment>,
+}
+
+// Passes of the last rendered frame: the render layers (see `RenderLayers`) and the passes the app records in them with
+// `begin_pass` / `end_pass`, with their CPU recording times. The GPU times are the ones of the `GpuTimer` scopes of the
+// same paths, displayed along the CPU times by `ui`.
+#[derive(Default)]
+pub struct FrameTimeline {
+    // Keep the passes of the last frame rather than updating them each frame, to inspect them
+    pub paused: bool,
+    frame_start: Option<Instant>,
+    recording: Vec<PassRecord>,
+    open_passes: Vec<usize>,
+    passes: Vec<PassRecord>,
+    frame_cpu_time: Duration,
+}
+
+impl FrameTimeline {
+    pub fn new() -> Self { Self::default() }
+
+    pub fn begin_frame(&mut self) {
+        self.frame_start = Some(Instant::now());
+        self.recording.clear();
+        self.open_passes.clear();
+    }
+
+    // Start a pass nested in the open passes, ignored out of a frame
+    pub fn begin_pass(&mut self, name: &str) {
+        let Some(frame_start) = self.frame_start else {
+            return;
+        };
+        let path = match self.open_passes.last() {
+            Some(&parent_index) => format!("{}/{}", self.recording[parent_index].path, name),
+            None => name.to_string(),
+        };
+        self.recording.push(PassRecord {
+            name: name.to_string(),
+            path,
+            depth: self.open_passes.len(),
+            cpu_start: frame_start.elapsed(),
+            cpu_time: Duration::ZERO,
+            attachments: Vec::new(),
+        });
+        self.open_passes.push(self.recording.len() - 1);
+    }
+
+    // End the last open pass
+    pub fn end_pass(&mut self) {
+        if let (Some(frame_start), Some(index)) = (self.frame_start, self.open_passes.pop()) {
+            let pass = &mut self.recording[index];
+            pass.cpu_time = frame_start.elapsed().saturating_sub(pass.cpu_start);
+        }
+    }
+
+    // Texture the last open pass renders to
+    pub fn add_attachment(&mut self, label: &str, format: wgpu::TextureFormat, width: u32, height: u32) {
+        if let Some(&index) = self.open_passes.last() {
+            self.recording[index].attachments.push(PassAttachment {
+                label: label.to_string(),
+                format,
+                width,
+                height,
+            });
+        }
+    }
+
+    // Close the passes left open and replace the passes of the last frame when not paused
+    pub fn end_frame(&mut self) {
+        while !self.open_passes.is_empty() {
+            self.end_pass();
+        }
+        let Some(frame_start) = self.frame_start.take() else {
+            return;
+        };
+        if !self.paused {
+            std::mem::swap(&mut self.passes, &mut self.recording);
+            self.frame_cpu_time = frame_start.elapsed();
+        }
+        self.recording.clear();
+    }
+
+    // Passes of the last frame, in recording order
+    pub fn passes(&self) -> &[PassRecord] { &self.passes }
+
+    // CPU time from the start to the end of the last frame recording
+    pub fn frame_cpu_time(&self) -> Duration { self.frame_cpu_time }
+}
+
+#[cfg(feature = "egui")]
+impl FrameTimeline {
+    const CPU_COLOR: egui::Color32 = egui::Color32::from_rgb(90, 160, 230);
+    const GPU_COLOR: egui::Color32 = egui::Color32::from_rgb(230, 140, 60);
+    const BAR_WIDTH: f32 = 160.0;
+
+    // Passes in execution order with their CPU and GPU average times, and their CPU and GPU spans on the frame timeline.
+    // The GPU spans are laid out one after the other as the GPU executes the passes in order. Hovering a pass shows its
+    // attachments.
+    pub fn ui(&mut self, ui: &mut egui::Ui, gpu_timer: &GpuTimer) {
+        let gpu_tree = gpu_timer.tree();
+        let gpu_ms = |path: &str| gpu_timer.stats(path).map(|stats| stats.average_ms);
+        let frame_cpu_ms = self.frame_cpu_time.as_secs_f64() * 1000.0;
+        let frame_gpu_ms: f64 = self
+            .passes
+            .iter()
+            .filter(|pass| pass.depth == 0)
+            .filter_map(|pass| gpu_ms(&pass.path))
+            .sum();
+        let scale_ms = frame_cpu_ms.max(frame_gpu_ms).max(f64::EPSILON);
+
+        ui.horizontal(|ui| {
+            ui.checkbox(&mut self.paused, "Pause");
+            ui.colored_label(Self::CPU_COLOR, format!("CPU {:.3} ms", frame_cpu_ms));
+            ui.colored_label(Self::GPU_COLOR, format!("GPU {:.3} ms", frame_gpu_ms));
+        });
+        if !gpu_timer.is_supported() {
+            ui.label("Timestamp queries not supported by the device, no GPU times");
+        }
+
+        egui::Grid::new("frame_timeline").num_columns(4).striped(true).show(ui, |ui| {
+            ui.strong("Pass");
+            ui.strong("CPU");
+            ui.strong("GPU");
+            ui.strong("Timeline");
+            ui.end_row();
+
+            // GPU start of the next pass at each depth
+            let mut gpu_cursors = vec![0.0];
+            for pass in &self.passes {
+                let pass_gpu_ms = gpu_ms(&pass.path);
+                gpu_cursors.truncate(pass.depth + 1);
+                let gpu_start_ms = gpu_cursors[pass.depth];
+                gpu_cursors[pass.depth] += pass_gpu_ms.unwrap_or(0.0);
+                gpu_cursors.push(gpu_start_ms);
+
+                let cpu_span = (pass.cpu_start.as_secs_f64() * 1000.0, pass.cpu_time.as_secs_f64() * 1000.0);
+                let gpu_span = pass_gpu_ms.map(|pass_gpu_ms| (gpu_start_ms, pass_gpu_ms));
+                let name_response = Self::pass_row(ui, pass.depth, &pass.name, Some(cpu_span), gpu_span, scale_ms);
+                if !pass.attachments.is_empty() {
+                    name_response.on_hover_ui(|ui| {
+                        for attachment in &pass.attachments {
+                            ui.monospace(format!(
+                                "{}: {:?} {} x {}",
+                                attachment.label, attachment.format, attachment.width, attachment.height
+                            ));
+                        }
+                    });
+                }
+
+                // GPU scopes of the pass without a pass of their own, e.g. `AppState::gpu_scope`
+                if let Some(node) = find_node(&gpu_tree, &pass.path) {
+                    let mut child_start_ms = gpu_start_ms;
+                    for child in &node.children {
+                        if !self.passes.iter().any(|other| other.path == child.path) {
+                            Self::gpu_scope_rows(ui, pass.depth + 1, child, child_start_ms, scale_ms);
+                        }
+                        child_start_ms += child.stats.average_ms;
+                    }
+                }
+            }
+        });
+    }
+
+    fn gpu_scope_rows(ui: &mut egui::Ui, depth: usize, node: &GpuTimingNode, start_ms: f64, scale_ms: f64) {
+        Self::pass_row(ui, depth, &node.label, None, Some((start_ms, node.stats.average_ms)), scale_ms);
+        let mut child_start_ms = start_ms;
+        for child in &node.children {
+            Self::gpu_scope_rows(ui, depth + 1, child, child_start_ms, scale_ms);
+            child_start_ms += child.stats.average_ms;
+        }
+    }
+
+    // Row of the grid with the `(start, duration)` spans in milliseconds, returns the response of the name
+    fn pass_row(
+        ui: &mut egui::Ui,
+        depth: usize,
+        name: &str,
+        cpu_span: Option<(f64, f64)>,
+        gpu_span: Option<(f64, f64)>,
+        scale_ms: f64,
+    ) -> egui::Response {
+        let name_response = ui.label(format!("{}{}", "  ".repeat(depth), name));
+        let format_ms = |span: Option<(f64, f64)>| span.map_or("-".to_string(), |(_, duration_ms)| format!("{:.3} ms", duration_ms));
+        ui.monospace(format_ms(cpu_span));
+        ui.monospace(format_ms(gpu_span));
+
+        let row_height = ui.spacing().interact_size.y;
+        let (rect, _) = ui.allocate_exact_size(egui::vec2(Self::BAR_WIDTH, row_height), egui::Sense::hover());
+        let painter = ui.painter_at(rect);
+        painter.rect_filled(rect, 0.0, ui.visuals().extreme_bg_color);
+        let half_height = rect.height() * 0.5;
+        for (span, color, top) in [
+            (cpu_span, Self::CPU_COLOR, rect.top()),
+            (gpu_span, Self::GPU_COLOR, rect.top() + half_height),
+        ] {
+            if let Some((start_ms, duration_ms)) = span {
+                let left = rect.left() + (start_ms / scale_ms) as f32 * rect.width();
+                // At least a pixel wide so that the short passes stay visible
+                let width = ((duration_ms / scale_ms) as f32 * rect.width()).max(1.0);
+                painter.rect_filled(egui::Rect::from_min_size(egui::pos2(left, top), egui::vec2(width, half_height - 1.0)), 0.0, color);
+            }
+        }
+        ui.end_row();
+        name_response
+    }
+}
+
+#[cfg(feature = "egui")]
+fn find_node<'a>(nodes: &'a [GpuTimingNode], path: &str) -> Option<&'a GpuTimingNode> {
+    nodes.iter().find_map(|node| match node.path == path {
+        true => Some(node),
+        false if path.starts_with(&format!("{}/", node.path)) => find_node(&node.children, path),
+        false => None,
+    })
+}
@@ -0,0 +1,299 @@
+use glam::{Mat4, Quat, Vec2, Vec3};
+
+use crate::{
+    input::MouseState,
+    math::{Plane, Ray, Viewport},
+    wgpu_utils::DepthConvention,
+};
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum GizmoMode {
+    #[default]
+    Translate,
+    Rotate,
+    Scale,
+}
+
+// Axes the gizmo edits along, the scale is always along the local axes
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum GizmoOrientation {
+    #[default]
+    Global,
+    Local,
+}
+
+// Camera the gizmo is seen through, with the viewport in physical pixels like the `MouseState` position
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct GizmoCamera {
+    pub view: Mat4,
+    pub projection: Mat4,
+    pub viewport: Viewport,
+    pub depth_convention: DepthConvention,
+}
+
+impl GizmoCamera {
+    pub fn new(view: Mat4, projection: Mat4, viewport: Viewport) -> Self {
+        Self {
+            view,
+            projection,
+            viewport,
+            depth_convention: DepthConvention::Standard,
+        }
+    }
+
+    pub fn with_depth_convention(mut self, depth_convention: DepthConvention) -> Self {
+        self.depth_convention = depth_convention;
+        self
+    }
+
+    fn view_proj(&self) -> Mat4 { self.projection * self.view }
+
+    // Position in pixels, None behind the camera
+    fn world_to_screen(&self, point: Vec3) -> Option<Vec2> {
+        let clip = self.view_proj() * point.extend(1.0);
+        if clip.w <= 0.0 {
+            return None;
+        }
+        Some(self.viewport.from_ndc(Vec2::new(clip.x, clip.y) / clip.w))
+    }
+
+    // World length covering `pixels` on screen at the depth of `point`, to keep the gizmo the same size on screen
+    fn pixels_to_world(&self, point: Vec3, pixels: f32) -> f32 {
+        let w = (self.view_proj() * point.extend(1.0)).w.abs().max(f32::EPSILON);
+        pixels * 2.0 * w / (self.projection.y_axis.y.abs() * self.viewport.size.y)
+    }
+}
+
+#[derive(Clone, Copy, Debug)]
+struct GizmoDrag {
+    axis: usize,
+    start_scale: Vec3,
+    start_rotation: Quat,
+    start_translation: Vec3,
+    // Point of the drag plane under the cursor when the drag started
+    start_point: Vec3,
+    plane: Plane,
+}
+
+// Translate, rotate and scale manipulator of a transform, drawn with an egui painter over the 3D view (e.g.
+// `egui::Context::layer_painter` on a foreground layer) and controlled with the left mouse button. Call `update` each
+// frame and skip the camera controls while the gizmo `is_hovered` or `is_active`.
+pub struct Gizmo {
+    pub mode: GizmoMode,
+    pub orientation: GizmoOrientation,
+    // Length of the axes on screen, in pixels
+    pub size: f32,
+    hovered_axis: Option<usize>,
+    drag: Option<GizmoDrag>,
+    was_left_clicked: bool,
+}
+
+impl Default for Gizmo {
+    fn default() -> Self {
+        Self {
+            mode: GizmoMode::default(),
+            orientation: GizmoOrientation::default(),
+            size: 100.0,
+            hovered_axis: None,
+            drag: None,
+            was_left_clicked: false,
+        }
+    }
+}
+
+const AXIS_COLORS: [egui::Color32; 3] = [
+    egui::Color32::from_rgb(230, 60, 60),
+    egui::Color32::from_rgb(60, 200, 60),
+    egui::Color32::from_rgb(60, 110, 240),
+];
+const HIGHLIGHT_COLOR: egui::Color32 = egui::Color32::from_rgb(250, 210, 50);
+// Distance in pixels from the cursor to an axis for it to be picked
+const PICK_DISTANCE: f32 = 8.0;
+const CIRCLE_SEGMENTS: usize = 64;
+
+fn distance_to_segment(point: Vec2, start: Vec2, end: Vec2) -> f32 {
+    let segment = end - start;
+    let t = ((point - start).dot(segment) / segment.length_squared().max(f32::EPSILON)).clamp(0.0, 1.0);
+    point.distance(start + segment * t)
+}
+
+impl Gizmo {
+    pub fn new(mode: GizmoMode) -> Self { Self { mode, ..Default::default() } }
+
+    // Cursor over an axis of the gizmo
+    pub fn is_hovered(&self) -> bool { self.hovered_axis.is_some() }
+
+    // Transform being dragged
+    pub fn is_active(&self) -> bool { self.drag.is_some() }
+
+    // Handle the mouse and draw the gizmo of `transform`. Returns the edited transform while it is dragged.
+    pub fn update(&mut self, painter: &egui::Painter, mouse: &MouseState, camera: &GizmoCamera, transform: Mat4) -> Option<Mat4> {
+        let edited_transform = self.interact(mouse, camera, transform);
+        self.draw(painter, camera, edited_transform.unwrap_or(transform));
+        edited_transform
+    }
+
+    // Handle the mouse without drawing, returns the edited transform while it is dragged
+    pub fn interact(&mut self, mouse: &MouseState, camera: &GizmoCamera, transform: Mat4) -> Option<Mat4> {
+        let pressed = mouse.is_left_clicked && !self.was_left_clicked;
+        self.was_left_clicked = mouse.is_left_clicked;
+        let ray = Ray::from_screen_with_convention(mouse.position, &camera.viewport, &camera.view_proj(), camera.depth_convention);
+
+        if !mouse.is_left_clicked {
+            self.drag = None;
+        }
+
+        let Some(drag) = self.drag else {
+            self.hovered_axis = self.pick_axis(mouse.position, camera, transform);
+            if pressed {
+                if let Some(axis) = self.hovered_axis {
+                    self.drag = self.start_drag(axis, &ray, transform);
+                }
+            }
+            return None;
+        };
+
+        let distance = ray.intersect_plane(&drag.plane)?;
+        let point = ray.at(distance);
+        let axis_direction = self.axis_directions(drag.start_rotation)[drag.axis];
+        let (mut scale, mut rotation, mut translation) = (drag.start_scale, drag.start_rotation, drag.start_translation);
+        match self.mode {
+            GizmoMode::Translate => translation += axis_direction * (point - drag.start_point).dot(axis_direction),
+            GizmoMode::Rotate => {
+                let from = (drag.start_point - drag.start_translation).normalize_or_zero();
+                let to = (point - drag.start_translation).normalize_or_zero();
+                let angle = from.cross(to).dot(axis_direction).atan2(from.dot(to));
+                rotation = Quat::from_axis_angle(axis_direction, angle) * drag.start_rotation;
+            },
+            GizmoMode::Scale => {
+                let start_distance = (drag.start_point - drag.start_translation).dot(axis_direction);
+                let distance = (point - drag.start_translation).dot(axis_direction);
+                if start_distance.abs() > f32::EPSILON {
+                    scale[drag.axis] *= distance / start_distance;
+                }
+            },
+        }
+        Some(Mat4::from_scale_rotation_translation(scale, rotation, translation))
+    }
+
+    // Unit axes the gizmo edits along
+    fn axis_directions(&self, rotation: Quat) -> [Vec3; 3] {
+        match (self.mode, self.orientation) {
+            (GizmoMode::Scale, _) | (_, GizmoOrientation::Local) => [rotation * Vec3::X, rotation * Vec3::Y, rotation * Vec3::Z],
+            (_, GizmoOrientation::Global) => [Vec3::X, Vec3::Y, Vec3::Z],
+        }
+    }
+
+    fn start_drag(&self, axis: usize, ray: &Ray, transform: Mat4) -> Option<GizmoDrag> {
+        let (scale, rotation, translation) = transform.to_scale_rotation_translation();
+        let axis_direction = self.axis_directions(rotation)[axis];
+        let normal = match self.mode {
+            // Plane containing the axis facing the camera the most
+            GizmoMode::Translate | GizmoMode::Scale => axis_direction.cross(ray.direction).cross(axis_direction).normalize_or_zero(),
+            GizmoMode::Rotate => axis_direction,
+        };
+        if normal == Vec3::ZERO {
+            return None;
+        }
+        let plane = Plane {
+            normal,
+            distance: -normal.dot(translation),
+        };
+        let distance = ray.intersect_plane(&plane)?;
+        Some(GizmoDrag {
+            axis,
+            start_scale: scale,
+            start_rotation: rotation,
+            start_translation: translation,
+            start_point: ray.at(distance),
+            plane,
+        })
+    }
+
+    // Closest axis to the cursor, on screen
+    fn pick_axis(&self, cursor: Vec2, camera: &GizmoCamera, transform: Mat4) -> Option<usize> {
+        let (_, rotation, origin) = transform.to_scale_rotation_translation();
+        let length = camera.pixels_to_world(origin, self.size);
+        let mut closest: Option<(usize, f32)> = None;
+        for (axis, direction) in self.axis_directions(rotation).into_iter().enumerate() {
+            let distance = self
+                .axis_polyline(origin, direction, length)
+                .iter()
+                .map(|point| camera.world_to_screen(*point))
+                .collect::<Option<Vec<_>>>()
+                .and_then(|points| {
+                    points
+                        .windows(2)
+                        .map(|segment| distance_to_segment(cursor, segment[0], segment[1]))
+                        .reduce(f32::min)
+                });
+            if let Some(distance) = distance.filter(|distance| *distance < PICK_DISTANCE) {
+                if closest.is_none_or(|(_, closest_distance)| distance < closest_distance) {
+                    closest = Some((axis, distance));
+                }
+            }
+        }
+        closest.map(|(axis, _)| axis)
+    }
+
+    // Line of the axis, or circle around it when rotating
+    fn axis_polyline(&self, origin: Vec3, direction: Vec3, length: f32) -> Vec<Vec3> {
+        match self.mode {
+            GizmoMode::Translate | GizmoMode::Scale => vec![origin, origin + direction * length],
+            GizmoMode::Rotate => {
+                let tangent = direction.any_orthonormal_vector();
+                let bitangent = direction.cross(tangent);
+                (0..=CIRCLE_SEGMENTS)
+                    .map(|index| {
+                        let angle = index as f32 / CIRCLE_SEGMENTS as f32 * std::f32::consts::TAU;
+                        origin + (tangent * angle.cos() + bitangent * angle.sin()) * length
+                    })
+                    .collect()
+            },
+        }
+    }
+
+    pub fn draw(&self, painter: &egui::Painter, camera: &GizmoCamera, transform: Mat4) {
+        let (_, rotation, origin) = transform.to_scale_rotation_translation();
+        let length = camera.pixels_to_world(origin, self.size);
+        // The camera works in physical pixels, egui in points
+        let pixels_per_point = painter.ctx().pixels_per_point();
+        let to_pos = |point: Vec2| egui::pos2(point.x / pixels_per_point, point.y / pixels_per_point);
+        let highlighted_axis = self.drag.map(|drag| drag.axis).or(self.hovered_axis);
+
+        for (axis, direction) in self.axis_directions(rotation).into_iter().enumerate() {
+            let color = if highlighted_axis == Some(axis) {
+                HIGHLIGHT_COLOR
+            } else {
+                AXIS_COLORS[axis]
+            };
+            let Some(points) = self
+                .axis_polyline(origin, direction, length)
+                .iter()
+                .map(|point| camera.world_to_screen(*point).map(to_pos))
+                .collect::<Option<Vec<_>>>()
+            else {
+                continue;
+            };
+            painter.add(egui::Shape::line(points.clone(), egui::Stroke::new(2.5, color)));
+            let tip = points[points.len() - 1];
+            match self.mode {
+                GizmoMode::Translate => {
+                    let back = (points[0] - tip).normalized() * 12.0;
+                    let side = back.rot90() * 0.4;
+                    painter.add(egui::Shape::convex_polygon(
+                        vec![tip, tip + back + side, tip + back - side],
+                        color,
+                        egui::Stroke::NONE,
+                    ));
+                },
+                GizmoMode::Scale => {
+                    painter.rect_filled(egui::Rect::from_center_size(tip, egui::vec2(8.0, 8.0)), 0.0, color);
+                },
+                GizmoMode::Rotate => {},
+            }
+        }
+    }
+}
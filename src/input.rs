@@ -5,15 +5,30 @@ use winit::{
     keyboard,
 };
 
+use crate::math::Viewport;
+
 pub struct InputsState {
     pub keycode_states: [bool; 1024],
     pub mouse: MouseState,
+    // Egui input capture, updated by the application for each window event
+    #[cfg(feature = "egui")]
+    pub egui_consumed_event: bool,
+    #[cfg(feature = "egui")]
+    pub egui_wants_pointer_input: bool,
+    #[cfg(feature = "egui")]
+    pub egui_wants_keyboard_input: bool,
 }
 impl Default for InputsState {
     fn default() -> Self {
         Self {
             keycode_states: [false; 1024],
             mouse: MouseState::default(),
+            #[cfg(feature = "egui")]
+            egui_consumed_event: false,
+            #[cfg(feature = "egui")]
+            egui_wants_pointer_input: false,
+            #[cfg(feature = "egui")]
+            egui_wants_keyboard_input: false,
         }
     }
 }
@@ -24,6 +39,14 @@ pub trait WinitEventHandler {
 
 impl InputsState {
     pub fn is_key_pressed(&self, keycode: keyboard::KeyCode) -> bool { self.keycode_states[keycode as usize] }
+
+    // Pointer used by egui (hovering or dragging a widget, or last event consumed by egui)
+    #[cfg(feature = "egui")]
+    pub fn is_pointer_captured_by_egui(&self) -> bool { self.egui_consumed_event || self.egui_wants_pointer_input }
+
+    // Keyboard used by egui (e.g. focused text edit, or last event consumed by egui)
+    #[cfg(feature = "egui")]
+    pub fn is_keyboard_captured_by_egui(&self) -> bool { self.egui_consumed_event || self.egui_wants_keyboard_input }
 }
 
 impl WinitEventHandler for InputsState {
@@ -110,10 +133,14 @@ impl WinitEventHandler for MouseState {
     }
 }
 
+// Window size and frame timing. Positions in the window are in physical pixels (like `MouseState::position` and the
+// render passes), logical points are pixels divided by the scale factor of the window.
 pub struct SystemState {
     pub window_dimensions: PhysicalSize<u32>,
+    pub scale_factor: f64,
     pub delta_time: f64,
     last_frame: Instant,
+    // Set when the window is closed or `AppState::request_exit` is called, reset once handled by the loop
     pub exit_requested: bool,
 }
 
@@ -122,6 +149,7 @@ impl SystemState {
         Self {
             last_frame: Instant::now(),
             window_dimensions,
+            scale_factor: 1.0,
             delta_time: 0.00,
             exit_requested: false,
         }
@@ -133,7 +161,24 @@ impl SystemState {
         width as f32 / height as f32
     }
 
+    pub fn with_scale_factor(mut self, scale_factor: f64) -> Self {
+        self.scale_factor = scale_factor;
+        self
+    }
+
     pub fn window_center(&self) -> glam::Vec2 { glam::vec2(self.window_dimensions.width as f32 / 2.0, self.window_dimensions.height as f32 / 2.0) }
+
+    // Whole window, in physical pixels
+    pub fn window_viewport(&self) -> Viewport { Viewport::from_size(self.window_dimensions.width, self.window_dimensions.height) }
+
+    pub fn physical_to_logical(&self, position: glam::Vec2) -> glam::Vec2 { position / self.scale_factor as f32 }
+
+    pub fn logical_to_physical(&self, position: glam::Vec2) -> glam::Vec2 { position * self.scale_factor as f32 }
+
+    // Normalized device coordinates (x right and y up in [-1, 1]) of a position in pixels of the window
+    pub fn window_to_ndc(&self, position: glam::Vec2) -> glam::Vec2 { self.window_viewport().to_ndc(position) }
+
+    pub fn ndc_to_window(&self, ndc: glam::Vec2) -> glam::Vec2 { self.window_viewport().from_ndc(ndc) }
 }
 
 impl WinitEventHandler for SystemState {
@@ -148,6 +193,7 @@ impl WinitEventHandler for SystemState {
                 WindowEvent::Resized(dimensions) => {
                     self.window_dimensions = dimensions;
                 },
+                WindowEvent::ScaleFactorChanged { scale_factor, .. } => self.scale_factor = scale_factor,
                 _ => {},
             },
             _ => {},
@@ -0,0 +1,142 @@
+// Egui widgets editing values, e.g. the content of a `UniformBufferWrapper` with `UniformBufferWrapper::inspect_ui`.
+// Structs implement `Inspect` with `impl_inspect!`, listing the fields to show and their widgets:
+// `impl_inspect!(Settings { exposure: range(0.0, 10.0), tint: color, enabled });`
+use std::ops::RangeInclusive;
+
+pub trait Inspect {
+    // Widgets editing the value, named `label`. Returns true if the value changed.
+    fn inspect(&mut self, ui: &mut egui::Ui, label: &str) -> bool;
+}
+
+pub trait InspectColor {
+    // Color picker editing the value, named `label`. Returns true if the value changed.
+    fn inspect_color(&mut self, ui: &mut egui::Ui, label: &str) -> bool;
+}
+
+// Slider between the bounds of `range`
+pub fn inspect_range<T: egui::emath::Numeric>(ui: &mut egui::Ui, label: &str, value: &mut T, range: RangeInclusive<T>) -> bool {
+    ui.add(egui::Slider::new(value, range).text(label)).changed()
+}
+
+fn inspect_drag_values(ui: &mut egui::Ui, label: &str, values: &mut [f32]) -> bool {
+    ui.horizontal(|ui| {
+        let mut changed = false;
+        for value in values {
+            changed |= ui.add(egui::DragValue::new(value).speed(0.01)).changed();
+        }
+        ui.label(label);
+        changed
+    })
+    .inner
+}
+
+impl Inspect for bool {
+    fn inspect(&mut self, ui: &mut egui::Ui, label: &str) -> bool { ui.checkbox(self, label).changed() }
+}
+
+macro_rules! inspect_numeric {
+    ($($type:ty => $speed:expr),* $(,)?) => {
+        $(impl Inspect for $type {
+            fn inspect(&mut self, ui: &mut egui::Ui, label: &str) -> bool {
+                ui.horizontal(|ui| {
+                    let changed = ui.add(egui::DragValue::new(self).speed($speed)).changed();
+                    ui.label(label);
+                    changed
+                })
+                .inner
+            }
+        })*
+    };
+}
+
+inspect_numeric!(f32 => 0.01, f64 => 0.01, u32 => 1.0, i32 => 1.0);
+
+impl<const N: usize> Inspect for [f32; N] {
+    fn inspect(&mut self, ui: &mut egui::Ui, label: &str) -> bool { inspect_drag_values(ui, label, self) }
+}
+
+impl InspectColor for [f32; 3] {
+    fn inspect_color(&mut self, ui: &mut egui::Ui, label: &str) -> bool {
+        ui.horizontal(|ui| {
+            let changed = ui.color_edit_button_rgb(self).changed();
+            ui.label(label);
+            changed
+        })
+        .inner
+    }
+}
+
+impl InspectColor for [f32; 4] {
+    fn inspect_color(&mut self, ui: &mut egui::Ui, label: &str) -> bool {
+        ui.horizontal(|ui| {
+            let changed = ui.color_edit_button_rgba_unmultiplied(self).changed();
+            ui.label(label);
+            changed
+        })
+        .inner
+    }
+}
+
+#[cfg(feature = "math")]
+macro_rules! inspect_glam_vector {
+    ($($type:ty),* $(,)?) => {
+        $(impl Inspect for $type {
+            fn inspect(&mut self, ui: &mut egui::Ui, label: &str) -> bool {
+                let mut values = self.to_array();
+                let changed = values.inspect(ui, label);
+                *self = <$type>::from_array(values);
+                changed
+            }
+        })*
+    };
+}
+
+#[cfg(feature = "math")]
+inspect_glam_vector!(glam::Vec2, glam::Vec3, glam::Vec4);
+
+#[cfg(feature = "math")]
+impl InspectColor for glam::Vec3 {
+    fn inspect_color(&mut self, ui: &mut egui::Ui, label: &str) -> bool {
+        let mut color = self.to_array();
+        let changed = color.inspect_color(ui, label);
+        *self = glam::Vec3::from_array(color);
+        changed
+    }
+}
+
+#[cfg(feature = "math")]
+impl InspectColor for glam::Vec4 {
+    fn inspect_color(&mut self, ui: &mut egui::Ui, label: &str) -> bool {
+        let mut color = self.to_array();
+        let changed = color.inspect_color(ui, label);
+        *self = glam::Vec4::from_array(color);
+        changed
+    }
+}
+
+// Implement `Inspect` for a struct, showing the listed fields in a collapsing header: `field` uses the `Inspect` widget
+// of the field type, `field: range(min, max)` a slider, `field: color` a color picker (`InspectColor`). Fields not
+// listed (e.g. the padding of the uniforms) are not shown.
+#[macro_export]
+macro_rules! impl_inspect {
+    ($type:ty { $($field:ident $(: $widget:ident $(($($argument:expr),*))?)?),* $(,)? }) => {
+        impl $crate::inspect::Inspect for $type {
+            fn inspect(&mut self, ui: &mut $crate::egui::Ui, label: &str) -> bool {
+                let mut changed = false;
+                $crate::egui::CollapsingHeader::new(label).default_open(true).show(ui, |ui| {
+                    $(changed |= $crate::impl_inspect!(@field ui, self.$field, stringify!($field) $(, $widget $(($($argument),*))?)?);)*
+                });
+                changed
+            }
+        }
+    };
+    (@field $ui:ident, $value:expr, $label:expr) => {
+        $crate::inspect::Inspect::inspect(&mut $value, $ui, $label)
+    };
+    (@field $ui:ident, $value:expr, $label:expr, range($min:expr, $max:expr)) => {
+        $crate::inspect::inspect_range($ui, $label, &mut $value, $min..=$max)
+    };
+    (@field $ui:ident, $value:expr, $label:expr, color) => {
+        $crate::inspect::InspectColor::inspect_color(&mut $value, $ui, $label)
+    };
+}
@@ -1,19 +1,108 @@
+// Span covering the rest of the enclosing scope, only when the tracing feature is enabled
+#[allow(unused_macros)]
+macro_rules! trace_scope {
+    ($name:expr) => {
+        #[cfg(feature = "tracing")]
+        let _trace_span = tracing::info_span!($name).entered();
+    };
+}
+
+// Error or warning reported through the log crate when the log feature is enabled, on stderr otherwise
+#[allow(unused_macros)]
+macro_rules! report_error {
+    ($($arg:tt)+) => {{
+        #[cfg(feature = "log")]
+        log::error!($($arg)+);
+        #[cfg(not(feature = "log"))]
+        eprintln!($($arg)+);
+    }};
+}
+
+#[allow(unused_macros)]
+macro_rules! report_warn {
+    ($($arg:tt)+) => {{
+        #[cfg(feature = "log")]
+        log::warn!($($arg)+);
+        #[cfg(not(feature = "log"))]
+        eprintln!($($arg)+);
+    }};
+}
+
 #[cfg(feature = "application")]
 pub mod app;
+#[cfg(all(feature = "application", feature = "clipboard"))]
+pub mod clipboard;
+#[cfg(all(feature = "application", feature = "serde"))]
+pub mod config;
+#[cfg(feature = "application")]
+pub mod cursor;
+#[cfg(feature = "application")]
+pub mod frame_stats;
+#[cfg(feature = "application")]
+pub mod frame_timeline;
 #[cfg(feature = "application")]
 pub mod input;
+#[cfg(feature = "math")]
+pub mod math;
+#[cfg(feature = "application")]
+pub mod plugin;
+#[cfg(feature = "application")]
+pub mod render_layers;
+#[cfg(feature = "application")]
+pub mod resize_listeners;
+#[cfg(feature = "application")]
+pub mod resources;
+#[cfg(all(feature = "application", feature = "image"))]
+pub mod screenshot;
+// sRGB transfer function of `math::color` and the texture readback
+#[cfg_attr(not(any(feature = "math", feature = "image")), allow(dead_code))]
+mod srgb;
+#[cfg(feature = "math")]
+pub mod terrain;
 pub mod wgpu_utils;
+#[cfg(feature = "testing")]
+pub mod testing;
+#[cfg(feature = "serde")]
+pub mod watched_config;
+
+#[cfg(feature = "logging")]
+pub mod logging;
+#[cfg(feature = "tracing")]
+pub mod tracing_utils;
 
 pub extern crate wgpu;
 
 pub extern crate bytemuck;
 
+#[cfg(feature = "encase")]
+pub extern crate encase;
+
 #[cfg(feature = "egui")]
 pub mod egui_wgpu_renderer;
+#[cfg(feature = "egui")]
+pub mod egui_docking;
+#[cfg(all(feature = "application", feature = "egui"))]
+pub mod debug_panel;
+#[cfg(all(feature = "application", feature = "egui"))]
+pub mod gizmo;
+#[cfg(feature = "egui")]
+pub mod inspect;
+#[cfg(feature = "egui")]
+pub mod shader_error_overlay;
+#[cfg(feature = "egui")]
+pub mod tweaks;
 
 #[cfg(feature = "egui")]
 pub extern crate egui;
+#[cfg(feature = "egui")]
+pub extern crate egui_dock;
 #[cfg(any(feature = "egui", feature = "application"))]
 pub extern crate winit;
 
 pub extern crate anyhow;
+
+#[cfg(feature = "math")]
+pub extern crate glam;
+
+#[cfg(feature = "tracing")]
+pub extern crate tracing;
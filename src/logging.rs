@@ -0,0 +1,119 @@
+use std::{io::IsTerminal, path::PathBuf};
+
+use fern::{
+    colors::{Color, ColoredLevelConfig},
+    FormatCallback,
+};
+use log::{LevelFilter, Record};
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub enum ColorMode {
+    // Colors only when stdout is a terminal
+    #[default]
+    Auto,
+    Always,
+    Never,
+}
+
+pub struct LoggingOptions {
+    // Per module levels, applied on top of the global level (e.g. to quiet wgpu/naga)
+    pub level_overrides: Vec<(&'static str, LevelFilter)>,
+    // Also write the logs (without colors) to this file, appending to it
+    pub file: Option<PathBuf>,
+    pub color: ColorMode,
+    pub timestamps: bool,
+}
+
+impl Default for LoggingOptions {
+    fn default() -> Self {
+        Self {
+            level_overrides: vec![
+                ("wgpu_core", LevelFilter::Warn),
+                ("wgpu_hal", LevelFilter::Warn),
+                ("naga", LevelFilter::Warn),
+            ],
+            file: None,
+            color: ColorMode::Auto,
+            timestamps: true,
+        }
+    }
+}
+
+pub fn default_colors() -> ColoredLevelConfig {
+    ColoredLevelConfig::new()
+        .error(Color::Red)
+        .warn(Color::Yellow)
+        .info(Color::Green)
+        .debug(Color::Cyan)
+        .trace(Color::BrightBlack)
+}
+
+// [timestamp LEVEL target] message
+pub fn format_plain(out: FormatCallback, message: &std::fmt::Arguments, record: &Record, timestamps: bool) {
+    if timestamps {
+        out.finish(format_args!(
+            "[{} {} {}] {}",
+            humantime::format_rfc3339_millis(std::time::SystemTime::now()),
+            record.level(),
+            record.target(),
+            message
+        ))
+    } else {
+        out.finish(format_args!("[{} {}] {}", record.level(), record.target(), message))
+    }
+}
+
+// Same as `format_plain` with a colored level
+pub fn format_colored(out: FormatCallback, message: &std::fmt::Arguments, record: &Record, timestamps: bool, colors: &ColoredLevelConfig) {
+    if timestamps {
+        out.finish(format_args!(
+            "[{} {} {}] {}",
+            humantime::format_rfc3339_millis(std::time::SystemTime::now()),
+            colors.color(record.level()),
+            record.target(),
+            message
+        ))
+    } else {
+        out.finish(format_args!("[{} {}] {}", colors.color(record.level()), record.target(), message))
+    }
+}
+
+// Install a global logger printing to stdout (and optionally to a file), can only be called once
+pub fn init(level: LevelFilter, options: LoggingOptions) -> anyhow::Result<()> {
+    let timestamps = options.timestamps;
+    let use_colors = match options.color {
+        ColorMode::Auto => std::io::stdout().is_terminal(),
+        ColorMode::Always => true,
+        ColorMode::Never => false,
+    };
+
+    let stdout_dispatch = if use_colors {
+        let colors = default_colors();
+        fern::Dispatch::new().format(move |out, message, record| format_colored(out, message, record, timestamps, &colors))
+    } else {
+        fern::Dispatch::new().format(move |out, message, record| format_plain(out, message, record, timestamps))
+    }
+    .chain(std::io::stdout());
+
+    let mut dispatch = options
+        .level_overrides
+        .iter()
+        .fold(fern::Dispatch::new().level(level), |dispatch, (module, module_level)| {
+            dispatch.level_for(*module, *module_level)
+        })
+        .chain(stdout_dispatch);
+
+    if let Some(file) = &options.file {
+        if let Some(parent) = file.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        dispatch = dispatch.chain(
+            fern::Dispatch::new()
+                .format(move |out, message, record| format_plain(out, message, record, timestamps))
+                .chain(fern::log_file(file)?),
+        );
+    }
+
+    dispatch.apply()?;
+    Ok(())
+}
@@ -0,0 +1,40 @@
+// Math helpers over glam (re-exported here and as `oxyde::glam`), see `prelude`
+pub use glam;
+
+mod animation;
+mod color;
+mod damping;
+mod frustum;
+mod jitter;
+mod letterbox;
+mod lod;
+pub mod prelude;
+mod projection;
+mod ray;
+mod simplify;
+mod tangents;
+mod transform_history;
+
+pub use animation::{Curve, Interpolate, Interpolation, Keyframe, PlaybackMode, Timeline, Track, TransformCurves};
+pub use color::{
+    linear_to_srgb,
+    linear_to_srgb_rgb,
+    linear_to_srgb_rgba,
+    linear_to_srgba8,
+    srgb_to_linear,
+    srgb_to_linear_rgb,
+    srgb_to_linear_rgba,
+    srgba8_to_linear,
+};
+pub use damping::{damp, damp_factor, half_life_to_rate, move_towards};
+pub use frustum::{Aabb, BoundingSphere, Frustum, FrustumBounds, Plane};
+pub use jitter::{halton, JitterSequence, ProjectionJitter};
+pub use letterbox::{Letterbox, ViewportPolicy};
+pub use lod::{LodChain, LodSelector, MeshLod};
+pub use projection::{look_at, look_rotation, look_to, opengl_to_wgpu, orthographic, perspective};
+pub use ray::{Ray, Viewport};
+pub use simplify::{simplify_mesh, SimplifiedMesh};
+pub use tangents::generate_tangents;
+#[cfg(feature = "mikktspace")]
+pub use tangents::{generate_mikktspace_tangents, SplitTangents};
+pub use transform_history::TransformHistory;
@@ -0,0 +1,381 @@
+use glam::{Mat4, Quat, Vec2, Vec3, Vec4};
+
+use crate::wgpu_utils::uniform_buffer::UniformBufferWrapper;
+
+// Values a `Curve` can interpolate
+pub trait Interpolate: Copy {
+    fn lerp(self, other: Self, t: f32) -> Self;
+
+    // Catmull-Rom spline between `from` and `to` using the keys around them for the tangents
+    fn catmull_rom(previous: Self, from: Self, to: Self, next: Self, t: f32) -> Self;
+}
+
+macro_rules! interpolate_vector {
+    ($($type:ty),* $(,)?) => {
+        $(impl Interpolate for $type {
+            fn lerp(self, other: Self, t: f32) -> Self { self + (other - self) * t }
+
+            fn catmull_rom(previous: Self, from: Self, to: Self, next: Self, t: f32) -> Self {
+                let t2 = t * t;
+                let t3 = t2 * t;
+                (from * 2.0
+                    + (to - previous) * t
+                    + (previous * 2.0 - from * 5.0 + to * 4.0 - next) * t2
+                    + (from * 3.0 - previous - to * 3.0 + next) * t3)
+                    * 0.5
+            }
+        })*
+    };
+}
+
+interpolate_vector!(f32, Vec2, Vec3, Vec4);
+
+impl Interpolate for Quat {
+    fn lerp(self, other: Self, t: f32) -> Self { self.slerp(other, t) }
+
+    // Spline of the components in the hemisphere of `from`, normalized
+    fn catmull_rom(previous: Self, from: Self, to: Self, next: Self, t: f32) -> Self {
+        let from_vector = Vec4::from(from);
+        let align = |quat: Quat| {
+            let vector = Vec4::from(quat);
+            if vector.dot(from_vector) < 0.0 {
+                -vector
+            } else {
+                vector
+            }
+        };
+        Quat::from_vec4(Vec4::catmull_rom(align(previous), from_vector, align(to), align(next), t)).normalize()
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Interpolation {
+    // Value of the previous key
+    Step,
+    #[default]
+    Linear,
+    // Catmull-Rom spline through the keys
+    Cubic,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Keyframe<T> {
+    pub time: f32,
+    pub value: T,
+}
+
+// Keyframed value, clamped to the first and last keys outside of them
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Curve<T> {
+    // Sorted by time
+    keyframes: Vec<Keyframe<T>>,
+    pub interpolation: Interpolation,
+}
+
+impl<T: Interpolate> Curve<T> {
+    pub fn new(interpolation: Interpolation) -> Self { Self { keyframes: Vec::new(), interpolation } }
+
+    pub fn with_key(mut self, time: f32, value: T) -> Self {
+        self.insert(time, value);
+        self
+    }
+
+    // Add a key, replacing the key at the same time if any
+    pub fn insert(&mut self, time: f32, value: T) {
+        let index = self.keyframes.partition_point(|keyframe| keyframe.time < time);
+        match self.keyframes.get_mut(index) {
+            Some(keyframe) if keyframe.time == time => keyframe.value = value,
+            _ => self.keyframes.insert(index, Keyframe { time, value }),
+        }
+    }
+
+    pub fn keyframes(&self) -> &[Keyframe<T>] { &self.keyframes }
+
+    // Time of the last key
+    pub fn duration(&self) -> f32 { self.keyframes.last().map_or(0.0, |keyframe| keyframe.time) }
+
+    // None without keys
+    pub fn sample(&self, time: f32) -> Option<T> {
+        let first = self.keyframes.first()?;
+        let last = self.keyframes.last()?;
+        if time <= first.time {
+            return Some(first.value);
+        }
+        if time >= last.time {
+            return Some(last.value);
+        }
+
+        // Index of the key after `time`, in [1, len - 1]
+        let next_index = self.keyframes.partition_point(|keyframe| keyframe.time <= time);
+        let from = &self.keyframes[next_index - 1];
+        let to = &self.keyframes[next_index];
+        let t = (time - from.time) / (to.time - from.time);
+        Some(match self.interpolation {
+            Interpolation::Step => from.value,
+            Interpolation::Linear => from.value.lerp(to.value, t),
+            Interpolation::Cubic => {
+                let previous = self.keyframes[next_index.saturating_sub(2)].value;
+                let next = self.keyframes[(next_index + 1).min(self.keyframes.len() - 1)].value;
+                T::catmull_rom(previous, from.value, to.value, next, t)
+            },
+        })
+    }
+}
+
+// Translation, rotation and scale curves of an object or a camera, the missing ones keep the identity
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct TransformCurves {
+    pub translation: Option<Curve<Vec3>>,
+    pub rotation: Option<Curve<Quat>>,
+    pub scale: Option<Curve<Vec3>>,
+}
+
+impl TransformCurves {
+    pub fn duration(&self) -> f32 {
+        let translation = self.translation.as_ref().map_or(0.0, Curve::duration);
+        let rotation = self.rotation.as_ref().map_or(0.0, Curve::duration);
+        let scale = self.scale.as_ref().map_or(0.0, Curve::duration);
+        translation.max(rotation).max(scale)
+    }
+
+    pub fn sample(&self, time: f32) -> Mat4 {
+        Mat4::from_scale_rotation_translation(
+            self.scale.as_ref().and_then(|curve| curve.sample(time)).unwrap_or(Vec3::ONE),
+            self.rotation.as_ref().and_then(|curve| curve.sample(time)).unwrap_or(Quat::IDENTITY),
+            self.translation.as_ref().and_then(|curve| curve.sample(time)).unwrap_or(Vec3::ZERO),
+        )
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum Track {
+    Float(Curve<f32>),
+    Vec2(Curve<Vec2>),
+    Vec3(Curve<Vec3>),
+    Vec4(Curve<Vec4>),
+    Quat(Curve<Quat>),
+    Transform(TransformCurves),
+}
+
+impl Track {
+    pub fn duration(&self) -> f32 {
+        match self {
+            Track::Float(curve) => curve.duration(),
+            Track::Vec2(curve) => curve.duration(),
+            Track::Vec3(curve) => curve.duration(),
+            Track::Vec4(curve) => curve.duration(),
+            Track::Quat(curve) => curve.duration(),
+            Track::Transform(curves) => curves.duration(),
+        }
+    }
+}
+
+macro_rules! track_from {
+    ($($variant:ident => $type:ty),* $(,)?) => {
+        $(impl From<$type> for Track {
+            fn from(value: $type) -> Self { Track::$variant(value) }
+        })*
+    };
+}
+
+track_from!(Float => Curve<f32>, Vec2 => Curve<Vec2>, Vec3 => Curve<Vec3>, Vec4 => Curve<Vec4>, Quat => Curve<Quat>, Transform => TransformCurves);
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum PlaybackMode {
+    // Stops at the end
+    #[default]
+    Once,
+    Loop,
+    // Plays forward then backward
+    PingPong,
+}
+
+// Named tracks played together (e.g. a camera fly-through or a demo sequence), advanced each frame with the frame delta
+// time (`SystemState::delta_time`) and sampled at the playback time
+#[derive(Clone, Debug)]
+pub struct Timeline {
+    tracks: Vec<(String, Track)>,
+    pub mode: PlaybackMode,
+    pub speed: f32,
+    pub playing: bool,
+    // Elapsed playback time, not wrapped
+    elapsed: f32,
+    // Duration of the longest track unless set
+    duration: Option<f32>,
+}
+
+impl Default for Timeline {
+    fn default() -> Self { Self::new(PlaybackMode::default()) }
+}
+
+impl Timeline {
+    pub fn new(mode: PlaybackMode) -> Self {
+        Self {
+            tracks: Vec::new(),
+            mode,
+            speed: 1.0,
+            playing: true,
+            elapsed: 0.0,
+            duration: None,
+        }
+    }
+
+    // Replace the track of the same name if any
+    pub fn with_track(mut self, name: impl Into<String>, track: impl Into<Track>) -> Self {
+        self.add_track(name, track);
+        self
+    }
+
+    pub fn add_track(&mut self, name: impl Into<String>, track: impl Into<Track>) {
+        let name = name.into();
+        let track = track.into();
+        match self.tracks.iter_mut().find(|(track_name, _)| *track_name == name) {
+            Some((_, existing_track)) => *existing_track = track,
+            None => self.tracks.push((name, track)),
+        }
+    }
+
+    pub fn track(&self, name: &str) -> Option<&Track> { self.tracks.iter().find(|(track_name, _)| track_name == name).map(|(_, track)| track) }
+
+    pub fn set_duration(&mut self, duration: Option<f32>) { self.duration = duration; }
+
+    pub fn duration(&self) -> f32 {
+        self.duration
+            .unwrap_or_else(|| self.tracks.iter().map(|(_, track)| track.duration()).fold(0.0, f32::max))
+    }
+
+    pub fn advance(&mut self, delta_time: f64) {
+        if self.playing {
+            self.elapsed += delta_time as f32 * self.speed;
+        }
+    }
+
+    pub fn seek(&mut self, time: f32) { self.elapsed = time; }
+
+    pub fn restart(&mut self) {
+        self.elapsed = 0.0;
+        self.playing = true;
+    }
+
+    // A timeline played once is finished past its duration
+    pub fn is_finished(&self) -> bool { self.mode == PlaybackMode::Once && self.elapsed >= self.duration() }
+
+    // Time the tracks are sampled at, in [0, duration]
+    pub fn time(&self) -> f32 {
+        let duration = self.duration();
+        if duration <= 0.0 {
+            return 0.0;
+        }
+        match self.mode {
+            PlaybackMode::Once => self.elapsed.clamp(0.0, duration),
+            PlaybackMode::Loop => self.elapsed.rem_euclid(duration),
+            PlaybackMode::PingPong => {
+                let time = self.elapsed.rem_euclid(2.0 * duration);
+                if time > duration {
+                    2.0 * duration - time
+                } else {
+                    time
+                }
+            },
+        }
+    }
+
+    pub fn float(&self, name: &str) -> Option<f32> {
+        match self.track(name)? {
+            Track::Float(curve) => curve.sample(self.time()),
+            _ => None,
+        }
+    }
+
+    pub fn vec2(&self, name: &str) -> Option<Vec2> {
+        match self.track(name)? {
+            Track::Vec2(curve) => curve.sample(self.time()),
+            _ => None,
+        }
+    }
+
+    pub fn vec3(&self, name: &str) -> Option<Vec3> {
+        match self.track(name)? {
+            Track::Vec3(curve) => curve.sample(self.time()),
+            _ => None,
+        }
+    }
+
+    pub fn vec4(&self, name: &str) -> Option<Vec4> {
+        match self.track(name)? {
+            Track::Vec4(curve) => curve.sample(self.time()),
+            _ => None,
+        }
+    }
+
+    pub fn quat(&self, name: &str) -> Option<Quat> {
+        match self.track(name)? {
+            Track::Quat(curve) => curve.sample(self.time()),
+            _ => None,
+        }
+    }
+
+    // Matrix of a transform track, e.g. the camera to world matrix of a fly-through (inverse it for the view matrix)
+    pub fn transform(&self, name: &str) -> Option<Mat4> {
+        match self.track(name)? {
+            Track::Transform(curves) => Some(curves.sample(self.time())),
+            _ => None,
+        }
+    }
+
+    // Write the sampled tracks to the content of `uniform_buffer` with `apply` and upload it if it changed
+    pub fn drive_uniform<C: bytemuck::Pod>(
+        &self,
+        queue: &wgpu::Queue,
+        uniform_buffer: &mut UniformBufferWrapper<C>,
+        apply: impl FnOnce(&Self, &mut C),
+    ) {
+        apply(self, uniform_buffer.content_mut());
+        uniform_buffer.update_content(queue);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn catmull_rom_passes_through_the_keys() {
+        assert_eq!(f32::catmull_rom(-1.0, 2.0, 5.0, 3.0, 0.0), 2.0);
+        assert_eq!(f32::catmull_rom(-1.0, 2.0, 5.0, 3.0, 1.0), 5.0);
+    }
+
+    #[test]
+    fn catmull_rom_is_linear_on_evenly_spaced_keys() {
+        for step in 0..=10 {
+            let t = step as f32 / 10.0;
+            let value = Vec3::catmull_rom(Vec3::splat(0.0), Vec3::splat(1.0), Vec3::splat(2.0), Vec3::splat(3.0), t);
+            assert!(value.abs_diff_eq(Vec3::splat(1.0 + t), 1.0e-6), "{t}: {value}");
+        }
+    }
+
+    #[test]
+    fn quat_catmull_rom_ignores_the_sign_of_the_keys() {
+        let (from, to) = (Quat::IDENTITY, Quat::from_rotation_y(1.0));
+        let value = Quat::catmull_rom(-from, from, -to, to, 0.5);
+        assert!(value.is_normalized());
+        assert!(value.abs_diff_eq(Quat::from_rotation_y(0.5), 1.0e-3) || value.abs_diff_eq(-Quat::from_rotation_y(0.5), 1.0e-3), "{value}");
+    }
+
+    #[test]
+    fn default_timeline_plays() {
+        let mut timeline = Timeline::default().with_track("value", Curve::new(Interpolation::Linear).with_key(0.0, 0.0).with_key(2.0, 2.0));
+        timeline.advance(0.5);
+        assert_eq!(timeline.float("value"), Some(0.5));
+    }
+
+    #[test]
+    fn cubic_curve_is_clamped_to_its_keys() {
+        let curve = Curve::new(Interpolation::Cubic).with_key(0.0, 0.0).with_key(1.0, 1.0).with_key(2.0, 4.0);
+        assert_eq!(curve.sample(-1.0), Some(0.0));
+        assert_eq!(curve.sample(1.0), Some(1.0));
+        assert_eq!(curve.sample(3.0), Some(4.0));
+        let value = curve.sample(1.5).unwrap();
+        assert!(value > 1.0 && value < 4.0, "{value}");
+    }
+}
@@ -0,0 +1,53 @@
+use glam::{Vec3, Vec4};
+
+// Shared with the texture readback
+pub use crate::srgb::{linear_to_srgb, srgb_to_linear};
+
+pub fn srgb_to_linear_rgb(color: Vec3) -> Vec3 { Vec3::new(srgb_to_linear(color.x), srgb_to_linear(color.y), srgb_to_linear(color.z)) }
+
+pub fn linear_to_srgb_rgb(color: Vec3) -> Vec3 { Vec3::new(linear_to_srgb(color.x), linear_to_srgb(color.y), linear_to_srgb(color.z)) }
+
+// The alpha is linear in both
+pub fn srgb_to_linear_rgba(color: Vec4) -> Vec4 { srgb_to_linear_rgb(color.truncate()).extend(color.w) }
+
+pub fn linear_to_srgb_rgba(color: Vec4) -> Vec4 { linear_to_srgb_rgb(color.truncate()).extend(color.w) }
+
+// 8-bit sRGB color with a linear alpha (e.g. `egui::Color32::to_srgba_unmultiplied`) to linear floats
+pub fn srgba8_to_linear(color: [u8; 4]) -> Vec4 {
+    let [r, g, b, a] = color.map(|channel| channel as f32 / 255.0);
+    srgb_to_linear_rgba(Vec4::new(r, g, b, a))
+}
+
+pub fn linear_to_srgba8(color: Vec4) -> [u8; 4] {
+    linear_to_srgb_rgba(color)
+        .to_array()
+        .map(|channel| (channel.clamp(0.0, 1.0) * 255.0).round() as u8)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn transfer_functions_round_trip() {
+        for step in 0..=100 {
+            let value = step as f32 / 100.0;
+            assert!((linear_to_srgb(srgb_to_linear(value)) - value).abs() < 1.0e-5, "{value}");
+        }
+    }
+
+    #[test]
+    fn transfer_function_known_values() {
+        assert_eq!(srgb_to_linear(0.0), 0.0);
+        assert!((srgb_to_linear(1.0) - 1.0).abs() < 1.0e-6);
+        assert!((srgb_to_linear(0.5) - 0.214_041).abs() < 1.0e-5);
+        assert!((linear_to_srgb(0.0031308) - 0.040_45).abs() < 1.0e-5);
+    }
+
+    #[test]
+    fn srgba8_keeps_the_alpha_linear() {
+        let linear = srgba8_to_linear([255, 128, 0, 128]);
+        assert!((linear.w - 128.0 / 255.0).abs() < 1.0e-6);
+        assert_eq!(linear_to_srgba8(linear), [255, 128, 0, 128]);
+    }
+}
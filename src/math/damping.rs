@@ -0,0 +1,47 @@
+use super::Interpolate;
+
+// Blend factor moving a value towards its target by the same amount for the same elapsed time whatever the frame rate,
+// `rate` being the fraction of the remaining distance covered (1 - e^-rate) each second. A frame-rate-dependent
+// `lerp(target, 0.1)` each frame becomes `lerp(target, damp_factor(rate, delta_time))`.
+pub fn damp_factor(rate: f32, delta_time: f32) -> f32 { 1.0 - (-rate * delta_time.max(0.0)).exp() }
+
+// Rate halving the remaining distance every `half_life` seconds
+pub fn half_life_to_rate(half_life: f32) -> f32 { std::f32::consts::LN_2 / half_life.max(f32::EPSILON) }
+
+// Value moved towards `target` over `delta_time` seconds (exponential smoothing, e.g. a camera following a target),
+// rotations are slerped
+pub fn damp<T: Interpolate>(current: T, target: T, rate: f32, delta_time: f32) -> T { current.lerp(target, damp_factor(rate, delta_time)) }
+
+// Linear step of `current` towards `target` by at most `max_delta`, reaching it exactly
+pub fn move_towards(current: f32, target: f32, max_delta: f32) -> f32 { current + (target - current).clamp(-max_delta.abs(), max_delta.abs()) }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn damping_is_frame_rate_independent() {
+        let (rate, target) = (3.0, 10.0);
+        let one_step = damp(0.0, target, rate, 1.0);
+        let many_steps = (0..60).fold(0.0, |value, _| damp(value, target, rate, 1.0 / 60.0));
+        assert!((one_step - many_steps).abs() < 1.0e-4, "{one_step} {many_steps}");
+    }
+
+    #[test]
+    fn half_life_halves_the_distance() {
+        let value = damp(0.0, 8.0, half_life_to_rate(0.5), 0.5);
+        assert!((value - 4.0).abs() < 1.0e-5, "{value}");
+    }
+
+    #[test]
+    fn negative_delta_time_doesnt_move() {
+        assert_eq!(damp_factor(5.0, -1.0), 0.0);
+    }
+
+    #[test]
+    fn move_towards_stops_at_the_target() {
+        assert_eq!(move_towards(0.0, 1.0, 0.25), 0.25);
+        assert_eq!(move_towards(0.9, 1.0, 0.25), 1.0);
+        assert_eq!(move_towards(1.0, -1.0, -0.5), 0.5);
+    }
+}
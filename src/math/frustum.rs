@@ -0,0 +1,221 @@
+use glam::{Mat4, Vec3, Vec4};
+
+// Plane with a unit normal, points with a positive signed distance are in front of it
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct Plane {
+    pub normal: Vec3,
+    pub distance: f32,
+}
+
+impl Plane {
+    // Plane `ax + by + cz + d = 0` from (a, b, c, d), normalized. A degenerate plane (zero normal, e.g. the far plane of
+    // an infinite projection) is kept with a zero normal and distance, every point being at a zero distance from it.
+    pub fn from_vec4(plane: Vec4) -> Self {
+        let length = plane.truncate().length();
+        if length <= f32::EPSILON * plane.abs().max_element().max(1.0) {
+            return Self {
+                normal: Vec3::ZERO,
+                distance: 0.0,
+            };
+        }
+        let inverse_length = length.recip();
+        Self {
+            normal: plane.truncate() * inverse_length,
+            distance: plane.w * inverse_length,
+        }
+    }
+
+    pub fn signed_distance(&self, point: Vec3) -> f32 { self.normal.dot(point) + self.distance }
+}
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct Aabb {
+    pub min: Vec3,
+    pub max: Vec3,
+}
+
+impl Aabb {
+    pub fn new(min: Vec3, max: Vec3) -> Self { Self { min, max } }
+
+    pub fn from_center_half_extents(center: Vec3, half_extents: Vec3) -> Self { Self::new(center - half_extents, center + half_extents) }
+
+    pub fn from_points(points: impl IntoIterator<Item = Vec3>) -> Option<Self> {
+        points.into_iter().fold(None, |aabb: Option<Self>, point| {
+            Some(aabb.map_or(Self::new(point, point), |aabb| Self::new(aabb.min.min(point), aabb.max.max(point))))
+        })
+    }
+
+    pub fn center(&self) -> Vec3 { (self.min + self.max) * 0.5 }
+
+    pub fn half_extents(&self) -> Vec3 { (self.max - self.min) * 0.5 }
+
+    // Box enclosing the transformed box (e.g. world bounds of a mesh from its local bounds)
+    pub fn transformed(&self, transform: &Mat4) -> Self {
+        let center = transform.transform_point3(self.center());
+        // Each world axis extent is the sum of the absolute contributions of the local axes
+        let absolute = Mat4::from_cols(transform.x_axis.abs(), transform.y_axis.abs(), transform.z_axis.abs(), Vec4::W);
+        Self::from_center_half_extents(center, absolute.transform_vector3(self.half_extents()))
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct BoundingSphere {
+    pub center: Vec3,
+    pub radius: f32,
+}
+
+impl BoundingSphere {
+    pub fn new(center: Vec3, radius: f32) -> Self { Self { center, radius } }
+
+    // Sphere enclosing the transformed sphere, scaled by the largest axis scale
+    pub fn transformed(&self, transform: &Mat4) -> Self {
+        let scale = transform
+            .x_axis
+            .truncate()
+            .length()
+            .max(transform.y_axis.truncate().length())
+            .max(transform.z_axis.truncate().length());
+        Self::new(transform.transform_point3(self.center), self.radius * scale)
+    }
+}
+
+// Bounds tested by `Frustum::cull`
+pub trait FrustumBounds {
+    fn intersects_frustum(&self, frustum: &Frustum) -> bool;
+}
+
+impl FrustumBounds for Aabb {
+    fn intersects_frustum(&self, frustum: &Frustum) -> bool { frustum.intersects_aabb(self) }
+}
+
+impl FrustumBounds for BoundingSphere {
+    fn intersects_frustum(&self, frustum: &Frustum) -> bool { frustum.intersects_sphere(self) }
+}
+
+// View frustum as six inward facing planes: left, right, bottom, top, near and far
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct Frustum {
+    pub planes: [Plane; 6],
+}
+
+impl Frustum {
+    // Planes of the clip space volume of a projection * view matrix (Gribb-Hartmann), with the 0 to 1 depth range of wgpu.
+    // With a projection matrix alone the planes are in view space.
+    pub fn from_view_proj(view_proj: &Mat4) -> Self {
+        let (row_x, row_y, row_z, row_w) = (view_proj.row(0), view_proj.row(1), view_proj.row(2), view_proj.row(3));
+        Self {
+            planes: [
+                Plane::from_vec4(row_w + row_x),
+                Plane::from_vec4(row_w - row_x),
+                Plane::from_vec4(row_w + row_y),
+                Plane::from_vec4(row_w - row_y),
+                Plane::from_vec4(row_z),
+                Plane::from_vec4(row_w - row_z),
+            ],
+        }
+    }
+
+    pub fn contains_point(&self, point: Vec3) -> bool { self.planes.iter().all(|plane| plane.signed_distance(point) >= 0.0) }
+
+    pub fn intersects_sphere(&self, sphere: &BoundingSphere) -> bool {
+        self.planes.iter().all(|plane| plane.signed_distance(sphere.center) >= -sphere.radius)
+    }
+
+    // Conservative test: boxes outside of the frustum but not fully behind a single plane (near the corners) pass
+    pub fn intersects_aabb(&self, aabb: &Aabb) -> bool {
+        let (center, half_extents) = (aabb.center(), aabb.half_extents());
+        self.planes
+            .iter()
+            .all(|plane| plane.signed_distance(center) >= -half_extents.dot(plane.normal.abs()))
+    }
+
+    // Indices of the bounds intersecting the frustum, written to `visible` (cleared first) to reuse its allocation
+    pub fn cull<B: FrustumBounds>(&self, bounds: &[B], visible: &mut Vec<u32>) {
+        visible.clear();
+        visible.extend(
+            bounds
+                .iter()
+                .enumerate()
+                .filter(|(_, bounds)| bounds.intersects_frustum(self))
+                .map(|(index, _)| index as u32),
+        );
+    }
+
+    pub fn visible_indices<B: FrustumBounds>(&self, bounds: &[B]) -> Vec<u32> {
+        let mut visible = Vec::new();
+        self.cull(bounds, &mut visible);
+        visible
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn frustums() -> Vec<(&'static str, Frustum)> {
+        let view = Mat4::look_at_rh(Vec3::new(0.0, 0.0, 5.0), Vec3::ZERO, Vec3::Y);
+        let fov = std::f32::consts::FRAC_PI_2;
+        [
+            ("standard finite", Mat4::perspective_rh(fov, 1.0, 0.1, 100.0)),
+            ("standard infinite", Mat4::perspective_infinite_rh(fov, 1.0, 0.1)),
+            // Near and far swapped for a reversed depth
+            ("reversed finite", Mat4::perspective_rh(fov, 1.0, 100.0, 0.1)),
+            ("reversed infinite", Mat4::perspective_infinite_reverse_rh(fov, 1.0, 0.1)),
+        ]
+        .into_iter()
+        .map(|(name, projection)| (name, Frustum::from_view_proj(&(projection * view))))
+        .collect()
+    }
+
+    #[test]
+    fn planes_are_finite() {
+        for (name, frustum) in frustums() {
+            for plane in frustum.planes {
+                assert!(plane.normal.is_finite() && plane.distance.is_finite(), "{name}: {plane:?}");
+            }
+        }
+    }
+
+    #[test]
+    fn visible_bounds_pass() {
+        for (name, frustum) in frustums() {
+            assert!(frustum.contains_point(Vec3::ZERO), "{name}");
+            assert!(frustum.intersects_sphere(&BoundingSphere::new(Vec3::ZERO, 1.0)), "{name}");
+            assert!(frustum.intersects_aabb(&Aabb::from_center_half_extents(Vec3::ZERO, Vec3::ONE)), "{name}");
+        }
+    }
+
+    #[test]
+    fn bounds_outside_are_culled() {
+        for (name, frustum) in frustums() {
+            // Behind the camera, and far on the side
+            assert!(!frustum.intersects_sphere(&BoundingSphere::new(Vec3::new(0.0, 0.0, 10.0), 1.0)), "{name}");
+            assert!(!frustum.intersects_aabb(&Aabb::from_center_half_extents(Vec3::new(50.0, 0.0, 0.0), Vec3::ONE)), "{name}");
+        }
+    }
+
+    #[test]
+    fn far_plane_culls_only_finite_projections() {
+        let far_away = BoundingSphere::new(Vec3::new(0.0, 0.0, -1000.0), 1.0);
+        for (name, frustum) in frustums() {
+            assert_eq!(frustum.intersects_sphere(&far_away), name.ends_with("infinite"), "{name}");
+        }
+    }
+
+    #[test]
+    fn degenerate_plane_passes() {
+        let plane = Plane::from_vec4(Vec4::new(0.0, 0.0, 0.0, 1.0));
+        assert_eq!(plane.signed_distance(Vec3::new(1.0e6, -3.0, 2.0)), 0.0);
+    }
+
+    #[test]
+    fn cull_returns_visible_indices() {
+        let (_, frustum) = frustums().remove(0);
+        let spheres = [
+            BoundingSphere::new(Vec3::ZERO, 1.0),
+            BoundingSphere::new(Vec3::new(0.0, 0.0, 10.0), 1.0),
+            BoundingSphere::new(Vec3::new(1.0, 1.0, -3.0), 0.5),
+        ];
+        assert_eq!(frustum.visible_indices(&spheres), vec![0, 2]);
+    }
+}
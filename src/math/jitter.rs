@@ -0,0 +1,78 @@
+use glam::{Mat4, Vec2, Vec3};
+
+// Radical inverse of `index` in `base`, the Halton sequence value in [0, 1)
+pub fn halton(mut index: u32, base: u32) -> f32 {
+    let mut result = 0.0;
+    let mut fraction = 1.0;
+    while index > 0 {
+        fraction /= base as f32;
+        result += fraction * (index % base) as f32;
+        index /= base;
+    }
+    result
+}
+
+// Low discrepancy sequences of sub-pixel offsets
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum JitterSequence {
+    // Halton bases 2 and 3, the usual TAA pattern (with a length of 8 or 16)
+    #[default]
+    Halton23,
+    // Roberts R2 additive recurrence, well distributed at any length
+    R2,
+}
+
+impl JitterSequence {
+    // Offset of the sample `index`, in [-0.5, 0.5) pixels
+    pub fn sample(&self, index: u32) -> Vec2 {
+        match self {
+            // Starts at 1 as the first Halton point is the origin
+            JitterSequence::Halton23 => Vec2::new(halton(index + 1, 2), halton(index + 1, 3)) - 0.5,
+            JitterSequence::R2 => {
+                // Plastic number
+                const G: f64 = 1.324_717_957_244_746;
+                let point = (0.5 + (index as f64 + 1.0) * glam::DVec2::new(1.0 / G, 1.0 / (G * G))).fract();
+                point.as_vec2() - 0.5
+            },
+        }
+    }
+}
+
+// Per frame sub-pixel offsets of the projection, cycling over the first `length` samples of a sequence
+#[derive(Clone, Copy, PartialEq, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ProjectionJitter {
+    pub sequence: JitterSequence,
+    pub length: u32,
+    // Scale of the offsets, 1 covers a whole pixel
+    pub scale: f32,
+}
+
+impl Default for ProjectionJitter {
+    fn default() -> Self { Self::new(JitterSequence::Halton23, 8) }
+}
+
+impl ProjectionJitter {
+    pub fn new(sequence: JitterSequence, length: u32) -> Self {
+        Self {
+            sequence,
+            length: length.max(1),
+            scale: 1.0,
+        }
+    }
+
+    // Offset in pixels of a frame, x right and y down
+    pub fn offset(&self, frame_index: u64) -> Vec2 { self.sequence.sample((frame_index % self.length.max(1) as u64) as u32) * self.scale }
+
+    // Projection shifted by `offset` pixels on a viewport of `viewport_size` pixels, for perspective and orthographic projections
+    pub fn apply_offset(projection: &Mat4, offset: Vec2, viewport_size: Vec2) -> Mat4 {
+        let ndc_offset = Vec2::new(2.0, -2.0) * offset / viewport_size;
+        Mat4::from_translation(Vec3::new(ndc_offset.x, ndc_offset.y, 0.0)) * *projection
+    }
+
+    // Projection jittered by the offset of `frame_index`
+    pub fn apply(&self, projection: &Mat4, frame_index: u64, viewport_size: Vec2) -> Mat4 {
+        Self::apply_offset(projection, self.offset(frame_index), viewport_size)
+    }
+}
@@ -0,0 +1,138 @@
+use glam::{Mat4, Vec2};
+
+use super::Viewport;
+
+// How the content of an app with a locked aspect ratio (width / height) is placed in the area it renders to
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum ViewportPolicy {
+    // Whole area, the aspect ratio follows the area
+    #[default]
+    Stretch,
+    // Largest centered rect of the aspect ratio inside the area, with bars on the sides or the top and bottom
+    FitWithBars {
+        aspect_ratio: f32,
+    },
+    // Smallest centered rect of the aspect ratio covering the area, the content outside of the area is cropped
+    FillCrop {
+        aspect_ratio: f32,
+    },
+}
+
+impl ViewportPolicy {
+    pub fn apply(&self, area: &Viewport) -> Letterbox {
+        let fitted_size = |aspect_ratio: f32, fill: bool| {
+            let width_limited = (area.size.x / area.size.y > aspect_ratio) == fill;
+            if width_limited {
+                Vec2::new(area.size.x, area.size.x / aspect_ratio)
+            } else {
+                Vec2::new(area.size.y * aspect_ratio, area.size.y)
+            }
+        };
+        let content_size = match *self {
+            _ if area.size.cmple(Vec2::ZERO).any() => area.size,
+            Self::Stretch => area.size,
+            Self::FitWithBars { aspect_ratio } => fitted_size(aspect_ratio.max(f32::EPSILON), false),
+            Self::FillCrop { aspect_ratio } => fitted_size(aspect_ratio.max(f32::EPSILON), true),
+        };
+        let content = Viewport::new(area.position + (area.size - content_size) * 0.5, content_size);
+        // Render passes reject viewports outside of the target, the cropped content is rendered over the whole area
+        let position = content.position.max(area.position);
+        let viewport = Viewport::new(position, (content.position + content.size).min(area.position + area.size) - position);
+        Letterbox { area: *area, viewport, content }
+    }
+}
+
+// Placement of the content in an area by a `ViewportPolicy`, all in physical pixels
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct Letterbox {
+    pub area: Viewport,
+    // Viewport of the render passes, the content clipped to the area
+    pub viewport: Viewport,
+    // Rect of the content with its aspect ratio, larger than the area when cropped. Positions are converted to the NDC
+    // of the content with it, e.g. `AppState::cursor_ray(&letterbox.content, ..)`.
+    pub content: Viewport,
+}
+
+impl Letterbox {
+    // Matrix applied to the projection (`crop_projection() * projection`) so that the content is rendered with its aspect
+    // ratio in the viewport, identity when the content isn't cropped
+    pub fn crop_projection(&self) -> Mat4 {
+        let scale = self.content.size / self.viewport.size;
+        let content_center = self.content.position + self.content.size * 0.5;
+        let viewport_center = self.viewport.position + self.viewport.size * 0.5;
+        // Y up in NDC
+        let offset = (content_center - viewport_center) / self.viewport.size * Vec2::new(2.0, -2.0);
+        Mat4::from_translation(offset.extend(0.0)) * Mat4::from_scale(scale.extend(1.0))
+    }
+
+    // Parts of the area outside of the viewport, the bars of `FitWithBars`
+    pub fn bars(&self) -> Vec<Viewport> {
+        let (area_min, area_max) = (self.area.position, self.area.position + self.area.size);
+        let (min, max) = (self.viewport.position, self.viewport.position + self.viewport.size);
+        [
+            Viewport::new(area_min, Vec2::new(area_max.x - area_min.x, min.y - area_min.y)),
+            Viewport::new(Vec2::new(area_min.x, max.y), Vec2::new(area_max.x - area_min.x, area_max.y - max.y)),
+            Viewport::new(Vec2::new(area_min.x, min.y), Vec2::new(min.x - area_min.x, max.y - min.y)),
+            Viewport::new(Vec2::new(max.x, min.y), Vec2::new(area_max.x - max.x, max.y - min.y)),
+        ]
+        .into_iter()
+        .filter(|bar| bar.size.cmpge(Vec2::ONE).all())
+        .collect()
+    }
+
+    // Set the viewport of the render pass
+    pub fn set_viewport(&self, render_pass: &mut wgpu::RenderPass) {
+        let Viewport { position, size } = self.viewport;
+        render_pass.set_viewport(position.x, position.y, size.x, size.y, 0.0, 1.0);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn area() -> Viewport { Viewport::new(Vec2::new(10.0, 20.0), Vec2::new(400.0, 200.0)) }
+
+    #[test]
+    fn stretch_covers_the_area() {
+        let letterbox = ViewportPolicy::Stretch.apply(&area());
+        assert_eq!(letterbox.viewport, area());
+        assert_eq!(letterbox.content, area());
+        assert!(letterbox.bars().is_empty());
+        assert_eq!(letterbox.crop_projection(), Mat4::IDENTITY);
+    }
+
+    #[test]
+    fn fit_with_bars_centers_the_content() {
+        let letterbox = ViewportPolicy::FitWithBars { aspect_ratio: 1.0 }.apply(&area());
+        assert_eq!(letterbox.viewport, Viewport::new(Vec2::new(110.0, 20.0), Vec2::new(200.0, 200.0)));
+        assert_eq!(letterbox.content, letterbox.viewport);
+        assert_eq!(
+            letterbox.bars(),
+            vec![
+                Viewport::new(Vec2::new(10.0, 20.0), Vec2::new(100.0, 200.0)),
+                Viewport::new(Vec2::new(310.0, 20.0), Vec2::new(100.0, 200.0)),
+            ]
+        );
+        assert_eq!(letterbox.crop_projection(), Mat4::IDENTITY);
+    }
+
+    #[test]
+    fn fill_crop_clips_the_viewport_to_the_area() {
+        let letterbox = ViewportPolicy::FillCrop { aspect_ratio: 1.0 }.apply(&area());
+        assert_eq!(letterbox.content, Viewport::new(Vec2::new(10.0, -80.0), Vec2::new(400.0, 400.0)));
+        assert_eq!(letterbox.viewport, area());
+        assert!(letterbox.bars().is_empty());
+        // The content corners land outside of the viewport NDC
+        let corner = letterbox.crop_projection().project_point3(glam::Vec3::new(1.0, 1.0, 0.0));
+        assert!(corner.truncate().abs_diff_eq(Vec2::new(1.0, 2.0), 1.0e-5), "{corner}");
+    }
+
+    #[test]
+    fn empty_area_is_kept() {
+        let empty = Viewport::new(Vec2::ZERO, Vec2::new(0.0, 100.0));
+        let letterbox = ViewportPolicy::FitWithBars { aspect_ratio: 1.0 }.apply(&empty);
+        assert_eq!(letterbox.viewport, empty);
+    }
+}
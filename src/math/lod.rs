@@ -0,0 +1,125 @@
+use glam::Vec3;
+
+use super::{simplify_mesh, BoundingSphere};
+use crate::wgpu_utils::CullingDraw;
+
+// Level of a `LodChain`: a range of its index buffer
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct MeshLod {
+    pub first_index: u32,
+    pub index_count: u32,
+    // Approximate distance to the source mesh surface, in mesh units
+    pub error: f32,
+}
+
+// Levels of detail of a mesh sharing its vertex buffer, level 0 is the source mesh.
+// The indices of all the levels are concatenated in a single index buffer.
+#[derive(Clone, Debug)]
+pub struct LodChain {
+    pub indices: Vec<u32>,
+    pub levels: Vec<MeshLod>,
+}
+
+impl LodChain {
+    // Up to `level_count` levels, each one keeping `reduction` (e.g. 0.5) of the triangles of the previous one.
+    // The chain stops early when the simplification stalls (seams, borders) or would exceed `max_error`.
+    pub fn generate(positions: &[[f32; 3]], indices: &[u32], level_count: usize, reduction: f32, max_error: f32) -> Self {
+        let mut chain = Self {
+            indices: indices.to_vec(),
+            levels: vec![MeshLod {
+                first_index: 0,
+                index_count: indices.len() as u32,
+                error: 0.0,
+            }],
+        };
+
+        let mut level_indices = indices.to_vec();
+        let mut error: f32 = 0.0;
+        while chain.levels.len() < level_count {
+            let target_index_count = ((level_indices.len() / 3) as f32 * reduction) as usize * 3;
+            let simplified = simplify_mesh(positions, &level_indices, target_index_count, max_error);
+            // Less than 5% fewer triangles isn't worth a level
+            if simplified.indices.is_empty() || simplified.indices.len() as f32 > level_indices.len() as f32 * 0.95 {
+                break;
+            }
+            // Each level is simplified from the previous one, its errors add up
+            error += simplified.error;
+            chain.levels.push(MeshLod {
+                first_index: chain.indices.len() as u32,
+                index_count: simplified.indices.len() as u32,
+                error,
+            });
+            chain.indices.extend_from_slice(&simplified.indices);
+            level_indices = simplified.indices;
+        }
+        chain
+    }
+
+    pub fn level_count(&self) -> usize { self.levels.len() }
+
+    // Draw of a level for `GpuCulling`, the level is clamped to the last one
+    pub fn draw(&self, level: usize) -> CullingDraw {
+        let lod = self.levels[level.min(self.levels.len() - 1)];
+        CullingDraw::Indexed {
+            index_count: lod.index_count,
+            first_index: lod.first_index,
+            base_vertex: 0,
+        }
+    }
+
+    // Indirect draw arguments of a level, the level is clamped to the last one
+    pub fn draw_indexed_args(&self, level: usize, instance_count: u32, first_instance: u32) -> wgpu::util::DrawIndexedIndirectArgs {
+        let lod = self.levels[level.min(self.levels.len() - 1)];
+        wgpu::util::DrawIndexedIndirectArgs {
+            index_count: lod.index_count,
+            instance_count,
+            first_index: lod.first_index,
+            base_vertex: 0,
+            first_instance,
+        }
+    }
+}
+
+// Distance based level selection: level i + 1 is used from `switch_distances[i]`
+#[derive(Clone, Debug, Default)]
+pub struct LodSelector {
+    pub switch_distances: Vec<f32>,
+}
+
+impl LodSelector {
+    // Distances must be increasing
+    pub fn new(switch_distances: Vec<f32>) -> Self { Self { switch_distances } }
+
+    // Each level is used from the distance where its error projects to less than `max_pixel_error` pixels,
+    // for a perspective projection of vertical field of view `fov_y` (radians) on a viewport of `viewport_height` pixels
+    pub fn from_screen_error(chain: &LodChain, fov_y: f32, viewport_height: f32, max_pixel_error: f32) -> Self {
+        let pixels_per_unit_at_unit_distance = viewport_height / (2.0 * (fov_y * 0.5).tan());
+        let mut previous_distance: f32 = 0.0;
+        Self::new(
+            chain.levels[1..]
+                .iter()
+                .map(|lod| {
+                    previous_distance = previous_distance.max(lod.error * pixels_per_unit_at_unit_distance / max_pixel_error);
+                    previous_distance
+                })
+                .collect(),
+        )
+    }
+
+    pub fn select(&self, distance: f32) -> usize { self.switch_distances.partition_point(|switch_distance| *switch_distance <= distance) }
+
+    // Level of an object from the distance of the camera to its bounds (world space)
+    pub fn select_for_bounds(&self, camera_position: Vec3, bounds: &BoundingSphere) -> usize {
+        self.select((bounds.center.distance(camera_position) - bounds.radius).max(0.0))
+    }
+
+    // Indices of the objects per level, for one instanced (indirect) draw per level. `groups` is resized to the level count,
+    // its vectors are cleared first to reuse their allocations.
+    pub fn group_instances(&self, camera_position: Vec3, bounds: &[BoundingSphere], groups: &mut Vec<Vec<u32>>) {
+        groups.resize_with(self.switch_distances.len() + 1, Vec::new);
+        groups.iter_mut().for_each(Vec::clear);
+        for (index, bounds) in bounds.iter().enumerate() {
+            groups[self.select_for_bounds(camera_position, bounds)].push(index as u32);
+        }
+    }
+}
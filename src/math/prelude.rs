@@ -0,0 +1,31 @@
+// `use oxyde::math::prelude::*;` for the glam types and the math helpers of an app
+pub use glam::{mat3, mat4, quat, vec2, vec3, vec4, Affine3A, EulerRot, IVec2, Mat3, Mat4, Quat, UVec2, UVec3, Vec2, Vec3, Vec3A, Vec4};
+
+pub use super::{
+    damp,
+    damp_factor,
+    half_life_to_rate,
+    linear_to_srgb,
+    linear_to_srgb_rgb,
+    linear_to_srgb_rgba,
+    linear_to_srgba8,
+    look_at,
+    look_rotation,
+    look_to,
+    move_towards,
+    opengl_to_wgpu,
+    orthographic,
+    perspective,
+    srgb_to_linear,
+    srgb_to_linear_rgb,
+    srgb_to_linear_rgba,
+    srgba8_to_linear,
+    Aabb,
+    Frustum,
+    Interpolate,
+    Plane,
+    Ray,
+    Viewport,
+    ViewportPolicy,
+};
+pub use crate::wgpu_utils::DepthConvention;
@@ -0,0 +1,51 @@
+use glam::{Mat3, Mat4, Quat, Vec3, Vec4};
+
+use crate::wgpu_utils::DepthConvention;
+
+// Right-handed perspective projection with the 0 to 1 depth range of wgpu, the far plane is at infinity without `far`.
+// The reversed infinite projection has the best depth precision with a floating point depth format.
+pub fn perspective(convention: DepthConvention, fov_y: f32, aspect_ratio: f32, near: f32, far: Option<f32>) -> Mat4 {
+    match (convention, far) {
+        (DepthConvention::Standard, Some(far)) => Mat4::perspective_rh(fov_y, aspect_ratio, near, far),
+        (DepthConvention::Standard, None) => Mat4::perspective_infinite_rh(fov_y, aspect_ratio, near),
+        // The depth of the planes swapped
+        (DepthConvention::Reversed, Some(far)) => Mat4::perspective_rh(fov_y, aspect_ratio, far, near),
+        (DepthConvention::Reversed, None) => Mat4::perspective_infinite_reverse_rh(fov_y, aspect_ratio, near),
+    }
+}
+
+// Right-handed orthographic projection with the 0 to 1 depth range of wgpu
+pub fn orthographic(convention: DepthConvention, left: f32, right: f32, bottom: f32, top: f32, near: f32, far: f32) -> Mat4 {
+    match convention {
+        DepthConvention::Standard => Mat4::orthographic_rh(left, right, bottom, top, near, far),
+        DepthConvention::Reversed => Mat4::orthographic_rh(left, right, bottom, top, far, near),
+    }
+}
+
+// Projection of an OpenGL convention (-1 to 1 depth range, e.g. `Mat4::perspective_rh_gl` or a loaded camera) remapped
+// to the 0 to 1 depth range of wgpu, standard depth convention
+pub fn opengl_to_wgpu(projection: Mat4) -> Mat4 {
+    Mat4::from_cols(Vec4::X, Vec4::Y, Vec4::new(0.0, 0.0, 0.5, 0.0), Vec4::new(0.0, 0.0, 0.5, 1.0)) * projection
+}
+
+// Right-handed view matrix of a camera at `eye` looking at `target`. `up` falls back to another axis when it is
+// aligned with the view direction (e.g. looking straight down with `Vec3::Y`).
+pub fn look_at(eye: Vec3, target: Vec3, up: Vec3) -> Mat4 { look_to(eye, target - eye, up) }
+
+// Right-handed view matrix of a camera at `eye` looking along `direction`
+pub fn look_to(eye: Vec3, direction: Vec3, up: Vec3) -> Mat4 { Mat4::from_rotation_translation(look_rotation(direction, up), eye).inverse() }
+
+// Rotation turning the -Z forward axis of a camera (or any right-handed -Z forward object) towards `direction`, with
+// its Y axis as close as possible to `up`
+pub fn look_rotation(direction: Vec3, up: Vec3) -> Quat {
+    let forward = match direction.normalize_or_zero() {
+        Vec3::ZERO => Vec3::NEG_Z,
+        forward => forward,
+    };
+    let mut right = forward.cross(up);
+    if right.length_squared() <= 1e-8 {
+        right = forward.cross(forward.any_orthogonal_vector());
+    }
+    let right = right.normalize();
+    Quat::from_mat3(&Mat3::from_cols(right, right.cross(forward), -forward))
+}
@@ -0,0 +1,102 @@
+use glam::{Mat4, Vec2, Vec3};
+
+use super::{Aabb, BoundingSphere, Plane};
+use crate::wgpu_utils::DepthConvention;
+
+// Rectangle of the render target the camera renders to, in physical pixels (e.g. the egui available rect)
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct Viewport {
+    pub position: Vec2,
+    pub size: Vec2,
+}
+
+impl Viewport {
+    pub fn new(position: Vec2, size: Vec2) -> Self { Self { position, size } }
+
+    pub fn from_size(width: u32, height: u32) -> Self { Self::new(Vec2::ZERO, Vec2::new(width as f32, height as f32)) }
+
+    pub fn aspect_ratio(&self) -> f32 { self.size.x / self.size.y }
+
+    pub fn contains(&self, position: Vec2) -> bool {
+        let local_position = position - self.position;
+        local_position.cmpge(Vec2::ZERO).all() && local_position.cmplt(self.size).all()
+    }
+
+    // Normalized device coordinates of a position in pixels, x right and y up in [-1, 1]
+    pub fn to_ndc(&self, position: Vec2) -> Vec2 {
+        let uv = (position - self.position) / self.size;
+        Vec2::new(uv.x * 2.0 - 1.0, 1.0 - uv.y * 2.0)
+    }
+
+    // Position in pixels of normalized device coordinates, inverse of `to_ndc`
+    pub fn from_ndc(&self, ndc: Vec2) -> Vec2 { self.position + Vec2::new(ndc.x + 1.0, 1.0 - ndc.y) * 0.5 * self.size }
+
+    // Same rectangle in another unit, e.g. `scaled(1.0 / scale_factor)` from physical pixels to logical points
+    pub fn scaled(&self, factor: f32) -> Self { Self::new(self.position * factor, self.size * factor) }
+}
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct Ray {
+    pub origin: Vec3,
+    // Normalized
+    pub direction: Vec3,
+}
+
+impl Ray {
+    pub fn new(origin: Vec3, direction: Vec3) -> Self { Self { origin, direction: direction.normalize() } }
+
+    // Ray from the near plane at a NDC position, for a projection * view matrix with the 0 to 1 depth range of wgpu
+    pub fn from_ndc(ndc: Vec2, view_proj: &Mat4) -> Self { Self::from_ndc_with_convention(ndc, view_proj, DepthConvention::Standard) }
+
+    // `from_ndc` for a projection with the depth convention, e.g. a reversed infinite projection
+    pub fn from_ndc_with_convention(ndc: Vec2, view_proj: &Mat4, convention: DepthConvention) -> Self {
+        let inverse_view_proj = view_proj.inverse();
+        let near = inverse_view_proj.project_point3(ndc.extend(convention.near_depth()));
+        // Halfway rather than on the far plane, which is at infinity for infinite projections
+        let further = inverse_view_proj.project_point3(ndc.extend(0.5));
+        Self::new(near, further - near)
+    }
+
+    // Ray through a position in pixels of the viewport (e.g. the cursor)
+    pub fn from_screen(position: Vec2, viewport: &Viewport, view_proj: &Mat4) -> Self { Self::from_ndc(viewport.to_ndc(position), view_proj) }
+
+    pub fn from_screen_with_convention(position: Vec2, viewport: &Viewport, view_proj: &Mat4, convention: DepthConvention) -> Self {
+        Self::from_ndc_with_convention(viewport.to_ndc(position), view_proj, convention)
+    }
+
+    pub fn at(&self, distance: f32) -> Vec3 { self.origin + self.direction * distance }
+
+    // The intersections return the distance along the ray of the closest hit in front of the origin
+
+    pub fn intersect_plane(&self, plane: &Plane) -> Option<f32> {
+        let denominator = plane.normal.dot(self.direction);
+        if denominator.abs() < f32::EPSILON {
+            return None;
+        }
+        let distance = -plane.signed_distance(self.origin) / denominator;
+        (distance >= 0.0).then_some(distance)
+    }
+
+    // Slab test, 0 when the origin is inside of the box
+    pub fn intersect_aabb(&self, aabb: &Aabb) -> Option<f32> {
+        let inverse_direction = self.direction.recip();
+        let t0 = (aabb.min - self.origin) * inverse_direction;
+        let t1 = (aabb.max - self.origin) * inverse_direction;
+        let near = t0.min(t1).max_element();
+        let far = t0.max(t1).min_element();
+        (near <= far && far >= 0.0).then_some(near.max(0.0))
+    }
+
+    // 0 when the origin is inside of the sphere
+    pub fn intersect_sphere(&self, sphere: &BoundingSphere) -> Option<f32> {
+        let to_center = sphere.center - self.origin;
+        let projection = to_center.dot(self.direction);
+        let discriminant = sphere.radius * sphere.radius - (to_center.length_squared() - projection * projection);
+        if discriminant < 0.0 {
+            return None;
+        }
+        let half_chord = discriminant.sqrt();
+        let (near, far) = (projection - half_chord, projection + half_chord);
+        (far >= 0.0).then_some(near.max(0.0))
+    }
+}
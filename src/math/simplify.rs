@@ -0,0 +1,265 @@
+use std::{
+    cmp::Ordering,
+    collections::{BinaryHeap, HashMap},
+};
+
+use glam::DVec3;
+
+// Border edges are kept in place by planes orthogonal to their triangle, weighted more than the surface planes
+const BORDER_WEIGHT: f64 = 10.0;
+
+// Symmetric 4x4 matrix of the sum of the squared distances to a set of planes (Garland and Heckbert)
+#[derive(Clone, Copy, Default)]
+struct Quadric([f64; 10]);
+
+impl Quadric {
+    fn from_plane(normal: DVec3, distance: f64, weight: f64) -> Self {
+        let (a, b, c, d) = (normal.x, normal.y, normal.z, distance);
+        Self([a * a, a * b, a * c, a * d, b * b, b * c, b * d, c * c, c * d, d * d].map(|value| value * weight))
+    }
+
+    fn add(&mut self, other: &Quadric) {
+        for (value, other_value) in self.0.iter_mut().zip(other.0) {
+            *value += other_value;
+        }
+    }
+
+    fn error(&self, point: DVec3) -> f64 {
+        let [aa, ab, ac, ad, bb, bc, bd, cc, cd, dd] = self.0;
+        let (x, y, z) = (point.x, point.y, point.z);
+        (x * (aa * x + ab * y + ac * z + ad)
+            + y * (ab * x + bb * y + bc * z + bd)
+            + z * (ac * x + bc * y + cc * z + cd)
+            + (ad * x + bd * y + cd * z + dd))
+            .max(0.0)
+    }
+}
+
+// Candidate collapse of `from` onto `to`, outdated once one of the vertices changed
+struct Collapse {
+    cost: f64,
+    from: u32,
+    to: u32,
+    versions: (u32, u32),
+}
+
+impl PartialEq for Collapse {
+    fn eq(&self, other: &Self) -> bool { self.cost == other.cost }
+}
+
+impl Eq for Collapse {}
+
+impl PartialOrd for Collapse {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> { Some(self.cmp(other)) }
+}
+
+// Reversed for the max heap to pop the cheapest collapse first
+impl Ord for Collapse {
+    fn cmp(&self, other: &Self) -> Ordering { other.cost.total_cmp(&self.cost) }
+}
+
+// Simplified triangle list of a mesh, see `simplify_mesh`
+#[derive(Clone, Debug)]
+pub struct SimplifiedMesh {
+    pub indices: Vec<u32>,
+    // Approximate distance of the simplified surface to the source, in mesh units
+    pub error: f32,
+}
+
+// Quadric error metrics simplification by edge collapses onto existing vertices, so the vertex buffer is kept and only the
+// indices change (unreferenced vertices stay). Collapses stop at `target_index_count` or when the error would exceed `max_error`.
+// Borders are preserved and vertices sharing their position with another one (uv or normal seams) are never moved, which keeps
+// the seams closed at the cost of a lower reduction around them.
+pub fn simplify_mesh(positions: &[[f32; 3]], indices: &[u32], target_index_count: usize, max_error: f32) -> SimplifiedMesh {
+    let points: Vec<DVec3> = positions.iter().map(|position| DVec3::from(position.map(f64::from))).collect();
+    let mut triangles: Vec<[u32; 3]> = indices.chunks_exact(3).map(|triangle| [triangle[0], triangle[1], triangle[2]]).collect();
+    let mut removed = vec![false; triangles.len()];
+
+    let mut position_counts: HashMap<[u32; 3], u32> = HashMap::new();
+    for position in positions {
+        *position_counts.entry(position.map(f32::to_bits)).or_default() += 1;
+    }
+    let locked: Vec<bool> = positions
+        .iter()
+        .map(|position| position_counts[&position.map(f32::to_bits)] > 1)
+        .collect();
+
+    let mut vertex_triangles: Vec<Vec<u32>> = vec![Vec::new(); points.len()];
+    let mut quadrics = vec![Quadric::default(); points.len()];
+    let mut edge_counts: HashMap<(u32, u32), (u32, u32)> = HashMap::new();
+    for (triangle_index, triangle) in triangles.iter().enumerate() {
+        for vertex in triangle {
+            vertex_triangles[*vertex as usize].push(triangle_index as u32);
+        }
+        let normal = triangle_normal(&points, triangle);
+        if normal == DVec3::ZERO {
+            continue;
+        }
+        let plane = Quadric::from_plane(normal, -normal.dot(points[triangle[0] as usize]), 1.0);
+        for corner in 0..3 {
+            quadrics[triangle[corner] as usize].add(&plane);
+            let (start, end) = (triangle[corner], triangle[(corner + 1) % 3]);
+            edge_counts
+                .entry((start.min(end), start.max(end)))
+                .or_insert((0, triangle_index as u32))
+                .0 += 1;
+        }
+    }
+    for (&(start, end), &(count, triangle_index)) in &edge_counts {
+        if count != 1 {
+            continue;
+        }
+        let (start_point, end_point) = (points[start as usize], points[end as usize]);
+        let border_normal = (end_point - start_point)
+            .cross(triangle_normal(&points, &triangles[triangle_index as usize]))
+            .normalize_or_zero();
+        let plane = Quadric::from_plane(border_normal, -border_normal.dot(start_point), BORDER_WEIGHT);
+        quadrics[start as usize].add(&plane);
+        quadrics[end as usize].add(&plane);
+    }
+
+    let mut versions = vec![0u32; points.len()];
+    let mut heap = BinaryHeap::new();
+    let push_collapse = |heap: &mut BinaryHeap<Collapse>, quadrics: &[Quadric], versions: &[u32], from: u32, to: u32| {
+        if from == to || locked[from as usize] {
+            return;
+        }
+        let mut quadric = quadrics[from as usize];
+        quadric.add(&quadrics[to as usize]);
+        heap.push(Collapse {
+            cost: quadric.error(points[to as usize]),
+            from,
+            to,
+            versions: (versions[from as usize], versions[to as usize]),
+        });
+    };
+    for triangle in &triangles {
+        for corner in 0..3 {
+            let (start, end) = (triangle[corner], triangle[(corner + 1) % 3]);
+            push_collapse(&mut heap, &quadrics, &versions, start, end);
+            push_collapse(&mut heap, &quadrics, &versions, end, start);
+        }
+    }
+
+    let max_cost = f64::from(max_error) * f64::from(max_error);
+    let mut triangle_count = triangles.len();
+    let mut max_collapse_cost: f64 = 0.0;
+    while triangle_count * 3 > target_index_count {
+        let Some(collapse) = heap.pop() else {
+            break;
+        };
+        let (from, to) = (collapse.from as usize, collapse.to as usize);
+        if collapse.versions != (versions[from], versions[to]) {
+            continue;
+        }
+        // Remaining valid collapses are all more expensive
+        if collapse.cost > max_cost {
+            break;
+        }
+        // Collapses flipping a remaining triangle are rejected
+        let flips = vertex_triangles[from].iter().any(|&triangle_index| {
+            let triangle = triangles[triangle_index as usize];
+            if removed[triangle_index as usize] || triangle.contains(&collapse.to) {
+                return false;
+            }
+            let moved = triangle.map(|vertex| if vertex == collapse.from { collapse.to } else { vertex });
+            let normal = triangle_normal(&points, &triangle);
+            normal != DVec3::ZERO && normal.dot(triangle_normal(&points, &moved)) <= 0.0
+        });
+        if flips {
+            continue;
+        }
+
+        for triangle_index in std::mem::take(&mut vertex_triangles[from]) {
+            if removed[triangle_index as usize] {
+                continue;
+            }
+            let triangle = &mut triangles[triangle_index as usize];
+            if triangle.contains(&collapse.to) {
+                removed[triangle_index as usize] = true;
+                triangle_count -= 1;
+            } else {
+                for vertex in triangle.iter_mut().filter(|vertex| **vertex == collapse.from) {
+                    *vertex = collapse.to;
+                }
+                vertex_triangles[to].push(triangle_index);
+            }
+        }
+        let from_quadric = quadrics[from];
+        quadrics[to].add(&from_quadric);
+        versions[from] += 1;
+        versions[to] += 1;
+        max_collapse_cost = max_collapse_cost.max(collapse.cost);
+
+        vertex_triangles[to].retain(|triangle_index| !removed[*triangle_index as usize]);
+        for &triangle_index in &vertex_triangles[to] {
+            for vertex in triangles[triangle_index as usize] {
+                push_collapse(&mut heap, &quadrics, &versions, collapse.to, vertex);
+                push_collapse(&mut heap, &quadrics, &versions, vertex, collapse.to);
+            }
+        }
+    }
+
+    SimplifiedMesh {
+        indices: triangles
+            .iter()
+            .zip(&removed)
+            .filter(|(_, removed)| !**removed)
+            .flat_map(|(triangle, _)| *triangle)
+            .collect(),
+        error: max_collapse_cost.sqrt() as f32,
+    }
+}
+
+// Unit normal, zero for degenerate triangles
+fn triangle_normal(points: &[DVec3], triangle: &[u32; 3]) -> DVec3 {
+    let [p0, p1, p2] = triangle.map(|vertex| points[vertex as usize]);
+    (p1 - p0).cross(p2 - p0).normalize_or_zero()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Flat grid of `size` x `size` quads, each split in two triangles
+    fn grid(size: u32) -> (Vec<[f32; 3]>, Vec<u32>) {
+        let positions = (0..=size)
+            .flat_map(|y| (0..=size).map(move |x| [x as f32, y as f32, 0.0]))
+            .collect();
+        let indices = (0..size)
+            .flat_map(|y| (0..size).map(move |x| y * (size + 1) + x))
+            .flat_map(|corner| [corner, corner + 1, corner + size + 2, corner, corner + size + 2, corner + size + 1])
+            .collect();
+        (positions, indices)
+    }
+
+    #[test]
+    fn flat_grid_is_reduced_without_error() {
+        let (positions, indices) = grid(8);
+        let simplified = simplify_mesh(&positions, &indices, 0, 1.0e-3);
+        assert!(simplified.indices.len() < indices.len() / 4, "{}", simplified.indices.len());
+        assert!(simplified.error < 1.0e-3);
+        // The corners are on two borders, they are kept
+        for corner in [0, 8, 72, 80] {
+            assert!(simplified.indices.contains(&corner), "{corner}");
+        }
+    }
+
+    #[test]
+    fn target_index_count_is_a_lower_bound() {
+        let (positions, indices) = grid(4);
+        let simplified = simplify_mesh(&positions, &indices, 48, f32::MAX);
+        assert!(simplified.indices.len() >= 48 && simplified.indices.len() < indices.len());
+    }
+
+    #[test]
+    fn max_error_keeps_a_bent_surface() {
+        let (mut positions, indices) = grid(4);
+        // Tent along the middle column
+        for position in &mut positions {
+            position[2] = 2.0 - (position[0] - 2.0).abs();
+        }
+        let simplified = simplify_mesh(&positions, &indices, 0, 1.0e-3);
+        assert!(simplified.indices.iter().any(|vertex| positions[*vertex as usize][0] == 2.0));
+    }
+}
@@ -0,0 +1,227 @@
+use glam::{Vec2, Vec3};
+
+// Tangents of a mesh split where its vertices need several tangents (uv seams, mirrored uvs), see
+// `generate_mikktspace_tangents`. The other attributes of the new vertex `i` are the ones of `vertex_sources[i]`.
+#[cfg(feature = "mikktspace")]
+#[derive(Clone, Debug, PartialEq)]
+pub struct SplitTangents {
+    pub tangents: Vec<[f32; 4]>,
+    // Index of the source vertex of each new vertex
+    pub vertex_sources: Vec<u32>,
+    // Triangles indexing the new vertices
+    pub indices: Vec<u32>,
+}
+
+#[cfg(feature = "mikktspace")]
+struct MikktspaceGeometry<'a> {
+    positions: &'a [[f32; 3]],
+    normals: &'a [[f32; 3]],
+    uvs: &'a [[f32; 2]],
+    triangles: Vec<[u32; 3]>,
+    corner_tangents: Vec<[f32; 4]>,
+}
+
+#[cfg(feature = "mikktspace")]
+impl MikktspaceGeometry<'_> {
+    fn vertex(&self, face: usize, vert: usize) -> usize { self.triangles[face][vert] as usize }
+}
+
+#[cfg(feature = "mikktspace")]
+impl bevy_mikktspace::Geometry for MikktspaceGeometry<'_> {
+    fn num_faces(&self) -> usize { self.triangles.len() }
+
+    fn num_vertices_of_face(&self, _face: usize) -> usize { 3 }
+
+    fn position(&self, face: usize, vert: usize) -> [f32; 3] { self.positions[self.vertex(face, vert)] }
+
+    fn normal(&self, face: usize, vert: usize) -> [f32; 3] { self.normals[self.vertex(face, vert)] }
+
+    // MikkTSpace expects the OpenGL uv convention (origin at the bottom left), as the tools baking normal maps with it
+    fn tex_coord(&self, face: usize, vert: usize) -> [f32; 2] {
+        let [u, v] = self.uvs[self.vertex(face, vert)];
+        [u, 1.0 - v]
+    }
+
+    fn set_tangent_encoded(&mut self, tangent: [f32; 4], face: usize, vert: usize) { self.corner_tangents[face * 3 + vert] = tangent; }
+}
+
+// MikkTSpace tangents (as expected by normal maps baked by Blender, Substance... and by glTF), same conventions as
+// `generate_tangents`. MikkTSpace computes a tangent per triangle corner, the vertices whose corners get different
+// tangents are split. Returns None if MikkTSpace rejects the mesh (e.g. no triangle).
+#[cfg(feature = "mikktspace")]
+pub fn generate_mikktspace_tangents(positions: &[[f32; 3]], normals: &[[f32; 3]], uvs: &[[f32; 2]], indices: Option<&[u32]>) -> Option<SplitTangents> {
+    assert!(
+        positions.len() == normals.len() && positions.len() == uvs.len(),
+        "Tangent generation needs as many normals ({}) and uvs ({}) as positions ({})",
+        normals.len(),
+        uvs.len(),
+        positions.len()
+    );
+
+    let triangles: Vec<[u32; 3]> = match indices {
+        Some(indices) => indices.chunks_exact(3).map(|triangle| [triangle[0], triangle[1], triangle[2]]).collect(),
+        None => (0..positions.len() as u32 / 3).map(|triangle| [triangle * 3, triangle * 3 + 1, triangle * 3 + 2]).collect(),
+    };
+    let mut geometry = MikktspaceGeometry {
+        positions,
+        normals,
+        uvs,
+        corner_tangents: vec![[0.0; 4]; triangles.len() * 3],
+        triangles,
+    };
+    if !bevy_mikktspace::generate_tangents(&mut geometry) {
+        return None;
+    }
+
+    // Corners of a vertex sharing the exact same tangent share a new vertex
+    let mut split_vertices = std::collections::HashMap::new();
+    let mut split = SplitTangents {
+        tangents: Vec::new(),
+        vertex_sources: Vec::new(),
+        indices: Vec::with_capacity(geometry.corner_tangents.len()),
+    };
+    for (corner, tangent) in geometry.corner_tangents.iter().enumerate() {
+        let source = geometry.triangles[corner / 3][corner % 3];
+        let index = *split_vertices.entry((source, tangent.map(f32::to_bits))).or_insert_with(|| {
+            split.tangents.push(*tangent);
+            split.vertex_sources.push(source);
+            split.tangents.len() as u32 - 1
+        });
+        split.indices.push(index);
+    }
+    Some(split)
+}
+
+// Per vertex tangents for normal mapping, (x, y, z, w) with w the bitangent sign: bitangent = cross(normal, tangent.xyz) * w
+// (glTF convention). Angle weighted accumulation of the triangle tangents, orthogonalized against the normal. This is not
+// MikkTSpace: vertices shared by triangles with mirrored uvs get an averaged (or cancelled) tangent and normal maps baked
+// by other tools won't match exactly, see `generate_mikktspace_tangents`. The uvs have the glTF convention (origin at the
+// top left) and the normal maps the OpenGL one (green up). `indices` lists the triangles, None for a non indexed triangle
+// list. Vertices without usable uvs get an arbitrary tangent orthogonal to their normal.
+pub fn generate_tangents(positions: &[[f32; 3]], normals: &[[f32; 3]], uvs: &[[f32; 2]], indices: Option<&[u32]>) -> Vec<[f32; 4]> {
+    assert!(
+        positions.len() == normals.len() && positions.len() == uvs.len(),
+        "Tangent generation needs as many normals ({}) and uvs ({}) as positions ({})",
+        normals.len(),
+        uvs.len(),
+        positions.len()
+    );
+
+    let mut tangents = vec![Vec3::ZERO; positions.len()];
+    let mut bitangents = vec![Vec3::ZERO; positions.len()];
+    let mut accumulate_triangle = |triangle: [usize; 3]| {
+        let [p0, p1, p2] = triangle.map(|index| Vec3::from(positions[index]));
+        // v is flipped so the bitangent points up in the texture, as the green channel of the normal maps
+        let [uv0, uv1, uv2] = triangle.map(|index| Vec2::new(uvs[index][0], -uvs[index][1]));
+        let (edge1, edge2) = (p1 - p0, p2 - p0);
+        let (delta_uv1, delta_uv2) = (uv1 - uv0, uv2 - uv0);
+        let determinant = delta_uv1.perp_dot(delta_uv2);
+        if determinant.abs() <= f32::EPSILON {
+            return;
+        }
+        let tangent = (edge1 * delta_uv2.y - edge2 * delta_uv1.y) / determinant;
+        let bitangent = (edge2 * delta_uv1.x - edge1 * delta_uv2.x) / determinant;
+        // Only the direction of the triangle tangents is kept, weighted by the corner angle at each vertex
+        let (tangent, bitangent) = (tangent.normalize_or_zero(), bitangent.normalize_or_zero());
+        for corner in 0..3 {
+            let position = Vec3::from(positions[triangle[corner]]);
+            let to_next = Vec3::from(positions[triangle[(corner + 1) % 3]]) - position;
+            let to_previous = Vec3::from(positions[triangle[(corner + 2) % 3]]) - position;
+            let angle = to_next.angle_between(to_previous);
+            if angle.is_finite() {
+                tangents[triangle[corner]] += tangent * angle;
+                bitangents[triangle[corner]] += bitangent * angle;
+            }
+        }
+    };
+    match indices {
+        Some(indices) => indices
+            .chunks_exact(3)
+            .for_each(|triangle| accumulate_triangle([triangle[0] as usize, triangle[1] as usize, triangle[2] as usize])),
+        None => (0..positions.len() / 3).for_each(|triangle| accumulate_triangle([triangle * 3, triangle * 3 + 1, triangle * 3 + 2])),
+    }
+
+    normals
+        .iter()
+        .zip(tangents.iter().zip(&bitangents))
+        .map(|(normal, (tangent, bitangent))| {
+            let normal = Vec3::from(*normal).normalize_or_zero();
+            // Gram-Schmidt orthogonalization against the normal
+            let tangent = (*tangent - normal * normal.dot(*tangent))
+                .try_normalize()
+                .unwrap_or_else(|| normal.any_orthonormal_vector());
+            let sign = if normal.cross(tangent).dot(*bitangent) < 0.0 {
+                -1.0
+            } else {
+                1.0
+            };
+            tangent.extend(sign).to_array()
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    type Quad = ([[f32; 3]; 4], [[f32; 3]; 4], [[f32; 2]; 4], [u32; 6]);
+
+    // Quad in the XY plane facing +Z, u along +X and v (glTF, top left origin) along -Y
+    fn quad() -> Quad {
+        (
+            [[0.0, 0.0, 0.0], [1.0, 0.0, 0.0], [1.0, 1.0, 0.0], [0.0, 1.0, 0.0]],
+            [[0.0, 0.0, 1.0]; 4],
+            [[0.0, 1.0], [1.0, 1.0], [1.0, 0.0], [0.0, 0.0]],
+            [0, 1, 2, 0, 2, 3],
+        )
+    }
+
+    #[test]
+    fn quad_tangents_follow_u() {
+        let (positions, normals, uvs, indices) = quad();
+        for tangent in generate_tangents(&positions, &normals, &uvs, Some(&indices)) {
+            assert!(Vec3::from_slice(&tangent).abs_diff_eq(Vec3::X, 1.0e-5), "{tangent:?}");
+            assert_eq!(tangent[3], 1.0);
+        }
+    }
+
+    #[test]
+    fn mirrored_uvs_flip_the_sign() {
+        let (positions, normals, mut uvs, indices) = quad();
+        uvs.iter_mut().for_each(|uv| uv[0] = 1.0 - uv[0]);
+        for tangent in generate_tangents(&positions, &normals, &uvs, Some(&indices)) {
+            assert!(Vec3::from_slice(&tangent).abs_diff_eq(-Vec3::X, 1.0e-5), "{tangent:?}");
+            assert_eq!(tangent[3], -1.0);
+        }
+    }
+
+    #[cfg(feature = "mikktspace")]
+    #[test]
+    fn mikktspace_matches_on_a_quad() {
+        let (positions, normals, uvs, indices) = quad();
+        let split = generate_mikktspace_tangents(&positions, &normals, &uvs, Some(&indices)).unwrap();
+        assert_eq!(split.vertex_sources.len(), 4);
+        for tangent in split.tangents {
+            assert!(Vec3::from_slice(&tangent).abs_diff_eq(Vec3::X, 1.0e-5), "{tangent:?}");
+            assert_eq!(tangent[3], 1.0);
+        }
+    }
+
+    #[cfg(feature = "mikktspace")]
+    #[test]
+    fn mikktspace_splits_mirrored_seams() {
+        // Two quads sharing the edge x = 1, the second one with mirrored uvs
+        let positions = [[0.0, 0.0, 0.0], [1.0, 0.0, 0.0], [1.0, 1.0, 0.0], [0.0, 1.0, 0.0], [2.0, 0.0, 0.0], [2.0, 1.0, 0.0]];
+        let normals = [[0.0, 0.0, 1.0]; 6];
+        let uvs = [[0.0, 1.0], [1.0, 1.0], [1.0, 0.0], [0.0, 0.0], [0.0, 1.0], [0.0, 0.0]];
+        let indices = [0, 1, 2, 0, 2, 3, 1, 4, 5, 1, 5, 2];
+        let split = generate_mikktspace_tangents(&positions, &normals, &uvs, Some(&indices)).unwrap();
+        // The two seam vertices are split
+        assert_eq!(split.vertex_sources.len(), 8);
+        for (corner, index) in split.indices.iter().enumerate() {
+            let expected_sign = if corner < 6 { 1.0 } else { -1.0 };
+            assert_eq!(split.tangents[*index as usize][3], expected_sign, "corner {corner}");
+            assert_eq!(split.vertex_sources[*index as usize], indices[corner]);
+        }
+    }
+}
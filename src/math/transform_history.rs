@@ -0,0 +1,37 @@
+use glam::Mat4;
+
+use crate::wgpu_utils::MotionUniforms;
+
+// Matrix of the current frame and of the previous one (e.g. the camera view projection or an object model matrix),
+// `update` is called once per frame, even when the matrix didn't change, to keep the previous frame in sync
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct TransformHistory {
+    pub current: Mat4,
+    pub previous: Mat4,
+}
+
+impl Default for TransformHistory {
+    fn default() -> Self { Self::new(Mat4::IDENTITY) }
+}
+
+impl TransformHistory {
+    pub fn new(matrix: Mat4) -> Self { Self { current: matrix, previous: matrix } }
+
+    pub fn update(&mut self, matrix: Mat4) {
+        self.previous = self.current;
+        self.current = matrix;
+    }
+
+    // Jump without motion (camera cuts, spawned objects)
+    pub fn teleport(&mut self, matrix: Mat4) { *self = Self::new(matrix); }
+
+    pub fn has_moved(&self) -> bool { self.current != self.previous }
+
+    // Motion uniforms of a camera history tracking the unjittered view projection
+    pub fn motion_uniforms(&self) -> MotionUniforms {
+        MotionUniforms {
+            view_projection: self.current.to_cols_array_2d(),
+            previous_view_projection: self.previous.to_cols_array_2d(),
+        }
+    }
+}
@@ -0,0 +1,21 @@
+use anyhow::Result;
+use winit::event::Event;
+
+use crate::app::{AppState, FrameContext};
+
+// Feature composed into any app with `AppConfig::with_plugin` (e.g. the `ScreenshotPlugin` hotkey or the `DebugPanel`
+// overlay), its hooks are called in the plugins order before the matching `App` callbacks, except `post_render` and `gui`
+// called after them
+pub trait Plugin {
+    // Called once the app state is created, before `App::create`
+    fn setup(&mut self, _app_state: &mut AppState) -> Result<()> { Ok(()) }
+
+    fn handle_event(&mut self, _app_state: &mut AppState, _event: &Event<()>) -> Result<()> { Ok(()) }
+
+    fn pre_update(&mut self, _app_state: &mut AppState, _frame: &mut FrameContext) -> Result<()> { Ok(()) }
+
+    fn post_render(&mut self, _app_state: &mut AppState) -> Result<()> { Ok(()) }
+
+    #[cfg(feature = "egui")]
+    fn gui(&mut self, _app_state: &mut AppState) -> Result<()> { Ok(()) }
+}
@@ -0,0 +1,124 @@
+use anyhow::Result;
+
+use crate::app::{AppState, FrameContext};
+
+pub type RenderLayerFn = Box<dyn FnMut(&mut AppState, &mut FrameContext) -> Result<()>>;
+
+pub(crate) enum RenderLayerKind {
+    // `App::render`
+    App,
+    #[cfg(feature = "egui")]
+    Egui,
+    Custom(RenderLayerFn),
+}
+
+pub(crate) struct RenderLayer {
+    pub(crate) name: String,
+    pub(crate) order: i32,
+    pub(crate) enabled: bool,
+    pub(crate) kind: RenderLayerKind,
+}
+
+// Named layers recorded in order into the frame encoder (world, debug draw, HUD...), the app rendering and the egui pass
+// being the built-in `APP` and `EGUI` layers. Layers of the same order are rendered in their insertion order.
+pub struct RenderLayers {
+    // Sorted by order
+    layers: Vec<RenderLayer>,
+}
+
+impl Default for RenderLayers {
+    fn default() -> Self {
+        let mut render_layers = Self::empty();
+        render_layers.insert(RenderLayer {
+            name: Self::APP.to_string(),
+            order: Self::APP_ORDER,
+            enabled: true,
+            kind: RenderLayerKind::App,
+        });
+        #[cfg(feature = "egui")]
+        render_layers.insert(RenderLayer {
+            name: Self::EGUI.to_string(),
+            order: Self::EGUI_ORDER,
+            enabled: true,
+            kind: RenderLayerKind::Egui,
+        });
+        render_layers
+    }
+}
+
+impl RenderLayers {
+    pub const APP: &'static str = "app";
+    pub const APP_ORDER: i32 = 0;
+    #[cfg(feature = "egui")]
+    pub const EGUI: &'static str = "egui";
+    #[cfg(feature = "egui")]
+    pub const EGUI_ORDER: i32 = 1000;
+
+    // Without the built-in layers, used while the layers are taken out of the state to be rendered
+    pub(crate) fn empty() -> Self { Self { layers: Vec::new() } }
+
+    fn insert(&mut self, layer: RenderLayer) {
+        let index = self.layers.partition_point(|other| other.order <= layer.order);
+        self.layers.insert(index, layer);
+    }
+
+    fn position(&self, name: &str) -> Option<usize> { self.layers.iter().position(|layer| layer.name == name) }
+
+    // Add an enabled layer rendered by `render`, replacing the custom layer of the same name. Panics for the name of a
+    // built-in layer.
+    pub fn add(&mut self, name: impl Into<String>, order: i32, render: impl FnMut(&mut AppState, &mut FrameContext) -> Result<()> + 'static) {
+        let name = name.into();
+        if let Some(index) = self.position(&name) {
+            assert!(
+                matches!(self.layers[index].kind, RenderLayerKind::Custom(_)),
+                "The built-in render layer \"{}\" can't be replaced",
+                name
+            );
+            self.layers.remove(index);
+        }
+        self.insert(RenderLayer {
+            name,
+            order,
+            enabled: true,
+            kind: RenderLayerKind::Custom(Box::new(render)),
+        });
+    }
+
+    // Remove a custom layer, returns false if there is none of this name (built-in layers can only be disabled)
+    pub fn remove(&mut self, name: &str) -> bool {
+        match self.position(name) {
+            Some(index) if matches!(self.layers[index].kind, RenderLayerKind::Custom(_)) => {
+                self.layers.remove(index);
+                true
+            },
+            _ => false,
+        }
+    }
+
+    pub fn contains(&self, name: &str) -> bool { self.position(name).is_some() }
+
+    // Returns false if there is no layer of this name
+    pub fn set_enabled(&mut self, name: &str, enabled: bool) -> bool {
+        self.position(name).map(|index| self.layers[index].enabled = enabled).is_some()
+    }
+
+    pub fn is_enabled(&self, name: &str) -> bool { self.position(name).is_some_and(|index| self.layers[index].enabled) }
+
+    // Move a layer, after the layers already of this order. Returns false if there is no layer of this name.
+    pub fn set_order(&mut self, name: &str, order: i32) -> bool {
+        let Some(index) = self.position(name) else {
+            return false;
+        };
+        let mut layer = self.layers.remove(index);
+        layer.order = order;
+        self.insert(layer);
+        true
+    }
+
+    pub fn order(&self, name: &str) -> Option<i32> { self.position(name).map(|index| self.layers[index].order) }
+
+    // Names of the layers in render order, with their enable flag
+    pub fn iter(&self) -> impl Iterator<Item = (&str, bool)> { self.layers.iter().map(|layer| (layer.name.as_str(), layer.enabled)) }
+
+    pub(crate) fn enabled_layers_mut(&mut self) -> impl Iterator<Item = &mut RenderLayer> { self.layers.iter_mut().filter(|layer| layer.enabled) }
+}
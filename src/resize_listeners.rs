@@ -0,0 +1,85 @@
+use crate::{
+    resources::Resources,
+    wgpu_utils::{DepthPrepass, GBuffer, ObjectPicking, TemporalAccumulation, WeightedBlendedOit},
+};
+
+// New sizes after a window resize or a scale factor change
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct ResizeEvent {
+    // Size of the view `App::render` renders to (the canvas with a canvas), the size of the screen sized resources
+    pub width: u32,
+    pub height: u32,
+    pub surface_width: u32,
+    pub surface_height: u32,
+    pub scale_factor: f64,
+}
+
+// Resource recreated at the size of the app view, see `ResizeListeners::add_resource`
+pub trait ResizeListener {
+    fn on_resize(&mut self, device: &wgpu::Device, event: &ResizeEvent);
+}
+
+macro_rules! resize_listener {
+    ($($type:ty),* $(,)?) => {
+        $(impl ResizeListener for $type {
+            fn on_resize(&mut self, device: &wgpu::Device, event: &ResizeEvent) {
+                self.resize(device, event.width, event.height);
+            }
+        })*
+    };
+}
+
+resize_listener!(DepthPrepass, GBuffer, ObjectPicking, TemporalAccumulation, WeightedBlendedOit);
+
+type ResizeFn = Box<dyn FnMut(&wgpu::Device, &mut Resources, &ResizeEvent)>;
+
+// Screen sized resources recreated together when the window is resized or changes of scale factor, after the surface,
+// the canvas and `AppState::render_targets` and before `App::on_resize`. Listeners are called in the order they are
+// added, e.g. a post-processing chain after the G-buffer it reads.
+#[derive(Default)]
+pub struct ResizeListeners {
+    listeners: Vec<(String, ResizeFn)>,
+}
+
+impl ResizeListeners {
+    pub fn new() -> Self { Self::default() }
+
+    // Replaces the listener with the same name if any. Resources owned by the app are shared with the closure (e.g. an
+    // `Rc<RefCell<_>>`) or stored in `AppState::resources`.
+    pub fn add(&mut self, name: &str, listener: impl FnMut(&wgpu::Device, &mut Resources, &ResizeEvent) + 'static) {
+        let listener: ResizeFn = Box::new(listener);
+        match self.listeners.iter_mut().find(|(listener_name, _)| listener_name == name) {
+            Some((_, previous)) => *previous = listener,
+            None => self.listeners.push((name.to_string(), listener)),
+        }
+    }
+
+    // Resize the resource of this type in `AppState::resources` (skipped while there is none), named after the type
+    pub fn add_resource<T: ResizeListener + 'static>(&mut self) {
+        self.add(std::any::type_name::<T>(), |device, resources, event| {
+            if let Some(resource) = resources.get_mut::<T>() {
+                resource.on_resize(device, event);
+            }
+        });
+    }
+
+    pub fn remove(&mut self, name: &str) -> bool {
+        let count = self.listeners.len();
+        self.listeners.retain(|(listener_name, _)| listener_name != name);
+        self.listeners.len() != count
+    }
+
+    pub fn contains(&self, name: &str) -> bool { self.listeners.iter().any(|(listener_name, _)| listener_name == name) }
+
+    pub fn names(&self) -> impl Iterator<Item = &str> { self.listeners.iter().map(|(name, _)| name.as_str()) }
+
+    pub fn len(&self) -> usize { self.listeners.len() }
+
+    pub fn is_empty(&self) -> bool { self.listeners.is_empty() }
+
+    pub fn notify(&mut self, device: &wgpu::Device, resources: &mut Resources, event: &ResizeEvent) {
+        for (_, listener) in &mut self.listeners {
+            listener(device, resources, event);
+        }
+    }
+}
@@ -0,0 +1,48 @@
+use std::{
+    any::{Any, TypeId},
+    collections::HashMap,
+};
+
+// Values indexed by their type, at most one per type (e.g. a camera or a profiler shared by the app, its plugins and
+// render layers). Wrap values in a newtype to store several values of the same type.
+#[derive(Default)]
+pub struct Resources {
+    values: HashMap<TypeId, Box<dyn Any>>,
+}
+
+impl Resources {
+    pub fn new() -> Self { Self::default() }
+
+    // Returns the replaced value of this type, if any
+    pub fn insert<T: 'static>(&mut self, value: T) -> Option<T> {
+        self.values
+            .insert(TypeId::of::<T>(), Box::new(value))
+            .map(|previous| *previous.downcast::<T>().expect("Resource stored under the TypeId of another type"))
+    }
+
+    pub fn remove<T: 'static>(&mut self) -> Option<T> {
+        self.values
+            .remove(&TypeId::of::<T>())
+            .map(|value| *value.downcast::<T>().expect("Resource stored under the TypeId of another type"))
+    }
+
+    pub fn contains<T: 'static>(&self) -> bool { self.values.contains_key(&TypeId::of::<T>()) }
+
+    pub fn get<T: 'static>(&self) -> Option<&T> { self.values.get(&TypeId::of::<T>()).and_then(|value| value.downcast_ref()) }
+
+    pub fn get_mut<T: 'static>(&mut self) -> Option<&mut T> { self.values.get_mut(&TypeId::of::<T>()).and_then(|value| value.downcast_mut()) }
+
+    pub fn get_or_insert_with<T: 'static>(&mut self, create: impl FnOnce() -> T) -> &mut T {
+        self.values
+            .entry(TypeId::of::<T>())
+            .or_insert_with(|| Box::new(create()))
+            .downcast_mut()
+            .expect("Resource stored under the TypeId of another type")
+    }
+
+    pub fn len(&self) -> usize { self.values.len() }
+
+    pub fn is_empty(&self) -> bool { self.values.is_empty() }
+
+    pub fn clear(&mut self) { self.values.clear(); }
+}
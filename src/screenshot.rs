@@ -0,0 +1,261 @@
+use std::{
+    path::{Path, PathBuf},
+    sync::mpsc::{channel, Receiver, Sender},
+    time::{Duration, Instant, SystemTime, UNIX_EPOCH},
+};
+
+use anyhow::Result;
+use winit::{
+    event::{ElementState, Event, WindowEvent},
+    keyboard,
+};
+
+use crate::{
+    app::AppState,
+    plugin::Plugin,
+    wgpu_utils::{buffers::MapFuture, texels_to_image, TextureReadback},
+};
+
+enum SlotState {
+    Free,
+    // Copy encoded in the frame, mapped once the frame is submitted
+    Copied(PathBuf),
+    Mapping(PathBuf, MapFuture),
+}
+
+struct ScreenshotSlot {
+    readback: TextureReadback,
+    state: SlotState,
+}
+
+// Captures of the presented frames without stalling the frame loop: the surface texture is copied into one of a ring of
+// staging buffers, mapped asynchronously and the image is encoded and written on a background thread.
+// Usage per frame: `encode_capture` before the frame submit, `after_submit` after it then `process` (polls the device).
+pub struct Screenshots {
+    directory: PathBuf,
+    ring_size: usize,
+    slots: Vec<ScreenshotSlot>,
+    // Paths of the requested captures, taken by the next presented frames
+    requests: Vec<PathBuf>,
+    // Keep the alpha channel of the surface, otherwise the images are saved opaque
+    keep_alpha: bool,
+    results_sender: Sender<Result<PathBuf, String>>,
+    results_receiver: Receiver<Result<PathBuf, String>>,
+    // Message of the last written (or failed) capture with the time it was received
+    toast: Option<(String, Instant)>,
+}
+
+impl Screenshots {
+    pub const TOAST_DURATION: Duration = Duration::from_secs(3);
+
+    pub fn new(directory: impl Into<PathBuf>, ring_size: usize) -> Self {
+        let (results_sender, results_receiver) = channel();
+        Self {
+            directory: directory.into(),
+            ring_size: ring_size.max(1),
+            slots: Vec::new(),
+            requests: Vec::new(),
+            keep_alpha: false,
+            results_sender,
+            results_receiver,
+            toast: None,
+        }
+    }
+
+    pub fn with_keep_alpha(mut self, keep_alpha: bool) -> Self {
+        self.keep_alpha = keep_alpha;
+        self
+    }
+
+    pub fn directory(&self) -> &Path { &self.directory }
+
+    pub fn set_directory(&mut self, directory: impl Into<PathBuf>) { self.directory = directory.into(); }
+
+    // Capture the next presented frame in the screenshot directory, named after the current time
+    pub fn request(&mut self) {
+        let timestamp = SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default();
+        let file_name = format!("screenshot_{}_{:03}.png", timestamp.as_secs(), timestamp.subsec_millis());
+        self.request_to(self.directory.join(file_name));
+    }
+
+    pub fn request_to(&mut self, path: impl Into<PathBuf>) { self.requests.push(path.into()); }
+
+    // Captures copied or being mapped, not counting the ones being written
+    pub fn pending_count(&self) -> usize { self.requests.len() + self.slots.iter().filter(|slot| !matches!(slot.state, SlotState::Free)).count() }
+
+    // Copy the surface texture into a free staging buffer if a capture is requested. The request is kept for the next frame
+    // when the whole ring is still in use.
+    pub fn encode_capture(&mut self, device: &wgpu::Device, encoder: &mut wgpu::CommandEncoder, texture: &wgpu::Texture) {
+        if self.requests.is_empty() {
+            return;
+        }
+        if !texture.usage().contains(wgpu::TextureUsages::COPY_SRC) {
+            let path = self.requests.remove(0);
+            self.send_result(Err(format!("Can't capture {}, the surface doesn't support copies", path.display())));
+            return;
+        }
+
+        // Buffers of another size or format (e.g. after a resize) are recreated when free
+        let matches_texture = |readback: &TextureReadback| {
+            readback.format() == texture.format() && readback.width() == texture.width() && readback.height() == texture.height()
+        };
+        self.slots
+            .retain(|slot| !matches!(slot.state, SlotState::Free) || matches_texture(&slot.readback));
+
+        let slot_index = match self.slots.iter().position(|slot| matches!(slot.state, SlotState::Free)) {
+            Some(slot_index) => slot_index,
+            None if self.slots.len() < self.ring_size => match TextureReadback::for_texture(device, texture, Some("Screenshot")) {
+                Ok(readback) => {
+                    self.slots.push(ScreenshotSlot { readback, state: SlotState::Free });
+                    self.slots.len() - 1
+                },
+                Err(error) => {
+                    let path = self.requests.remove(0);
+                    self.send_result(Err(format!("Can't capture {}: {}", path.display(), error)));
+                    return;
+                },
+            },
+            None => return,
+        };
+
+        let slot = &mut self.slots[slot_index];
+        slot.readback.encode_copy(encoder, texture);
+        slot.state = SlotState::Copied(self.requests.remove(0));
+    }
+
+    // Request the mapping of the buffers copied in the submitted frame
+    pub fn after_submit(&mut self) {
+        for slot in &mut self.slots {
+            if let SlotState::Copied(path) = &slot.state {
+                slot.state = SlotState::Mapping(path.clone(), slot.readback.map());
+            }
+        }
+    }
+
+    // Hand the mapped captures to background threads writing the images and collect the written ones
+    pub fn process(&mut self, device: &wgpu::Device) {
+        if self.slots.iter().any(|slot| matches!(slot.state, SlotState::Mapping(..))) {
+            device.poll(wgpu::Maintain::Poll);
+        }
+
+        for slot in &mut self.slots {
+            let SlotState::Mapping(_, map_future) = &slot.state else {
+                continue;
+            };
+            let Some(map_result) = map_future.try_take_result() else {
+                continue;
+            };
+            let SlotState::Mapping(path, _) = std::mem::replace(&mut slot.state, SlotState::Free) else {
+                unreachable!()
+            };
+            if let Err(error) = map_result {
+                let _ = self.results_sender.send(Err(format!("Can't capture {}: {}", path.display(), error)));
+                continue;
+            }
+
+            // Only the copy out of the mapped buffer is done on the frame loop
+            let bytes = slot.readback.read_bytes();
+            let (format, width, height) = (slot.readback.format(), slot.readback.width(), slot.readback.height());
+            let keep_alpha = self.keep_alpha;
+            let results_sender = self.results_sender.clone();
+            std::thread::spawn(move || {
+                let result = write_image(&path, format, width, height, bytes, keep_alpha)
+                    .map(|_| path.clone())
+                    .map_err(|error| format!("Can't write {}: {}", path.display(), error));
+                let _ = results_sender.send(result);
+            });
+        }
+
+        while let Ok(result) = self.results_receiver.try_recv() {
+            let message = match result {
+                Ok(path) => {
+                    #[cfg(feature = "log")]
+                    log::info!("Screenshot saved to {}", path.display());
+                    format!("Screenshot saved to {}", path.display())
+                },
+                Err(error) => {
+                    #[cfg(feature = "log")]
+                    log::error!("{}", error);
+                    error
+                },
+            };
+            self.toast = Some((message, Instant::now()));
+        }
+    }
+
+    // Brief notification of the last capture in the bottom right corner
+    #[cfg(feature = "egui")]
+    pub fn show_toast(&mut self, context: &egui::Context) {
+        let Some((message, time)) = &self.toast else {
+            return;
+        };
+        if time.elapsed() > Self::TOAST_DURATION {
+            self.toast = None;
+            return;
+        }
+        egui::Area::new(egui::Id::new("oxyde screenshot toast"))
+            .anchor(egui::Align2::RIGHT_BOTTOM, egui::vec2(-10.0, -10.0))
+            .order(egui::Order::Foreground)
+            .interactable(false)
+            .show(context, |ui| {
+                egui::Frame::popup(ui.style()).show(ui, |ui| ui.label(message.as_str()));
+            });
+    }
+
+    fn send_result(&self, result: Result<PathBuf, String>) { let _ = self.results_sender.send(result); }
+}
+
+// Plugin requesting a capture of the next presented frame (see `AppState::screenshots`) when its key is pressed, and
+// showing a toast of the written captures (egui feature). The key is still passed to the app.
+pub struct ScreenshotPlugin {
+    pub key: keyboard::KeyCode,
+}
+
+impl Default for ScreenshotPlugin {
+    fn default() -> Self { Self::new(keyboard::KeyCode::PrintScreen) }
+}
+
+impl ScreenshotPlugin {
+    pub fn new(key: keyboard::KeyCode) -> Self { Self { key } }
+}
+
+impl Plugin for ScreenshotPlugin {
+    fn handle_event(&mut self, app_state: &mut AppState, event: &Event<()>) -> Result<()> {
+        let Event::WindowEvent {
+            event: WindowEvent::KeyboardInput { event, .. },
+            ..
+        } = event
+        else {
+            return Ok(());
+        };
+        #[cfg(feature = "egui")]
+        if app_state.suppress_inputs_captured_by_egui && app_state.input_state.is_keyboard_captured_by_egui() {
+            return Ok(());
+        }
+        if event.state == ElementState::Pressed && !event.repeat && event.physical_key == keyboard::PhysicalKey::Code(self.key) {
+            app_state.screenshots.request();
+        }
+        Ok(())
+    }
+
+    #[cfg(feature = "egui")]
+    fn gui(&mut self, app_state: &mut AppState) -> Result<()> {
+        app_state.screenshots.show_toast(app_state.egui_renderer.context());
+        Ok(())
+    }
+}
+
+fn write_image(path: &Path, format: wgpu::TextureFormat, width: u32, height: u32, bytes: Vec<u8>, keep_alpha: bool) -> Result<()> {
+    if let Some(directory) = path.parent().filter(|directory| !directory.as_os_str().is_empty()) {
+        std::fs::create_dir_all(directory)?;
+    }
+    let image = texels_to_image(format, width, height, bytes)?;
+    // The alpha of opaque surfaces is undefined
+    let image = if keep_alpha {
+        image::DynamicImage::ImageRgba8(image.into_rgba8())
+    } else {
+        image::DynamicImage::ImageRgb8(image.into_rgb8())
+    };
+    image.save_with_format(path, image::ImageFormat::Png)?;
+    Ok(())
+}
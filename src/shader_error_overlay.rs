@@ -0,0 +1,111 @@
+use anyhow::Result;
+
+use crate::wgpu_utils::PipelineReload;
+
+struct ShaderError {
+    label: String,
+    message: String,
+    dismissed: bool,
+}
+
+// Overlay listing the shader and pipeline compilation errors (e.g. of a hot reload) until they are dismissed or fixed,
+// the previous pipelines keeping rendering meanwhile. Errors are also logged when reported. A dismissed error shows
+// again if a later compilation fails with another message.
+#[derive(Default)]
+pub struct ShaderErrorOverlay {
+    errors: Vec<ShaderError>,
+}
+
+impl ShaderErrorOverlay {
+    pub fn report(&mut self, label: &str, message: impl ToString) {
+        let message = message.to_string();
+        report_error!("Shader error in \"{}\": {}", label, message);
+
+        match self.errors.iter_mut().find(|error| error.label == label) {
+            Some(error) if error.message == message => (),
+            Some(error) => {
+                error.message = message;
+                error.dismissed = false;
+            },
+            None => self.errors.push(ShaderError {
+                label: label.to_string(),
+                message,
+                dismissed: false,
+            }),
+        }
+    }
+
+    // Forget the error of `label`, e.g. once it compiles again
+    pub fn clear(&mut self, label: &str) { self.errors.retain(|error| error.label != label); }
+
+    // Report the failed rebuilds returned by `PipelineRegistry::poll` and clear the succeeded ones
+    pub fn report_reloads(&mut self, reloads: &[PipelineReload]) {
+        for reload in reloads {
+            match &reload.error {
+                Some(error) => self.report(&reload.label, error),
+                None => self.clear(&reload.label),
+            }
+        }
+    }
+
+    // Report the error of a compilation result (e.g. taken from a `PipelineCompiler` handle), the value if it succeeded
+    pub fn report_result<T>(&mut self, label: &str, result: Result<T>) -> Option<T> {
+        match result {
+            Ok(value) => {
+                self.clear(label);
+                Some(value)
+            },
+            Err(error) => {
+                self.report(label, format!("{:#}", error));
+                None
+            },
+        }
+    }
+
+    pub fn dismiss_all(&mut self) {
+        for error in &mut self.errors {
+            error.dismissed = true;
+        }
+    }
+
+    pub fn has_errors(&self) -> bool { !self.errors.is_empty() }
+
+    pub fn show(&mut self, context: &egui::Context) {
+        if self.errors.iter().all(|error| error.dismissed) {
+            return;
+        }
+
+        let mut dismiss_all = false;
+        egui::Area::new(egui::Id::new("oxyde shader error overlay"))
+            .anchor(egui::Align2::LEFT_TOP, egui::vec2(10.0, 10.0))
+            .order(egui::Order::Foreground)
+            .show(context, |ui| {
+                egui::Frame::popup(ui.style()).show(ui, |ui| {
+                    ui.set_max_width(context.screen_rect().width() - 40.0);
+                    ui.horizontal(|ui| {
+                        ui.colored_label(ui.visuals().error_fg_color, egui::RichText::new("Shader errors").strong());
+                        dismiss_all = ui.button("Dismiss all").clicked();
+                    });
+                    egui::ScrollArea::vertical().max_height(context.screen_rect().height() * 0.6).show(ui, |ui| {
+                        for error in self.errors.iter_mut().filter(|error| !error.dismissed) {
+                            ui.separator();
+                            ui.horizontal(|ui| {
+                                ui.strong(error.label.as_str());
+                                if ui.small_button("Dismiss").clicked() {
+                                    error.dismissed = true;
+                                }
+                                if ui.small_button("Copy").clicked() {
+                                    ui.ctx().copy_text(error.message.clone());
+                                }
+                            });
+                            ui.label(egui::RichText::new(error.message.as_str()).monospace());
+                        }
+                    });
+                });
+            });
+
+        if dismiss_all {
+            self.dismiss_all();
+        }
+    }
+}
@@ -0,0 +1,17 @@
+// sRGB transfer function, same as the `oxyde::color` WGSL module. Colors picked in egui or read from 8-bit textures are
+// sRGB encoded, uniforms and lighting expect linear values.
+pub fn srgb_to_linear(value: f32) -> f32 {
+    if value <= 0.04045 {
+        value / 12.92
+    } else {
+        ((value + 0.055) / 1.055).powf(2.4)
+    }
+}
+
+pub fn linear_to_srgb(value: f32) -> f32 {
+    if value <= 0.0031308 {
+        value * 12.92
+    } else {
+        1.055 * value.powf(1.0 / 2.4) - 0.055
+    }
+}
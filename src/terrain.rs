@@ -0,0 +1,265 @@
+use glam::{Vec2, Vec3};
+use wgpu::util::DeviceExt;
+
+use crate::{math::Aabb, wgpu_utils::memory_tracker::MemoryAllocation};
+
+// Heights on a regular grid, row major
+#[derive(Clone, Debug)]
+pub struct Heightmap {
+    width: u32,
+    height: u32,
+    heights: Vec<f32>,
+}
+
+impl Heightmap {
+    pub fn new(width: u32, height: u32, heights: Vec<f32>) -> Self {
+        assert!(width >= 2 && height >= 2, "A heightmap needs at least 2x2 samples, got {}x{}", width, height);
+        assert_eq!(heights.len(), (width * height) as usize, "Heightmap sample count doesn't match its size");
+        Self { width, height, heights }
+    }
+
+    // Luminance of the image from 0 to 1, 16 bits images keep their precision
+    #[cfg(feature = "image")]
+    pub fn from_image(image: &image::DynamicImage) -> Self {
+        let luma = image.to_luma32f();
+        Self::new(luma.width(), luma.height(), luma.into_raw())
+    }
+
+    #[cfg(feature = "image")]
+    pub fn open(path: impl AsRef<std::path::Path>) -> anyhow::Result<Self> { Ok(Self::from_image(&image::open(path)?)) }
+
+    pub fn width(&self) -> u32 { self.width }
+
+    pub fn height(&self) -> u32 { self.height }
+
+    // Coordinates are clamped to the grid
+    pub fn height_at(&self, x: i64, y: i64) -> f32 {
+        let x = x.clamp(0, self.width as i64 - 1) as usize;
+        let y = y.clamp(0, self.height as i64 - 1) as usize;
+        self.heights[y * self.width as usize + x]
+    }
+}
+
+#[derive(Clone, Copy, Debug)]
+pub struct TerrainDescriptor {
+    // Extent of the terrain on the x and z axes, centered on the origin
+    pub size: Vec2,
+    // World height of a heightmap value of 1
+    pub height_scale: f32,
+    // Quads per side of a chunk
+    pub chunk_quads: u32,
+}
+
+impl Default for TerrainDescriptor {
+    fn default() -> Self {
+        Self {
+            size: Vec2::splat(100.0),
+            height_scale: 10.0,
+            chunk_quads: 64,
+        }
+    }
+}
+
+#[repr(C)]
+#[derive(Clone, Copy, Default, Debug, bytemuck::Pod, bytemuck::Zeroable)]
+pub struct TerrainVertex {
+    pub position: [f32; 3],
+    pub normal: [f32; 3],
+    // 0 to 1 over the whole terrain
+    pub uv: [f32; 2],
+}
+
+impl TerrainVertex {
+    const ATTRIBUTES: [wgpu::VertexAttribute; 3] = wgpu::vertex_attr_array![0 => Float32x3, 1 => Float32x3, 2 => Float32x2];
+
+    pub fn layout() -> wgpu::VertexBufferLayout<'static> {
+        wgpu::VertexBufferLayout {
+            array_stride: std::mem::size_of::<Self>() as wgpu::BufferAddress,
+            step_mode: wgpu::VertexStepMode::Vertex,
+            attributes: &Self::ATTRIBUTES,
+        }
+    }
+}
+
+// Chunk of the terrain grid with its own vertices, the border vertices are duplicated in the neighbour chunks
+#[derive(Clone, Debug)]
+pub struct TerrainChunk {
+    pub vertices: Vec<TerrainVertex>,
+    // Indices of the chunk vertices, counter clockwise seen from above
+    pub indices: Vec<u32>,
+    pub bounds: Aabb,
+    // Chunk coordinates in the chunk grid
+    pub coordinates: (u32, u32),
+}
+
+// Grid mesh of a heightmap (one vertex per sample) split in chunks for culling
+#[derive(Clone, Debug)]
+pub struct TerrainMesh {
+    pub chunks: Vec<TerrainChunk>,
+    // Chunks per axis, the chunks are stored row major
+    pub chunk_counts: (u32, u32),
+}
+
+impl TerrainMesh {
+    pub fn generate(heightmap: &Heightmap, descriptor: &TerrainDescriptor) -> Self {
+        let chunk_quads = descriptor.chunk_quads.max(1);
+        let (quads_x, quads_z) = (heightmap.width() - 1, heightmap.height() - 1);
+        let chunk_counts = (quads_x.div_ceil(chunk_quads), quads_z.div_ceil(chunk_quads));
+        let spacing = descriptor.size / Vec2::new(quads_x as f32, quads_z as f32);
+
+        let vertex = |x: u32, z: u32| {
+            let (x_sample, z_sample) = (x as i64, z as i64);
+            // Normals from central differences on the whole heightmap so the chunk borders match
+            let slope_x = (heightmap.height_at(x_sample + 1, z_sample) - heightmap.height_at(x_sample - 1, z_sample)) * descriptor.height_scale
+                / (2.0 * spacing.x);
+            let slope_z = (heightmap.height_at(x_sample, z_sample + 1) - heightmap.height_at(x_sample, z_sample - 1)) * descriptor.height_scale
+                / (2.0 * spacing.y);
+            let uv = Vec2::new(x as f32 / quads_x as f32, z as f32 / quads_z as f32);
+            TerrainVertex {
+                position: [
+                    (uv.x - 0.5) * descriptor.size.x,
+                    heightmap.height_at(x_sample, z_sample) * descriptor.height_scale,
+                    (uv.y - 0.5) * descriptor.size.y,
+                ],
+                normal: Vec3::new(-slope_x, 1.0, -slope_z).normalize().to_array(),
+                uv: uv.to_array(),
+            }
+        };
+
+        let mut chunks = Vec::with_capacity((chunk_counts.0 * chunk_counts.1) as usize);
+        for chunk_z in 0..chunk_counts.1 {
+            for chunk_x in 0..chunk_counts.0 {
+                let (start_x, start_z) = (chunk_x * chunk_quads, chunk_z * chunk_quads);
+                let (end_x, end_z) = ((start_x + chunk_quads).min(quads_x), (start_z + chunk_quads).min(quads_z));
+                let row_length = end_x - start_x + 1;
+
+                let vertices: Vec<TerrainVertex> = (start_z..=end_z)
+                    .flat_map(|z| (start_x..=end_x).map(move |x| (x, z)))
+                    .map(|(x, z)| vertex(x, z))
+                    .collect();
+                let mut indices = Vec::with_capacity(((end_x - start_x) * (end_z - start_z) * 6) as usize);
+                for z in 0..end_z - start_z {
+                    for x in 0..end_x - start_x {
+                        let top_left = z * row_length + x;
+                        let bottom_left = top_left + row_length;
+                        indices.extend_from_slice(&[top_left, bottom_left, top_left + 1, top_left + 1, bottom_left, bottom_left + 1]);
+                    }
+                }
+                let bounds = Aabb::from_points(vertices.iter().map(|vertex| Vec3::from(vertex.position))).expect("Chunks have vertices");
+
+                chunks.push(TerrainChunk {
+                    vertices,
+                    indices,
+                    bounds,
+                    coordinates: (chunk_x, chunk_z),
+                });
+            }
+        }
+
+        Self { chunks, chunk_counts }
+    }
+
+    // World bounds of the chunks, to cull them with `Frustum::cull`
+    pub fn chunk_bounds(&self) -> Vec<Aabb> { self.chunks.iter().map(|chunk| chunk.bounds).collect() }
+}
+
+// Range of a chunk in the terrain buffers
+#[derive(Clone, Copy, Debug)]
+struct ChunkDraw {
+    first_index: u32,
+    index_count: u32,
+    base_vertex: i32,
+}
+
+// Terrain uploaded to the GPU: the chunks share a vertex and an index buffer and are drawn individually
+pub struct Terrain {
+    mesh: TerrainMesh,
+    vertex_buffer: wgpu::Buffer,
+    index_buffer: wgpu::Buffer,
+    chunk_draws: Vec<ChunkDraw>,
+    _memory: [MemoryAllocation; 2],
+}
+
+impl Terrain {
+    pub fn new(device: &wgpu::Device, mesh: TerrainMesh) -> Self {
+        let mut vertices = Vec::new();
+        let mut indices = Vec::new();
+        let chunk_draws = mesh
+            .chunks
+            .iter()
+            .map(|chunk| {
+                let chunk_draw = ChunkDraw {
+                    first_index: indices.len() as u32,
+                    index_count: chunk.indices.len() as u32,
+                    base_vertex: vertices.len() as i32,
+                };
+                vertices.extend_from_slice(&chunk.vertices);
+                indices.extend_from_slice(&chunk.indices);
+                chunk_draw
+            })
+            .collect();
+
+        let vertex_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("Terrain: vertices"),
+            contents: bytemuck::cast_slice(&vertices),
+            usage: wgpu::BufferUsages::VERTEX,
+        });
+        let index_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("Terrain: indices"),
+            contents: bytemuck::cast_slice(&indices),
+            usage: wgpu::BufferUsages::INDEX,
+        });
+        let memory = [
+            MemoryAllocation::for_buffer(&vertex_buffer, Some("Terrain: vertices")),
+            MemoryAllocation::for_buffer(&index_buffer, Some("Terrain: indices")),
+        ];
+
+        Self {
+            mesh,
+            vertex_buffer,
+            index_buffer,
+            chunk_draws,
+            _memory: memory,
+        }
+    }
+
+    // Load a heightmap image and generate the mesh on the loader threads, the buffers are created on the main loop
+    #[cfg(feature = "image")]
+    pub fn load(
+        loader: &crate::wgpu_utils::AssetLoader,
+        path: impl Into<std::path::PathBuf>,
+        descriptor: TerrainDescriptor,
+    ) -> crate::wgpu_utils::LoadHandle<Terrain> {
+        let path = path.into();
+        loader.load(
+            path.display().to_string(),
+            move || Ok(TerrainMesh::generate(&Heightmap::open(&path)?, &descriptor)),
+            |mesh, device, _| Ok(Terrain::new(device, mesh)),
+        )
+    }
+
+    pub fn mesh(&self) -> &TerrainMesh { &self.mesh }
+
+    pub fn chunk_count(&self) -> usize { self.chunk_draws.len() }
+
+    pub fn vertex_buffer(&self) -> &wgpu::Buffer { &self.vertex_buffer }
+
+    pub fn index_buffer(&self) -> &wgpu::Buffer { &self.index_buffer }
+
+    // Draw the given chunks (e.g. the visible ones from `Frustum::cull` on `TerrainMesh::chunk_bounds`),
+    // with a pipeline using the `TerrainVertex` layout
+    pub fn draw_chunks<'a>(&'a self, render_pass: &mut wgpu::RenderPass<'a>, chunks: impl IntoIterator<Item = u32>) {
+        render_pass.set_vertex_buffer(0, self.vertex_buffer.slice(..));
+        render_pass.set_index_buffer(self.index_buffer.slice(..), wgpu::IndexFormat::Uint32);
+        for chunk in chunks {
+            let chunk_draw = self.chunk_draws[chunk as usize];
+            render_pass.draw_indexed(
+                chunk_draw.first_index..chunk_draw.first_index + chunk_draw.index_count,
+                chunk_draw.base_vertex,
+                0..1,
+            );
+        }
+    }
+
+    pub fn draw<'a>(&'a self, render_pass: &mut wgpu::RenderPass<'a>) { self.draw_chunks(render_pass, 0..self.chunk_draws.len() as u32); }
+}
@@ -0,0 +1,383 @@
+// Rendering tests helpers: a device without window rendering into offscreen textures, read back and compared to reference
+// (golden) images. References are written when missing or when the OXYDE_UPDATE_GOLDEN environment variable is set.
+// Compute tests helpers: a device falling back to a software adapter and `ComputeTest` running a shader over input slices.
+use std::path::{Path, PathBuf};
+
+use anyhow::{anyhow, Result};
+
+use crate::wgpu_utils::{
+    binding_builder::{BindGroupBuilder, BindGroupLayoutBuilder},
+    buffers::{create_buffer_for_size, create_buffer_from_content, map_async},
+    render_handles::{DeviceHandle, RenderInstance},
+    TextureReadback,
+};
+
+pub const UPDATE_GOLDEN_ENV: &str = "OXYDE_UPDATE_GOLDEN";
+
+pub struct TestDevice {
+    pub adapter_info: wgpu::AdapterInfo,
+    pub device: wgpu::Device,
+    pub queue: wgpu::Queue,
+}
+
+// Device of a hardware adapter, or of a software one (lavapipe, WARP, llvmpipe...) when there is no GPU.
+// Returns None (with the reason printed) when no adapter is available so tests can be skipped, see `test_device_or_skip!`.
+pub fn create_test_device() -> Option<TestDevice> {
+    match pollster::block_on(request_test_device()) {
+        Ok(test_device) => Some(test_device),
+        Err(error) => {
+            report_warn!("No test device, skipping: {}", error);
+            None
+        },
+    }
+}
+
+async fn request_test_device() -> Result<TestDevice> {
+    let instance = wgpu::Instance::new(wgpu::InstanceDescriptor {
+        backends: wgpu::util::backend_bits_from_env().unwrap_or(wgpu::Backends::all()),
+        ..Default::default()
+    });
+    let mut adapter = None;
+    for force_fallback_adapter in [false, true] {
+        adapter = instance
+            .request_adapter(&wgpu::RequestAdapterOptions {
+                power_preference: wgpu::PowerPreference::default(),
+                force_fallback_adapter,
+                compatible_surface: None,
+            })
+            .await;
+        if adapter.is_some() {
+            break;
+        }
+    }
+    let adapter = adapter.ok_or_else(|| anyhow!("No hardware or software adapter"))?;
+    let (device, queue) = adapter
+        .request_device(
+            &wgpu::DeviceDescriptor {
+                label: Some("Test device"),
+                required_features: wgpu::Features::empty(),
+                required_limits: wgpu::Limits::downlevel_defaults().using_resolution(adapter.limits()),
+            },
+            None,
+        )
+        .await?;
+    Ok(TestDevice {
+        adapter_info: adapter.get_info(),
+        device,
+        queue,
+    })
+}
+
+// Test device, or return from the calling test when none is available
+#[macro_export]
+macro_rules! test_device_or_skip {
+    () => {
+        match $crate::testing::create_test_device() {
+            Some(test_device) => test_device,
+            None => return,
+        }
+    };
+}
+
+// Compute shader run over input slices, bound in order as read only storage buffers of the group 0,
+// followed by the output as a read write storage buffer, e.g. for two inputs:
+// `@group(0) @binding(0) var<storage, read> a: array<f32>;` `@group(0) @binding(1) var<storage, read> b: array<f32>;`
+// `@group(0) @binding(2) var<storage, read_write> output: array<f32>;`
+pub struct ComputeTest<'a> {
+    wgsl_source: &'a str,
+    entry_point: &'a str,
+    inputs: Vec<&'a [u8]>,
+    output_size: wgpu::BufferAddress,
+    workgroups: (u32, u32, u32),
+}
+
+impl<'a> ComputeTest<'a> {
+    pub fn new(wgsl_source: &'a str, entry_point: &'a str) -> Self {
+        Self {
+            wgsl_source,
+            entry_point,
+            inputs: Vec::new(),
+            output_size: 0,
+            workgroups: (1, 1, 1),
+        }
+    }
+
+    pub fn input<T: bytemuck::Pod>(mut self, values: &'a [T]) -> Self {
+        self.inputs.push(bytemuck::cast_slice(values));
+        self
+    }
+
+    // Output of `len` elements of `T`, zero initialized
+    pub fn output<T: bytemuck::Pod>(mut self, len: usize) -> Self {
+        self.output_size = (len * std::mem::size_of::<T>()) as wgpu::BufferAddress;
+        self
+    }
+
+    pub fn workgroups(mut self, x: u32, y: u32, z: u32) -> Self {
+        self.workgroups = (x, y, z);
+        self
+    }
+
+    // Dispatch the shader and read back the output, shader and validation errors are returned
+    pub fn run<T: bytemuck::Pod>(&self, test_device: &TestDevice) -> Result<Vec<T>> {
+        let TestDevice { device, queue, .. } = test_device;
+        if self.output_size == 0 {
+            return Err(anyhow!("ComputeTest needs a non empty output"));
+        }
+
+        device.push_error_scope(wgpu::ErrorFilter::Validation);
+
+        let shader_module = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: Some("ComputeTest: shader"),
+            source: wgpu::ShaderSource::Wgsl(self.wgsl_source.into()),
+        });
+
+        let storage_binding = |read_only| wgpu::BindingType::Buffer {
+            ty: wgpu::BufferBindingType::Storage { read_only },
+            has_dynamic_offset: false,
+            min_binding_size: None,
+        };
+        let bind_group_layout = self
+            .inputs
+            .iter()
+            .fold(BindGroupLayoutBuilder::new(), |builder, _| builder.add_binding_compute(storage_binding(true)))
+            .add_binding_compute(storage_binding(false))
+            .create(device, Some("ComputeTest: bind group layout"));
+
+        // Empty buffers can't be bound, empty inputs are padded to a single element
+        let input_buffers: Vec<wgpu::Buffer> = self
+            .inputs
+            .iter()
+            .map(|input| {
+                let content: &[u8] = if input.is_empty() { &[0; 4] } else { input };
+                create_buffer_from_content(device, wgpu::BufferUsages::STORAGE, Some("ComputeTest: input"), Some(content))
+            })
+            .collect();
+        let output_buffer = create_buffer_for_size(
+            device,
+            wgpu::BufferUsages::STORAGE | wgpu::BufferUsages::COPY_SRC,
+            Some("ComputeTest: output"),
+            self.output_size,
+        );
+        let readback_buffer = create_buffer_for_size(
+            device,
+            wgpu::BufferUsages::COPY_DST | wgpu::BufferUsages::MAP_READ,
+            Some("ComputeTest: readback"),
+            self.output_size,
+        );
+
+        let bind_group = input_buffers
+            .iter()
+            .chain(std::iter::once(&output_buffer))
+            .fold(BindGroupBuilder::new(&bind_group_layout), |builder, buffer| {
+                builder.resource(buffer.as_entire_binding())
+            })
+            .create(device, Some("ComputeTest: bind group"));
+
+        let pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("ComputeTest: pipeline layout"),
+            bind_group_layouts: &[&bind_group_layout.layout],
+            push_constant_ranges: &[],
+        });
+        let pipeline = device.create_compute_pipeline(&wgpu::ComputePipelineDescriptor {
+            label: Some("ComputeTest: pipeline"),
+            layout: Some(&pipeline_layout),
+            module: &shader_module,
+            entry_point: self.entry_point,
+        });
+
+        let mut encoder = device.create_command_encoder(&wgpu::CommandEncoderDescriptor { label: Some("ComputeTest encoder") });
+        {
+            let mut compute_pass = encoder.begin_compute_pass(&wgpu::ComputePassDescriptor {
+                label: Some("ComputeTest pass"),
+                timestamp_writes: None,
+            });
+            compute_pass.set_pipeline(&pipeline);
+            compute_pass.set_bind_group(0, &bind_group, &[]);
+            let (x, y, z) = self.workgroups;
+            compute_pass.dispatch_workgroups(x, y, z);
+        }
+        encoder.copy_buffer_to_buffer(&output_buffer, 0, &readback_buffer, 0, self.output_size);
+        queue.submit(Some(encoder.finish()));
+
+        if let Some(error) = pollster::block_on(device.pop_error_scope()) {
+            return Err(anyhow!("ComputeTest failed: {}", error));
+        }
+
+        let map_future = map_async(readback_buffer.slice(..), wgpu::MapMode::Read);
+        device.poll(wgpu::Maintain::Wait);
+        map_future
+            .try_take_result()
+            .ok_or_else(|| anyhow!("ComputeTest output readback did not complete"))??;
+        let output = bytemuck::pod_collect_to_vec(&readback_buffer.slice(..).get_mapped_range());
+        readback_buffer.unmap();
+        Ok(output)
+    }
+}
+
+// Panics with the first differing element when a value differs from the expected one by more than `epsilon`
+#[track_caller]
+pub fn assert_approx_eq(actual: &[f32], expected: &[f32], epsilon: f32) {
+    assert_eq!(
+        actual.len(),
+        expected.len(),
+        "Length {} doesn't match the expected length {}",
+        actual.len(),
+        expected.len()
+    );
+    if let Some((index, (a, e))) = actual
+        .iter()
+        .zip(expected)
+        .enumerate()
+        .find(|(_, (a, e))| (*a - *e).abs() > epsilon || a.is_nan() != e.is_nan())
+    {
+        panic!("Element {} is {} but {} was expected (epsilon {})", index, a, e, epsilon);
+    }
+}
+
+pub struct HeadlessRenderer {
+    render_instance: RenderInstance,
+    device_handle_id: usize,
+}
+
+impl HeadlessRenderer {
+    // Device of the backends from the WGPU_BACKEND environment variable (primary backends by default)
+    pub fn new() -> Result<Self> { Self::with_backends(None) }
+
+    pub fn with_backends(backends: Option<wgpu::Backends>) -> Result<Self> {
+        let mut render_instance = RenderInstance::new(backends, None);
+        let device_handle_id = pollster::block_on(render_instance.device(None, None))?;
+        Ok(Self { render_instance, device_handle_id })
+    }
+
+    pub fn device_handle(&self) -> &DeviceHandle { &self.render_instance.devices[self.device_handle_id] }
+
+    pub fn device(&self) -> &wgpu::Device { &self.device_handle().device }
+
+    pub fn queue(&self) -> &wgpu::Queue { &self.device_handle().queue }
+
+    // Run `render` into a new texture of the given size and format (render attachment) and read it back
+    pub fn render(
+        &self,
+        width: u32,
+        height: u32,
+        format: wgpu::TextureFormat,
+        render: impl FnOnce(&DeviceHandle, &mut wgpu::CommandEncoder, &wgpu::TextureView),
+    ) -> Result<image::DynamicImage> {
+        let DeviceHandle { device, queue, .. } = self.device_handle();
+        let texture = device.create_texture(&wgpu::TextureDescriptor {
+            label: Some("HeadlessRenderer: target"),
+            size: wgpu::Extent3d { width, height, depth_or_array_layers: 1 },
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: wgpu::TextureDimension::D2,
+            format,
+            usage: wgpu::TextureUsages::RENDER_ATTACHMENT | wgpu::TextureUsages::COPY_SRC,
+            view_formats: &[],
+        });
+        let view = texture.create_view(&wgpu::TextureViewDescriptor::default());
+        let readback = TextureReadback::for_texture(device, &texture, Some("HeadlessRenderer"))?;
+
+        let mut encoder = device.create_command_encoder(&wgpu::CommandEncoderDescriptor { label: Some("HeadlessRenderer encoder") });
+        render(self.device_handle(), &mut encoder, &view);
+        readback.encode_copy(&mut encoder, &texture);
+        queue.submit(Some(encoder.finish()));
+
+        let map_future = readback.map();
+        device.poll(wgpu::Maintain::Wait);
+        map_future
+            .try_take_result()
+            .ok_or_else(|| anyhow!("Headless render readback did not complete"))??;
+        readback.read_image()
+    }
+}
+
+pub struct ImageComparison {
+    // Pixels with a channel differing by more than the tolerance
+    pub mismatched_pixels: usize,
+    pub max_difference: u8,
+    // Mismatched pixels in red over the dimmed reference
+    pub diff_image: image::RgbaImage,
+}
+
+impl ImageComparison {
+    pub fn is_match(&self) -> bool { self.mismatched_pixels == 0 }
+}
+
+// Per channel comparison of 8 bits RGBA images, with a tolerance on each channel difference
+pub fn compare_images(actual: &image::RgbaImage, reference: &image::RgbaImage, tolerance: u8) -> Result<ImageComparison> {
+    if actual.dimensions() != reference.dimensions() {
+        return Err(anyhow!(
+            "Image size {:?} doesn't match the reference size {:?}",
+            actual.dimensions(),
+            reference.dimensions()
+        ));
+    }
+
+    let mut mismatched_pixels = 0;
+    let mut max_difference = 0;
+    let diff_image = image::RgbaImage::from_fn(actual.width(), actual.height(), |x, y| {
+        let (actual_pixel, reference_pixel) = (actual.get_pixel(x, y), reference.get_pixel(x, y));
+        let difference = actual_pixel
+            .0
+            .iter()
+            .zip(reference_pixel.0.iter())
+            .map(|(a, b)| a.abs_diff(*b))
+            .max()
+            .unwrap_or(0);
+        max_difference = max_difference.max(difference);
+        if difference > tolerance {
+            mismatched_pixels += 1;
+            image::Rgba([255, 0, 0, 255])
+        } else {
+            let [r, g, b, _] = reference_pixel.0;
+            let luma = ((r as u32 + g as u32 + b as u32) / 12) as u8;
+            image::Rgba([luma, luma, luma, 255])
+        }
+    });
+
+    Ok(ImageComparison {
+        mismatched_pixels,
+        max_difference,
+        diff_image,
+    })
+}
+
+// Compare an image to the reference at `reference_path`. On mismatch the image and the diff are written next to the reference
+// (`<name>.actual.png` and `<name>.diff.png`) and an error is returned.
+pub fn assert_golden_image(actual: &image::DynamicImage, reference_path: impl AsRef<Path>, tolerance: u8) -> Result<()> {
+    let reference_path = reference_path.as_ref();
+    if !reference_path.exists() || std::env::var_os(UPDATE_GOLDEN_ENV).is_some() {
+        if let Some(directory) = reference_path.parent().filter(|directory| !directory.as_os_str().is_empty()) {
+            std::fs::create_dir_all(directory)?;
+        }
+        actual.to_rgba8().save_with_format(reference_path, image::ImageFormat::Png)?;
+        return Ok(());
+    }
+
+    let actual = actual.to_rgba8();
+    let reference = image::open(reference_path)?.into_rgba8();
+    let comparison = compare_images(&actual, &reference, tolerance)?;
+    if comparison.is_match() {
+        return Ok(());
+    }
+
+    let actual_path = sibling_path(reference_path, "actual");
+    let diff_path = sibling_path(reference_path, "diff");
+    actual.save_with_format(&actual_path, image::ImageFormat::Png)?;
+    comparison.diff_image.save_with_format(&diff_path, image::ImageFormat::Png)?;
+    Err(anyhow!(
+        "{} pixels differ from {} by more than {} (max difference {}), see {} and {}",
+        comparison.mismatched_pixels,
+        reference_path.display(),
+        tolerance,
+        comparison.max_difference,
+        actual_path.display(),
+        diff_path.display()
+    ))
+}
+
+fn sibling_path(path: &Path, suffix: &str) -> PathBuf {
+    let stem = path.file_stem().and_then(|stem| stem.to_str()).unwrap_or("image");
+    path.with_file_name(format!("{}.{}.png", stem, suffix))
+}
@@ -0,0 +1,19 @@
+// Helpers installing a global tracing subscriber to inspect the frame phases spans (update, render, egui, submit, present)
+#[cfg(any(feature = "tracing-chrome", feature = "tracing-tracy"))]
+use tracing_subscriber::layer::SubscriberExt;
+
+// Record the spans in a Chrome trace file (chrome://tracing or https://ui.perfetto.dev)
+// The trace is written when the returned guard is dropped, it must be kept alive until the end of the application.
+#[cfg(feature = "tracing-chrome")]
+pub fn install_chrome_subscriber(path: impl AsRef<std::path::Path>) -> anyhow::Result<tracing_chrome::FlushGuard> {
+    let (chrome_layer, guard) = tracing_chrome::ChromeLayerBuilder::new().file(path).include_args(true).build();
+    tracing::subscriber::set_global_default(tracing_subscriber::registry().with(chrome_layer))?;
+    Ok(guard)
+}
+
+// Stream the spans to a connected Tracy profiler
+#[cfg(feature = "tracing-tracy")]
+pub fn install_tracy_subscriber() -> anyhow::Result<()> {
+    tracing::subscriber::set_global_default(tracing_subscriber::registry().with(tracing_tracy::TracyLayer::default()))?;
+    Ok(())
+}
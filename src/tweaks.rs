@@ -0,0 +1,251 @@
+use std::{collections::BTreeMap, ops::RangeInclusive};
+
+#[cfg(feature = "serde")]
+use anyhow::{Context, Result};
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum TweakValue {
+    Float(f32),
+    Vec2([f32; 2]),
+    Vec3([f32; 3]),
+    Vec4([f32; 4]),
+    Color3([f32; 3]),
+    Color4([f32; 4]),
+    Bool(bool),
+}
+
+struct Tweak {
+    name: String,
+    value: TweakValue,
+    default: TweakValue,
+    range: Option<RangeInclusive<f32>>,
+}
+
+// Named parameters tuned at runtime (e.g. simulation constants): read each frame with `float`, `color3`... which register
+// the parameter with its default on first use, and edited in the `ui`/`show` panel.
+// With a file (`with_file`, serde feature), the values are saved to it once edited and restored when the parameters are
+// registered again in a later session.
+#[derive(Default)]
+pub struct Tweaks {
+    // In registration order
+    tweaks: Vec<Tweak>,
+    // Values read from the file, applied to the parameters when they are registered
+    saved_values: BTreeMap<String, TweakValue>,
+    #[cfg(feature = "serde")]
+    path: Option<std::path::PathBuf>,
+    dirty: bool,
+}
+
+impl Tweaks {
+    pub fn new() -> Self { Self::default() }
+
+    // Restore the values saved to `path` (a ron file) by a previous session and save the edited values to it. A missing
+    // file is not an error (first run).
+    #[cfg(feature = "serde")]
+    pub fn with_file(mut self, path: impl Into<std::path::PathBuf>) -> Result<Self> {
+        let path = path.into();
+        if path.exists() {
+            let source = std::fs::read_to_string(&path).with_context(|| format!("Failed to read tweaks {}", path.display()))?;
+            self.saved_values = ron::from_str(&source).with_context(|| format!("Failed to parse tweaks {}", path.display()))?;
+            for tweak in &mut self.tweaks {
+                if let Some(value) = self.saved_values.get(&tweak.name).filter(|value| Self::same_kind(value, &tweak.default)) {
+                    tweak.value = *value;
+                }
+            }
+        }
+        self.path = Some(path);
+        Ok(self)
+    }
+
+    // Write the values to the file, does nothing without file
+    #[cfg(feature = "serde")]
+    pub fn save(&mut self) -> Result<()> {
+        let Some(path) = &self.path else {
+            return Ok(());
+        };
+        // Values of parameters not registered in this session are kept
+        let mut values = self.saved_values.clone();
+        values.extend(self.tweaks.iter().map(|tweak| (tweak.name.clone(), tweak.value)));
+        let source = ron::ser::to_string_pretty(&values, ron::ser::PrettyConfig::default())?;
+        if let Some(directory) = path.parent() {
+            std::fs::create_dir_all(directory)?;
+        }
+        std::fs::write(path, source).with_context(|| format!("Failed to write tweaks {}", path.display()))?;
+        self.dirty = false;
+        Ok(())
+    }
+
+    fn same_kind(a: &TweakValue, b: &TweakValue) -> bool { std::mem::discriminant(a) == std::mem::discriminant(b) }
+
+    // Current value of the parameter, registered with `default` if new. A parameter registered with another kind is
+    // replaced.
+    fn value(&mut self, name: &str, default: TweakValue, range: Option<RangeInclusive<f32>>) -> TweakValue {
+        match self.tweaks.iter().position(|tweak| tweak.name == name) {
+            Some(index) if Self::same_kind(&self.tweaks[index].value, &default) => self.tweaks[index].value,
+            index => {
+                let value = self
+                    .saved_values
+                    .get(name)
+                    .copied()
+                    .filter(|value| Self::same_kind(value, &default))
+                    .unwrap_or(default);
+                let tweak = Tweak {
+                    name: name.to_string(),
+                    value,
+                    default,
+                    range,
+                };
+                match index {
+                    Some(index) => self.tweaks[index] = tweak,
+                    None => self.tweaks.push(tweak),
+                }
+                value
+            },
+        }
+    }
+
+    pub fn float(&mut self, name: &str, default: f32, range: RangeInclusive<f32>) -> f32 {
+        match self.value(name, TweakValue::Float(default), Some(range)) {
+            TweakValue::Float(value) => value,
+            _ => unreachable!(),
+        }
+    }
+
+    pub fn vec2(&mut self, name: &str, default: [f32; 2]) -> [f32; 2] {
+        match self.value(name, TweakValue::Vec2(default), None) {
+            TweakValue::Vec2(value) => value,
+            _ => unreachable!(),
+        }
+    }
+
+    pub fn vec3(&mut self, name: &str, default: [f32; 3]) -> [f32; 3] {
+        match self.value(name, TweakValue::Vec3(default), None) {
+            TweakValue::Vec3(value) => value,
+            _ => unreachable!(),
+        }
+    }
+
+    pub fn vec4(&mut self, name: &str, default: [f32; 4]) -> [f32; 4] {
+        match self.value(name, TweakValue::Vec4(default), None) {
+            TweakValue::Vec4(value) => value,
+            _ => unreachable!(),
+        }
+    }
+
+    // Linear RGB color
+    pub fn color3(&mut self, name: &str, default: [f32; 3]) -> [f32; 3] {
+        match self.value(name, TweakValue::Color3(default), None) {
+            TweakValue::Color3(value) => value,
+            _ => unreachable!(),
+        }
+    }
+
+    // Linear RGBA color, not premultiplied
+    pub fn color4(&mut self, name: &str, default: [f32; 4]) -> [f32; 4] {
+        match self.value(name, TweakValue::Color4(default), None) {
+            TweakValue::Color4(value) => value,
+            _ => unreachable!(),
+        }
+    }
+
+    pub fn flag(&mut self, name: &str, default: bool) -> bool {
+        match self.value(name, TweakValue::Bool(default), None) {
+            TweakValue::Bool(value) => value,
+            _ => unreachable!(),
+        }
+    }
+
+    pub fn get(&self, name: &str) -> Option<TweakValue> { self.tweaks.iter().find(|tweak| tweak.name == name).map(|tweak| tweak.value) }
+
+    // Set the value of a registered parameter, returns false if there is none of this name and kind
+    pub fn set(&mut self, name: &str, value: TweakValue) -> bool {
+        match self
+            .tweaks
+            .iter_mut()
+            .find(|tweak| tweak.name == name && Self::same_kind(&tweak.value, &value))
+        {
+            Some(tweak) => {
+                tweak.value = value;
+                self.dirty = true;
+                true
+            },
+            None => false,
+        }
+    }
+
+    pub fn reset_all(&mut self) {
+        for tweak in &mut self.tweaks {
+            tweak.value = tweak.default;
+        }
+        self.dirty = true;
+    }
+
+    // Widgets of the registered parameters, saving the values once an edit is done. Returns true if a value changed.
+    pub fn ui(&mut self, ui: &mut egui::Ui) -> bool {
+        let mut changed = false;
+        egui::Grid::new("tweaks").num_columns(2).show(ui, |ui| {
+            for tweak in &mut self.tweaks {
+                let name_response = ui
+                    .add(egui::Label::new(&tweak.name).sense(egui::Sense::click()))
+                    .on_hover_text("Double click to reset");
+                if name_response.double_clicked() {
+                    tweak.value = tweak.default;
+                    changed = true;
+                }
+                let response = match &mut tweak.value {
+                    TweakValue::Float(value) => match &tweak.range {
+                        Some(range) => ui.add(egui::Slider::new(value, range.clone())),
+                        None => ui.add(egui::DragValue::new(value).speed(0.01)),
+                    },
+                    TweakValue::Vec2(values) => Self::drag_values(ui, values),
+                    TweakValue::Vec3(values) => Self::drag_values(ui, values),
+                    TweakValue::Vec4(values) => Self::drag_values(ui, values),
+                    TweakValue::Color3(color) => ui.color_edit_button_rgb(color),
+                    TweakValue::Color4(color) => ui.color_edit_button_rgba_unmultiplied(color),
+                    TweakValue::Bool(value) => ui.checkbox(value, ""),
+                };
+                changed |= response.changed();
+                ui.end_row();
+            }
+        });
+        ui.horizontal(|ui| {
+            if ui.button("Reset all").clicked() {
+                self.reset_all();
+                changed = true;
+            }
+        });
+        self.dirty |= changed;
+
+        // Saved once the pointer is released rather than at each step of a drag
+        #[cfg(feature = "serde")]
+        if self.dirty && !ui.ctx().is_using_pointer() {
+            if let Err(error) = self.save() {
+                report_error!("{:#}", error);
+                // Not retried each frame
+                self.dirty = false;
+            }
+        }
+        changed
+    }
+
+    // Window with the `ui` of the parameters
+    pub fn show(&mut self, ctx: &egui::Context, open: &mut bool) -> bool {
+        let mut changed = false;
+        egui::Window::new("Tweaks").open(open).default_width(320.0).show(ctx, |ui| {
+            egui::ScrollArea::vertical().show(ui, |ui| changed = self.ui(ui));
+        });
+        changed
+    }
+
+    fn drag_values(ui: &mut egui::Ui, values: &mut [f32]) -> egui::Response {
+        ui.horizontal(|ui| {
+            let mut response = ui.add(egui::DragValue::new(&mut values[0]).speed(0.01));
+            for value in &mut values[1..] {
+                response |= ui.add(egui::DragValue::new(value).speed(0.01));
+            }
+            response
+        })
+        .inner
+    }
+}
@@ -0,0 +1,91 @@
+use std::{
+    path::{Path, PathBuf},
+    time::{Duration, Instant, SystemTime},
+};
+
+use anyhow::{bail, Context, Result};
+use serde::de::DeserializeOwned;
+
+use crate::wgpu_utils::uniform_buffer::UniformBufferWrapper;
+
+// Struct loaded from a toml or ron file (by extension) and reloaded when the file changes, to tune parameters live from
+// a text editor. The modification time of the file is checked by `poll`, at most once per check interval.
+pub struct WatchedConfig<T> {
+    path: PathBuf,
+    value: T,
+    modified: Option<SystemTime>,
+    check_interval: Duration,
+    last_check: Instant,
+}
+
+fn parse<T: DeserializeOwned>(path: &Path) -> Result<T> {
+    let source = std::fs::read_to_string(path).with_context(|| format!("Failed to read config {}", path.display()))?;
+    match path.extension().and_then(|extension| extension.to_str()) {
+        Some("toml") => toml::from_str(&source).with_context(|| format!("Failed to parse config {}", path.display())),
+        Some("ron") => ron::from_str(&source).with_context(|| format!("Failed to parse config {}", path.display())),
+        _ => bail!("Unknown config format of {}, expected a toml or ron file", path.display()),
+    }
+}
+
+fn modified_time(path: &Path) -> Option<SystemTime> { std::fs::metadata(path).and_then(|metadata| metadata.modified()).ok() }
+
+impl<T: DeserializeOwned> WatchedConfig<T> {
+    pub fn load(path: impl Into<PathBuf>) -> Result<Self> {
+        let path = path.into();
+        let modified = modified_time(&path);
+        let value = parse(&path)?;
+        Ok(Self {
+            path,
+            value,
+            modified,
+            check_interval: Duration::from_millis(250),
+            last_check: Instant::now(),
+        })
+    }
+
+    // Minimum time between two checks of the file by `poll`
+    pub fn with_check_interval(mut self, check_interval: Duration) -> Self {
+        self.check_interval = check_interval;
+        self
+    }
+
+    pub fn path(&self) -> &Path { &self.path }
+
+    pub fn get(&self) -> &T { &self.value }
+
+    // Reload the file if it was modified since the last load. Returns true if the value was reloaded.
+    // On an invalid file the previous value is kept and the error is returned once, until the file is modified again.
+    pub fn poll(&mut self) -> Result<bool> {
+        if self.last_check.elapsed() < self.check_interval {
+            return Ok(false);
+        }
+        self.last_check = Instant::now();
+
+        let modified = modified_time(&self.path);
+        if modified.is_none() || modified == self.modified {
+            return Ok(false);
+        }
+        self.modified = modified;
+        self.value = parse(&self.path)?;
+        Ok(true)
+    }
+
+    // Reload the file now
+    pub fn reload(&mut self) -> Result<()> {
+        self.modified = modified_time(&self.path);
+        self.value = parse(&self.path)?;
+        Ok(())
+    }
+}
+
+impl<T: DeserializeOwned + bytemuck::Pod> WatchedConfig<T> {
+    // `poll` and copy a reloaded value to the content of `uniform_buffer`, uploaded to the GPU
+    pub fn poll_uniform(&mut self, queue: &wgpu::Queue, uniform_buffer: &mut UniformBufferWrapper<T>) -> Result<bool> {
+        let reloaded = self.poll()?;
+        if reloaded {
+            *uniform_buffer.content_mut() = self.value;
+            uniform_buffer.update_content(queue);
+        }
+        Ok(reloaded)
+    }
+}
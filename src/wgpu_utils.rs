@@ -1,9 +1,47 @@
+mod asset_loader;
+mod auto_exposure;
 pub mod binding_builder;
 pub mod binding_glsl;
+pub mod binding_wgsl;
+mod buffer_pool;
 pub mod buffers;
-pub mod render_handles;
+mod canvas;
+mod compute_queue;
+mod debug_view;
+mod depth_prepass;
+mod diagnostics;
+mod dispatch_indirect;
+mod equirect_to_cubemap;
+mod error_policy;
+mod error_scopes;
+mod frame_uniforms;
+mod frames_in_flight;
+mod gbuffer;
+mod gpu_culling;
+mod gpu_timer;
+mod grid;
+mod image_processing;
+mod labels;
+pub mod memory_tracker;
+mod occlusion_queries;
+mod oit;
+mod picking;
+mod pipeline_compiler;
+mod pipeline_registry;
 mod ping_pong_buffer;
 mod ping_pong_texture;
+mod rect_fill;
+pub mod render_handles;
+mod render_states;
+mod render_targets;
+mod staging_belt;
+mod storage_texture;
+mod temporal_accumulation;
+mod texture_array;
+mod texture_builder;
+mod texture_readback;
+mod velocity;
+mod volume_texture;
 
 #[cfg(feature = "glsl")]
 pub mod shaders_glsl;
@@ -12,8 +50,86 @@ pub mod shaders_glsl;
 mod shader_composer;
 #[cfg(feature = "naga")]
 pub use shader_composer::ShaderComposer;
+#[cfg(feature = "naga")]
+mod noise;
+#[cfg(feature = "naga")]
+pub use noise::NOISE_MODULES;
+#[cfg(feature = "naga")]
+mod pbr;
+#[cfg(feature = "naga")]
+pub use pbr::{
+    PbrForwardPipeline,
+    PbrInstance,
+    PbrMaterial,
+    PbrMaterialBindGroup,
+    PbrMaterialUniforms,
+    PbrScene,
+    PbrSceneUniforms,
+    PbrTextureSlot,
+    PbrVertex,
+    PBR_BRDF_IMPORT_PATH,
+    PBR_BRDF_WGSL,
+    PBR_LIGHTING_IMPORT_PATH,
+    PBR_LIGHTING_WGSL,
+    PBR_MATERIAL_IMPORT_PATH,
+    PBR_MATERIAL_WGSL,
+};
+#[cfg(feature = "naga")]
+mod shader_library;
+#[cfg(feature = "naga")]
+pub use shader_library::{Tonemapping, COMMON_MODULES};
+#[cfg(feature = "naga")]
+mod tonemapping;
+#[cfg(feature = "naga")]
+pub use tonemapping::{TonemappingPass, TonemappingSettings};
 
 pub mod uniform_buffer;
+pub mod wgsl_struct;
+
+#[cfg(feature = "encase")]
+mod storage_buffer;
+#[cfg(feature = "encase")]
+pub use storage_buffer::StorageBufferWrapper;
 
-pub use ping_pong_buffer::PingPongBuffer;
-pub use ping_pong_texture::PingPongTexture;
+pub use asset_loader::{AssetLoader, LoadHandle, LoaderWaker};
+pub use auto_exposure::{AutoExposure, AutoExposureSettings, Exposure, EXPOSURE_IMPORT_PATH, EXPOSURE_WGSL};
+pub use buffer_pool::{BufferAllocation, BufferPool};
+pub use canvas::{Canvas, CanvasDescriptor, CanvasFilter};
+pub use compute_queue::{ComputeCompletionFn, ComputePriority, ComputeQueue, ComputeRecordFn};
+pub use debug_view::{DebugViewMode, DebugViewRenderer, DebugViewSettings};
+pub use depth_prepass::DepthPrepass;
+pub use diagnostics::{record_pass_label, record_shader, DiagnosticCallback, DiagnosticReport, DiagnosticReporter};
+pub use dispatch_indirect::{DispatchIndirectBuffer, WorkgroupCountPass, DISPATCH_INDIRECT_WGSL};
+pub use equirect_to_cubemap::EquirectToCubemap;
+pub use error_policy::{ErrorAction, ErrorMatcher, ErrorPolicy, ErrorRule, UncapturedErrorHandler};
+pub use error_scopes::{ErrorScopes, ScopeError};
+pub use frame_uniforms::{FrameUniforms, FRAME_UNIFORMS_IMPORT_PATH, FRAME_UNIFORMS_WGSL};
+pub use frames_in_flight::{FramesInFlight, PerFrame};
+pub use gbuffer::{GBuffer, GBufferAttachment, GBufferDescriptor};
+pub use gpu_culling::{CullingDraw, CullingObject, GpuCulling};
+pub use gpu_timer::{GpuScope, GpuTimer, GpuTimingNode, GpuTimingStats};
+pub use grid::{GridSettings, InfiniteGrid};
+pub use image_processing::{BoxBlur, Downsample, GaussianBlur, SobelEdges, Upsample};
+pub use labels::{child_label, variant_label};
+pub use occlusion_queries::OcclusionQueries;
+pub use oit::{WeightedBlendedOit, OIT_WGSL};
+pub use picking::{ObjectPicking, PickResult};
+pub use pipeline_compiler::PipelineCompiler;
+#[cfg(feature = "naga")]
+pub use pipeline_compiler::parse_wgsl;
+pub use pipeline_registry::{ComputePipelineId, PipelineRegistry, PipelineReload, RenderPipelineId};
+pub use ping_pong_buffer::{PingPongBuffer, PingPongBufferAccess, PingPongBufferBuilder};
+pub use ping_pong_texture::{PingPongTexture, PingPongTextureBinding, PingPongTextureLayout};
+pub use rect_fill::RectFill;
+pub use render_states::{BlendPreset, DepthConvention, DepthPreset, DEPTH_IMPORT_PATH, DEPTH_WGSL};
+pub use render_targets::{RenderTarget, RenderTargetDescriptor, RenderTargetSize, RenderTargets};
+pub use staging_belt::StagingBelt;
+pub use storage_texture::StorageTexture;
+pub use temporal_accumulation::TemporalAccumulation;
+pub use texture_array::TextureArray;
+pub use texture_builder::{TextureBuilder, TextureResource};
+pub use texture_readback::TextureReadback;
+#[cfg(feature = "image")]
+pub use texture_readback::{save_texture_to_exr, save_texture_to_png, texels_to_image};
+pub use velocity::{MotionUniforms, VELOCITY_IMPORT_PATH, VELOCITY_WGSL};
+pub use volume_texture::{VolumeSliceAxis, VolumeSliceMode, VolumeSliceRenderer, VolumeSliceSettings, VolumeTexture};
@@ -0,0 +1,219 @@
+use std::{
+    path::PathBuf,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        mpsc,
+        Arc,
+        Mutex,
+    },
+};
+
+use anyhow::{anyhow, Result};
+use wgpu::util::DeviceExt;
+
+type Job = Box<dyn FnOnce() + Send>;
+// Run on the main loop with the device to turn the decoded data into GPU resources
+type Completion = Box<dyn FnOnce(&wgpu::Device, &wgpu::Queue) + Send>;
+pub type LoaderWaker = Box<dyn Fn() + Send>;
+
+enum LoadState<T> {
+    Loading,
+    Loaded(Result<T>),
+    Taken,
+}
+
+// Result slot of a job submitted to the AssetLoader, filled on the main loop by `AssetLoader::process_completed`
+pub struct LoadHandle<T> {
+    label: String,
+    state: Arc<Mutex<LoadState<T>>>,
+}
+
+impl<T> LoadHandle<T> {
+    pub fn label(&self) -> &str { &self.label }
+
+    pub fn is_loading(&self) -> bool { matches!(*self.state.lock().unwrap(), LoadState::Loading) }
+
+    pub fn is_ready(&self) -> bool { matches!(*self.state.lock().unwrap(), LoadState::Loaded(_)) }
+
+    // Take the loaded resource (or the load error), None while loading or once taken
+    pub fn take(&self) -> Option<Result<T>> {
+        let mut state = self.state.lock().unwrap();
+        match std::mem::replace(&mut *state, LoadState::Taken) {
+            LoadState::Loaded(result) => Some(result),
+            other => {
+                *state = other;
+                None
+            },
+        }
+    }
+}
+
+// Worker threads decoding assets off the main thread. Decoded data is sent back through a channel
+// drained each frame by `process_completed` which uploads it to the GPU and fills the LoadHandle.
+// The waker (the event loop proxy in the application) is called when a job completes so a waiting event loop processes it.
+pub struct AssetLoader {
+    job_sender: mpsc::Sender<Job>,
+    completion_sender: mpsc::Sender<Completion>,
+    completion_receiver: mpsc::Receiver<Completion>,
+    waker: Arc<Mutex<Option<LoaderWaker>>>,
+    pending_count: Arc<AtomicUsize>,
+}
+
+impl Default for AssetLoader {
+    fn default() -> Self { Self::new(std::thread::available_parallelism().map_or(2, |count| count.get().clamp(1, 4))) }
+}
+
+impl AssetLoader {
+    pub fn new(worker_count: usize) -> Self {
+        let (job_sender, job_receiver) = mpsc::channel::<Job>();
+        let job_receiver = Arc::new(Mutex::new(job_receiver));
+        // Workers are detached and stop once the loader (and so the job sender) is dropped
+        for worker_index in 0..worker_count.max(1) {
+            let job_receiver = job_receiver.clone();
+            std::thread::Builder::new()
+                .name(format!("oxyde asset loader {}", worker_index))
+                .spawn(move || loop {
+                    let job = job_receiver.lock().unwrap().recv();
+                    match job {
+                        Ok(job) => job(),
+                        Err(_) => break,
+                    }
+                })
+                .expect("Failed to spawn asset loader thread");
+        }
+
+        let (completion_sender, completion_receiver) = mpsc::channel();
+        Self {
+            job_sender,
+            completion_sender,
+            completion_receiver,
+            waker: Arc::new(Mutex::new(None)),
+            pending_count: Arc::new(AtomicUsize::new(0)),
+        }
+    }
+
+    pub fn set_waker(&mut self, waker: impl Fn() + Send + 'static) { *self.waker.lock().unwrap() = Some(Box::new(waker)); }
+
+    // Jobs submitted whose result has not been processed on the main loop yet
+    pub fn pending_count(&self) -> usize { self.pending_count.load(Ordering::Acquire) }
+
+    // Run `decode` on a worker thread then `upload` on the main loop with the device.
+    // A panic in `decode` is reported as a load error.
+    pub fn load<D, T>(
+        &self,
+        label: impl Into<String>,
+        decode: impl FnOnce() -> Result<D> + Send + 'static,
+        upload: impl FnOnce(D, &wgpu::Device, &wgpu::Queue) -> Result<T> + Send + 'static,
+    ) -> LoadHandle<T>
+    where
+        D: Send + 'static,
+        T: Send + 'static,
+    {
+        let handle = LoadHandle {
+            label: label.into(),
+            state: Arc::new(Mutex::new(LoadState::Loading)),
+        };
+
+        let label = handle.label.clone();
+        let state = handle.state.clone();
+        let completion_sender = self.completion_sender.clone();
+        let waker = self.waker.clone();
+        self.pending_count.fetch_add(1, Ordering::AcqRel);
+        let job: Job = Box::new(move || {
+            let decoded =
+                std::panic::catch_unwind(std::panic::AssertUnwindSafe(decode)).unwrap_or_else(|_| Err(anyhow!("Decoding of \"{}\" panicked", label)));
+            let completion: Completion = Box::new(move |device, queue| {
+                let result = decoded.and_then(|decoded| upload(decoded, device, queue));
+                *state.lock().unwrap() = LoadState::Loaded(result);
+            });
+            // The loader may have been dropped meanwhile
+            if completion_sender.send(completion).is_ok() {
+                if let Some(waker) = waker.lock().unwrap().as_ref() {
+                    waker();
+                }
+            }
+        });
+        self.job_sender.send(job).expect("Asset loader threads stopped");
+
+        handle
+    }
+
+    // Upload the assets decoded since the last call, to call once per frame on the main loop. Returns the number of completed jobs.
+    pub fn process_completed(&self, device: &wgpu::Device, queue: &wgpu::Queue) -> usize {
+        let mut completed_count = 0;
+        for completion in self.completion_receiver.try_iter() {
+            completion(device, queue);
+            completed_count += 1;
+        }
+        self.pending_count.fetch_sub(completed_count, Ordering::AcqRel);
+        completed_count
+    }
+
+    pub fn load_bytes(&self, path: impl Into<PathBuf>) -> LoadHandle<Vec<u8>> {
+        let path = path.into();
+        let label = path.display().to_string();
+        self.load(label, move || Ok(std::fs::read(&path)?), |bytes, _, _| Ok(bytes))
+    }
+
+    // Load a file in a buffer, e.g. vertices or indices of a mesh stored raw
+    pub fn load_buffer(&self, path: impl Into<PathBuf>, usage: wgpu::BufferUsages) -> LoadHandle<wgpu::Buffer> {
+        let path = path.into();
+        let label = path.display().to_string();
+        self.load(
+            label.clone(),
+            move || Ok(std::fs::read(&path)?),
+            move |bytes, device, _| {
+                Ok(device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+                    label: Some(&label),
+                    contents: &bytes,
+                    usage,
+                }))
+            },
+        )
+    }
+
+    pub fn load_wgsl_shader(&self, path: impl Into<PathBuf>) -> LoadHandle<wgpu::ShaderModule> {
+        let path = path.into();
+        let label = path.display().to_string();
+        self.load(
+            label.clone(),
+            move || Ok(std::fs::read_to_string(&path)?),
+            move |source, device, _| {
+                super::record_shader(label.as_str());
+                Ok(device.create_shader_module(wgpu::ShaderModuleDescriptor {
+                    label: Some(&label),
+                    source: wgpu::ShaderSource::Wgsl(source.into()),
+                }))
+            },
+        )
+    }
+
+    // Decode an image to a Rgba8UnormSrgb texture (single mip level)
+    #[cfg(feature = "image")]
+    pub fn load_texture(&self, path: impl Into<PathBuf>, usage: wgpu::TextureUsages) -> LoadHandle<wgpu::Texture> {
+        let path = path.into();
+        let label = path.display().to_string();
+        self.load(
+            label.clone(),
+            move || Ok(image::open(&path)?.into_rgba8()),
+            move |image, device, queue| {
+                let (width, height) = image.dimensions();
+                Ok(device.create_texture_with_data(
+                    queue,
+                    &wgpu::TextureDescriptor {
+                        label: Some(&label),
+                        size: wgpu::Extent3d { width, height, depth_or_array_layers: 1 },
+                        mip_level_count: 1,
+                        sample_count: 1,
+                        dimension: wgpu::TextureDimension::D2,
+                        format: wgpu::TextureFormat::Rgba8UnormSrgb,
+                        usage,
+                        view_formats: &[],
+                    },
+                    wgpu::util::TextureDataOrder::LayerMajor,
+                    &image,
+                ))
+            },
+        )
+    }
+}
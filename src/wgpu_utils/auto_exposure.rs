@@ -0,0 +1,315 @@
+use wgpu::util::DeviceExt;
+
+use super::{
+    binding_builder::{BindGroupBuilder, BindGroupLayoutBuilder, BindGroupLayoutWithDesc},
+    memory_tracker::MemoryAllocation,
+    uniform_buffer::UniformBuffer,
+};
+
+const HISTOGRAM_BIN_COUNT: u64 = 256;
+
+// Exposure computed on the GPU, read as a uniform by the passes applying it (e.g. `TonemappingPass`)
+#[repr(C)]
+#[derive(Clone, Copy, Default, Debug, PartialEq, bytemuck::Pod, bytemuck::Zeroable)]
+pub struct Exposure {
+    // Exposure value to apply (colors are scaled by 2^exposure_value)
+    pub exposure_value: f32,
+    // Adapted log2 of the scene luminance
+    pub average_log_luminance: f32,
+    _padding: [f32; 2],
+}
+
+pub const EXPOSURE_IMPORT_PATH: &str = "oxyde::exposure";
+
+// Matching WGSL struct, as a composable module (`#import oxyde::exposure::Exposure`, see `ShaderComposer::with_exposure`)
+pub const EXPOSURE_WGSL: &str = r#"#define_import_path oxyde::exposure
+
+struct Exposure {
+    exposure_value: f32,
+    average_log_luminance: f32,
+    _padding: vec2<f32>,
+}
+"#;
+
+// Luminances and exposures are in EV (log2)
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct AutoExposureSettings {
+    // Range of the luminance histogram, darker and brighter pixels are counted at its bounds
+    pub min_log_luminance: f32,
+    pub max_log_luminance: f32,
+    // Rate of the exponential moving average of the luminance, per second
+    pub adaptation_speed: f32,
+    // Added to the computed exposure, positive values brighten the image
+    pub exposure_compensation: f32,
+    pub min_exposure: f32,
+    pub max_exposure: f32,
+}
+
+impl Default for AutoExposureSettings {
+    fn default() -> Self {
+        Self {
+            min_log_luminance: -8.0,
+            max_log_luminance: 4.0,
+            adaptation_speed: 2.0,
+            exposure_compensation: 0.0,
+            min_exposure: -8.0,
+            max_exposure: 8.0,
+        }
+    }
+}
+
+#[repr(C)]
+#[derive(Clone, Copy, Default, Debug, PartialEq, bytemuck::Pod, bytemuck::Zeroable)]
+struct AutoExposureParams {
+    min_log_luminance: f32,
+    log_luminance_range: f32,
+    delta_time: f32,
+    adaptation_speed: f32,
+    exposure_compensation: f32,
+    min_exposure: f32,
+    max_exposure: f32,
+    _padding: f32,
+}
+
+// Bin 0 counts the black pixels, ignored by the average, bins 1 to 255 cover the log luminance range
+const AUTO_EXPOSURE_WGSL: &str = r#"
+struct AutoExposureParams {
+    min_log_luminance: f32,
+    log_luminance_range: f32,
+    delta_time: f32,
+    adaptation_speed: f32,
+    exposure_compensation: f32,
+    min_exposure: f32,
+    max_exposure: f32,
+    _padding: f32,
+}
+
+@group(0) @binding(0) var hdr: texture_2d<f32>;
+@group(0) @binding(1) var<uniform> params: AutoExposureParams;
+@group(0) @binding(2) var<storage, read_write> histogram: array<atomic<u32>, 256>;
+@group(0) @binding(3) var<storage, read_write> exposure: Exposure;
+
+var<workgroup> local_histogram: array<atomic<u32>, 256>;
+var<workgroup> weighted_bins: array<f32, 256>;
+
+fn luminance_bin(color: vec3<f32>) -> u32 {
+    let luminance = dot(color, vec3<f32>(0.2126, 0.7152, 0.0722));
+    if luminance < 1.0e-5 {
+        return 0u;
+    }
+    let position = clamp((log2(luminance) - params.min_log_luminance) / params.log_luminance_range, 0.0, 1.0);
+    return u32(position * 254.0 + 1.0);
+}
+
+@compute @workgroup_size(16, 16)
+fn build_histogram(@builtin(global_invocation_id) id: vec3<u32>, @builtin(local_invocation_index) local_index: u32) {
+    atomicStore(&local_histogram[local_index], 0u);
+    workgroupBarrier();
+    if all(id.xy < textureDimensions(hdr)) {
+        atomicAdd(&local_histogram[luminance_bin(textureLoad(hdr, id.xy, 0).rgb)], 1u);
+    }
+    workgroupBarrier();
+    let count = atomicLoad(&local_histogram[local_index]);
+    if count > 0u {
+        atomicAdd(&histogram[local_index], count);
+    }
+}
+
+// Average of the histogram, adaptation and clear of the histogram for the next frame
+@compute @workgroup_size(256)
+fn adapt_exposure(@builtin(local_invocation_index) local_index: u32) {
+    let count = atomicLoad(&histogram[local_index]);
+    atomicStore(&histogram[local_index], 0u);
+    weighted_bins[local_index] = f32(count) * f32(local_index);
+    workgroupBarrier();
+    for (var stride = 128u; stride > 0u; stride >>= 1u) {
+        if local_index < stride {
+            weighted_bins[local_index] += weighted_bins[local_index + stride];
+        }
+        workgroupBarrier();
+    }
+
+    if local_index == 0u {
+        let size = textureDimensions(hdr);
+        let lit_pixel_count = f32(size.x * size.y) - f32(count);
+        // A black frame keeps the previous exposure
+        if lit_pixel_count < 1.0 {
+            return;
+        }
+        let average_bin = weighted_bins[0] / lit_pixel_count;
+        let average_log_luminance = (average_bin - 1.0) / 254.0 * params.log_luminance_range + params.min_log_luminance;
+        let blend = 1.0 - exp(-params.delta_time * params.adaptation_speed);
+        exposure.average_log_luminance = mix(exposure.average_log_luminance, average_log_luminance, blend);
+        // Exposure bringing the average luminance to middle grey (0.18)
+        exposure.exposure_value = clamp(
+            -2.47393118833 - exposure.average_log_luminance + params.exposure_compensation,
+            params.min_exposure,
+            params.max_exposure
+        );
+    }
+}
+"#;
+
+// Eye adaptation from the log luminance histogram of an HDR texture, smoothed over time.
+// The resulting `Exposure` stays on the GPU in a buffer bound as a uniform by the passes applying it.
+pub struct AutoExposure {
+    settings: AutoExposureSettings,
+    params: UniformBuffer<AutoExposureParams>,
+    histogram: wgpu::Buffer,
+    exposure: wgpu::Buffer,
+    bind_group_layout: BindGroupLayoutWithDesc,
+    histogram_pipeline: wgpu::ComputePipeline,
+    adapt_pipeline: wgpu::ComputePipeline,
+    exposure_bind_group_layout: BindGroupLayoutWithDesc,
+    exposure_bind_group: wgpu::BindGroup,
+    _memory: [MemoryAllocation; 2],
+}
+
+impl AutoExposure {
+    pub fn new(device: &wgpu::Device, settings: AutoExposureSettings) -> Self {
+        let storage_binding = wgpu::BindingType::Buffer {
+            ty: wgpu::BufferBindingType::Storage { read_only: false },
+            has_dynamic_offset: false,
+            min_binding_size: None,
+        };
+        let bind_group_layout = BindGroupLayoutBuilder::new()
+            .add_binding_compute(wgpu::BindingType::Texture {
+                sample_type: wgpu::TextureSampleType::Float { filterable: false },
+                view_dimension: wgpu::TextureViewDimension::D2,
+                multisampled: false,
+            })
+            .add_binding_compute(wgpu::BindingType::Buffer {
+                ty: wgpu::BufferBindingType::Uniform,
+                has_dynamic_offset: false,
+                min_binding_size: None,
+            })
+            .add_binding_compute(storage_binding)
+            .add_binding_compute(storage_binding)
+            .create(device, Some("AutoExposure"));
+
+        let histogram = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("AutoExposure: histogram"),
+            size: HISTOGRAM_BIN_COUNT * std::mem::size_of::<u32>() as u64,
+            usage: wgpu::BufferUsages::STORAGE,
+            mapped_at_creation: false,
+        });
+        // Starts at middle grey (exposure 0)
+        let exposure = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("AutoExposure: exposure"),
+            contents: bytemuck::bytes_of(&Exposure {
+                exposure_value: 0.0,
+                average_log_luminance: 0.18f32.log2(),
+                _padding: [0.0; 2],
+            }),
+            usage: wgpu::BufferUsages::STORAGE | wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_SRC,
+        });
+        let memory = [
+            MemoryAllocation::for_buffer(&histogram, Some("AutoExposure: histogram")),
+            MemoryAllocation::for_buffer(&exposure, Some("AutoExposure: exposure")),
+        ];
+
+        let exposure_bind_group_layout = BindGroupLayoutBuilder::new()
+            .add_binding(
+                wgpu::ShaderStages::FRAGMENT | wgpu::ShaderStages::COMPUTE,
+                wgpu::BindingType::Buffer {
+                    ty: wgpu::BufferBindingType::Uniform,
+                    has_dynamic_offset: false,
+                    min_binding_size: None,
+                },
+            )
+            .create(device, Some("AutoExposure: exposure"));
+        let exposure_bind_group = BindGroupBuilder::new(&exposure_bind_group_layout)
+            .resource(exposure.as_entire_binding())
+            .create(device, Some("AutoExposure: exposure"));
+
+        let shader_module = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: Some("AutoExposure"),
+            source: wgpu::ShaderSource::Wgsl(
+                format!(
+                    "{}{}",
+                    EXPOSURE_WGSL.replacen(&format!("#define_import_path {}", EXPOSURE_IMPORT_PATH), "", 1),
+                    AUTO_EXPOSURE_WGSL
+                )
+                .into(),
+            ),
+        });
+        let pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("AutoExposure"),
+            bind_group_layouts: &[&bind_group_layout.layout],
+            push_constant_ranges: &[],
+        });
+        let create_pipeline = |entry_point: &str| {
+            device.create_compute_pipeline(&wgpu::ComputePipelineDescriptor {
+                label: Some(&format!("AutoExposure: {}", entry_point)),
+                layout: Some(&pipeline_layout),
+                module: &shader_module,
+                entry_point,
+            })
+        };
+
+        Self {
+            settings,
+            params: UniformBuffer::new(device),
+            histogram,
+            exposure,
+            bind_group_layout,
+            histogram_pipeline: create_pipeline("build_histogram"),
+            adapt_pipeline: create_pipeline("adapt_exposure"),
+            exposure_bind_group_layout,
+            exposure_bind_group,
+            _memory: memory,
+        }
+    }
+
+    pub fn settings(&self) -> &AutoExposureSettings { &self.settings }
+
+    pub fn set_settings(&mut self, settings: AutoExposureSettings) { self.settings = settings; }
+
+    // Upload the settings and the frame delta time (seconds), before `encode`
+    pub fn update(&mut self, queue: &wgpu::Queue, delta_time: f32) {
+        let settings = &self.settings;
+        self.params.update_content(
+            queue,
+            AutoExposureParams {
+                min_log_luminance: settings.min_log_luminance,
+                log_luminance_range: (settings.max_log_luminance - settings.min_log_luminance).max(1.0e-3),
+                delta_time,
+                adaptation_speed: settings.adaptation_speed,
+                exposure_compensation: settings.exposure_compensation,
+                min_exposure: settings.min_exposure,
+                max_exposure: settings.max_exposure,
+                _padding: 0.0,
+            },
+        );
+    }
+
+    // Measure the HDR texture (any float format, of size `width` x `height`) and adapt the exposure
+    pub fn encode(&self, device: &wgpu::Device, encoder: &mut wgpu::CommandEncoder, hdr_view: &wgpu::TextureView, width: u32, height: u32) {
+        let bind_group = BindGroupBuilder::new(&self.bind_group_layout)
+            .texture(hdr_view)
+            .resource(self.params.binding_resource())
+            .resource(self.histogram.as_entire_binding())
+            .resource(self.exposure.as_entire_binding())
+            .create(device, Some("AutoExposure"));
+        let mut compute_pass = encoder.begin_compute_pass(&wgpu::ComputePassDescriptor {
+            label: Some("AutoExposure"),
+            timestamp_writes: None,
+        });
+        compute_pass.set_bind_group(0, &bind_group, &[]);
+        compute_pass.set_pipeline(&self.histogram_pipeline);
+        compute_pass.dispatch_workgroups(width.div_ceil(16), height.div_ceil(16), 1);
+        compute_pass.set_pipeline(&self.adapt_pipeline);
+        compute_pass.dispatch_workgroups(1, 1, 1);
+    }
+
+    // Buffer of the `Exposure` struct, with the UNIFORM, STORAGE and COPY_SRC usages
+    pub fn exposure_buffer(&self) -> &wgpu::Buffer { &self.exposure }
+
+    pub fn exposure_binding_resource(&self) -> wgpu::BindingResource<'_> { self.exposure.as_entire_binding() }
+
+    // Uniform binding of the exposure, visible in the fragment and compute stages
+    pub fn exposure_bind_group_layout(&self) -> &wgpu::BindGroupLayout { &self.exposure_bind_group_layout.layout }
+
+    pub fn exposure_bind_group(&self) -> &wgpu::BindGroup { &self.exposure_bind_group }
+}
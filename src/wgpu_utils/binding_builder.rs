@@ -1,8 +1,19 @@
+use anyhow::{bail, Result};
+
+use super::memory_tracker::MemoryAllocation;
+
 pub struct BindGroupLayoutWithDesc {
     pub layout: wgpu::BindGroupLayout,
     pub entries: Vec<wgpu::BindGroupLayoutEntry>,
 }
 
+impl BindGroupLayoutWithDesc {
+    // Number of offsets `set_bind_group` expects for the bind groups of this layout
+    pub fn dynamic_offset_count(&self) -> usize { self.entries.iter().filter(|entry| has_dynamic_offset(entry)).count() }
+}
+
+fn has_dynamic_offset(entry: &wgpu::BindGroupLayoutEntry) -> bool { matches!(entry.ty, wgpu::BindingType::Buffer { has_dynamic_offset: true, .. }) }
+
 #[derive(Default)]
 pub struct BindGroupLayoutBuilder {
     entries: Vec<wgpu::BindGroupLayoutEntry>,
@@ -23,6 +34,54 @@ impl BindGroupLayoutBuilder {
         self.add_raw_binding(wgpu::BindGroupLayoutEntry { binding, visibility, ty, count: None })
     }
 
+    // `binding_array<T, count>` binding, e.g. the textures of all the materials indexed in the shaders (bindless). The
+    // device needs the features of `binding_array_features`.
+    pub fn add_binding_array(self, visibility: wgpu::ShaderStages, ty: wgpu::BindingType, count: u32) -> Self {
+        let binding: u32 = self.next_binding_index;
+        let count = std::num::NonZeroU32::new(count).expect("A binding array must have at least one element");
+        self.add_raw_binding(wgpu::BindGroupLayoutEntry {
+            binding,
+            visibility,
+            ty,
+            count: Some(count),
+        })
+    }
+
+    // Entries of an existing layout with their binding indices, e.g. the shared camera and frame uniforms of a pass layout.
+    // Panics on a binding index already used by a different binding, see `merge_layout_builders`.
+    pub fn extend_from(mut self, layout_with_desc: &BindGroupLayoutWithDesc) -> Self {
+        for entry in &layout_with_desc.entries {
+            if let Err(error) = self.merge_entry(*entry) {
+                panic!("{}", error);
+            }
+        }
+        self
+    }
+
+    // Entries with the binding index of an entry are merged when they have the same type and count, their visibilities
+    // are combined
+    fn merge_entry(&mut self, entry: wgpu::BindGroupLayoutEntry) -> Result<()> {
+        match self.entries.iter_mut().find(|existing| existing.binding == entry.binding) {
+            Some(existing) if existing.ty == entry.ty && existing.count == entry.count => existing.visibility |= entry.visibility,
+            Some(existing) => bail!("Conflicting bindings at index {}: {:?} and {:?}", entry.binding, existing.ty, entry.ty),
+            None => {
+                self.next_binding_index = self.next_binding_index.max(entry.binding + 1);
+                self.entries.push(entry);
+            },
+        }
+        Ok(())
+    }
+
+    // Bind the last added buffer binding with a dynamic offset given to `set_bind_group`, e.g. the per-object uniforms of a
+    // `BufferPool` page bound once and offset for each object
+    pub fn with_dynamic_offset(mut self) -> Self {
+        match self.entries.last_mut().map(|entry| &mut entry.ty) {
+            Some(wgpu::BindingType::Buffer { has_dynamic_offset, .. }) => *has_dynamic_offset = true,
+            _ => panic!("Only buffer bindings can have a dynamic offset"),
+        }
+        self
+    }
+
     // convenient helpers
     pub fn add_binding_compute(self, ty: wgpu::BindingType) -> Self { self.add_binding(wgpu::ShaderStages::COMPUTE, ty) }
 
@@ -32,6 +91,26 @@ impl BindGroupLayoutBuilder {
 
     pub fn add_binding_rendering(self, ty: wgpu::BindingType) -> Self { self.add_binding(wgpu::ShaderStages::VERTEX_FRAGMENT, ty) }
 
+    // `texture_2d_array<T>` binding, a single binding for all the layers of a texture array
+    pub fn add_texture_2d_array(self, visibility: wgpu::ShaderStages, sample_type: wgpu::TextureSampleType) -> Self {
+        self.add_binding(
+            visibility,
+            wgpu::BindingType::Texture {
+                sample_type,
+                view_dimension: wgpu::TextureViewDimension::D2Array,
+                multisampled: false,
+            },
+        )
+    }
+
+    // Features the device needs for the binding arrays of the layout
+    pub fn required_features(&self) -> wgpu::Features {
+        self.entries
+            .iter()
+            .filter(|entry| entry.count.is_some())
+            .fold(wgpu::Features::empty(), |features, entry| features | binding_array_features(&entry.ty))
+    }
+
     pub fn create(self, device: &wgpu::Device, label: Option<&str>) -> BindGroupLayoutWithDesc {
         BindGroupLayoutWithDesc {
             layout: device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
@@ -43,6 +122,34 @@ impl BindGroupLayoutBuilder {
     }
 }
 
+// Entries of all the builders in a single builder, keeping their binding indices, e.g. global bindings shared by the
+// passes composed with the bindings of each pass. Fails when two builders use a binding index for different bindings.
+pub fn merge_layout_builders(builders: impl IntoIterator<Item = BindGroupLayoutBuilder>) -> Result<BindGroupLayoutBuilder> {
+    let mut merged = BindGroupLayoutBuilder::new();
+    for builder in builders {
+        for entry in builder.entries {
+            merged.merge_entry(entry)?;
+        }
+    }
+    Ok(merged)
+}
+
+// Features needed to declare a binding array of this type, without the non uniform indexing ones
+// (SAMPLED_TEXTURE_AND_STORAGE_BUFFER_ARRAY_NON_UNIFORM_INDEXING, UNIFORM_BUFFER_AND_STORAGE_TEXTURE_ARRAY_NON_UNIFORM_INDEXING)
+// needed to index them with values varying in the invocations, e.g. a material index read from a buffer
+pub fn binding_array_features(ty: &wgpu::BindingType) -> wgpu::Features {
+    match ty {
+        wgpu::BindingType::Texture { .. } | wgpu::BindingType::Sampler(_) => wgpu::Features::TEXTURE_BINDING_ARRAY,
+        wgpu::BindingType::StorageTexture { .. } => wgpu::Features::TEXTURE_BINDING_ARRAY | wgpu::Features::STORAGE_RESOURCE_BINDING_ARRAY,
+        wgpu::BindingType::Buffer {
+            ty: wgpu::BufferBindingType::Storage { .. },
+            ..
+        } => wgpu::Features::BUFFER_BINDING_ARRAY | wgpu::Features::STORAGE_RESOURCE_BINDING_ARRAY,
+        wgpu::BindingType::Buffer { .. } => wgpu::Features::BUFFER_BINDING_ARRAY,
+        wgpu::BindingType::AccelerationStructure => wgpu::Features::empty(),
+    }
+}
+
 pub struct BindGroupBuilder<'a> {
     layout_with_desc: &'a BindGroupLayoutWithDesc,
     entries: Vec<wgpu::BindGroupEntry<'a>>,
@@ -54,6 +161,15 @@ impl<'a> BindGroupBuilder<'a> {
     // Uses same binding index as binding group layout at the same ordering
     pub fn resource(mut self, resource: wgpu::BindingResource<'a>) -> Self {
         assert!(self.entries.len() < self.layout_with_desc.entries.len());
+        let layout_entry = &self.layout_with_desc.entries[self.entries.len()];
+        // The dynamic offset moves the bound range in the buffer, the range must have a size to not overflow the buffer
+        if has_dynamic_offset(layout_entry) {
+            assert!(
+                matches!(resource, wgpu::BindingResource::Buffer(wgpu::BufferBinding { size: Some(_), .. })),
+                "The binding {} has a dynamic offset, it must be bound to a sub-range of a buffer (see `buffer_range`)",
+                layout_entry.binding
+            );
+        }
         self.entries.push(wgpu::BindGroupEntry {
             binding: self.layout_with_desc.entries[self.entries.len()].binding,
             resource,
@@ -64,6 +180,47 @@ impl<'a> BindGroupBuilder<'a> {
     // convenient helpers
     pub fn sampler(self, sampler: &'a wgpu::Sampler) -> Self { self.resource(wgpu::BindingResource::Sampler(sampler)) }
     pub fn texture(self, texture_view: &'a wgpu::TextureView) -> Self { self.resource(wgpu::BindingResource::TextureView(texture_view)) }
+    pub fn buffer(self, buffer: &'a wgpu::Buffer) -> Self { self.resource(buffer.as_entire_binding()) }
+
+    // `size` bytes of the buffer from `offset`, the range seen by the shaders at each dynamic offset for dynamic bindings
+    pub fn buffer_range(self, buffer: &'a wgpu::Buffer, offset: wgpu::BufferAddress, size: wgpu::BufferAddress) -> Self {
+        self.resource(wgpu::BindingResource::Buffer(wgpu::BufferBinding {
+            buffer,
+            offset,
+            size: Some(wgpu::BufferSize::new(size).expect("A buffer range can't be empty")),
+        }))
+    }
+
+    // Resources of the next binding array, at most its count of elements (exactly its count without the
+    // PARTIALLY_BOUND_BINDING_ARRAY feature)
+    pub fn texture_view_array(self, texture_views: &'a [&'a wgpu::TextureView]) -> Self {
+        self.array_resource(texture_views.len(), wgpu::BindingResource::TextureViewArray(texture_views))
+    }
+
+    pub fn sampler_array(self, samplers: &'a [&'a wgpu::Sampler]) -> Self {
+        self.array_resource(samplers.len(), wgpu::BindingResource::SamplerArray(samplers))
+    }
+
+    pub fn buffer_array(self, buffers: &'a [wgpu::BufferBinding<'a>]) -> Self {
+        self.array_resource(buffers.len(), wgpu::BindingResource::BufferArray(buffers))
+    }
+
+    fn array_resource(self, len: usize, resource: wgpu::BindingResource<'a>) -> Self {
+        let entry = self
+            .layout_with_desc
+            .entries
+            .get(self.entries.len())
+            .expect("More resources than bindings in the layout");
+        let count = entry.count.expect("The binding isn't a binding array, see `add_binding_array`");
+        assert!(
+            len > 0 && len <= count.get() as usize,
+            "{} resources for the binding array {} of {} elements",
+            len,
+            entry.binding,
+            count
+        );
+        self.resource(resource)
+    }
 
     pub fn create(&self, device: &wgpu::Device, label: Option<&str>) -> wgpu::BindGroup {
         assert_eq!(self.entries.len(), self.layout_with_desc.entries.len());
@@ -73,4 +230,88 @@ impl<'a> BindGroupBuilder<'a> {
             label: Some(format!("BindGroup: {}", label.unwrap_or("unknown")).as_str()),
         })
     }
+
+    // Bind group registered in the memory tracker, reported by `memory_tracker::resource_warnings` when it isn't used
+    // (dereferenced, e.g. `render_pass.set_bind_group(0, &bind_group, &[])`) for many frames or leaked
+    #[track_caller]
+    pub fn create_tracked(&self, device: &wgpu::Device, label: Option<&str>) -> TrackedBindGroup {
+        TrackedBindGroup {
+            bind_group: self.create(device, label),
+            memory: MemoryAllocation::for_bind_group(label),
+        }
+    }
+
+    // Bind group checking the dynamic offsets given when it's set on a pass
+    pub fn create_bound(&self, device: &wgpu::Device, label: Option<&str>) -> BoundGroup {
+        let limits = device.limits();
+        let mut dynamic_entries: Vec<&wgpu::BindGroupLayoutEntry> =
+            self.layout_with_desc.entries.iter().filter(|entry| has_dynamic_offset(entry)).collect();
+        // The offsets are applied in the binding order of the layout
+        dynamic_entries.sort_by_key(|entry| entry.binding);
+        BoundGroup {
+            bind_group: self.create(device, label),
+            offset_alignments: dynamic_entries
+                .into_iter()
+                .map(|entry| match entry.ty {
+                    wgpu::BindingType::Buffer { ty: wgpu::BufferBindingType::Uniform, .. } => limits.min_uniform_buffer_offset_alignment,
+                    _ => limits.min_storage_buffer_offset_alignment,
+                })
+                .collect(),
+        }
+    }
+}
+
+// Bind group with the alignments of its dynamic offsets, `set_render` / `set_compute` panic on a wrong number of offsets
+// or a misaligned offset rather than letting wgpu report a validation error at the end of the pass
+pub struct BoundGroup {
+    bind_group: wgpu::BindGroup,
+    offset_alignments: Vec<u32>,
+}
+
+impl BoundGroup {
+    #[inline]
+    pub fn bind_group(&self) -> &wgpu::BindGroup { &self.bind_group }
+    #[inline]
+    pub fn dynamic_offset_count(&self) -> usize { self.offset_alignments.len() }
+
+    pub fn set_render<'p>(&'p self, render_pass: &mut wgpu::RenderPass<'p>, index: u32, offsets: &[wgpu::DynamicOffset]) {
+        self.check_offsets(offsets);
+        render_pass.set_bind_group(index, &self.bind_group, offsets);
+    }
+
+    pub fn set_compute<'p>(&'p self, compute_pass: &mut wgpu::ComputePass<'p>, index: u32, offsets: &[wgpu::DynamicOffset]) {
+        self.check_offsets(offsets);
+        compute_pass.set_bind_group(index, &self.bind_group, offsets);
+    }
+
+    fn check_offsets(&self, offsets: &[wgpu::DynamicOffset]) {
+        assert_eq!(offsets.len(), self.offset_alignments.len(), "Wrong number of dynamic offsets for the bind group");
+        for (index, (offset, alignment)) in offsets.iter().zip(&self.offset_alignments).enumerate() {
+            assert!(
+                offset % *alignment == 0,
+                "The dynamic offset {} ({}) isn't aligned to {}",
+                index,
+                offset,
+                alignment
+            );
+        }
+    }
+}
+
+pub struct TrackedBindGroup {
+    bind_group: wgpu::BindGroup,
+    memory: MemoryAllocation,
+}
+
+impl TrackedBindGroup {
+    pub fn allocation(&self) -> &MemoryAllocation { &self.memory }
+}
+
+impl std::ops::Deref for TrackedBindGroup {
+    type Target = wgpu::BindGroup;
+
+    fn deref(&self) -> &Self::Target {
+        self.memory.mark_used();
+        &self.bind_group
+    }
 }
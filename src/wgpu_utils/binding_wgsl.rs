@@ -0,0 +1,197 @@
+// common binding types mapped to wgsl types and the wgsl declarations of the bindings
+
+use super::binding_builder::BindGroupLayoutWithDesc;
+
+pub fn uniform() -> wgpu::BindingType {
+    wgpu::BindingType::Buffer {
+        ty: wgpu::BufferBindingType::Uniform,
+        has_dynamic_offset: false,
+        min_binding_size: None,
+    }
+}
+
+pub fn storage(read_only: bool) -> wgpu::BindingType {
+    wgpu::BindingType::Buffer {
+        ty: wgpu::BufferBindingType::Storage { read_only },
+        has_dynamic_offset: false,
+        min_binding_size: None,
+    }
+}
+
+pub fn sampler(sampler_binding_type: wgpu::SamplerBindingType) -> wgpu::BindingType { wgpu::BindingType::Sampler(sampler_binding_type) }
+
+pub fn texture(view_dimension: wgpu::TextureViewDimension, sample_type: wgpu::TextureSampleType) -> wgpu::BindingType {
+    wgpu::BindingType::Texture {
+        sample_type,
+        view_dimension,
+        multisampled: false,
+    }
+}
+
+pub fn texture_multisampled_2d(sample_type: wgpu::TextureSampleType) -> wgpu::BindingType {
+    wgpu::BindingType::Texture {
+        sample_type,
+        view_dimension: wgpu::TextureViewDimension::D2,
+        multisampled: true,
+    }
+}
+
+pub fn storage_texture(
+    view_dimension: wgpu::TextureViewDimension,
+    format: wgpu::TextureFormat,
+    access: wgpu::StorageTextureAccess,
+) -> wgpu::BindingType {
+    wgpu::BindingType::StorageTexture { access, format, view_dimension }
+}
+
+// convenient helpers
+pub fn texture_2d() -> wgpu::BindingType { texture(wgpu::TextureViewDimension::D2, wgpu::TextureSampleType::Float { filterable: true }) }
+
+pub fn texture_2d_array() -> wgpu::BindingType { texture(wgpu::TextureViewDimension::D2Array, wgpu::TextureSampleType::Float { filterable: true }) }
+
+pub fn texture_3d() -> wgpu::BindingType { texture(wgpu::TextureViewDimension::D3, wgpu::TextureSampleType::Float { filterable: true }) }
+
+pub fn texture_cube() -> wgpu::BindingType { texture(wgpu::TextureViewDimension::Cube, wgpu::TextureSampleType::Float { filterable: true }) }
+
+pub fn texture_depth_2d() -> wgpu::BindingType { texture(wgpu::TextureViewDimension::D2, wgpu::TextureSampleType::Depth) }
+
+pub fn texture_storage_2d(format: wgpu::TextureFormat, access: wgpu::StorageTextureAccess) -> wgpu::BindingType {
+    storage_texture(wgpu::TextureViewDimension::D2, format, access)
+}
+
+fn dimension_suffix(view_dimension: wgpu::TextureViewDimension) -> &'static str {
+    match view_dimension {
+        wgpu::TextureViewDimension::D1 => "1d",
+        wgpu::TextureViewDimension::D2 => "2d",
+        wgpu::TextureViewDimension::D2Array => "2d_array",
+        wgpu::TextureViewDimension::Cube => "cube",
+        wgpu::TextureViewDimension::CubeArray => "cube_array",
+        wgpu::TextureViewDimension::D3 => "3d",
+    }
+}
+
+// Texel format of a storage texture, wgsl only has storage textures of these formats
+pub fn texel_format(format: wgpu::TextureFormat) -> Option<&'static str> {
+    use wgpu::TextureFormat;
+    Some(match format {
+        TextureFormat::Rgba8Unorm => "rgba8unorm",
+        TextureFormat::Rgba8Snorm => "rgba8snorm",
+        TextureFormat::Rgba8Uint => "rgba8uint",
+        TextureFormat::Rgba8Sint => "rgba8sint",
+        TextureFormat::Bgra8Unorm => "bgra8unorm",
+        TextureFormat::Rgba16Uint => "rgba16uint",
+        TextureFormat::Rgba16Sint => "rgba16sint",
+        TextureFormat::Rgba16Float => "rgba16float",
+        TextureFormat::R32Uint => "r32uint",
+        TextureFormat::R32Sint => "r32sint",
+        TextureFormat::R32Float => "r32float",
+        TextureFormat::Rg32Uint => "rg32uint",
+        TextureFormat::Rg32Sint => "rg32sint",
+        TextureFormat::Rg32Float => "rg32float",
+        TextureFormat::Rgba32Uint => "rgba32uint",
+        TextureFormat::Rgba32Sint => "rgba32sint",
+        TextureFormat::Rgba32Float => "rgba32float",
+        _ => return None,
+    })
+}
+
+// Address space of the `var` declaration, e.g. `<storage, read>`, empty for the handle types (textures and samplers)
+pub fn address_space(ty: &wgpu::BindingType) -> &'static str {
+    match ty {
+        wgpu::BindingType::Buffer { ty: wgpu::BufferBindingType::Uniform, .. } => "<uniform>",
+        wgpu::BindingType::Buffer {
+            ty: wgpu::BufferBindingType::Storage { read_only: true },
+            ..
+        } => "<storage, read>",
+        wgpu::BindingType::Buffer {
+            ty: wgpu::BufferBindingType::Storage { read_only: false },
+            ..
+        } => "<storage, read_write>",
+        _ => "",
+    }
+}
+
+// Wgsl type of a binding, `buffer_type` is the type of the buffer content (e.g. a struct) as it isn't part of the
+// binding type, ignored for the other bindings
+pub fn wgsl_type(ty: &wgpu::BindingType, buffer_type: Option<&str>) -> String {
+    match *ty {
+        wgpu::BindingType::Buffer { .. } => buffer_type.expect("The wgsl type of a buffer binding needs its content type").to_string(),
+        wgpu::BindingType::Sampler(wgpu::SamplerBindingType::Comparison) => "sampler_comparison".to_string(),
+        wgpu::BindingType::Sampler(_) => "sampler".to_string(),
+        wgpu::BindingType::Texture {
+            sample_type: wgpu::TextureSampleType::Depth,
+            multisampled: true,
+            ..
+        } => "texture_depth_multisampled_2d".to_string(),
+        wgpu::BindingType::Texture {
+            sample_type: wgpu::TextureSampleType::Depth,
+            view_dimension,
+            ..
+        } => format!("texture_depth_{}", dimension_suffix(view_dimension)),
+        wgpu::BindingType::Texture {
+            sample_type,
+            view_dimension,
+            multisampled,
+        } => {
+            let scalar = match sample_type {
+                wgpu::TextureSampleType::Sint => "i32",
+                wgpu::TextureSampleType::Uint => "u32",
+                _ => "f32",
+            };
+            match multisampled {
+                true => format!("texture_multisampled_2d<{}>", scalar),
+                false => format!("texture_{}<{}>", dimension_suffix(view_dimension), scalar),
+            }
+        },
+        wgpu::BindingType::StorageTexture { access, format, view_dimension } => {
+            let access = match access {
+                wgpu::StorageTextureAccess::WriteOnly => "write",
+                wgpu::StorageTextureAccess::ReadOnly => "read",
+                wgpu::StorageTextureAccess::ReadWrite => "read_write",
+            };
+            let format = texel_format(format).unwrap_or_else(|| panic!("{:?} isn't a storage texture format in wgsl", format));
+            format!("texture_storage_{}<{}, {}>", dimension_suffix(view_dimension), format, access)
+        },
+        wgpu::BindingType::AccelerationStructure => "acceleration_structure".to_string(),
+    }
+}
+
+// `@group(g) @binding(b) var<...> name: type;` declaration of a layout entry, a `binding_array` for the binding arrays
+pub fn declaration(group: u32, entry: &wgpu::BindGroupLayoutEntry, name: &str, buffer_type: Option<&str>) -> String {
+    let ty = wgsl_type(&entry.ty, buffer_type);
+    let ty = match entry.count {
+        Some(count) => format!("binding_array<{}, {}>", ty, count),
+        None => ty,
+    };
+    format!("@group({}) @binding({}) var{} {}: {};", group, entry.binding, address_space(&entry.ty), name, ty)
+}
+
+// Declarations of all the bindings of a layout, one line each in the order of the entries, with the names and the buffer
+// content types of the entries
+pub fn declarations(group: u32, layout_with_desc: &BindGroupLayoutWithDesc, bindings: &[(&str, Option<&str>)]) -> String {
+    assert_eq!(layout_with_desc.entries.len(), bindings.len(), "A name is needed for each binding of the layout");
+    layout_with_desc
+        .entries
+        .iter()
+        .zip(bindings)
+        .map(|(entry, (name, buffer_type))| declaration(group, entry, name, *buffer_type) + "\n")
+        .collect()
+}
+
+// Shader library module declaring the bindings of a layout, imported by the shaders using the bind group (e.g.
+// `#import app::scene_bindings::camera`). `header` holds what the declarations need, e.g. the buffer structs or their
+// `#import`.
+pub fn bindings_module(
+    import_path: &str,
+    header: &str,
+    group: u32,
+    layout_with_desc: &BindGroupLayoutWithDesc,
+    bindings: &[(&str, Option<&str>)],
+) -> String {
+    format!(
+        "#define_import_path {}\n\n{}\n\n{}",
+        import_path,
+        header.trim(),
+        declarations(group, layout_with_desc, bindings)
+    )
+}
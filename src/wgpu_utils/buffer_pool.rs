@@ -0,0 +1,181 @@
+use std::{ops::Range, sync::Arc};
+
+use wgpu::{util::align_to, Buffer, BufferAddress, BufferDescriptor, BufferSize, BufferUsages, Device, Queue};
+
+use super::{child_label, memory_tracker::MemoryAllocation};
+
+// Lightweight handle on a sub-allocation of a `BufferPool` page
+// It must be given back to the pool with `BufferPool::free` to be reused
+pub struct BufferAllocation {
+    buffer: Arc<Buffer>,
+    page_index: usize,
+    offset: BufferAddress,
+    size: BufferAddress,
+    allocated_size: BufferAddress,
+}
+
+impl BufferAllocation {
+    #[inline]
+    pub fn buffer(&self) -> &Buffer { &self.buffer }
+    #[inline]
+    pub fn offset(&self) -> BufferAddress { self.offset }
+    #[inline]
+    pub fn size(&self) -> BufferAddress { self.size }
+
+    pub fn slice(&self) -> wgpu::BufferSlice<'_> { self.buffer.slice(self.offset..self.offset + self.size) }
+
+    pub fn binding_resource(&self) -> wgpu::BindingResource<'_> {
+        wgpu::BindingResource::Buffer(wgpu::BufferBinding {
+            buffer: &self.buffer,
+            offset: self.offset,
+            size: BufferSize::new(self.size),
+        })
+    }
+
+    pub fn write(&self, queue: &Queue, data: &[u8]) {
+        assert!(data.len() as BufferAddress <= self.size);
+        queue.write_buffer(&self.buffer, self.offset, data);
+    }
+}
+
+struct Page {
+    buffer: Arc<Buffer>,
+    // Sorted and coalesced free ranges of the page
+    free_ranges: Vec<Range<BufferAddress>>,
+    _memory: MemoryAllocation,
+}
+
+impl Page {
+    // First fit allocation in the free ranges
+    fn allocate(&mut self, size: BufferAddress) -> Option<BufferAddress> {
+        let index = self.free_ranges.iter().position(|range| range.end - range.start >= size)?;
+        let range = &mut self.free_ranges[index];
+        let offset = range.start;
+        range.start += size;
+        if range.start == range.end {
+            self.free_ranges.remove(index);
+        }
+        Some(offset)
+    }
+
+    fn free(&mut self, range: Range<BufferAddress>) {
+        let index = self.free_ranges.partition_point(|free_range| free_range.start < range.start);
+        self.free_ranges.insert(index, range);
+
+        // Merge with the next then the previous free range when contiguous
+        if index + 1 < self.free_ranges.len() && self.free_ranges[index].end == self.free_ranges[index + 1].start {
+            self.free_ranges[index].end = self.free_ranges.remove(index + 1).end;
+        }
+        if index > 0 && self.free_ranges[index - 1].end == self.free_ranges[index].start {
+            self.free_ranges[index - 1].end = self.free_ranges.remove(index).end;
+        }
+    }
+}
+
+// Pool suballocating many small allocations (per-object or transient data) from large buffers (pages)
+// Every allocation offset is aligned to the pool alignment so it can be directly used in bindings
+pub struct BufferPool {
+    label: Option<&'static str>,
+    usage: BufferUsages,
+    page_size: BufferAddress,
+    alignment: BufferAddress,
+    pages: Vec<Page>,
+    allocated_size: BufferAddress,
+}
+
+impl BufferPool {
+    pub fn new(usage: BufferUsages, page_size: BufferAddress, alignment: BufferAddress, label: Option<&'static str>) -> Self {
+        assert!(alignment > 0, "BufferPool alignment must be greater than 0");
+        Self {
+            label,
+            usage,
+            page_size: align_to(page_size, alignment),
+            alignment,
+            pages: Vec::new(),
+            allocated_size: 0,
+        }
+    }
+
+    // Pool of uniform buffers aligned on the device uniform offset alignment
+    pub fn uniform(device: &Device, page_size: BufferAddress, label: Option<&'static str>) -> Self {
+        Self::new(
+            BufferUsages::UNIFORM | BufferUsages::COPY_DST,
+            page_size,
+            device.limits().min_uniform_buffer_offset_alignment as BufferAddress,
+            label,
+        )
+    }
+
+    // Pool of storage buffers aligned on the device storage offset alignment
+    pub fn storage(device: &Device, page_size: BufferAddress, label: Option<&'static str>) -> Self {
+        Self::new(
+            BufferUsages::STORAGE | BufferUsages::COPY_DST,
+            page_size,
+            device.limits().min_storage_buffer_offset_alignment as BufferAddress,
+            label,
+        )
+    }
+
+    // The size must be greater than 0, an empty binding would cover the rest of the page
+    pub fn allocate(&mut self, device: &Device, size: BufferAddress) -> BufferAllocation {
+        assert!(size > 0, "BufferPool allocations must not be empty");
+        let allocated_size = align_to(size, self.alignment);
+
+        let (page_index, offset) = match self
+            .pages
+            .iter_mut()
+            .enumerate()
+            .find_map(|(page_index, page)| page.allocate(allocated_size).map(|offset| (page_index, offset)))
+        {
+            Some(allocation) => allocation,
+            None => {
+                let page_index = self.create_page(device, allocated_size);
+                (page_index, self.pages[page_index].allocate(allocated_size).unwrap())
+            },
+        };
+
+        self.allocated_size += allocated_size;
+
+        BufferAllocation {
+            buffer: self.pages[page_index].buffer.clone(),
+            page_index,
+            offset,
+            size,
+            allocated_size,
+        }
+    }
+
+    pub fn free(&mut self, allocation: BufferAllocation) {
+        assert!(
+            allocation.page_index < self.pages.len() && Arc::ptr_eq(&self.pages[allocation.page_index].buffer, &allocation.buffer),
+            "BufferAllocation freed in a pool it does not come from"
+        );
+        self.allocated_size -= allocation.allocated_size;
+        self.pages[allocation.page_index].free(allocation.offset..allocation.offset + allocation.allocated_size);
+    }
+
+    #[inline]
+    pub fn page_count(&self) -> usize { self.pages.len() }
+    // Bytes currently allocated (including alignment padding)
+    #[inline]
+    pub fn allocated_size(&self) -> BufferAddress { self.allocated_size }
+    // Bytes of all the pages
+    pub fn capacity(&self) -> BufferAddress { self.pages.iter().map(|page| page.buffer.size()).sum() }
+
+    fn create_page(&mut self, device: &Device, min_size: BufferAddress) -> usize {
+        let size = self.page_size.max(min_size);
+        let label = child_label(Some(self.label.unwrap_or("BufferPool")), &format!("page {}", self.pages.len()));
+        let buffer = device.create_buffer(&BufferDescriptor {
+            label: Some(label.as_str()),
+            size,
+            usage: self.usage,
+            mapped_at_creation: false,
+        });
+        self.pages.push(Page {
+            _memory: MemoryAllocation::for_buffer(&buffer, Some(label.as_str())),
+            buffer: Arc::new(buffer),
+            free_ranges: vec![Range { start: 0, end: size }],
+        });
+        self.pages.len() - 1
+    }
+}
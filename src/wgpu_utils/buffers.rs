@@ -27,6 +27,13 @@ pub fn create_buffer_from_content(device: &Device, usage: BufferUsages, label: O
 }
 
 impl<T: bytemuck::Pod, const READ_OR_WRITE: bool> StagingBufferWrapper<T, READ_OR_WRITE> {
+    // e.g. "StagingBuffer: f32 [read]"
+    fn label() -> String {
+        let type_name = std::any::type_name::<T>();
+        let name = format!("StagingBuffer: {}", &type_name[type_name.rfind(':').map_or(0, |pos| pos + 1)..]);
+        super::variant_label(Some(&name), if READ_OR_WRITE { "read" } else { "write" })
+    }
+
     pub fn new(device: &Device, size: usize) -> Self {
         let usages = BufferUsages::COPY_DST
             | match READ_OR_WRITE {
@@ -35,7 +42,7 @@ impl<T: bytemuck::Pod, const READ_OR_WRITE: bool> StagingBufferWrapper<T, READ_O
             };
         Self {
             values: vec![T::zeroed(); size],
-            staging_buffer: create_buffer_for_size(device, usages, None, (size * std::mem::size_of::<T>()) as BufferAddress),
+            staging_buffer: create_buffer_for_size(device, usages, Some(&Self::label()), (size * std::mem::size_of::<T>()) as BufferAddress),
         }
     }
 
@@ -47,7 +54,7 @@ impl<T: bytemuck::Pod, const READ_OR_WRITE: bool> StagingBufferWrapper<T, READ_O
             };
         Self {
             values: Vec::from(slice_content),
-            staging_buffer: create_buffer_from_content(device, usages, None, Some(bytemuck::cast_slice(slice_content))),
+            staging_buffer: create_buffer_from_content(device, usages, Some(&Self::label()), Some(bytemuck::cast_slice(slice_content))),
         }
     }
 
@@ -102,3 +109,50 @@ impl<T: bytemuck::Pod, const READ_OR_WRITE: bool> std::ops::Index<usize> for Sta
 
     fn index(&self, index: usize) -> &Self::Output { &self.values[index] }
 }
+
+struct MapFutureState {
+    result: Option<Result<(), wgpu::BufferAsyncError>>,
+    waker: Option<std::task::Waker>,
+}
+
+// Future resolved once a `map_async` request completes
+// The device still has to be polled (`device.poll`) for the mapping to progress
+pub struct MapFuture {
+    state: std::sync::Arc<std::sync::Mutex<MapFutureState>>,
+}
+
+impl MapFuture {
+    // Non blocking check of the mapping completion (succeeded or not)
+    pub fn is_ready(&self) -> bool { self.state.lock().unwrap().result.is_some() }
+
+    // Non blocking retrieval of the mapping result, None while it is not completed
+    pub fn try_take_result(&self) -> Option<Result<(), wgpu::BufferAsyncError>> { self.state.lock().unwrap().result.take() }
+}
+
+impl std::future::Future for MapFuture {
+    type Output = Result<(), wgpu::BufferAsyncError>;
+
+    fn poll(self: std::pin::Pin<&mut Self>, context: &mut std::task::Context<'_>) -> std::task::Poll<Self::Output> {
+        let mut state = self.state.lock().unwrap();
+        match state.result.take() {
+            Some(result) => std::task::Poll::Ready(result),
+            None => {
+                state.waker = Some(context.waker().clone());
+                std::task::Poll::Pending
+            },
+        }
+    }
+}
+
+pub fn map_async(buffer_slice: wgpu::BufferSlice, mode: wgpu::MapMode) -> MapFuture {
+    let state = std::sync::Arc::new(std::sync::Mutex::new(MapFutureState { result: None, waker: None }));
+    let callback_state = state.clone();
+    buffer_slice.map_async(mode, move |result| {
+        let mut state = callback_state.lock().unwrap();
+        state.result = Some(result);
+        if let Some(waker) = state.waker.take() {
+            waker.wake();
+        }
+    });
+    MapFuture { state }
+}
@@ -0,0 +1,288 @@
+use super::{
+    binding_builder::{BindGroupBuilder, BindGroupLayoutBuilder, BindGroupLayoutWithDesc},
+    memory_tracker::MemoryAllocation,
+    RenderTargetSize,
+};
+
+const CANVAS_BLIT_WGSL: &str = r#"
+struct VertexOutput {
+    @builtin(position) position: vec4<f32>,
+    @location(0) uv: vec2<f32>,
+}
+
+@group(0) @binding(0) var canvas_texture: texture_2d<f32>;
+@group(0) @binding(1) var canvas_sampler: sampler;
+
+// Fullscreen triangle over the viewport of the canvas
+@vertex
+fn vs_main(@builtin(vertex_index) vertex_index: u32) -> VertexOutput {
+    let uv = vec2<f32>(f32((vertex_index << 1u) & 2u), f32(vertex_index & 2u));
+    var output: VertexOutput;
+    output.position = vec4<f32>(uv.x * 2.0 - 1.0, 1.0 - uv.y * 2.0, 0.0, 1.0);
+    output.uv = uv;
+    return output;
+}
+
+@fragment
+fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
+    return textureSample(canvas_texture, canvas_sampler, in.uv);
+}
+"#;
+
+// Scaling of the canvas to the window, the aspect ratio of the canvas is kept with bars around it
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum CanvasFilter {
+    // Sharp pixels of uneven sizes with non-integer scales
+    Nearest,
+    // Smooth, e.g. for a render scale
+    #[default]
+    Linear,
+    // Largest integer scale fitting in the window, sharp pixels of the same size (pixel art). Scaled down like `Nearest`
+    // when the window is smaller than the canvas.
+    Integer,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct CanvasDescriptor {
+    // Resolution of the canvas, e.g. `Fixed(640, 360)` for pixel art or `SurfaceRelative(0.5, 0.5)` for a render scale
+    pub size: RenderTargetSize,
+    pub filter: CanvasFilter,
+    // Format of the canvas, the format of the target it is blitted to when None
+    pub format: Option<wgpu::TextureFormat>,
+}
+
+impl CanvasDescriptor {
+    pub fn new(size: RenderTargetSize, filter: CanvasFilter) -> Self { Self { size, filter, format: None } }
+
+    // Pixel art canvas of a fixed resolution, integer scaled
+    pub fn pixel_art(width: u32, height: u32) -> Self { Self::new(RenderTargetSize::Fixed(width, height), CanvasFilter::Integer) }
+
+    // Canvas of a fraction of the window resolution, linearly upscaled
+    pub fn render_scale(scale: f32) -> Self { Self::new(RenderTargetSize::SurfaceRelative(scale, scale), CanvasFilter::Linear) }
+
+    pub fn with_format(mut self, format: wgpu::TextureFormat) -> Self {
+        self.format = Some(format);
+        self
+    }
+}
+
+// Offscreen target of a fixed internal resolution the app renders to, blitted (scaled up or down) to the window with
+// the canvas filter. Follows the window size with a surface relative size, see `resize`.
+pub struct Canvas {
+    descriptor: CanvasDescriptor,
+    format: wgpu::TextureFormat,
+    texture: wgpu::Texture,
+    view: wgpu::TextureView,
+    bind_group_layout: BindGroupLayoutWithDesc,
+    bind_group: wgpu::BindGroup,
+    nearest_sampler: wgpu::Sampler,
+    linear_sampler: wgpu::Sampler,
+    pipeline: wgpu::RenderPipeline,
+    _memory: MemoryAllocation,
+}
+
+impl Canvas {
+    pub fn new(
+        device: &wgpu::Device,
+        descriptor: CanvasDescriptor,
+        surface_width: u32,
+        surface_height: u32,
+        target_format: wgpu::TextureFormat,
+    ) -> Self {
+        let format = descriptor.format.unwrap_or(target_format);
+        let bind_group_layout = BindGroupLayoutBuilder::new()
+            .add_binding_fragment(wgpu::BindingType::Texture {
+                sample_type: wgpu::TextureSampleType::Float { filterable: true },
+                view_dimension: wgpu::TextureViewDimension::D2,
+                multisampled: false,
+            })
+            .add_binding_fragment(wgpu::BindingType::Sampler(wgpu::SamplerBindingType::Filtering))
+            .create(device, Some("Canvas"));
+        let create_sampler = |filter_mode: wgpu::FilterMode, label: &str| {
+            device.create_sampler(&wgpu::SamplerDescriptor {
+                label: Some(label),
+                mag_filter: filter_mode,
+                min_filter: filter_mode,
+                ..Default::default()
+            })
+        };
+        let nearest_sampler = create_sampler(wgpu::FilterMode::Nearest, "Canvas: nearest sampler");
+        let linear_sampler = create_sampler(wgpu::FilterMode::Linear, "Canvas: linear sampler");
+
+        let shader_module = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: Some("Canvas: blit"),
+            source: wgpu::ShaderSource::Wgsl(CANVAS_BLIT_WGSL.into()),
+        });
+        let pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("Canvas: blit"),
+            bind_group_layouts: &[&bind_group_layout.layout],
+            push_constant_ranges: &[],
+        });
+        let pipeline = device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+            label: Some("Canvas: blit"),
+            layout: Some(&pipeline_layout),
+            vertex: wgpu::VertexState {
+                module: &shader_module,
+                entry_point: "vs_main",
+                buffers: &[],
+            },
+            fragment: Some(wgpu::FragmentState {
+                module: &shader_module,
+                entry_point: "fs_main",
+                targets: &[Some(target_format.into())],
+            }),
+            primitive: wgpu::PrimitiveState::default(),
+            depth_stencil: None,
+            multisample: wgpu::MultisampleState::default(),
+            multiview: None,
+        });
+
+        let (width, height) = descriptor.size.resolve(surface_width, surface_height);
+        let (texture, view, bind_group, memory) = Self::create_texture(
+            device,
+            &bind_group_layout,
+            Self::sampler_for(descriptor.filter, &nearest_sampler, &linear_sampler),
+            format,
+            width,
+            height,
+        );
+        Self {
+            descriptor,
+            format,
+            texture,
+            view,
+            bind_group_layout,
+            bind_group,
+            nearest_sampler,
+            linear_sampler,
+            pipeline,
+            _memory: memory,
+        }
+    }
+
+    fn sampler_for<'a>(filter: CanvasFilter, nearest_sampler: &'a wgpu::Sampler, linear_sampler: &'a wgpu::Sampler) -> &'a wgpu::Sampler {
+        match filter {
+            CanvasFilter::Linear => linear_sampler,
+            CanvasFilter::Nearest | CanvasFilter::Integer => nearest_sampler,
+        }
+    }
+
+    fn create_texture(
+        device: &wgpu::Device,
+        bind_group_layout: &BindGroupLayoutWithDesc,
+        sampler: &wgpu::Sampler,
+        format: wgpu::TextureFormat,
+        width: u32,
+        height: u32,
+    ) -> (wgpu::Texture, wgpu::TextureView, wgpu::BindGroup, MemoryAllocation) {
+        let texture = device.create_texture(&wgpu::TextureDescriptor {
+            label: Some("Canvas"),
+            size: wgpu::Extent3d { width, height, depth_or_array_layers: 1 },
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: wgpu::TextureDimension::D2,
+            format,
+            usage: wgpu::TextureUsages::RENDER_ATTACHMENT | wgpu::TextureUsages::TEXTURE_BINDING | wgpu::TextureUsages::COPY_SRC,
+            view_formats: &[],
+        });
+        let view = texture.create_view(&wgpu::TextureViewDescriptor {
+            label: Some("Canvas view"),
+            ..Default::default()
+        });
+        let bind_group = BindGroupBuilder::new(bind_group_layout)
+            .texture(&view)
+            .sampler(sampler)
+            .create(device, Some("Canvas"));
+        let memory = MemoryAllocation::for_texture(&texture, Some("Canvas"));
+        (texture, view, bind_group, memory)
+    }
+
+    pub fn descriptor(&self) -> &CanvasDescriptor { &self.descriptor }
+
+    pub fn format(&self) -> wgpu::TextureFormat { self.format }
+
+    pub fn texture(&self) -> &wgpu::Texture { &self.texture }
+
+    // View the app renders to
+    pub fn view(&self) -> &wgpu::TextureView { &self.view }
+
+    pub fn size(&self) -> (u32, u32) { (self.texture.width(), self.texture.height()) }
+
+    pub fn set_filter(&mut self, device: &wgpu::Device, filter: CanvasFilter) {
+        if filter != self.descriptor.filter {
+            self.descriptor.filter = filter;
+            self.bind_group = BindGroupBuilder::new(&self.bind_group_layout)
+                .texture(&self.view)
+                .sampler(Self::sampler_for(filter, &self.nearest_sampler, &self.linear_sampler))
+                .create(device, Some("Canvas"));
+        }
+    }
+
+    // Recreate the canvas if its size depends on the surface and changed, returns whether it was recreated
+    pub fn resize(&mut self, device: &wgpu::Device, surface_width: u32, surface_height: u32) -> bool {
+        let (width, height) = self.descriptor.size.resolve(surface_width, surface_height);
+        if (width, height) == self.size() {
+            return false;
+        }
+        let sampler = Self::sampler_for(self.descriptor.filter, &self.nearest_sampler, &self.linear_sampler);
+        let (texture, view, bind_group, memory) = Self::create_texture(device, &self.bind_group_layout, sampler, self.format, width, height);
+        (self.texture, self.view, self.bind_group, self._memory) = (texture, view, bind_group, memory);
+        true
+    }
+
+    // `[x, y, width, height]` rect of a target of this size the canvas is blitted to, in pixels
+    pub fn destination_rect(&self, target_width: u32, target_height: u32) -> [f32; 4] {
+        let (width, height) = self.size();
+        let (target_width, target_height) = (target_width.max(1) as f32, target_height.max(1) as f32);
+        let fit_scale = (target_width / width as f32).min(target_height / height as f32);
+        let scale = match self.descriptor.filter {
+            CanvasFilter::Integer if fit_scale >= 1.0 => fit_scale.floor(),
+            _ => fit_scale,
+        };
+        let (destination_width, destination_height) = (width as f32 * scale, height as f32 * scale);
+        // Pixel aligned, so that the integer scaled pixels all have the same size
+        let x = ((target_width - destination_width) * 0.5).floor();
+        let y = ((target_height - destination_height) * 0.5).floor();
+        [x, y, destination_width.min(target_width - x), destination_height.min(target_height - y)]
+    }
+
+    // Position in pixels of the target to the canvas pixels, None outside of the canvas
+    pub fn target_to_canvas(&self, position: [f32; 2], target_width: u32, target_height: u32) -> Option<[f32; 2]> {
+        let [x, y, width, height] = self.destination_rect(target_width, target_height);
+        let (canvas_width, canvas_height) = self.size();
+        let u = (position[0] - x) / width;
+        let v = (position[1] - y) / height;
+        ((0.0..1.0).contains(&u) && (0.0..1.0).contains(&v)).then_some([u * canvas_width as f32, v * canvas_height as f32])
+    }
+
+    // Draw the canvas over the whole `target_view`, with the bars around it cleared to `bar_color`
+    pub fn encode_blit(
+        &self,
+        encoder: &mut wgpu::CommandEncoder,
+        target_view: &wgpu::TextureView,
+        target_width: u32,
+        target_height: u32,
+        bar_color: wgpu::Color,
+    ) {
+        let [x, y, width, height] = self.destination_rect(target_width, target_height);
+        let mut render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+            label: Some("Canvas: blit"),
+            color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+                view: target_view,
+                resolve_target: None,
+                ops: wgpu::Operations {
+                    load: wgpu::LoadOp::Clear(bar_color),
+                    store: wgpu::StoreOp::Store,
+                },
+            })],
+            depth_stencil_attachment: None,
+            timestamp_writes: None,
+            occlusion_query_set: None,
+        });
+        render_pass.set_viewport(x, y, width, height, 0.0, 1.0);
+        render_pass.set_pipeline(&self.pipeline);
+        render_pass.set_bind_group(0, &self.bind_group, &[]);
+        render_pass.draw(0..3, 0..1);
+    }
+}
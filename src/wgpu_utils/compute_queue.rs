@@ -0,0 +1,133 @@
+use std::{
+    collections::VecDeque,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    },
+};
+
+use wgpu::{CommandEncoder, Device, Queue, SubmissionIndex};
+
+pub type ComputeRecordFn = Box<dyn FnOnce(&mut CommandEncoder)>;
+// Called from `Device::poll` once the GPU is done with the job
+pub type ComputeCompletionFn = Box<dyn FnOnce() + Send>;
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum ComputePriority {
+    Normal,
+    // Submitted only when no other job of the queue is still running on the GPU, e.g. for background simulations
+    Low,
+}
+
+struct ComputeJob {
+    label: String,
+    priority: ComputePriority,
+    record: ComputeRecordFn,
+    on_complete: Option<ComputeCompletionFn>,
+}
+
+// Compute only work recorded in its own encoders and submitted apart from the render submit.
+// Jobs are either submitted right away (`submit`) or queued (`enqueue`) and submitted by `pump`, once per frame,
+// within the per frame budget and in flight limit so long simulation steps can be throttled.
+pub struct ComputeQueue {
+    label: String,
+    queued_jobs: VecDeque<ComputeJob>,
+    in_flight_count: Arc<AtomicUsize>,
+    // Queued jobs submitted by a single `pump`
+    pub max_jobs_per_pump: usize,
+    // Normal priority jobs are not submitted while that many jobs are running on the GPU
+    pub max_in_flight: usize,
+}
+
+impl ComputeQueue {
+    pub fn new(label: impl Into<String>) -> Self {
+        Self {
+            label: label.into(),
+            queued_jobs: VecDeque::new(),
+            in_flight_count: Arc::new(AtomicUsize::new(0)),
+            max_jobs_per_pump: 1,
+            max_in_flight: 2,
+        }
+    }
+
+    pub fn with_max_jobs_per_pump(mut self, max_jobs_per_pump: usize) -> Self {
+        self.max_jobs_per_pump = max_jobs_per_pump;
+        self
+    }
+
+    pub fn with_max_in_flight(mut self, max_in_flight: usize) -> Self {
+        self.max_in_flight = max_in_flight;
+        self
+    }
+
+    // Jobs submitted and not completed yet, updated when the device is polled
+    pub fn in_flight_count(&self) -> usize { self.in_flight_count.load(Ordering::Acquire) }
+
+    pub fn queued_count(&self) -> usize { self.queued_jobs.len() }
+
+    pub fn enqueue(
+        &mut self,
+        label: impl Into<String>,
+        priority: ComputePriority,
+        record: impl FnOnce(&mut CommandEncoder) + 'static,
+        on_complete: Option<ComputeCompletionFn>,
+    ) {
+        self.queued_jobs.push_back(ComputeJob {
+            label: label.into(),
+            priority,
+            record: Box::new(record),
+            on_complete,
+        });
+    }
+
+    // Record and submit a job right away, ignoring the throttling
+    pub fn submit(
+        &self,
+        device: &Device,
+        queue: &Queue,
+        label: &str,
+        record: impl FnOnce(&mut CommandEncoder),
+        on_complete: Option<ComputeCompletionFn>,
+    ) -> SubmissionIndex {
+        let encoder_label = format!("{}: {}", self.label, label);
+        super::record_pass_label(encoder_label.as_str());
+        let mut encoder = device.create_command_encoder(&wgpu::CommandEncoderDescriptor { label: Some(&encoder_label) });
+        record(&mut encoder);
+        let submission_index = queue.submit(Some(encoder.finish()));
+
+        self.in_flight_count.fetch_add(1, Ordering::AcqRel);
+        let in_flight_count = self.in_flight_count.clone();
+        queue.on_submitted_work_done(move || {
+            in_flight_count.fetch_sub(1, Ordering::AcqRel);
+            if let Some(on_complete) = on_complete {
+                on_complete();
+            }
+        });
+        submission_index
+    }
+
+    // Submit the queued jobs allowed by the throttling, in order. Returns the number of submitted jobs.
+    pub fn pump(&mut self, device: &Device, queue: &Queue) -> usize {
+        // Update the in flight count (and call the completion callbacks) of the finished jobs
+        device.poll(wgpu::Maintain::Poll);
+
+        let mut submitted_count = 0;
+        while submitted_count < self.max_jobs_per_pump {
+            let Some(job) = self.queued_jobs.front() else {
+                break;
+            };
+            let max_in_flight = match job.priority {
+                ComputePriority::Normal => self.max_in_flight,
+                ComputePriority::Low => 1,
+            };
+            if self.in_flight_count() >= max_in_flight {
+                break;
+            }
+
+            let job = self.queued_jobs.pop_front().unwrap();
+            self.submit(device, queue, &job.label, job.record, job.on_complete);
+            submitted_count += 1;
+        }
+        submitted_count
+    }
+}
@@ -0,0 +1,321 @@
+use super::{
+    binding_builder::{BindGroupBuilder, BindGroupLayoutBuilder, BindGroupLayoutWithDesc},
+    uniform_buffer::UniformBuffer,
+    DepthConvention,
+};
+
+// Debug visualization of the scene, selected in the debug panel (`AppState::debug_view`). Apps swap the pipeline variant
+// of the scene (`primitive_state` for the wireframe, `DebugViewRenderer::overdraw_color_target` for the overdraw) and
+// show the visualized texture with `DebugViewRenderer::encode`.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum DebugViewMode {
+    #[default]
+    Lit,
+    Normals,
+    LinearDepth,
+    Overdraw,
+    Wireframe,
+}
+
+impl DebugViewMode {
+    pub const ALL: [Self; 5] = [Self::Lit, Self::Normals, Self::LinearDepth, Self::Overdraw, Self::Wireframe];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Lit => "Lit",
+            Self::Normals => "Normals",
+            Self::LinearDepth => "Linear depth",
+            Self::Overdraw => "Overdraw",
+            Self::Wireframe => "Wireframe",
+        }
+    }
+
+    // The wireframe requires `POLYGON_MODE_LINE`, requested by the render instance when the adapter supports it
+    pub fn is_supported(self, features: wgpu::Features) -> bool { self != Self::Wireframe || features.contains(wgpu::Features::POLYGON_MODE_LINE) }
+
+    // Primitive state of the pipeline variant drawing the scene in this mode
+    pub fn primitive_state(self, primitive: wgpu::PrimitiveState) -> wgpu::PrimitiveState {
+        match self {
+            Self::Wireframe => wgpu::PrimitiveState {
+                polygon_mode: wgpu::PolygonMode::Line,
+                cull_mode: None,
+                ..primitive
+            },
+            _ => primitive,
+        }
+    }
+}
+
+const DEBUG_VIEW_WGSL: &str = r#"
+struct DebugViewParams {
+    mode: u32,
+    near: f32,
+    // 0 for an infinite far plane
+    far: f32,
+    display_distance: f32,
+    overdraw_max: f32,
+    reversed_depth: u32,
+    _padding0: f32,
+    _padding1: f32,
+}
+
+// Depth textures are read as float textures, `textureLoad` of depth textures isn't supported by all backends
+@group(0) @binding(0) var source: texture_2d<f32>;
+@group(0) @binding(1) var<uniform> params: DebugViewParams;
+
+@vertex
+fn vs_main(@builtin(vertex_index) vertex_index: u32) -> @builtin(position) vec4<f32> {
+    let uv = vec2<f32>(f32((vertex_index << 1u) & 2u), f32(vertex_index & 2u));
+    return vec4<f32>(uv * 2.0 - 1.0, 0.0, 1.0);
+}
+
+// Black, blue, green, yellow, red then white as the count goes up
+fn heatmap(t: f32) -> vec3<f32> {
+    let x = clamp(t, 0.0, 1.0) * 5.0;
+    var colors = array<vec3<f32>, 6>(
+        vec3<f32>(0.0, 0.0, 0.0),
+        vec3<f32>(0.0, 0.2, 1.0),
+        vec3<f32>(0.0, 0.9, 0.2),
+        vec3<f32>(1.0, 0.9, 0.0),
+        vec3<f32>(1.0, 0.1, 0.0),
+        vec3<f32>(1.0, 1.0, 1.0),
+    );
+    let index = min(u32(x), 4u);
+    return mix(colors[index], colors[index + 1u], x - f32(index));
+}
+
+// View distance for a perspective projection with the 0 to 1 depth range
+fn linear_depth(ndc_depth: f32) -> f32 {
+    let depth = select(ndc_depth, 1.0 - ndc_depth, params.reversed_depth != 0u);
+    if params.far > 0.0 {
+        return params.near * params.far / (params.far - depth * (params.far - params.near));
+    }
+    return params.near / max(1.0 - depth, 1e-7);
+}
+
+@fragment
+fn fs_main(@builtin(position) position: vec4<f32>) -> @location(0) vec4<f32> {
+    let value = textureLoad(source, vec2<i32>(position.xy), 0);
+    switch params.mode {
+        // Normals
+        case 1u: {
+            return vec4<f32>(value.xyz * 0.5 + 0.5, 1.0);
+        }
+        // Linear depth
+        case 2u: {
+            let shade = clamp((linear_depth(value.r) - params.near) / (params.display_distance - params.near), 0.0, 1.0);
+            return vec4<f32>(vec3<f32>(1.0 - shade), 1.0);
+        }
+        // Overdraw count
+        default: {
+            return vec4<f32>(heatmap(value.r / params.overdraw_max), 1.0);
+        }
+    }
+}
+"#;
+
+const OVERDRAW_WGSL: &str = r#"
+@fragment
+fn fs_overdraw() -> @location(0) vec4<f32> {
+    return vec4<f32>(1.0, 0.0, 0.0, 0.0);
+}
+"#;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct DebugViewSettings {
+    // Perspective projection of the depth, `far` is None for an infinite far plane
+    pub near: f32,
+    pub far: Option<f32>,
+    pub depth_convention: DepthConvention,
+    // Distance shown in black in the linear depth view, closer is brighter
+    pub display_distance: f32,
+    // Count shown in white in the overdraw heatmap
+    pub overdraw_max: f32,
+}
+
+impl Default for DebugViewSettings {
+    fn default() -> Self {
+        Self {
+            near: 0.1,
+            far: Some(100.0),
+            depth_convention: DepthConvention::Standard,
+            display_distance: 100.0,
+            overdraw_max: 10.0,
+        }
+    }
+}
+
+#[repr(C)]
+#[derive(Clone, Copy, Default, Debug, PartialEq, bytemuck::Pod, bytemuck::Zeroable)]
+struct DebugViewParams {
+    mode: u32,
+    near: f32,
+    far: f32,
+    display_distance: f32,
+    overdraw_max: f32,
+    reversed_depth: u32,
+    _padding: [f32; 2],
+}
+
+// Fullscreen visualization passes of the debug views: normals (float texture of [-1, 1] normals, e.g. a G-buffer
+// attachment), linear depth (depth texture, with `TEXTURE_BINDING` usage) and overdraw heatmap (count rendered by the overdraw variant of the scene
+// pipelines into an `OVERDRAW_FORMAT` target).
+pub struct DebugViewRenderer {
+    settings: DebugViewSettings,
+    normals_params: UniformBuffer<DebugViewParams>,
+    overdraw_params: UniformBuffer<DebugViewParams>,
+    depth_params: UniformBuffer<DebugViewParams>,
+    bind_group_layout: BindGroupLayoutWithDesc,
+    pipeline: wgpu::RenderPipeline,
+    overdraw_shader_module: wgpu::ShaderModule,
+}
+
+impl DebugViewRenderer {
+    pub const OVERDRAW_FORMAT: wgpu::TextureFormat = wgpu::TextureFormat::R16Float;
+
+    pub fn new(device: &wgpu::Device, target_format: wgpu::TextureFormat, settings: DebugViewSettings) -> Self {
+        let bind_group_layout = BindGroupLayoutBuilder::new()
+            .add_binding_fragment(wgpu::BindingType::Texture {
+                sample_type: wgpu::TextureSampleType::Float { filterable: false },
+                view_dimension: wgpu::TextureViewDimension::D2,
+                multisampled: false,
+            })
+            .add_binding_fragment(wgpu::BindingType::Buffer {
+                ty: wgpu::BufferBindingType::Uniform,
+                has_dynamic_offset: false,
+                min_binding_size: None,
+            })
+            .create(device, Some("DebugViewRenderer"));
+
+        let shader_module = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: Some("DebugViewRenderer"),
+            source: wgpu::ShaderSource::Wgsl(DEBUG_VIEW_WGSL.into()),
+        });
+        let pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("DebugViewRenderer"),
+            bind_group_layouts: &[&bind_group_layout.layout],
+            push_constant_ranges: &[],
+        });
+        let pipeline = device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+            label: Some("DebugViewRenderer"),
+            layout: Some(&pipeline_layout),
+            vertex: wgpu::VertexState {
+                module: &shader_module,
+                entry_point: "vs_main",
+                buffers: &[],
+            },
+            fragment: Some(wgpu::FragmentState {
+                module: &shader_module,
+                entry_point: "fs_main",
+                targets: &[Some(target_format.into())],
+            }),
+            primitive: wgpu::PrimitiveState::default(),
+            depth_stencil: None,
+            multisample: wgpu::MultisampleState::default(),
+            multiview: None,
+        });
+
+        let overdraw_shader_module = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: Some("DebugViewRenderer: overdraw"),
+            source: wgpu::ShaderSource::Wgsl(OVERDRAW_WGSL.into()),
+        });
+
+        Self {
+            settings,
+            normals_params: UniformBuffer::new_with_data(device, &Self::params(DebugViewMode::Normals, &settings)),
+            overdraw_params: UniformBuffer::new_with_data(device, &Self::params(DebugViewMode::Overdraw, &settings)),
+            depth_params: UniformBuffer::new_with_data(device, &Self::params(DebugViewMode::LinearDepth, &settings)),
+            bind_group_layout,
+            pipeline,
+            overdraw_shader_module,
+        }
+    }
+
+    fn params(mode: DebugViewMode, settings: &DebugViewSettings) -> DebugViewParams {
+        DebugViewParams {
+            mode: mode as u32,
+            near: settings.near,
+            far: settings.far.unwrap_or(0.0),
+            display_distance: settings.display_distance.max(settings.near + f32::EPSILON),
+            overdraw_max: settings.overdraw_max.max(1.0),
+            reversed_depth: (settings.depth_convention == DepthConvention::Reversed) as u32,
+            _padding: [0.0; 2],
+        }
+    }
+
+    pub fn settings(&self) -> &DebugViewSettings { &self.settings }
+
+    pub fn set_settings(&mut self, queue: &wgpu::Queue, settings: DebugViewSettings) {
+        self.settings = settings;
+        self.normals_params.update_content(queue, Self::params(DebugViewMode::Normals, &settings));
+        self.overdraw_params
+            .update_content(queue, Self::params(DebugViewMode::Overdraw, &settings));
+        self.depth_params
+            .update_content(queue, Self::params(DebugViewMode::LinearDepth, &settings));
+    }
+
+    // Fragment stage of the overdraw variant of a scene pipeline, usable with any vertex shader. Each fragment adds one
+    // to the count of an `OVERDRAW_FORMAT` target cleared to zero, with depth test and write disabled.
+    pub fn overdraw_fragment_state<'a>(&'a self, targets: &'a [Option<wgpu::ColorTargetState>]) -> wgpu::FragmentState<'a> {
+        wgpu::FragmentState {
+            module: &self.overdraw_shader_module,
+            entry_point: "fs_overdraw",
+            targets,
+        }
+    }
+
+    pub fn overdraw_color_target() -> wgpu::ColorTargetState {
+        let additive = wgpu::BlendComponent {
+            src_factor: wgpu::BlendFactor::One,
+            dst_factor: wgpu::BlendFactor::One,
+            operation: wgpu::BlendOperation::Add,
+        };
+        wgpu::ColorTargetState {
+            format: Self::OVERDRAW_FORMAT,
+            blend: Some(wgpu::BlendState { color: additive, alpha: additive }),
+            write_mask: wgpu::ColorWrites::ALL,
+        }
+    }
+
+    // Draw the visualization of `mode` into `target_view`: `source_view` is the normals, the depth or the overdraw count
+    // texture. Returns false for the modes without visualization pass (lit and wireframe).
+    pub fn encode(
+        &self,
+        device: &wgpu::Device,
+        encoder: &mut wgpu::CommandEncoder,
+        mode: DebugViewMode,
+        source_view: &wgpu::TextureView,
+        target_view: &wgpu::TextureView,
+    ) -> bool {
+        let params = match mode {
+            DebugViewMode::Normals => &self.normals_params,
+            DebugViewMode::LinearDepth => &self.depth_params,
+            DebugViewMode::Overdraw => &self.overdraw_params,
+            DebugViewMode::Lit | DebugViewMode::Wireframe => return false,
+        };
+        let bind_group = BindGroupBuilder::new(&self.bind_group_layout)
+            .texture(source_view)
+            .resource(params.binding_resource())
+            .create(device, Some("DebugViewRenderer"));
+        let mut render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+            label: Some("DebugViewRenderer"),
+            color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+                view: target_view,
+                resolve_target: None,
+                ops: wgpu::Operations {
+                    load: wgpu::LoadOp::Clear(wgpu::Color::BLACK),
+                    store: wgpu::StoreOp::Store,
+                },
+            })],
+            depth_stencil_attachment: None,
+            timestamp_writes: None,
+            occlusion_query_set: None,
+        });
+        render_pass.set_pipeline(&self.pipeline);
+        render_pass.set_bind_group(0, &bind_group, &[]);
+        render_pass.draw(0..3, 0..1);
+        true
+    }
+}
@@ -0,0 +1,143 @@
+use super::{memory_tracker::MemoryAllocation, DepthConvention, DepthPreset};
+
+// Depth pre-pass: the opaque geometry is first rendered depth only, then the main pass shades with an equal depth test
+// so each pixel is shaded once whatever the overdraw. Both passes share the depth target of the pre-pass.
+// The vertex positions of the two pipelines must be bit identical (same vertex shader or `@invariant` positions).
+pub struct DepthPrepass {
+    format: wgpu::TextureFormat,
+    sample_count: u32,
+    depth_convention: DepthConvention,
+    texture: wgpu::Texture,
+    view: wgpu::TextureView,
+    _memory: MemoryAllocation,
+}
+
+impl DepthPrepass {
+    pub fn new(device: &wgpu::Device, format: wgpu::TextureFormat, width: u32, height: u32, sample_count: u32) -> Self {
+        let (texture, view, memory) = Self::create_texture(device, format, width, height, sample_count);
+        Self {
+            format,
+            sample_count,
+            depth_convention: DepthConvention::Standard,
+            texture,
+            view,
+            _memory: memory,
+        }
+    }
+
+    // Reversed depth for the reverse-Z projections, applied to the depth states and the clear value
+    pub fn with_depth_convention(mut self, depth_convention: DepthConvention) -> Self {
+        self.depth_convention = depth_convention;
+        self
+    }
+
+    pub fn depth_convention(&self) -> DepthConvention { self.depth_convention }
+
+    fn create_texture(
+        device: &wgpu::Device,
+        format: wgpu::TextureFormat,
+        width: u32,
+        height: u32,
+        sample_count: u32,
+    ) -> (wgpu::Texture, wgpu::TextureView, MemoryAllocation) {
+        let texture = device.create_texture(&wgpu::TextureDescriptor {
+            label: Some("DepthPrepass: depth"),
+            size: wgpu::Extent3d {
+                width: width.max(1),
+                height: height.max(1),
+                depth_or_array_layers: 1,
+            },
+            mip_level_count: 1,
+            sample_count,
+            dimension: wgpu::TextureDimension::D2,
+            format,
+            usage: wgpu::TextureUsages::RENDER_ATTACHMENT | wgpu::TextureUsages::TEXTURE_BINDING,
+            view_formats: &[],
+        });
+        let view = texture.create_view(&wgpu::TextureViewDescriptor {
+            label: Some("DepthPrepass: depth view"),
+            ..Default::default()
+        });
+        let memory = MemoryAllocation::for_texture(&texture, Some("DepthPrepass: depth"));
+        (texture, view, memory)
+    }
+
+    pub fn resize(&mut self, device: &wgpu::Device, width: u32, height: u32) {
+        (self.texture, self.view, self._memory) = Self::create_texture(device, self.format, width, height, self.sample_count);
+    }
+
+    pub fn format(&self) -> wgpu::TextureFormat { self.format }
+
+    pub fn texture(&self) -> &wgpu::Texture { &self.texture }
+
+    // Shared depth target, can also be sampled after the main pass (e.g. for SSAO)
+    pub fn view(&self) -> &wgpu::TextureView { &self.view }
+
+    // Depth state of the pre-pass pipelines: depth written with a closer test
+    pub fn prepass_depth_stencil(&self) -> wgpu::DepthStencilState { DepthPreset::TestWrite.depth_stencil(self.format, self.depth_convention) }
+
+    // Depth state of the main pass pipelines: only the fragments left by the pre-pass are shaded, depth is not written again
+    pub fn main_depth_stencil(&self) -> wgpu::DepthStencilState { DepthPreset::Equal.depth_stencil(self.format, self.depth_convention) }
+
+    // Depth only variant of a main pass pipeline: no fragment stage (nor color target) and the pre-pass depth state
+    pub fn prepass_pipeline_descriptor<'a>(&self, descriptor: &wgpu::RenderPipelineDescriptor<'a>) -> wgpu::RenderPipelineDescriptor<'a> {
+        wgpu::RenderPipelineDescriptor {
+            fragment: None,
+            depth_stencil: Some(self.prepass_depth_stencil()),
+            ..descriptor.clone()
+        }
+    }
+
+    // Main pass variant of a pipeline, using the equal depth test
+    pub fn main_pipeline_descriptor<'a>(&self, descriptor: &wgpu::RenderPipelineDescriptor<'a>) -> wgpu::RenderPipelineDescriptor<'a> {
+        wgpu::RenderPipelineDescriptor {
+            depth_stencil: Some(self.main_depth_stencil()),
+            ..descriptor.clone()
+        }
+    }
+
+    // Both pipelines of a main pass pipeline descriptor: (pre-pass, main pass)
+    pub fn create_pipelines(
+        &self,
+        device: &wgpu::Device,
+        descriptor: &wgpu::RenderPipelineDescriptor,
+    ) -> (wgpu::RenderPipeline, wgpu::RenderPipeline) {
+        (
+            device.create_render_pipeline(&self.prepass_pipeline_descriptor(descriptor)),
+            device.create_render_pipeline(&self.main_pipeline_descriptor(descriptor)),
+        )
+    }
+
+    // Attachment of the pre-pass, clearing the depth
+    pub fn prepass_depth_attachment(&self) -> wgpu::RenderPassDepthStencilAttachment<'_> {
+        wgpu::RenderPassDepthStencilAttachment {
+            view: &self.view,
+            depth_ops: Some(self.depth_convention.clear_operations()),
+            stencil_ops: None,
+        }
+    }
+
+    // Attachment of the main pass, loading the pre-pass depth
+    pub fn main_depth_attachment(&self) -> wgpu::RenderPassDepthStencilAttachment<'_> {
+        wgpu::RenderPassDepthStencilAttachment {
+            view: &self.view,
+            depth_ops: Some(wgpu::Operations {
+                load: wgpu::LoadOp::Load,
+                store: wgpu::StoreOp::Store,
+            }),
+            stencil_ops: None,
+        }
+    }
+
+    // Depth only pass, `draw` records the opaque geometry with the pre-pass pipelines
+    pub fn encode_prepass<'a>(&'a self, encoder: &'a mut wgpu::CommandEncoder, draw: impl FnOnce(&mut wgpu::RenderPass<'a>)) {
+        let mut render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+            label: Some("DepthPrepass"),
+            color_attachments: &[],
+            depth_stencil_attachment: Some(self.prepass_depth_attachment()),
+            timestamp_writes: None,
+            occlusion_query_set: None,
+        });
+        draw(&mut render_pass);
+    }
+}
@@ -0,0 +1,161 @@
+use std::{
+    collections::VecDeque,
+    path::PathBuf,
+    sync::{Arc, Mutex},
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+const MAX_PASS_LABELS: usize = 32;
+
+struct Breadcrumbs {
+    pass_labels: VecDeque<String>,
+    last_shader: Option<String>,
+}
+
+// Recent GPU work of the process, recorded by the oxyde encoders and shader builds and included in the diagnostic reports
+static BREADCRUMBS: Mutex<Breadcrumbs> = Mutex::new(Breadcrumbs {
+    pass_labels: VecDeque::new(),
+    last_shader: None,
+});
+
+// Record the label of an encoded pass or section, only the last ones are kept
+pub fn record_pass_label(label: impl Into<String>) {
+    let mut breadcrumbs = BREADCRUMBS.lock().unwrap();
+    if breadcrumbs.pass_labels.len() == MAX_PASS_LABELS {
+        breadcrumbs.pass_labels.pop_front();
+    }
+    breadcrumbs.pass_labels.push_back(label.into());
+}
+
+// Record the name of the last compiled shader
+pub fn record_shader(name: impl Into<String>) { BREADCRUMBS.lock().unwrap().last_shader = Some(name.into()); }
+
+// State of the device and of the recent GPU work when a device error or loss occurred
+#[derive(Clone, Debug)]
+pub struct DiagnosticReport {
+    pub reason: String,
+    pub time: SystemTime,
+    pub adapter_info: wgpu::AdapterInfo,
+    pub features: wgpu::Features,
+    pub limits: wgpu::Limits,
+    // Oldest first
+    pub recent_pass_labels: Vec<String>,
+    pub last_shader: Option<String>,
+    // File the report was written to, if any
+    pub path: Option<PathBuf>,
+}
+
+impl std::fmt::Display for DiagnosticReport {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let time = self.time.duration_since(UNIX_EPOCH).unwrap_or_default();
+        writeln!(f, "GPU diagnostic report ({} s since the Unix epoch)", time.as_secs())?;
+        writeln!(f, "Reason: {}", self.reason)?;
+        writeln!(f)?;
+        writeln!(
+            f,
+            "Adapter: {} ({:?}, {:?})",
+            self.adapter_info.name, self.adapter_info.device_type, self.adapter_info.backend
+        )?;
+        writeln!(f, "Vendor: {:#06x}, device: {:#06x}", self.adapter_info.vendor, self.adapter_info.device)?;
+        writeln!(f, "Driver: {} {}", self.adapter_info.driver, self.adapter_info.driver_info)?;
+        writeln!(f)?;
+        writeln!(f, "Features: {:?}", self.features)?;
+        writeln!(f, "Limits: {:#?}", self.limits)?;
+        writeln!(f)?;
+        writeln!(f, "Last shader: {}", self.last_shader.as_deref().unwrap_or("none"))?;
+        writeln!(f, "Recent passes (oldest first):")?;
+        for label in &self.recent_pass_labels {
+            writeln!(f, "    {}", label)?;
+        }
+        Ok(())
+    }
+}
+
+pub type DiagnosticCallback = Box<dyn Fn(&DiagnosticReport) + Send + Sync>;
+
+struct ReporterState {
+    adapter_info: wgpu::AdapterInfo,
+    features: wgpu::Features,
+    limits: wgpu::Limits,
+    directory: Mutex<Option<PathBuf>>,
+    callback: Mutex<Option<DiagnosticCallback>>,
+}
+
+// Builds a `DiagnosticReport` of a device when it is lost (see `install_device_lost_handler`) or on an error (see `report`),
+// writes it to the report directory and passes it to the callback, e.g. to attach it to a crash report.
+// Clones share the same directory and callback.
+#[derive(Clone)]
+pub struct DiagnosticReporter {
+    state: Arc<ReporterState>,
+}
+
+impl DiagnosticReporter {
+    // `adapter_info` is the info of the adapter of `device`, e.g. `DeviceHandle::adapter().get_info()`
+    pub fn new(adapter_info: wgpu::AdapterInfo, device: &wgpu::Device) -> Self {
+        Self {
+            state: Arc::new(ReporterState {
+                adapter_info,
+                features: device.features(),
+                limits: device.limits(),
+                directory: Mutex::new(None),
+                callback: Mutex::new(None),
+            }),
+        }
+    }
+
+    // Directory the reports are written to, None to not write them
+    pub fn with_directory(self, directory: Option<impl Into<PathBuf>>) -> Self {
+        self.set_directory(directory);
+        self
+    }
+
+    pub fn set_directory(&self, directory: Option<impl Into<PathBuf>>) { *self.state.directory.lock().unwrap() = directory.map(Into::into); }
+
+    pub fn set_callback(&self, callback: impl Fn(&DiagnosticReport) + Send + Sync + 'static) {
+        *self.state.callback.lock().unwrap() = Some(Box::new(callback));
+    }
+
+    // Report the loss of `device` by the driver, the intentional losses (destroyed or dropped device) are not reported
+    pub fn install_device_lost_handler(&self, device: &wgpu::Device) {
+        let reporter = self.clone();
+        device.set_device_lost_callback(move |reason, message| {
+            if matches!(reason, wgpu::DeviceLostReason::Unknown) {
+                reporter.report(format!("Device lost: {}", message));
+            }
+        });
+    }
+
+    // Build the report, write it and pass it to the callback. A failure to write the report is printed.
+    pub fn report(&self, reason: impl Into<String>) -> DiagnosticReport {
+        let (recent_pass_labels, last_shader) = {
+            let breadcrumbs = BREADCRUMBS.lock().unwrap();
+            (breadcrumbs.pass_labels.iter().cloned().collect(), breadcrumbs.last_shader.clone())
+        };
+        let mut report = DiagnosticReport {
+            reason: reason.into(),
+            time: SystemTime::now(),
+            adapter_info: self.state.adapter_info.clone(),
+            features: self.state.features,
+            limits: self.state.limits.clone(),
+            recent_pass_labels,
+            last_shader,
+            path: None,
+        };
+
+        if let Some(directory) = self.state.directory.lock().unwrap().clone() {
+            let time = report.time.duration_since(UNIX_EPOCH).unwrap_or_default();
+            let path = directory.join(format!("gpu_report_{}_{:03}.txt", time.as_secs(), time.subsec_millis()));
+            match std::fs::create_dir_all(&directory).and_then(|_| std::fs::write(&path, report.to_string())) {
+                Ok(()) => report.path = Some(path),
+                Err(error) => {
+                    report_error!("Failed to write the GPU diagnostic report to {}: {}", path.display(), error);
+                },
+            }
+        }
+
+        if let Some(callback) = self.state.callback.lock().unwrap().as_ref() {
+            callback(&report);
+        }
+        report
+    }
+}
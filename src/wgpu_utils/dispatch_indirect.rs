@@ -0,0 +1,143 @@
+use wgpu::util::DeviceExt;
+
+use super::{
+    binding_builder::{BindGroupBuilder, BindGroupLayoutBuilder, BindGroupLayoutWithDesc},
+    memory_tracker::MemoryAllocation,
+};
+
+// WGSL layout of the workgroup counts, x is atomic so passes can grow it while appending work
+pub const DISPATCH_INDIRECT_WGSL: &str = "struct DispatchIndirectArgs {
+    x: atomic<u32>,
+    y: u32,
+    z: u32,
+}
+";
+
+fn storage_binding(read_only: bool) -> wgpu::BindingType {
+    wgpu::BindingType::Buffer {
+        ty: wgpu::BufferBindingType::Storage { read_only },
+        has_dynamic_offset: false,
+        min_binding_size: None,
+    }
+}
+
+// `[x, y, z]` workgroup counts written on the GPU (see `DISPATCH_INDIRECT_WGSL`) and used by `dispatch_workgroups_indirect`,
+// for workloads whose size is only known on the GPU (e.g. compacted particles)
+pub struct DispatchIndirectBuffer {
+    buffer: wgpu::Buffer,
+    bind_group_layout: BindGroupLayoutWithDesc,
+    // Read write storage binding of the counts, for the passes writing them
+    bind_group: wgpu::BindGroup,
+    _memory: MemoryAllocation,
+}
+
+impl DispatchIndirectBuffer {
+    pub fn new(device: &wgpu::Device, label: Option<&str>) -> Self { Self::new_with_counts(device, [0, 0, 0], label) }
+
+    pub fn new_with_counts(device: &wgpu::Device, counts: [u32; 3], label: Option<&str>) -> Self {
+        let label = format!("DispatchIndirectBuffer: {}", label.unwrap_or("unknown"));
+        let buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some(&label),
+            contents: bytemuck::cast_slice(&counts),
+            usage: wgpu::BufferUsages::STORAGE | wgpu::BufferUsages::INDIRECT | wgpu::BufferUsages::COPY_DST | wgpu::BufferUsages::COPY_SRC,
+        });
+        let bind_group_layout = BindGroupLayoutBuilder::new()
+            .add_binding_compute(storage_binding(false))
+            .create(device, Some(&label));
+        let bind_group = BindGroupBuilder::new(&bind_group_layout)
+            .resource(buffer.as_entire_binding())
+            .create(device, Some(&label));
+        let memory = MemoryAllocation::for_buffer(&buffer, Some(&label));
+
+        Self {
+            buffer,
+            bind_group_layout,
+            bind_group,
+            _memory: memory,
+        }
+    }
+
+    pub fn buffer(&self) -> &wgpu::Buffer { &self.buffer }
+
+    pub fn binding_resource(&self) -> wgpu::BindingResource<'_> { self.buffer.as_entire_binding() }
+
+    pub fn bind_group(&self) -> &wgpu::BindGroup { &self.bind_group }
+
+    pub fn layout(&self) -> &wgpu::BindGroupLayout { &self.bind_group_layout.layout }
+
+    // Zero the counts in the command stream, before the passes accumulating into them
+    pub fn encode_clear(&self, encoder: &mut wgpu::CommandEncoder) { encoder.clear_buffer(&self.buffer, 0, None); }
+
+    // Set the counts from the CPU, done before the next submit
+    pub fn write(&self, queue: &wgpu::Queue, counts: [u32; 3]) { queue.write_buffer(&self.buffer, 0, bytemuck::cast_slice(&counts)); }
+
+    pub fn dispatch<'a>(&'a self, compute_pass: &mut wgpu::ComputePass<'a>) { compute_pass.dispatch_workgroups_indirect(&self.buffer, 0); }
+}
+
+// Pass writing the counts of a `DispatchIndirectBuffer` from an item count on the GPU (e.g. the size of a compacted list):
+// `x = ceil(count / workgroup_size)`, `y = z = 1`
+pub struct WorkgroupCountPass {
+    workgroup_size: u32,
+    bind_group_layout: BindGroupLayoutWithDesc,
+    pipeline: wgpu::ComputePipeline,
+}
+
+impl WorkgroupCountPass {
+    pub fn new(device: &wgpu::Device, workgroup_size: u32) -> Self {
+        let workgroup_size = workgroup_size.max(1);
+        let bind_group_layout = BindGroupLayoutBuilder::new()
+            .add_binding_compute(storage_binding(true))
+            .add_binding_compute(storage_binding(false))
+            .create(device, Some("WorkgroupCountPass"));
+        let shader_module = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: Some("WorkgroupCountPass"),
+            source: wgpu::ShaderSource::Wgsl(
+                format!(
+                    "@group(0) @binding(0) var<storage, read> count: u32;
+@group(0) @binding(1) var<storage, read_write> counts: array<u32, 3>;
+
+@compute @workgroup_size(1)
+fn main() {{
+    counts = array<u32, 3>((count + {0}u - 1u) / {0}u, 1u, 1u);
+}}",
+                    workgroup_size
+                )
+                .into(),
+            ),
+        });
+        let pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("WorkgroupCountPass"),
+            bind_group_layouts: &[&bind_group_layout.layout],
+            push_constant_ranges: &[],
+        });
+        let pipeline = device.create_compute_pipeline(&wgpu::ComputePipelineDescriptor {
+            label: Some("WorkgroupCountPass"),
+            layout: Some(&pipeline_layout),
+            module: &shader_module,
+            entry_point: "main",
+        });
+
+        Self {
+            workgroup_size,
+            bind_group_layout,
+            pipeline,
+        }
+    }
+
+    pub fn workgroup_size(&self) -> u32 { self.workgroup_size }
+
+    // `count` is a binding of a single u32 in a buffer with the STORAGE usage
+    pub fn encode(&self, device: &wgpu::Device, encoder: &mut wgpu::CommandEncoder, count: wgpu::BindingResource, target: &DispatchIndirectBuffer) {
+        let bind_group = BindGroupBuilder::new(&self.bind_group_layout)
+            .resource(count)
+            .resource(target.binding_resource())
+            .create(device, Some("WorkgroupCountPass"));
+        let mut compute_pass = encoder.begin_compute_pass(&wgpu::ComputePassDescriptor {
+            label: Some("WorkgroupCountPass"),
+            timestamp_writes: None,
+        });
+        compute_pass.set_pipeline(&self.pipeline);
+        compute_pass.set_bind_group(0, &bind_group, &[]);
+        compute_pass.dispatch_workgroups(1, 1, 1);
+    }
+}
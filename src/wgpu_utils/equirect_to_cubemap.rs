@@ -0,0 +1,237 @@
+use super::{
+    binding_builder::{BindGroupBuilder, BindGroupLayoutBuilder, BindGroupLayoutWithDesc},
+    StorageTexture,
+    TextureBuilder,
+};
+
+const WORKGROUP_SIZE: u32 = 8;
+
+// Faces in the wgpu order (+X, -X, +Y, -Y, +Z, -Z), texel (0, 0) of each face being its top left corner as seen from the
+// center of the cube. The equirect u goes around Y from +X towards +Z and v from +Y (top) to -Y (bottom).
+// Texels are loaded and filtered manually so 32 bits float equirects, which can't be filtered, are supported.
+const EQUIRECT_TO_CUBEMAP_WGSL: &str = r#"
+const PI: f32 = 3.14159265358979323846;
+
+@group(0) @binding(0) var equirect: texture_2d<f32>;
+@group(0) @binding(1) var faces: texture_storage_2d_array<{format}, write>;
+
+fn face_direction(face: u32, uv: vec2<f32>) -> vec3<f32> {
+    switch face {
+        case 0u: { return vec3<f32>(1.0, -uv.y, -uv.x); }
+        case 1u: { return vec3<f32>(-1.0, -uv.y, uv.x); }
+        case 2u: { return vec3<f32>(uv.x, 1.0, uv.y); }
+        case 3u: { return vec3<f32>(uv.x, -1.0, -uv.y); }
+        case 4u: { return vec3<f32>(uv.x, -uv.y, 1.0); }
+        default: { return vec3<f32>(-uv.x, -uv.y, -1.0); }
+    }
+}
+
+// Bilinear filtering, wrapping horizontally and clamped vertically
+fn load_equirect(coordinates: vec2<i32>) -> vec4<f32> {
+    let size = vec2<i32>(textureDimensions(equirect));
+    let wrapped = vec2<i32>((coordinates.x % size.x + size.x) % size.x, clamp(coordinates.y, 0, size.y - 1));
+    return textureLoad(equirect, wrapped, 0);
+}
+
+fn sample_equirect(uv: vec2<f32>) -> vec4<f32> {
+    let position = uv * vec2<f32>(textureDimensions(equirect)) - 0.5;
+    let base = vec2<i32>(floor(position));
+    let t = fract(position);
+    let top = mix(load_equirect(base), load_equirect(base + vec2<i32>(1, 0)), t.x);
+    let bottom = mix(load_equirect(base + vec2<i32>(0, 1)), load_equirect(base + vec2<i32>(1, 1)), t.x);
+    return mix(top, bottom, t.y);
+}
+
+@compute @workgroup_size({workgroup_size}, {workgroup_size})
+fn main(@builtin(global_invocation_id) id: vec3<u32>) {
+    let size = textureDimensions(faces);
+    if any(id.xy >= size) {
+        return;
+    }
+    let uv = (vec2<f32>(id.xy) + 0.5) / vec2<f32>(size) * 2.0 - 1.0;
+    let direction = normalize(face_direction(id.z, uv));
+    let equirect_uv = vec2<f32>(atan2(direction.z, direction.x) / (2.0 * PI) + 0.5, acos(clamp(direction.y, -1.0, 1.0)) / PI);
+    textureStore(faces, id.xy, id.z, sample_equirect(equirect_uv));
+}
+"#;
+
+// Average of the 2x2 texels of the previous mip, per face
+const DOWNSAMPLE_WGSL: &str = r#"
+@group(0) @binding(0) var source: texture_2d_array<f32>;
+@group(0) @binding(1) var faces: texture_storage_2d_array<{format}, write>;
+
+@compute @workgroup_size({workgroup_size}, {workgroup_size})
+fn main(@builtin(global_invocation_id) id: vec3<u32>) {
+    if any(id.xy >= textureDimensions(faces)) {
+        return;
+    }
+    let source_max = vec2<u32>(textureDimensions(source)) - 1u;
+    let base = id.xy * 2u;
+    let sum = textureLoad(source, min(base, source_max), id.z, 0)
+        + textureLoad(source, min(base + vec2<u32>(1u, 0u), source_max), id.z, 0)
+        + textureLoad(source, min(base + vec2<u32>(0u, 1u), source_max), id.z, 0)
+        + textureLoad(source, min(base + vec2<u32>(1u, 1u), source_max), id.z, 0);
+    textureStore(faces, id.xy, id.z, sum * 0.25);
+}
+"#;
+
+struct FacesPass {
+    bind_group_layout: BindGroupLayoutWithDesc,
+    pipeline: wgpu::ComputePipeline,
+}
+
+impl FacesPass {
+    fn new(device: &wgpu::Device, format: wgpu::TextureFormat, source_dimension: wgpu::TextureViewDimension, wgsl: &str, label: &str) -> Self {
+        let format_wgsl = match format {
+            wgpu::TextureFormat::Rgba16Float => "rgba16float",
+            wgpu::TextureFormat::Rgba32Float => "rgba32float",
+            format => panic!("{:?} can't be used for the cubemap, use Rgba16Float or Rgba32Float", format),
+        };
+        let bind_group_layout = BindGroupLayoutBuilder::new()
+            .add_binding_compute(wgpu::BindingType::Texture {
+                sample_type: wgpu::TextureSampleType::Float { filterable: false },
+                view_dimension: source_dimension,
+                multisampled: false,
+            })
+            .add_binding_compute(wgpu::BindingType::StorageTexture {
+                access: wgpu::StorageTextureAccess::WriteOnly,
+                format,
+                view_dimension: wgpu::TextureViewDimension::D2Array,
+            })
+            .create(device, Some(label));
+        let shader_module = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: Some(label),
+            source: wgpu::ShaderSource::Wgsl(
+                wgsl.replace("{format}", format_wgsl)
+                    .replace("{workgroup_size}", &WORKGROUP_SIZE.to_string())
+                    .into(),
+            ),
+        });
+        let pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some(label),
+            bind_group_layouts: &[&bind_group_layout.layout],
+            push_constant_ranges: &[],
+        });
+        let pipeline = device.create_compute_pipeline(&wgpu::ComputePipelineDescriptor {
+            label: Some(label),
+            layout: Some(&pipeline_layout),
+            module: &shader_module,
+            entry_point: "main",
+        });
+
+        Self { bind_group_layout, pipeline }
+    }
+
+    fn encode(
+        &self,
+        device: &wgpu::Device,
+        encoder: &mut wgpu::CommandEncoder,
+        source: &wgpu::TextureView,
+        faces: &wgpu::TextureView,
+        face_size: u32,
+        label: &str,
+    ) {
+        let bind_group = BindGroupBuilder::new(&self.bind_group_layout)
+            .texture(source)
+            .texture(faces)
+            .create(device, Some(label));
+        let mut compute_pass = encoder.begin_compute_pass(&wgpu::ComputePassDescriptor {
+            label: Some(label),
+            timestamp_writes: None,
+        });
+        compute_pass.set_pipeline(&self.pipeline);
+        compute_pass.set_bind_group(0, &bind_group, &[]);
+        let workgroup_count = face_size.div_ceil(WORKGROUP_SIZE);
+        compute_pass.dispatch_workgroups(workgroup_count, workgroup_count, 6);
+    }
+}
+
+// Conversion of an equirectangular (latitude/longitude) HDR texture to the six faces of a cubemap, computing the mip chain
+// of the cubemap by averaging when it has several mips. The result is sampled through its cube view (e.g. by a skybox or
+// to prefilter the IBL maps).
+// Not supported by the GL backend, which can't write to 6 layers textures (created as cube maps) nor to a mip while
+// another mip of the texture is sampled.
+pub struct EquirectToCubemap {
+    format: wgpu::TextureFormat,
+    convert: FacesPass,
+    downsample: FacesPass,
+}
+
+impl EquirectToCubemap {
+    // `format` is the cubemap format, Rgba16Float or Rgba32Float
+    pub fn new(device: &wgpu::Device, format: wgpu::TextureFormat) -> Self {
+        Self {
+            format,
+            convert: FacesPass::new(
+                device,
+                format,
+                wgpu::TextureViewDimension::D2,
+                EQUIRECT_TO_CUBEMAP_WGSL,
+                "EquirectToCubemap: convert",
+            ),
+            downsample: FacesPass::new(
+                device,
+                format,
+                wgpu::TextureViewDimension::D2Array,
+                DOWNSAMPLE_WGSL,
+                "EquirectToCubemap: downsample",
+            ),
+        }
+    }
+
+    pub fn format(&self) -> wgpu::TextureFormat { self.format }
+
+    // Cubemap that `encode` can fill, with all its mips if `mipmapped`
+    pub fn create_cubemap(&self, device: &wgpu::Device, face_size: u32, mipmapped: bool, label: Option<&str>) -> StorageTexture {
+        // COPY_SRC to save the faces or copy them to other textures
+        let mut builder = TextureBuilder::cubemap(self.format, face_size)
+            .with_usage(wgpu::TextureUsages::COPY_SRC)
+            .with_label(format!("EquirectToCubemap: {}", label.unwrap_or("cubemap")));
+        if mipmapped {
+            builder = builder.with_full_mip_chain();
+        }
+        StorageTexture::from_builder(device, builder, wgpu::StorageTextureAccess::WriteOnly)
+    }
+
+    // Fill the faces of `cubemap` (created by `create_cubemap`) from `equirect_view` (a float 2D texture, usually twice as
+    // wide as high), then its other mips
+    pub fn encode(&self, device: &wgpu::Device, encoder: &mut wgpu::CommandEncoder, equirect_view: &wgpu::TextureView, cubemap: &StorageTexture) {
+        let face_size = cubemap.size().width;
+        self.convert
+            .encode(device, encoder, equirect_view, cubemap.storage_view(), face_size, "EquirectToCubemap: convert");
+
+        for mip_level in 1..cubemap.texture().mip_level_count() {
+            let source = cubemap.texture().create_view(&wgpu::TextureViewDescriptor {
+                label: Some(&format!("EquirectToCubemap: mip {} source", mip_level - 1)),
+                dimension: Some(wgpu::TextureViewDimension::D2Array),
+                base_mip_level: mip_level - 1,
+                mip_level_count: Some(1),
+                ..Default::default()
+            });
+            let destination = cubemap.create_storage_mip_view(mip_level);
+            self.downsample.encode(
+                device,
+                encoder,
+                &source,
+                &destination,
+                (face_size >> mip_level).max(1),
+                "EquirectToCubemap: downsample",
+            );
+        }
+    }
+
+    // Create and fill a cubemap from `equirect_view`
+    pub fn convert(
+        &self,
+        device: &wgpu::Device,
+        encoder: &mut wgpu::CommandEncoder,
+        equirect_view: &wgpu::TextureView,
+        face_size: u32,
+        mipmapped: bool,
+        label: Option<&str>,
+    ) -> StorageTexture {
+        let cubemap = self.create_cubemap(device, face_size, mipmapped, label);
+        self.encode(device, encoder, equirect_view, &cubemap);
+        cubemap
+    }
+}
@@ -0,0 +1,135 @@
+use std::sync::{Arc, Mutex};
+
+use super::DiagnosticReporter;
+
+// What to do with an uncaptured wgpu error
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum ErrorAction {
+    Ignore,
+    // Log the error and keep running
+    Log,
+    // Report the error to the diagnostics then panic, on the first error reaching it
+    #[default]
+    Break,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum ErrorMatcher {
+    // Substring of the error message
+    Contains(String),
+    // Label of a resource, pass or encoder involved in the error (given to its descriptor)
+    Label(String),
+}
+
+impl ErrorMatcher {
+    pub fn matches(&self, message: &str) -> bool {
+        match self {
+            Self::Contains(substring) => message.contains(substring.as_str()),
+            // wgpu quotes the labels with backticks in the error messages
+            Self::Label(label) => message.contains(&format!("`{}`", label)),
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ErrorRule {
+    pub matcher: ErrorMatcher,
+    pub action: ErrorAction,
+}
+
+// Handling of the uncaptured errors of a device (errors outside of `ErrorScopes`): the first rule matching the error
+// message gives the action, the default action applies otherwise.
+// The default policy breaks on all errors.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize), serde(default))]
+pub struct ErrorPolicy {
+    pub rules: Vec<ErrorRule>,
+    pub default_action: ErrorAction,
+}
+
+impl ErrorPolicy {
+    pub fn new(default_action: ErrorAction) -> Self { Self { rules: Vec::new(), default_action } }
+
+    pub fn with_rule(mut self, matcher: ErrorMatcher, action: ErrorAction) -> Self {
+        self.rules.push(ErrorRule { matcher, action });
+        self
+    }
+
+    pub fn ignore_containing(self, substring: impl Into<String>) -> Self {
+        self.with_rule(ErrorMatcher::Contains(substring.into()), ErrorAction::Ignore)
+    }
+
+    pub fn ignore_label(self, label: impl Into<String>) -> Self { self.with_rule(ErrorMatcher::Label(label.into()), ErrorAction::Ignore) }
+
+    pub fn log_containing(self, substring: impl Into<String>) -> Self { self.with_rule(ErrorMatcher::Contains(substring.into()), ErrorAction::Log) }
+
+    pub fn log_label(self, label: impl Into<String>) -> Self { self.with_rule(ErrorMatcher::Label(label.into()), ErrorAction::Log) }
+
+    pub fn action(&self, message: &str) -> ErrorAction {
+        self.rules
+            .iter()
+            .find(|rule| rule.matcher.matches(message))
+            .map_or(self.default_action, |rule| rule.action)
+    }
+}
+
+struct HandlerState {
+    policy: Mutex<ErrorPolicy>,
+    diagnostics: Option<DiagnosticReporter>,
+    // Errors handled per action, in the `ErrorAction` order
+    counts: Mutex<[u64; 3]>,
+}
+
+// Uncaptured error handler of a device applying an `ErrorPolicy`, which can be changed while the device is running.
+// Clones share the same policy.
+#[derive(Clone)]
+pub struct UncapturedErrorHandler {
+    state: Arc<HandlerState>,
+}
+
+impl UncapturedErrorHandler {
+    // The errors breaking are reported to `diagnostics` before panicking
+    pub fn new(policy: ErrorPolicy, diagnostics: Option<DiagnosticReporter>) -> Self {
+        Self {
+            state: Arc::new(HandlerState {
+                policy: Mutex::new(policy),
+                diagnostics,
+                counts: Mutex::new([0; 3]),
+            }),
+        }
+    }
+
+    // Replace the uncaptured error handler of `device`
+    pub fn install(&self, device: &wgpu::Device) {
+        let handler = self.clone();
+        device.on_uncaptured_error(Box::new(move |error| handler.handle(&error)));
+    }
+
+    pub fn policy(&self) -> ErrorPolicy { self.state.policy.lock().unwrap().clone() }
+
+    pub fn set_policy(&self, policy: ErrorPolicy) { *self.state.policy.lock().unwrap() = policy; }
+
+    // Number of errors handled with `action` since the creation of the handler
+    pub fn error_count(&self, action: ErrorAction) -> u64 { self.state.counts.lock().unwrap()[action as usize] }
+
+    pub fn handle(&self, error: &wgpu::Error) {
+        let message = error.to_string();
+        let action = self.state.policy.lock().unwrap().action(&message);
+        self.state.counts.lock().unwrap()[action as usize] += 1;
+        match action {
+            ErrorAction::Ignore => {},
+            ErrorAction::Log => {
+                report_error!("Uncaptured wgpu error: {}", message);
+            },
+            ErrorAction::Break => {
+                if let Some(diagnostics) = &self.state.diagnostics {
+                    diagnostics.report(format!("Uncaptured error: {}", message));
+                }
+                panic!("{}", message)
+            },
+        }
+    }
+}
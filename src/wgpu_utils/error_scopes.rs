@@ -0,0 +1,84 @@
+use std::{future::Future, pin::Pin, task::Poll};
+
+use wgpu::{Device, Error, ErrorFilter};
+
+// wgpu error raised inside a named section
+#[derive(Debug)]
+pub struct ScopeError {
+    pub label: String,
+    pub error: Error,
+}
+
+impl std::fmt::Display for ScopeError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result { write!(f, "wgpu error in \"{}\": {}", self.label, self.error) }
+}
+
+impl std::error::Error for ScopeError {}
+
+struct PendingScope {
+    label: String,
+    error: Pin<Box<dyn Future<Output = Option<Error>>>>,
+}
+
+// Validation and out of memory error scopes around user named sections (e.g. per pass).
+// Errors are resolved asynchronously by wgpu and collected with `poll`, reporting them with the label of their section.
+#[derive(Default)]
+pub struct ErrorScopes {
+    // Labels of the scopes pushed and not popped yet
+    open_scopes: Vec<String>,
+    pending_scopes: Vec<PendingScope>,
+}
+
+impl ErrorScopes {
+    pub fn new() -> Self { Self::default() }
+
+    pub fn push(&mut self, device: &Device, label: impl Into<String>) {
+        let label = label.into();
+        super::record_pass_label(label.as_str());
+        device.push_error_scope(ErrorFilter::OutOfMemory);
+        device.push_error_scope(ErrorFilter::Validation);
+        self.open_scopes.push(label);
+    }
+
+    // Pop the last pushed scope
+    pub fn pop(&mut self, device: &Device) {
+        let label = self.open_scopes.pop().expect("ErrorScopes::pop called without a matching push");
+        // Scopes are popped in the reverse order of their push
+        for error in [device.pop_error_scope(), device.pop_error_scope()] {
+            self.pending_scopes.push(PendingScope {
+                label: label.clone(),
+                error: Box::pin(error),
+            });
+        }
+    }
+
+    pub fn scope<R>(&mut self, device: &Device, label: impl Into<String>, section: impl FnOnce() -> R) -> R {
+        self.push(device, label);
+        let result = section();
+        self.pop(device);
+        result
+    }
+
+    // Errors of the popped scopes resolved so far
+    pub fn poll(&mut self) -> Vec<ScopeError> {
+        let mut context = std::task::Context::from_waker(std::task::Waker::noop());
+        let mut errors = Vec::new();
+        self.pending_scopes
+            .retain_mut(|pending_scope| match pending_scope.error.as_mut().poll(&mut context) {
+                Poll::Ready(error) => {
+                    errors.extend(error.map(|error| ScopeError {
+                        label: pending_scope.label.clone(),
+                        error,
+                    }));
+                    false
+                },
+                Poll::Pending => true,
+            });
+        errors
+    }
+
+    #[inline]
+    pub fn open_scope_count(&self) -> usize { self.open_scopes.len() }
+    #[inline]
+    pub fn pending_scope_count(&self) -> usize { self.pending_scopes.len() }
+}
@@ -0,0 +1,36 @@
+// Per frame built-in uniforms (similar to the ShaderToy inputs), maintained by the application in `AppState::frame_uniforms`
+#[repr(C)]
+#[derive(Clone, Copy, Default, Debug, PartialEq, bytemuck::Pod, bytemuck::Zeroable)]
+pub struct FrameUniforms {
+    // Seconds since the application start
+    pub time: f32,
+    pub delta_time: f32,
+    pub frame_index: u32,
+    // Bit 0: left, bit 1: right, bit 2: middle
+    pub mouse_buttons: u32,
+    // Surface size in pixels
+    pub resolution: [f32; 2],
+    // Mouse position in pixels from the top left corner
+    pub mouse: [f32; 2],
+    // Sub-pixel offsets of the projection (x right, y down) of this frame and of the previous one, zero without `AppState::projection_jitter`
+    pub jitter: [f32; 2],
+    pub previous_jitter: [f32; 2],
+}
+
+pub const FRAME_UNIFORMS_IMPORT_PATH: &str = "oxyde::frame_uniforms";
+
+// Matching WGSL struct, as a composable module (`#import oxyde::frame_uniforms::FrameUniforms`, see `ShaderComposer::with_frame_uniforms`)
+// or to paste in a shader
+pub const FRAME_UNIFORMS_WGSL: &str = r#"#define_import_path oxyde::frame_uniforms
+
+struct FrameUniforms {
+    time: f32,
+    delta_time: f32,
+    frame_index: u32,
+    mouse_buttons: u32,
+    resolution: vec2<f32>,
+    mouse: vec2<f32>,
+    jitter: vec2<f32>,
+    previous_jitter: vec2<f32>,
+}
+"#;
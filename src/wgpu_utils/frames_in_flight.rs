@@ -0,0 +1,83 @@
+use std::sync::{
+    atomic::{AtomicU64, Ordering},
+    Arc,
+};
+
+// Limits the number of frames the CPU records ahead of the GPU so per-frame resources (`PerFrame`) can be rewritten safely.
+// Usage per frame: `begin_frame` (waits for the GPU to be done with the frame slot), record and submit, then `end_frame`.
+pub struct FramesInFlight {
+    frame_count: usize,
+    // Index of the frame being recorded
+    frame_index: u64,
+    // Submission of the last frame which used each slot
+    slot_submissions: Vec<Option<wgpu::SubmissionIndex>>,
+    // Number of frames the GPU finished, updated from `on_submitted_work_done`
+    completed_frame_count: Arc<AtomicU64>,
+}
+
+impl FramesInFlight {
+    pub fn new(frame_count: usize) -> Self {
+        assert!(frame_count > 0, "At least one frame must be in flight");
+        Self {
+            frame_count,
+            frame_index: 0,
+            slot_submissions: vec![None; frame_count],
+            completed_frame_count: Arc::new(AtomicU64::new(0)),
+        }
+    }
+
+    #[inline]
+    pub fn frame_count(&self) -> usize { self.frame_count }
+
+    #[inline]
+    pub fn frame_index(&self) -> u64 { self.frame_index }
+
+    // Slot of the current frame in the per-frame resources
+    #[inline]
+    pub fn slot(&self) -> usize { (self.frame_index % self.frame_count as u64) as usize }
+
+    pub fn completed_frame_count(&self) -> u64 { self.completed_frame_count.load(Ordering::Acquire) }
+
+    pub fn is_frame_complete(&self, frame_index: u64) -> bool { frame_index < self.completed_frame_count() }
+
+    // Block until the GPU is done with the last frame which used the current slot
+    pub fn begin_frame(&mut self, device: &wgpu::Device) {
+        let slot = self.slot();
+        if let Some(submission_index) = self.slot_submissions[slot].take() {
+            device.poll(wgpu::Maintain::WaitForSubmissionIndex(submission_index));
+        }
+    }
+
+    // Register the last submission of the current frame and move to the next frame
+    pub fn end_frame(&mut self, queue: &wgpu::Queue, submission_index: wgpu::SubmissionIndex) {
+        let slot = self.slot();
+        self.slot_submissions[slot] = Some(submission_index);
+
+        let completed_frame_count = self.completed_frame_count.clone();
+        let frame_count = self.frame_index + 1;
+        queue.on_submitted_work_done(move || {
+            completed_frame_count.fetch_max(frame_count, Ordering::AcqRel);
+        });
+        self.frame_index += 1;
+    }
+}
+
+// One instance of a resource per frame in flight (e.g. staging buffers or dynamic uniforms), the one of the current slot
+// is not read by the GPU anymore once `FramesInFlight::begin_frame` returned
+pub struct PerFrame<T> {
+    resources: Vec<T>,
+}
+
+impl<T> PerFrame<T> {
+    pub fn new(frames_in_flight: &FramesInFlight, mut create: impl FnMut(usize) -> T) -> Self {
+        Self {
+            resources: (0..frames_in_flight.frame_count()).map(&mut create).collect(),
+        }
+    }
+
+    pub fn get(&self, frames_in_flight: &FramesInFlight) -> &T { &self.resources[frames_in_flight.slot()] }
+
+    pub fn get_mut(&mut self, frames_in_flight: &FramesInFlight) -> &mut T { &mut self.resources[frames_in_flight.slot()] }
+
+    pub fn iter(&self) -> impl Iterator<Item = &T> { self.resources.iter() }
+}
@@ -0,0 +1,294 @@
+use super::{
+    binding_builder::{BindGroupBuilder, BindGroupLayoutBuilder, BindGroupLayoutWithDesc},
+    child_label,
+    memory_tracker::MemoryAllocation,
+    DepthConvention,
+    DepthPreset,
+};
+
+#[derive(Clone, Debug)]
+pub struct GBufferAttachment {
+    pub name: String,
+    pub format: wgpu::TextureFormat,
+    // Value of the attachment where no geometry is rendered
+    pub clear_color: wgpu::Color,
+}
+
+// Attachments of a G-buffer, in the order of the geometry pass outputs (`@location`) and of the lighting bind group
+#[derive(Clone, Default, Debug)]
+pub struct GBufferDescriptor {
+    pub attachments: Vec<GBufferAttachment>,
+    pub depth_format: Option<wgpu::TextureFormat>,
+    // Clear value of the depth
+    pub depth_convention: DepthConvention,
+}
+
+impl GBufferDescriptor {
+    // Albedo, world normal, material (metallic, roughness, occlusion) and depth
+    pub fn standard() -> Self {
+        Self::default()
+            .with_attachment("albedo", wgpu::TextureFormat::Rgba8UnormSrgb)
+            .with_attachment("normal", wgpu::TextureFormat::Rgba16Float)
+            .with_attachment("material", wgpu::TextureFormat::Rgba8Unorm)
+            .with_depth(wgpu::TextureFormat::Depth32Float)
+    }
+
+    pub fn with_attachment(self, name: &str, format: wgpu::TextureFormat) -> Self {
+        self.with_cleared_attachment(name, format, wgpu::Color::TRANSPARENT)
+    }
+
+    pub fn with_cleared_attachment(mut self, name: &str, format: wgpu::TextureFormat, clear_color: wgpu::Color) -> Self {
+        self.attachments.push(GBufferAttachment {
+            name: name.to_string(),
+            format,
+            clear_color,
+        });
+        self
+    }
+
+    pub fn with_depth(mut self, depth_format: wgpu::TextureFormat) -> Self {
+        self.depth_format = Some(depth_format);
+        self
+    }
+
+    pub fn without_depth(mut self) -> Self {
+        self.depth_format = None;
+        self
+    }
+
+    pub fn with_depth_convention(mut self, depth_convention: DepthConvention) -> Self {
+        self.depth_convention = depth_convention;
+        self
+    }
+}
+
+struct GBufferTexture {
+    texture: wgpu::Texture,
+    view: wgpu::TextureView,
+    _memory: MemoryAllocation,
+}
+
+// Attachments of a deferred renderer: the geometry pass writes them all at once (MRT), the lighting pass reads them
+// through `bind_group`. The textures are loaded (`textureLoad`) rather than sampled, in the attachments order followed by the depth.
+// The bind group layout doesn't change on resize so the lighting pipelines stay valid, only the bind group is recreated.
+pub struct GBuffer {
+    descriptor: GBufferDescriptor,
+    size: (u32, u32),
+    textures: Vec<GBufferTexture>,
+    depth: Option<GBufferTexture>,
+    bind_group_layout: BindGroupLayoutWithDesc,
+    bind_group: wgpu::BindGroup,
+}
+
+impl GBuffer {
+    pub fn new(device: &wgpu::Device, descriptor: GBufferDescriptor, width: u32, height: u32) -> Self {
+        let visibility = wgpu::ShaderStages::FRAGMENT | wgpu::ShaderStages::COMPUTE;
+        let bind_group_layout = descriptor
+            .attachments
+            .iter()
+            .map(|attachment| attachment.format)
+            .chain(descriptor.depth_format)
+            .fold(BindGroupLayoutBuilder::new(), |builder, format| {
+                builder.add_binding(
+                    visibility,
+                    wgpu::BindingType::Texture {
+                        sample_type: Self::sample_type(format),
+                        view_dimension: wgpu::TextureViewDimension::D2,
+                        multisampled: false,
+                    },
+                )
+            })
+            .create(device, Some("GBuffer"));
+
+        let size = (width.max(1), height.max(1));
+        let (textures, depth, bind_group) = Self::create_textures(device, &descriptor, &bind_group_layout, size);
+
+        Self {
+            descriptor,
+            size,
+            textures,
+            depth,
+            bind_group_layout,
+            bind_group,
+        }
+    }
+
+    // Textures are loaded in the lighting pass, float formats are declared non filterable so any of them can be used
+    fn sample_type(format: wgpu::TextureFormat) -> wgpu::TextureSampleType {
+        let aspect = format.has_depth_aspect().then_some(wgpu::TextureAspect::DepthOnly);
+        match format.sample_type(aspect, None) {
+            Some(wgpu::TextureSampleType::Float { .. }) => wgpu::TextureSampleType::Float { filterable: false },
+            Some(sample_type) => sample_type,
+            None => panic!("G-buffer format {:?} can't be read in the lighting pass", format),
+        }
+    }
+
+    fn create_texture(device: &wgpu::Device, name: &str, format: wgpu::TextureFormat, size: (u32, u32)) -> GBufferTexture {
+        let label = format!("GBuffer: {}", name);
+        let texture = device.create_texture(&wgpu::TextureDescriptor {
+            label: Some(&label),
+            size: wgpu::Extent3d {
+                width: size.0,
+                height: size.1,
+                depth_or_array_layers: 1,
+            },
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: wgpu::TextureDimension::D2,
+            format,
+            usage: wgpu::TextureUsages::RENDER_ATTACHMENT | wgpu::TextureUsages::TEXTURE_BINDING,
+            view_formats: &[],
+        });
+        let view = texture.create_view(&wgpu::TextureViewDescriptor {
+            label: Some(&child_label(Some(&label), "view")),
+            ..Default::default()
+        });
+        let memory = MemoryAllocation::for_texture(&texture, Some(&label));
+        GBufferTexture { texture, view, _memory: memory }
+    }
+
+    fn create_textures(
+        device: &wgpu::Device,
+        descriptor: &GBufferDescriptor,
+        bind_group_layout: &BindGroupLayoutWithDesc,
+        size: (u32, u32),
+    ) -> (Vec<GBufferTexture>, Option<GBufferTexture>, wgpu::BindGroup) {
+        let textures: Vec<GBufferTexture> = descriptor
+            .attachments
+            .iter()
+            .map(|attachment| Self::create_texture(device, &attachment.name, attachment.format, size))
+            .collect();
+        let depth = descriptor
+            .depth_format
+            .map(|depth_format| Self::create_texture(device, "depth", depth_format, size));
+
+        // Combined depth stencil formats are bound through their depth aspect
+        let depth_binding_view = depth.as_ref().map(|depth| {
+            depth.texture.create_view(&wgpu::TextureViewDescriptor {
+                label: Some("GBuffer: depth binding view"),
+                aspect: wgpu::TextureAspect::DepthOnly,
+                ..Default::default()
+            })
+        });
+        let bind_group = textures
+            .iter()
+            .map(|texture| &texture.view)
+            .chain(depth_binding_view.as_ref())
+            .fold(BindGroupBuilder::new(bind_group_layout), |builder, view| builder.texture(view))
+            .create(device, Some("GBuffer"));
+
+        (textures, depth, bind_group)
+    }
+
+    // Recreate the attachments at the new size (e.g. on window resize), returns false when the size didn't change
+    pub fn resize(&mut self, device: &wgpu::Device, width: u32, height: u32) -> bool {
+        let size = (width.max(1), height.max(1));
+        if size == self.size {
+            return false;
+        }
+        self.size = size;
+        (self.textures, self.depth, self.bind_group) = Self::create_textures(device, &self.descriptor, &self.bind_group_layout, size);
+        true
+    }
+
+    pub fn size(&self) -> (u32, u32) { self.size }
+
+    pub fn descriptor(&self) -> &GBufferDescriptor { &self.descriptor }
+
+    pub fn attachment_index(&self, name: &str) -> Option<usize> { self.descriptor.attachments.iter().position(|attachment| attachment.name == name) }
+
+    // Panics if there is no attachment with this name
+    pub fn view(&self, name: &str) -> &wgpu::TextureView {
+        let index = self
+            .attachment_index(name)
+            .unwrap_or_else(|| panic!("Unknown G-buffer attachment \"{}\"", name));
+        &self.textures[index].view
+    }
+
+    pub fn texture(&self, name: &str) -> Option<&wgpu::Texture> { self.attachment_index(name).map(|index| &self.textures[index].texture) }
+
+    pub fn depth_view(&self) -> Option<&wgpu::TextureView> { self.depth.as_ref().map(|depth| &depth.view) }
+
+    // Color targets of the geometry pass pipelines, one per attachment without blending
+    pub fn color_targets(&self) -> Vec<Option<wgpu::ColorTargetState>> {
+        self.descriptor
+            .attachments
+            .iter()
+            .map(|attachment| {
+                Some(wgpu::ColorTargetState {
+                    format: attachment.format,
+                    blend: None,
+                    write_mask: wgpu::ColorWrites::ALL,
+                })
+            })
+            .collect()
+    }
+
+    // Depth state of the geometry pass pipelines, None without depth attachment
+    pub fn depth_stencil(&self) -> Option<wgpu::DepthStencilState> {
+        self.descriptor
+            .depth_format
+            .map(|format| DepthPreset::TestWrite.depth_stencil(format, self.descriptor.depth_convention))
+    }
+
+    pub fn color_attachments(&self) -> Vec<Option<wgpu::RenderPassColorAttachment<'_>>> {
+        self.textures
+            .iter()
+            .zip(&self.descriptor.attachments)
+            .map(|(texture, attachment)| {
+                Some(wgpu::RenderPassColorAttachment {
+                    view: &texture.view,
+                    resolve_target: None,
+                    ops: wgpu::Operations {
+                        load: wgpu::LoadOp::Clear(attachment.clear_color),
+                        store: wgpu::StoreOp::Store,
+                    },
+                })
+            })
+            .collect()
+    }
+
+    pub fn depth_attachment(&self) -> Option<wgpu::RenderPassDepthStencilAttachment<'_>> {
+        self.depth.as_ref().map(|depth| wgpu::RenderPassDepthStencilAttachment {
+            view: &depth.view,
+            depth_ops: Some(self.descriptor.depth_convention.clear_operations()),
+            stencil_ops: None,
+        })
+    }
+
+    // Geometry pass clearing all the attachments
+    pub fn begin_geometry_pass<'a>(&'a self, encoder: &'a mut wgpu::CommandEncoder) -> wgpu::RenderPass<'a> {
+        encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+            label: Some("GBuffer: geometry"),
+            color_attachments: &self.color_attachments(),
+            depth_stencil_attachment: self.depth_attachment(),
+            timestamp_writes: None,
+            occlusion_query_set: None,
+        })
+    }
+
+    // Layout of the lighting pass bind group, stable across resizes
+    pub fn bind_group_layout(&self) -> &BindGroupLayoutWithDesc { &self.bind_group_layout }
+
+    pub fn bind_group(&self) -> &wgpu::BindGroup { &self.bind_group }
+
+    // WGSL declarations of the bind group textures, named `gbuffer_<name>` and `gbuffer_depth`
+    pub fn wgsl_bindings(&self, group: u32) -> String {
+        self.descriptor
+            .attachments
+            .iter()
+            .map(|attachment| (attachment.name.as_str(), attachment.format))
+            .chain(self.descriptor.depth_format.map(|format| ("depth", format)))
+            .enumerate()
+            .map(|(binding, (name, format))| {
+                let texture_type = match Self::sample_type(format) {
+                    wgpu::TextureSampleType::Depth => "texture_depth_2d",
+                    wgpu::TextureSampleType::Sint => "texture_2d<i32>",
+                    wgpu::TextureSampleType::Uint => "texture_2d<u32>",
+                    wgpu::TextureSampleType::Float { .. } => "texture_2d<f32>",
+                };
+                format!("@group({}) @binding({}) var gbuffer_{}: {};\n", group, binding, name, texture_type)
+            })
+            .collect()
+    }
+}
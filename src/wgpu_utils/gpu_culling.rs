@@ -0,0 +1,227 @@
+use super::{
+    binding_builder::{BindGroupBuilder, BindGroupLayoutBuilder, BindGroupLayoutWithDesc},
+    memory_tracker::MemoryAllocation,
+    uniform_buffer::UniformBuffer,
+};
+
+// Object culled by `GpuCulling`: its transform and the bounding sphere of its mesh in object space
+#[repr(C)]
+#[derive(Clone, Copy, Default, Debug, bytemuck::Pod, bytemuck::Zeroable)]
+pub struct CullingObject {
+    pub transform: [[f32; 4]; 4],
+    pub bounds_center: [f32; 3],
+    pub bounds_radius: f32,
+}
+
+#[repr(C)]
+#[derive(Clone, Copy, Default, bytemuck::Pod, bytemuck::Zeroable)]
+struct CullingUniforms {
+    view_proj: [[f32; 4]; 4],
+    object_count: u32,
+    _padding: [u32; 3],
+}
+
+// Draw of the visible objects, all instances of the same mesh
+#[derive(Clone, Copy, Debug)]
+pub enum CullingDraw {
+    Indexed {
+        index_count: u32,
+        first_index: u32,
+        base_vertex: i32,
+    },
+    NonIndexed {
+        vertex_count: u32,
+        first_vertex: u32,
+    },
+}
+
+const CULLING_WGSL: &str = "struct CullingObject {
+    transform: mat4x4<f32>,
+    bounds_center: vec3<f32>,
+    bounds_radius: f32,
+}
+
+struct CullingUniforms {
+    view_proj: mat4x4<f32>,
+    object_count: u32,
+}
+
+@group(0) @binding(0) var<uniform> uniforms: CullingUniforms;
+@group(0) @binding(1) var<storage, read> objects: array<CullingObject>;
+@group(0) @binding(2) var<storage, read_write> visible_indices: array<u32>;
+// Indirect draw arguments, the instance count is the second value of both the indexed and non indexed layouts
+@group(0) @binding(3) var<storage, read_write> draw_args: array<atomic<u32>>;
+
+@compute @workgroup_size(64)
+fn main(@builtin(global_invocation_id) id: vec3<u32>) {
+    let index = id.x;
+    if index >= uniforms.object_count {
+        return;
+    }
+    let object = objects[index];
+    let center = (object.transform * vec4<f32>(object.bounds_center, 1.0)).xyz;
+    let scale = max(max(length(object.transform[0].xyz), length(object.transform[1].xyz)), length(object.transform[2].xyz));
+    let radius = object.bounds_radius * scale;
+
+    // Clip space planes (Gribb-Hartmann) with the 0 to 1 depth range of wgpu
+    let m = transpose(uniforms.view_proj);
+    var planes = array<vec4<f32>, 6>(m[3] + m[0], m[3] - m[0], m[3] + m[1], m[3] - m[1], m[2], m[3] - m[2]);
+    for (var i = 0u; i < 6u; i++) {
+        let plane = planes[i];
+        if dot(plane.xyz, center) + plane.w < -radius * length(plane.xyz) {
+            return;
+        }
+    }
+
+    // Compaction: visible objects take the next slot of the list
+    let slot = atomicAdd(&draw_args[1], 1u);
+    visible_indices[slot] = index;
+}
+";
+
+const WORKGROUP_SIZE: u32 = 64;
+
+// Frustum culling of objects bounding spheres on the GPU, outputs the compacted indices of the visible objects and the
+// indirect draw arguments drawing them as instances. The vertex shader fetches its object with
+// `visible_indices[instance_index]` (see `visible_indices_binding`).
+// Usage per frame: `update` then `encode` before the draw pass, `draw` in the pass.
+pub struct GpuCulling {
+    capacity: u32,
+    draw: CullingDraw,
+    uniforms: UniformBuffer<CullingUniforms>,
+    visible_indices: wgpu::Buffer,
+    draw_args: wgpu::Buffer,
+    bind_group_layout: BindGroupLayoutWithDesc,
+    pipeline: wgpu::ComputePipeline,
+    object_count: u32,
+    _memory: [MemoryAllocation; 2],
+}
+
+impl GpuCulling {
+    pub fn new(device: &wgpu::Device, capacity: u32, draw: CullingDraw) -> Self {
+        let visible_indices = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("GpuCulling: visible indices"),
+            size: (capacity.max(1) as usize * std::mem::size_of::<u32>()) as wgpu::BufferAddress,
+            usage: wgpu::BufferUsages::STORAGE,
+            mapped_at_creation: false,
+        });
+        let draw_args = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("GpuCulling: draw args"),
+            size: std::mem::size_of::<wgpu::util::DrawIndexedIndirectArgs>() as wgpu::BufferAddress,
+            usage: wgpu::BufferUsages::STORAGE | wgpu::BufferUsages::INDIRECT | wgpu::BufferUsages::COPY_DST | wgpu::BufferUsages::COPY_SRC,
+            mapped_at_creation: false,
+        });
+
+        let storage_binding = |read_only| wgpu::BindingType::Buffer {
+            ty: wgpu::BufferBindingType::Storage { read_only },
+            has_dynamic_offset: false,
+            min_binding_size: None,
+        };
+        let bind_group_layout = BindGroupLayoutBuilder::new()
+            .add_binding_compute(wgpu::BindingType::Buffer {
+                ty: wgpu::BufferBindingType::Uniform,
+                has_dynamic_offset: false,
+                min_binding_size: None,
+            })
+            .add_binding_compute(storage_binding(true))
+            .add_binding_compute(storage_binding(false))
+            .add_binding_compute(storage_binding(false))
+            .create(device, Some("GpuCulling"));
+        let shader_module = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: Some("GpuCulling"),
+            source: wgpu::ShaderSource::Wgsl(CULLING_WGSL.into()),
+        });
+        let pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("GpuCulling"),
+            bind_group_layouts: &[&bind_group_layout.layout],
+            push_constant_ranges: &[],
+        });
+        let pipeline = device.create_compute_pipeline(&wgpu::ComputePipelineDescriptor {
+            label: Some("GpuCulling"),
+            layout: Some(&pipeline_layout),
+            module: &shader_module,
+            entry_point: "main",
+        });
+
+        let memory = [
+            MemoryAllocation::for_buffer(&visible_indices, Some("GpuCulling: visible indices")),
+            MemoryAllocation::for_buffer(&draw_args, Some("GpuCulling: draw args")),
+        ];
+
+        Self {
+            capacity,
+            draw,
+            uniforms: UniformBuffer::new(device),
+            visible_indices,
+            draw_args,
+            bind_group_layout,
+            pipeline,
+            object_count: 0,
+            _memory: memory,
+        }
+    }
+
+    pub fn capacity(&self) -> u32 { self.capacity }
+
+    pub fn set_draw(&mut self, draw: CullingDraw) { self.draw = draw; }
+
+    // Camera matrix (projection * view) and number of objects to cull, at most the capacity
+    pub fn update(&mut self, queue: &wgpu::Queue, view_proj: [[f32; 4]; 4], object_count: u32) {
+        assert!(
+            object_count <= self.capacity,
+            "{} objects exceed the culling capacity of {}",
+            object_count,
+            self.capacity
+        );
+        self.object_count = object_count;
+        self.uniforms.update_content(
+            queue,
+            CullingUniforms {
+                view_proj,
+                object_count,
+                _padding: [0; 3],
+            },
+        );
+    }
+
+    // Reset the draw arguments and cull `objects` (a STORAGE buffer of `CullingObject`)
+    pub fn encode(&self, device: &wgpu::Device, queue: &wgpu::Queue, encoder: &mut wgpu::CommandEncoder, objects: &wgpu::Buffer) {
+        let draw_args: [u32; 5] = match self.draw {
+            CullingDraw::Indexed { index_count, first_index, base_vertex } => [index_count, 0, first_index, base_vertex as u32, 0],
+            CullingDraw::NonIndexed { vertex_count, first_vertex } => [vertex_count, 0, first_vertex, 0, 0],
+        };
+        queue.write_buffer(&self.draw_args, 0, bytemuck::cast_slice(&draw_args));
+        if self.object_count == 0 {
+            return;
+        }
+
+        let bind_group = BindGroupBuilder::new(&self.bind_group_layout)
+            .resource(self.uniforms.binding_resource())
+            .resource(objects.as_entire_binding())
+            .resource(self.visible_indices.as_entire_binding())
+            .resource(self.draw_args.as_entire_binding())
+            .create(device, Some("GpuCulling"));
+        let mut compute_pass = encoder.begin_compute_pass(&wgpu::ComputePassDescriptor {
+            label: Some("GpuCulling"),
+            timestamp_writes: None,
+        });
+        compute_pass.set_pipeline(&self.pipeline);
+        compute_pass.set_bind_group(0, &bind_group, &[]);
+        compute_pass.dispatch_workgroups(self.object_count.div_ceil(WORKGROUP_SIZE), 1, 1);
+    }
+
+    pub fn visible_indices(&self) -> &wgpu::Buffer { &self.visible_indices }
+
+    pub fn visible_indices_binding(&self) -> wgpu::BindingResource<'_> { self.visible_indices.as_entire_binding() }
+
+    // Indexed or non indexed indirect arguments depending on the `CullingDraw`
+    pub fn draw_args(&self) -> &wgpu::Buffer { &self.draw_args }
+
+    // Draw the visible instances, the index buffer must be set for indexed draws
+    pub fn draw<'a>(&'a self, render_pass: &mut wgpu::RenderPass<'a>) {
+        match self.draw {
+            CullingDraw::Indexed { .. } => render_pass.draw_indexed_indirect(&self.draw_args, 0),
+            CullingDraw::NonIndexed { .. } => render_pass.draw_indirect(&self.draw_args, 0),
+        }
+    }
+}
@@ -0,0 +1,342 @@
+use std::{cell::RefCell, collections::HashMap};
+
+use super::{
+    buffers::{map_async, MapFuture},
+    child_label,
+    memory_tracker::MemoryAllocation,
+};
+
+const QUERY_SIZE: wgpu::BufferAddress = std::mem::size_of::<u64>() as wgpu::BufferAddress;
+
+// Scope of a frame, `path` is the labels of its parent scopes and its own joined with '/'
+struct ScopeRecord {
+    path: String,
+    begin_query: u32,
+    end_query: Option<u32>,
+}
+
+enum ReadbackSlotState {
+    Free,
+    Copied(Vec<ScopeRecord>),
+    Mapping(Vec<ScopeRecord>, MapFuture),
+}
+
+struct ReadbackSlot {
+    buffer: wgpu::Buffer,
+    state: ReadbackSlotState,
+    _memory: MemoryAllocation,
+}
+
+// Duration of the scopes of a label path across the frames, in milliseconds. The scopes of the same path in a frame are
+// summed.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct GpuTimingStats {
+    pub last_ms: f64,
+    // Exponential moving average, smoothing out the noise of the last frames
+    pub average_ms: f64,
+    pub min_ms: f64,
+    pub max_ms: f64,
+    // Frames the path was timed in
+    pub frame_count: u64,
+}
+
+impl GpuTimingStats {
+    const AVERAGE_WEIGHT: f64 = 0.1;
+
+    fn add(&mut self, duration_ms: f64) {
+        if self.frame_count == 0 {
+            *self = Self {
+                last_ms: duration_ms,
+                average_ms: duration_ms,
+                min_ms: duration_ms,
+                max_ms: duration_ms,
+                frame_count: 1,
+            };
+            return;
+        }
+        self.last_ms = duration_ms;
+        self.average_ms += (duration_ms - self.average_ms) * Self::AVERAGE_WEIGHT;
+        self.min_ms = self.min_ms.min(duration_ms);
+        self.max_ms = self.max_ms.max(duration_ms);
+        self.frame_count += 1;
+    }
+}
+
+// Timed scope with the scopes nested in it, see `GpuTimer::tree`
+#[derive(Clone, Debug)]
+pub struct GpuTimingNode {
+    pub label: String,
+    pub path: String,
+    pub stats: GpuTimingStats,
+    pub children: Vec<GpuTimingNode>,
+}
+
+// Timestamps written around a part of a command encoder, the end timestamp is written when dropped. Derefs to the
+// encoder, for the passes of the scope and the nested scopes (`timer.scope(&mut scope, "child")`).
+pub struct GpuScope<'a> {
+    timer: &'a GpuTimer,
+    encoder: &'a mut wgpu::CommandEncoder,
+    record_index: Option<usize>,
+}
+
+impl std::ops::Deref for GpuScope<'_> {
+    type Target = wgpu::CommandEncoder;
+
+    fn deref(&self) -> &Self::Target { self.encoder }
+}
+
+impl std::ops::DerefMut for GpuScope<'_> {
+    fn deref_mut(&mut self) -> &mut Self::Target { self.encoder }
+}
+
+impl Drop for GpuScope<'_> {
+    fn drop(&mut self) {
+        if let Some(record_index) = self.record_index {
+            self.timer.end_scope(self.encoder, record_index);
+        }
+    }
+}
+
+// GPU time of labeled scopes of the command encoders, aggregated per label path across frames and read back a frame or
+// two later. Requires the TIMESTAMP_QUERY feature, the scopes do nothing otherwise.
+// Usage per frame: `scope` guards while recording, `encode_resolve` at the end of the last encoder, `after_submit` once it
+// is submitted then `poll` to update the timings.
+pub struct GpuTimer {
+    // None when the device doesn't support the timestamp queries
+    query_set: Option<wgpu::QuerySet>,
+    capacity: u32,
+    resolve_buffer: Option<wgpu::Buffer>,
+    timestamp_period: f32,
+    // Scopes of the frame and the indices of the open ones. RefCells as the scopes borrow the timer.
+    frame_records: RefCell<Vec<ScopeRecord>>,
+    open_scopes: RefCell<Vec<usize>>,
+    slots: Vec<ReadbackSlot>,
+    stats: HashMap<String, GpuTimingStats>,
+    // Paths in the order they were first timed, the order of the tree
+    paths: Vec<String>,
+    label: String,
+    _memory: Option<MemoryAllocation>,
+}
+
+impl GpuTimer {
+    const SLOT_COUNT: usize = 3;
+
+    pub fn required_features() -> wgpu::Features { wgpu::Features::TIMESTAMP_QUERY }
+
+    // `max_scopes` is the maximum number of scopes per frame, the next ones are not timed
+    pub fn new(device: &wgpu::Device, queue: &wgpu::Queue, max_scopes: u32, label: Option<&str>) -> Self {
+        let label = format!("GpuTimer: {}", label.unwrap_or("unknown"));
+        let resolve_label = child_label(Some(&label), "resolve");
+        let capacity = max_scopes.max(1) * 2;
+        let supported = device.features().contains(Self::required_features());
+        let query_set = supported.then(|| {
+            device.create_query_set(&wgpu::QuerySetDescriptor {
+                label: Some(&label),
+                ty: wgpu::QueryType::Timestamp,
+                count: capacity,
+            })
+        });
+        let resolve_buffer = supported.then(|| {
+            device.create_buffer(&wgpu::BufferDescriptor {
+                label: Some(&resolve_label),
+                size: capacity as wgpu::BufferAddress * QUERY_SIZE,
+                usage: wgpu::BufferUsages::QUERY_RESOLVE | wgpu::BufferUsages::COPY_SRC,
+                mapped_at_creation: false,
+            })
+        });
+        let memory = resolve_buffer
+            .as_ref()
+            .map(|buffer| MemoryAllocation::for_buffer(buffer, Some(&resolve_label)));
+
+        Self {
+            query_set,
+            capacity,
+            resolve_buffer,
+            timestamp_period: queue.get_timestamp_period(),
+            frame_records: RefCell::new(Vec::new()),
+            open_scopes: RefCell::new(Vec::new()),
+            slots: Vec::new(),
+            stats: HashMap::new(),
+            paths: Vec::new(),
+            label,
+            _memory: memory,
+        }
+    }
+
+    pub fn is_supported(&self) -> bool { self.query_set.is_some() }
+
+    // Time the commands recorded through the returned scope until it is dropped. The label is nested in the label of the
+    // open scopes of the frame.
+    pub fn scope<'a>(&'a self, encoder: &'a mut wgpu::CommandEncoder, label: &str) -> GpuScope<'a> {
+        let record_index = self.begin_scope(encoder, label);
+        GpuScope { timer: self, encoder, record_index }
+    }
+
+    pub(crate) fn begin_scope(&self, encoder: &mut wgpu::CommandEncoder, label: &str) -> Option<usize> {
+        let query_set = self.query_set.as_ref()?;
+        let mut frame_records = self.frame_records.borrow_mut();
+        let query_count = frame_records.len() as u32 * 2;
+        if query_count + 2 > self.capacity {
+            return None;
+        }
+        let mut open_scopes = self.open_scopes.borrow_mut();
+        let path = match open_scopes.last() {
+            Some(&parent_index) => format!("{}/{}", frame_records[parent_index].path, label),
+            None => label.to_string(),
+        };
+        encoder.write_timestamp(query_set, query_count);
+        frame_records.push(ScopeRecord {
+            path,
+            begin_query: query_count,
+            end_query: None,
+        });
+        open_scopes.push(frame_records.len() - 1);
+        Some(frame_records.len() - 1)
+    }
+
+    pub(crate) fn end_scope(&self, encoder: &mut wgpu::CommandEncoder, record_index: usize) {
+        let mut frame_records = self.frame_records.borrow_mut();
+        let (Some(query_set), Some(record)) = (&self.query_set, frame_records.get_mut(record_index)) else {
+            return;
+        };
+        let end_query = record.begin_query + 1;
+        encoder.write_timestamp(query_set, end_query);
+        record.end_query = Some(end_query);
+        self.open_scopes.borrow_mut().retain(|&index| index != record_index);
+    }
+
+    // Resolve the closed scopes of the frame and copy them for the readback. When all the readbacks are in use the
+    // timings of the frame are dropped.
+    pub fn encode_resolve(&mut self, device: &wgpu::Device, encoder: &mut wgpu::CommandEncoder) {
+        let (Some(query_set), Some(resolve_buffer)) = (&self.query_set, &self.resolve_buffer) else {
+            return;
+        };
+        // Scopes left open are not timed
+        self.open_scopes.get_mut().clear();
+        let records: Vec<ScopeRecord> = std::mem::take(self.frame_records.get_mut());
+        let query_count = records.len() as u32 * 2;
+        let records: Vec<ScopeRecord> = records.into_iter().filter(|record| record.end_query.is_some()).collect();
+        if records.is_empty() {
+            return;
+        }
+        let slot_index = match self.slots.iter().position(|slot| matches!(slot.state, ReadbackSlotState::Free)) {
+            Some(slot_index) => slot_index,
+            None if self.slots.len() < Self::SLOT_COUNT => {
+                let readback_label = child_label(Some(&self.label), &format!("readback {}", self.slots.len()));
+                let buffer = device.create_buffer(&wgpu::BufferDescriptor {
+                    label: Some(&readback_label),
+                    size: self.capacity as wgpu::BufferAddress * QUERY_SIZE,
+                    usage: wgpu::BufferUsages::COPY_DST | wgpu::BufferUsages::MAP_READ,
+                    mapped_at_creation: false,
+                });
+                let memory = MemoryAllocation::for_buffer(&buffer, Some(&readback_label));
+                self.slots.push(ReadbackSlot {
+                    buffer,
+                    state: ReadbackSlotState::Free,
+                    _memory: memory,
+                });
+                self.slots.len() - 1
+            },
+            None => return,
+        };
+
+        encoder.resolve_query_set(query_set, 0..query_count, resolve_buffer, 0);
+        let slot = &mut self.slots[slot_index];
+        encoder.copy_buffer_to_buffer(resolve_buffer, 0, &slot.buffer, 0, query_count as wgpu::BufferAddress * QUERY_SIZE);
+        slot.state = ReadbackSlotState::Copied(records);
+    }
+
+    pub fn after_submit(&mut self) {
+        for slot in &mut self.slots {
+            slot.state = match std::mem::replace(&mut slot.state, ReadbackSlotState::Free) {
+                ReadbackSlotState::Copied(records) => ReadbackSlotState::Mapping(records, map_async(slot.buffer.slice(..), wgpu::MapMode::Read)),
+                state => state,
+            };
+        }
+    }
+
+    // Update the timings with the resolved readbacks, without blocking. Returns true if timings were updated.
+    pub fn poll(&mut self, device: &wgpu::Device) -> bool {
+        if !self.slots.iter().any(|slot| matches!(slot.state, ReadbackSlotState::Mapping(..))) {
+            return false;
+        }
+        device.poll(wgpu::Maintain::Poll);
+
+        let mut updated = false;
+        for slot in &mut self.slots {
+            let ReadbackSlotState::Mapping(_, map_future) = &slot.state else {
+                continue;
+            };
+            let Some(map_result) = map_future.try_take_result() else {
+                continue;
+            };
+            let ReadbackSlotState::Mapping(records, _) = std::mem::replace(&mut slot.state, ReadbackSlotState::Free) else {
+                unreachable!()
+            };
+            if map_result.is_err() {
+                continue;
+            }
+
+            let timestamps: Vec<u64> = bytemuck::pod_collect_to_vec(&slot.buffer.slice(..).get_mapped_range());
+            slot.buffer.unmap();
+            let mut frame_durations: Vec<(String, f64)> = Vec::new();
+            for record in records {
+                let (begin, end) = (timestamps[record.begin_query as usize], timestamps[record.end_query.unwrap() as usize]);
+                let duration_ms = end.saturating_sub(begin) as f64 * self.timestamp_period as f64 / 1_000_000.0;
+                match frame_durations.iter_mut().find(|(path, _)| *path == record.path) {
+                    Some((_, total_ms)) => *total_ms += duration_ms,
+                    None => frame_durations.push((record.path, duration_ms)),
+                }
+            }
+            for (path, duration_ms) in frame_durations {
+                if !self.stats.contains_key(&path) {
+                    self.paths.push(path.clone());
+                }
+                self.stats.entry(path).or_default().add(duration_ms);
+            }
+            updated = true;
+        }
+        updated
+    }
+
+    // Timings of a label path (e.g. "app/shadows")
+    pub fn stats(&self, path: &str) -> Option<&GpuTimingStats> { self.stats.get(path) }
+
+    // Timings of the label paths, in the order they were first timed
+    pub fn timings(&self) -> impl Iterator<Item = (&str, &GpuTimingStats)> { self.paths.iter().map(|path| (path.as_str(), &self.stats[path])) }
+
+    // Timings as the tree of the nested scopes
+    pub fn tree(&self) -> Vec<GpuTimingNode> {
+        let mut roots: Vec<GpuTimingNode> = Vec::new();
+        for path in &self.paths {
+            let mut siblings = &mut roots;
+            let mut node_path = String::new();
+            for label in path.split('/') {
+                if !node_path.is_empty() {
+                    node_path.push('/');
+                }
+                node_path.push_str(label);
+                let index = match siblings.iter().position(|node| node.label == label) {
+                    Some(index) => index,
+                    None => {
+                        siblings.push(GpuTimingNode {
+                            label: label.to_string(),
+                            path: node_path.clone(),
+                            stats: self.stats.get(&node_path).copied().unwrap_or_default(),
+                            children: Vec::new(),
+                        });
+                        siblings.len() - 1
+                    },
+                };
+                siblings = &mut siblings[index].children;
+            }
+        }
+        roots
+    }
+
+    // Forget the timings, e.g. after changing the scene
+    pub fn clear_timings(&mut self) {
+        self.stats.clear();
+        self.paths.clear();
+    }
+}
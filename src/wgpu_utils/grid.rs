@@ -0,0 +1,305 @@
+use super::{
+    binding_builder::{BindGroupBuilder, BindGroupLayoutBuilder},
+    uniform_buffer::UniformBuffer,
+    BlendPreset,
+    DepthConvention,
+    DepthPreset,
+};
+
+const GRID_WGSL: &str = r#"
+struct GridUniforms {
+    view_proj: mat4x4<f32>,
+    inverse_view_proj: mat4x4<f32>,
+    camera_position: vec3<f32>,
+    minor_spacing: f32,
+    minor_color: vec4<f32>,
+    major_color: vec4<f32>,
+    major_spacing: f32,
+    fade_distance: f32,
+    axes_length: f32,
+    // 0, or 1 for the reversed depth
+    near_depth: f32,
+}
+
+@group(0) @binding(0) var<uniform> grid: GridUniforms;
+
+struct GridVertexOutput {
+    @builtin(position) position: vec4<f32>,
+    @location(0) ndc: vec2<f32>,
+}
+
+struct GridFragmentOutput {
+    @location(0) color: vec4<f32>,
+    @builtin(frag_depth) depth: f32,
+}
+
+@vertex
+fn vs_grid(@builtin(vertex_index) vertex_index: u32) -> GridVertexOutput {
+    let uv = vec2<f32>(f32((vertex_index << 1u) & 2u), f32(vertex_index & 2u));
+    var output: GridVertexOutput;
+    output.ndc = vec2<f32>(uv.x * 2.0 - 1.0, 1.0 - uv.y * 2.0);
+    output.position = vec4<f32>(output.ndc, 0.0, 1.0);
+    return output;
+}
+
+fn unproject(ndc: vec2<f32>, depth: f32) -> vec3<f32> {
+    let position = grid.inverse_view_proj * vec4<f32>(ndc, depth, 1.0);
+    return position.xyz / position.w;
+}
+
+// 1 on the lines of the grid of this spacing, anti-aliased over a pixel
+fn grid_lines(position: vec2<f32>, spacing: f32) -> f32 {
+    let coordinates = position / spacing;
+    let distance = abs(fract(coordinates - 0.5) - 0.5) / max(fwidth(coordinates), vec2<f32>(1e-6));
+    return 1.0 - min(min(distance.x, distance.y), 1.0);
+}
+
+@fragment
+fn fs_grid(in: GridVertexOutput) -> GridFragmentOutput {
+    // Intersection of the ray of the pixel with the y = 0 plane in front of the near plane. The ray goes through a point
+    // halfway in depth rather than the far plane, which is at infinity for infinite projections.
+    let near = unproject(in.ndc, grid.near_depth);
+    let direction = unproject(in.ndc, 0.5) - near;
+    let t = -near.y / direction.y;
+    if !(t > 0.0) {
+        discard;
+    }
+    let position = near + t * direction;
+
+    let minor = grid_lines(position.xz, grid.minor_spacing);
+    let major = grid_lines(position.xz, grid.major_spacing);
+    var color = mix(vec4<f32>(grid.minor_color.rgb, grid.minor_color.a * minor), grid.major_color, major);
+    let fade = 1.0 - clamp(distance(position.xz, grid.camera_position.xz) / grid.fade_distance, 0.0, 1.0);
+    color.a *= fade * fade;
+    if color.a <= 0.0 {
+        discard;
+    }
+
+    // Beyond the far plane
+    let clip_position = grid.view_proj * vec4<f32>(position, 1.0);
+    let depth = clip_position.z / clip_position.w;
+    if !(depth >= 0.0 && depth <= 1.0) {
+        discard;
+    }
+    var output: GridFragmentOutput;
+    output.color = color;
+    output.depth = depth;
+    return output;
+}
+
+struct AxesVertexOutput {
+    @builtin(position) position: vec4<f32>,
+    @location(0) color: vec4<f32>,
+}
+
+// Line list of the X (red), Y (green) and Z (blue) axes from the origin
+@vertex
+fn vs_axes(@builtin(vertex_index) vertex_index: u32) -> AxesVertexOutput {
+    let axis = vertex_index / 2u;
+    let direction = vec3<f32>(f32(axis == 0u), f32(axis == 1u), f32(axis == 2u));
+    let position = direction * f32(vertex_index % 2u) * grid.axes_length;
+    var output: AxesVertexOutput;
+    output.position = grid.view_proj * vec4<f32>(position, 1.0);
+    output.color = vec4<f32>(direction * 0.85 + 0.1, 1.0);
+    return output;
+}
+
+@fragment
+fn fs_axes(in: AxesVertexOutput) -> @location(0) vec4<f32> {
+    return in.color;
+}
+"#;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct GridSettings {
+    pub show_grid: bool,
+    pub show_axes: bool,
+    // World distance between the lines
+    pub minor_spacing: f32,
+    pub major_spacing: f32,
+    // Linear RGBA, not premultiplied
+    pub minor_color: [f32; 4],
+    pub major_color: [f32; 4],
+    // Distance from the camera at which the grid has faded out
+    pub fade_distance: f32,
+    pub axes_length: f32,
+}
+
+impl Default for GridSettings {
+    fn default() -> Self {
+        Self {
+            show_grid: true,
+            show_axes: true,
+            minor_spacing: 1.0,
+            major_spacing: 10.0,
+            minor_color: [0.5, 0.5, 0.5, 0.35],
+            major_color: [0.75, 0.75, 0.75, 0.7],
+            fade_distance: 100.0,
+            axes_length: 1.0,
+        }
+    }
+}
+
+#[repr(C)]
+#[derive(Clone, Copy, Default, Debug, PartialEq, bytemuck::Pod, bytemuck::Zeroable)]
+struct GridUniforms {
+    view_proj: [[f32; 4]; 4],
+    inverse_view_proj: [[f32; 4]; 4],
+    camera_position: [f32; 3],
+    minor_spacing: f32,
+    minor_color: [f32; 4],
+    major_color: [f32; 4],
+    major_spacing: f32,
+    fade_distance: f32,
+    axes_length: f32,
+    near_depth: f32,
+}
+
+// Infinite ground grid on the y = 0 plane, with minor and major lines faded with the distance to the camera, and the
+// axes of the origin, drawn over a target after the scene (alpha blended, depth tested against the scene with a depth
+// target). The grid and the axes are shown according to the settings, which can be toggled each frame.
+pub struct InfiniteGrid {
+    settings: GridSettings,
+    uniforms: GridUniforms,
+    uniform_buffer: UniformBuffer<GridUniforms>,
+    bind_group: wgpu::BindGroup,
+    grid_pipeline: wgpu::RenderPipeline,
+    axes_pipeline: wgpu::RenderPipeline,
+}
+
+impl InfiniteGrid {
+    // With a `depth_format`, `encode` requires the depth target of the scene, with values of the `depth_convention` of the
+    // camera projection
+    pub fn new(
+        device: &wgpu::Device,
+        target_format: wgpu::TextureFormat,
+        depth_format: Option<wgpu::TextureFormat>,
+        depth_convention: DepthConvention,
+        settings: GridSettings,
+    ) -> Self {
+        let bind_group_layout = BindGroupLayoutBuilder::new()
+            .add_binding_rendering(wgpu::BindingType::Buffer {
+                ty: wgpu::BufferBindingType::Uniform,
+                has_dynamic_offset: false,
+                min_binding_size: None,
+            })
+            .create(device, Some("InfiniteGrid"));
+
+        let mut uniforms = GridUniforms {
+            near_depth: depth_convention.near_depth(),
+            ..Default::default()
+        };
+        Self::apply_settings(&mut uniforms, &settings);
+        let uniform_buffer = UniformBuffer::new_with_data(device, &uniforms);
+        let bind_group = BindGroupBuilder::new(&bind_group_layout)
+            .resource(uniform_buffer.binding_resource())
+            .create(device, Some("InfiniteGrid"));
+
+        let shader_module = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: Some("InfiniteGrid"),
+            source: wgpu::ShaderSource::Wgsl(GRID_WGSL.into()),
+        });
+        let pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("InfiniteGrid"),
+            bind_group_layouts: &[&bind_group_layout.layout],
+            push_constant_ranges: &[],
+        });
+        // Tested but not written, the grid and the axes are overlays
+        let depth_stencil = depth_format.map(|format| DepthPreset::TestOnly.depth_stencil(format, depth_convention));
+        let create_pipeline = |label: &str, vertex_entry_point: &str, fragment_entry_point: &str, topology: wgpu::PrimitiveTopology| {
+            device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+                label: Some(label),
+                layout: Some(&pipeline_layout),
+                vertex: wgpu::VertexState {
+                    module: &shader_module,
+                    entry_point: vertex_entry_point,
+                    buffers: &[],
+                },
+                fragment: Some(wgpu::FragmentState {
+                    module: &shader_module,
+                    entry_point: fragment_entry_point,
+                    targets: &[Some(BlendPreset::AlphaBlend.color_target(target_format))],
+                }),
+                primitive: wgpu::PrimitiveState { topology, ..Default::default() },
+                depth_stencil: depth_stencil.clone(),
+                multisample: wgpu::MultisampleState::default(),
+                multiview: None,
+            })
+        };
+        let grid_pipeline = create_pipeline("InfiniteGrid: grid", "vs_grid", "fs_grid", wgpu::PrimitiveTopology::TriangleList);
+        let axes_pipeline = create_pipeline("InfiniteGrid: axes", "vs_axes", "fs_axes", wgpu::PrimitiveTopology::LineList);
+
+        Self {
+            settings,
+            uniforms,
+            uniform_buffer,
+            bind_group,
+            grid_pipeline,
+            axes_pipeline,
+        }
+    }
+
+    fn apply_settings(uniforms: &mut GridUniforms, settings: &GridSettings) {
+        uniforms.minor_spacing = settings.minor_spacing.max(f32::EPSILON);
+        uniforms.major_spacing = settings.major_spacing.max(f32::EPSILON);
+        uniforms.minor_color = settings.minor_color;
+        uniforms.major_color = settings.major_color;
+        uniforms.fade_distance = settings.fade_distance.max(f32::EPSILON);
+        uniforms.axes_length = settings.axes_length;
+    }
+
+    pub fn settings(&self) -> &GridSettings { &self.settings }
+
+    pub fn set_settings(&mut self, queue: &wgpu::Queue, settings: GridSettings) {
+        self.settings = settings;
+        Self::apply_settings(&mut self.uniforms, &settings);
+        self.uniform_buffer.update_content(queue, self.uniforms);
+    }
+
+    // Camera of the frame, with the projection * view matrix of the scene and its inverse
+    pub fn update_camera(&mut self, queue: &wgpu::Queue, view_proj: [[f32; 4]; 4], inverse_view_proj: [[f32; 4]; 4], camera_position: [f32; 3]) {
+        self.uniforms.view_proj = view_proj;
+        self.uniforms.inverse_view_proj = inverse_view_proj;
+        self.uniforms.camera_position = camera_position;
+        self.uniform_buffer.update_content(queue, self.uniforms);
+    }
+
+    // Draw the grid and the axes shown by the settings over `target_view`, tested against `depth_view` when created with
+    // a depth format
+    pub fn encode(&self, encoder: &mut wgpu::CommandEncoder, target_view: &wgpu::TextureView, depth_view: Option<&wgpu::TextureView>) {
+        if !self.settings.show_grid && !self.settings.show_axes {
+            return;
+        }
+        let mut render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+            label: Some("InfiniteGrid"),
+            color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+                view: target_view,
+                resolve_target: None,
+                ops: wgpu::Operations {
+                    load: wgpu::LoadOp::Load,
+                    store: wgpu::StoreOp::Store,
+                },
+            })],
+            depth_stencil_attachment: depth_view.map(|view| wgpu::RenderPassDepthStencilAttachment {
+                view,
+                depth_ops: Some(wgpu::Operations {
+                    load: wgpu::LoadOp::Load,
+                    store: wgpu::StoreOp::Store,
+                }),
+                stencil_ops: None,
+            }),
+            timestamp_writes: None,
+            occlusion_query_set: None,
+        });
+        render_pass.set_bind_group(0, &self.bind_group, &[]);
+        if self.settings.show_grid {
+            render_pass.set_pipeline(&self.grid_pipeline);
+            render_pass.draw(0..3, 0..1);
+        }
+        if self.settings.show_axes {
+            render_pass.set_pipeline(&self.axes_pipeline);
+            render_pass.draw(0..6, 0..1);
+        }
+    }
+}
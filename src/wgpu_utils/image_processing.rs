@@ -0,0 +1,385 @@
+use super::{
+    binding_builder::{BindGroupBuilder, BindGroupLayoutBuilder, BindGroupLayoutWithDesc},
+    binding_glsl,
+    memory_tracker::MemoryAllocation,
+};
+
+const WORKGROUP_SIZE: u32 = 8;
+
+// WGSL name of the formats usable as targets of the image passes: storage formats that are also filterable,
+// as the targets are read back by the next passes
+fn storage_format_wgsl(format: wgpu::TextureFormat) -> &'static str {
+    match format {
+        wgpu::TextureFormat::Rgba8Unorm => "rgba8unorm",
+        wgpu::TextureFormat::Rgba8Snorm => "rgba8snorm",
+        wgpu::TextureFormat::Rgba16Float => "rgba16float",
+        format => panic!(
+            "{:?} can't be written by the image processing passes, use Rgba16Float, Rgba8Unorm or Rgba8Snorm",
+            format
+        ),
+    }
+}
+
+// Compute pipeline reading a filterable float texture (with a linear clamped sampler) and writing a storage texture,
+// one invocation per target texel. `body` has `pixel: vec2<i32>` and `target_size: vec2<u32>` in scope and the helpers
+// `load_clamped(coordinates)` and `source_texel_size()`.
+struct ImagePass {
+    label: String,
+    bind_group_layout: BindGroupLayoutWithDesc,
+    pipeline: wgpu::ComputePipeline,
+}
+
+impl ImagePass {
+    fn new(device: &wgpu::Device, format: wgpu::TextureFormat, label: &str, body: &str) -> Self {
+        let bind_group_layout = BindGroupLayoutBuilder::new()
+            .add_binding_compute(binding_glsl::texture2D())
+            .add_binding_compute(binding_glsl::sampler(wgpu::SamplerBindingType::Filtering))
+            .add_binding_compute(binding_glsl::image2D(format, wgpu::StorageTextureAccess::WriteOnly))
+            .create(device, Some(label));
+        let shader_module = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: Some(label),
+            source: wgpu::ShaderSource::Wgsl(
+                format!(
+                    "@group(0) @binding(0) var source: texture_2d<f32>;
+@group(0) @binding(1) var source_sampler: sampler;
+@group(0) @binding(2) var destination: texture_storage_2d<{format}, write>;
+
+fn load_clamped(coordinates: vec2<i32>) -> vec4<f32> {{
+    let size = vec2<i32>(textureDimensions(source));
+    return textureLoad(source, clamp(coordinates, vec2<i32>(0), size - 1), 0);
+}}
+
+fn source_texel_size() -> vec2<f32> {{
+    return 1.0 / vec2<f32>(textureDimensions(source));
+}}
+
+@compute @workgroup_size({workgroup_size}, {workgroup_size})
+fn main(@builtin(global_invocation_id) id: vec3<u32>) {{
+    let target_size = textureDimensions(destination);
+    if any(id.xy >= target_size) {{
+        return;
+    }}
+    let pixel = vec2<i32>(id.xy);
+{body}
+}}",
+                    format = storage_format_wgsl(format),
+                    workgroup_size = WORKGROUP_SIZE,
+                    body = body,
+                )
+                .into(),
+            ),
+        });
+        let pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some(label),
+            bind_group_layouts: &[&bind_group_layout.layout],
+            push_constant_ranges: &[],
+        });
+        let pipeline = device.create_compute_pipeline(&wgpu::ComputePipelineDescriptor {
+            label: Some(label),
+            layout: Some(&pipeline_layout),
+            module: &shader_module,
+            entry_point: "main",
+        });
+
+        Self {
+            label: label.to_owned(),
+            bind_group_layout,
+            pipeline,
+        }
+    }
+
+    fn encode(
+        &self,
+        device: &wgpu::Device,
+        encoder: &mut wgpu::CommandEncoder,
+        sampler: &wgpu::Sampler,
+        source: &wgpu::TextureView,
+        target: &wgpu::TextureView,
+        target_size: (u32, u32),
+    ) {
+        let bind_group = BindGroupBuilder::new(&self.bind_group_layout)
+            .texture(source)
+            .sampler(sampler)
+            .texture(target)
+            .create(device, Some(&self.label));
+        let mut compute_pass = encoder.begin_compute_pass(&wgpu::ComputePassDescriptor {
+            label: Some(&self.label),
+            timestamp_writes: None,
+        });
+        compute_pass.set_pipeline(&self.pipeline);
+        compute_pass.set_bind_group(0, &bind_group, &[]);
+        compute_pass.dispatch_workgroups(target_size.0.div_ceil(WORKGROUP_SIZE), target_size.1.div_ceil(WORKGROUP_SIZE), 1);
+    }
+}
+
+fn create_sampler(device: &wgpu::Device, label: &str) -> wgpu::Sampler {
+    device.create_sampler(&wgpu::SamplerDescriptor {
+        label: Some(label),
+        mag_filter: wgpu::FilterMode::Linear,
+        min_filter: wgpu::FilterMode::Linear,
+        ..Default::default()
+    })
+}
+
+// Horizontal then vertical pass of a symmetric kernel through an intermediate texture, recreated when the size changes
+struct SeparableFilter {
+    format: wgpu::TextureFormat,
+    horizontal: ImagePass,
+    vertical: ImagePass,
+    sampler: wgpu::Sampler,
+    intermediate: Option<(wgpu::Texture, wgpu::TextureView, MemoryAllocation)>,
+}
+
+impl SeparableFilter {
+    // `weights[i]` is the weight of the texels at distance i
+    fn new(device: &wgpu::Device, format: wgpu::TextureFormat, label: &str, weights: &[f32]) -> Self {
+        let body = |direction: &str| {
+            let taps: String = (1..weights.len())
+                .map(|offset| {
+                    format!(
+                        "    sum += {weight:?} * (load_clamped(pixel + {offset} * {direction}) + load_clamped(pixel - {offset} * {direction}));\n",
+                        weight = weights[offset],
+                        offset = offset,
+                        direction = direction,
+                    )
+                })
+                .collect();
+            format!(
+                "    var sum = {:?} * load_clamped(pixel);\n{}    textureStore(destination, pixel, sum);",
+                weights[0], taps
+            )
+        };
+
+        Self {
+            format,
+            horizontal: ImagePass::new(device, format, &format!("{}: horizontal", label), &body("vec2<i32>(1, 0)")),
+            vertical: ImagePass::new(device, format, &format!("{}: vertical", label), &body("vec2<i32>(0, 1)")),
+            sampler: create_sampler(device, label),
+            intermediate: None,
+        }
+    }
+
+    fn encode(
+        &mut self,
+        device: &wgpu::Device,
+        encoder: &mut wgpu::CommandEncoder,
+        source: &wgpu::TextureView,
+        target: &wgpu::TextureView,
+        size: (u32, u32),
+    ) {
+        let up_to_date = self
+            .intermediate
+            .as_ref()
+            .is_some_and(|(texture, _, _)| (texture.width(), texture.height()) == size);
+        if !up_to_date {
+            let texture = device.create_texture(&wgpu::TextureDescriptor {
+                label: Some("SeparableFilter: intermediate"),
+                size: wgpu::Extent3d {
+                    width: size.0,
+                    height: size.1,
+                    depth_or_array_layers: 1,
+                },
+                mip_level_count: 1,
+                sample_count: 1,
+                dimension: wgpu::TextureDimension::D2,
+                format: self.format,
+                usage: wgpu::TextureUsages::STORAGE_BINDING | wgpu::TextureUsages::TEXTURE_BINDING,
+                view_formats: &[],
+            });
+            let view = texture.create_view(&wgpu::TextureViewDescriptor {
+                label: Some("SeparableFilter: intermediate view"),
+                ..Default::default()
+            });
+            let memory = MemoryAllocation::for_texture(&texture, Some("SeparableFilter: intermediate"));
+            self.intermediate = Some((texture, view, memory));
+        }
+        let (_, intermediate_view, _) = self.intermediate.as_ref().expect("Intermediate texture created above");
+
+        self.horizontal.encode(device, encoder, &self.sampler, source, intermediate_view, size);
+        self.vertical.encode(device, encoder, &self.sampler, intermediate_view, target, size);
+    }
+}
+
+// Separable Gaussian blur of a texture into another one of the same size (texels outside are clamped to the border).
+// The target view and the internal intermediate texture use `format`, Rgba16Float, Rgba8Unorm or Rgba8Snorm,
+// the source is any filterable float texture.
+pub struct GaussianBlur {
+    radius: u32,
+    filter: SeparableFilter,
+}
+
+impl GaussianBlur {
+    // Kernel truncated at 3 sigma
+    pub fn new(device: &wgpu::Device, format: wgpu::TextureFormat, sigma: f32) -> Self {
+        let sigma = sigma.max(0.01);
+        let radius = (sigma * 3.0).ceil() as u32;
+        let mut weights: Vec<f32> = (0..=radius)
+            .map(|offset| (-((offset * offset) as f32) / (2.0 * sigma * sigma)).exp())
+            .collect();
+        let total = weights[0] + 2.0 * weights[1..].iter().sum::<f32>();
+        weights.iter_mut().for_each(|weight| *weight /= total);
+
+        Self {
+            radius,
+            filter: SeparableFilter::new(device, format, "GaussianBlur", &weights),
+        }
+    }
+
+    pub fn radius(&self) -> u32 { self.radius }
+
+    // `size` is the size of both textures
+    pub fn encode(
+        &mut self,
+        device: &wgpu::Device,
+        encoder: &mut wgpu::CommandEncoder,
+        source: &wgpu::TextureView,
+        target: &wgpu::TextureView,
+        size: (u32, u32),
+    ) {
+        self.filter.encode(device, encoder, source, target, size);
+    }
+}
+
+// Separable box blur averaging the (2 * radius + 1)² texels around each one, same textures as `GaussianBlur`
+pub struct BoxBlur {
+    radius: u32,
+    filter: SeparableFilter,
+}
+
+impl BoxBlur {
+    pub fn new(device: &wgpu::Device, format: wgpu::TextureFormat, radius: u32) -> Self {
+        let weights = vec![1.0 / (2 * radius + 1) as f32; radius as usize + 1];
+        Self {
+            radius,
+            filter: SeparableFilter::new(device, format, "BoxBlur", &weights),
+        }
+    }
+
+    pub fn radius(&self) -> u32 { self.radius }
+
+    // `size` is the size of both textures
+    pub fn encode(
+        &mut self,
+        device: &wgpu::Device,
+        encoder: &mut wgpu::CommandEncoder,
+        source: &wgpu::TextureView,
+        target: &wgpu::TextureView,
+        size: (u32, u32),
+    ) {
+        self.filter.encode(device, encoder, source, target, size);
+    }
+}
+
+// Sobel operator on the luminance of the source, writes (gradient magnitude, gradient x, gradient y, 1).
+// The gradient components are signed, use a float target format to keep them.
+pub struct SobelEdges {
+    pass: ImagePass,
+    sampler: wgpu::Sampler,
+}
+
+impl SobelEdges {
+    pub fn new(device: &wgpu::Device, format: wgpu::TextureFormat) -> Self {
+        let body = "    var luminances: array<f32, 9>;
+    for (var y = -1; y <= 1; y++) {
+        for (var x = -1; x <= 1; x++) {
+            luminances[(y + 1) * 3 + x + 1] = dot(load_clamped(pixel + vec2<i32>(x, y)).rgb, vec3<f32>(0.2126, 0.7152, 0.0722));
+        }
+    }
+    let gradient_x = (luminances[2] + 2.0 * luminances[5] + luminances[8]) - (luminances[0] + 2.0 * luminances[3] + luminances[6]);
+    let gradient_y = (luminances[6] + 2.0 * luminances[7] + luminances[8]) - (luminances[0] + 2.0 * luminances[1] + luminances[2]);
+    textureStore(destination, pixel, vec4<f32>(length(vec2<f32>(gradient_x, gradient_y)), gradient_x, gradient_y, 1.0));";
+
+        Self {
+            pass: ImagePass::new(device, format, "SobelEdges", body),
+            sampler: create_sampler(device, "SobelEdges"),
+        }
+    }
+
+    // `size` is the size of both textures
+    pub fn encode(
+        &self,
+        device: &wgpu::Device,
+        encoder: &mut wgpu::CommandEncoder,
+        source: &wgpu::TextureView,
+        target: &wgpu::TextureView,
+        size: (u32, u32),
+    ) {
+        self.pass.encode(device, encoder, &self.sampler, source, target, size);
+    }
+}
+
+// Half resolution copy averaging 4x4 source texels with 4 bilinear samples (e.g. for a bloom or blur mip chain)
+pub struct Downsample {
+    pass: ImagePass,
+    sampler: wgpu::Sampler,
+}
+
+impl Downsample {
+    pub fn new(device: &wgpu::Device, format: wgpu::TextureFormat) -> Self {
+        let body = "    let uv = (vec2<f32>(pixel) + 0.5) / vec2<f32>(target_size);
+    let offset = source_texel_size();
+    var sum = textureSampleLevel(source, source_sampler, uv + vec2<f32>(-offset.x, -offset.y), 0.0);
+    sum += textureSampleLevel(source, source_sampler, uv + vec2<f32>(offset.x, -offset.y), 0.0);
+    sum += textureSampleLevel(source, source_sampler, uv + vec2<f32>(-offset.x, offset.y), 0.0);
+    sum += textureSampleLevel(source, source_sampler, uv + vec2<f32>(offset.x, offset.y), 0.0);
+    textureStore(destination, pixel, sum * 0.25);";
+
+        Self {
+            pass: ImagePass::new(device, format, "Downsample", body),
+            sampler: create_sampler(device, "Downsample"),
+        }
+    }
+
+    // `target_size` is usually half the source size, rounded up
+    pub fn encode(
+        &self,
+        device: &wgpu::Device,
+        encoder: &mut wgpu::CommandEncoder,
+        source: &wgpu::TextureView,
+        target: &wgpu::TextureView,
+        target_size: (u32, u32),
+    ) {
+        self.pass.encode(device, encoder, &self.sampler, source, target, target_size);
+    }
+}
+
+// Upscaled copy filtered by a 3x3 tent of bilinear samples spaced by `filter_radius` source texels
+pub struct Upsample {
+    pass: ImagePass,
+    sampler: wgpu::Sampler,
+}
+
+impl Upsample {
+    pub fn new(device: &wgpu::Device, format: wgpu::TextureFormat, filter_radius: f32) -> Self {
+        let body = format!(
+            "    let uv = (vec2<f32>(pixel) + 0.5) / vec2<f32>(target_size);
+    let offset = source_texel_size() * {:?};
+    var sum = vec4<f32>(0.0);
+    for (var y = -1; y <= 1; y++) {{
+        for (var x = -1; x <= 1; x++) {{
+            let weight = f32((2 - abs(x)) * (2 - abs(y)));
+            sum += weight * textureSampleLevel(source, source_sampler, uv + vec2<f32>(f32(x), f32(y)) * offset, 0.0);
+        }}
+    }}
+    textureStore(destination, pixel, sum / 16.0);",
+            filter_radius
+        );
+
+        Self {
+            pass: ImagePass::new(device, format, "Upsample", &body),
+            sampler: create_sampler(device, "Upsample"),
+        }
+    }
+
+    // `target_size` is usually twice the source size
+    pub fn encode(
+        &self,
+        device: &wgpu::Device,
+        encoder: &mut wgpu::CommandEncoder,
+        source: &wgpu::TextureView,
+        target: &wgpu::TextureView,
+        target_size: (u32, u32),
+    ) {
+        self.pass.encode(device, encoder, &self.sampler, source, target, target_size);
+    }
+}
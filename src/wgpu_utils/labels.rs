@@ -0,0 +1,8 @@
+// Labels of the resources created for a labeled resource, so that graphics debuggers show what each of them belongs to.
+// Unlabeled resources are "unknown", like the bind groups and layouts of `binding_builder`.
+
+// Label of one of the copies of a resource, e.g. "particles [ping]"
+pub fn variant_label(label: Option<&str>, variant: &str) -> String { format!("{} [{}]", label.unwrap_or("unknown"), variant) }
+
+// Label of a resource created for another one, e.g. "particles bind_group"
+pub fn child_label(label: Option<&str>, child: &str) -> String { format!("{} {}", label.unwrap_or("unknown"), child) }
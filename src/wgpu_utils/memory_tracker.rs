@@ -0,0 +1,403 @@
+use std::{
+    collections::{BTreeMap, HashMap},
+    panic::Location,
+    sync::{
+        atomic::{AtomicBool, AtomicU64, Ordering},
+        Arc,
+        Mutex,
+    },
+};
+
+// Kind of GPU memory tracked, buffers are categorized by their main usage
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub enum MemoryCategory {
+    UniformBuffer,
+    StorageBuffer,
+    VertexBuffer,
+    IndexBuffer,
+    StagingBuffer,
+    OtherBuffer,
+    Texture,
+    // No memory of their own, tracked to find the ones never used or leaked
+    BindGroup,
+}
+
+impl MemoryCategory {
+    pub const ALL: [MemoryCategory; 8] = [
+        MemoryCategory::UniformBuffer,
+        MemoryCategory::StorageBuffer,
+        MemoryCategory::VertexBuffer,
+        MemoryCategory::IndexBuffer,
+        MemoryCategory::StagingBuffer,
+        MemoryCategory::OtherBuffer,
+        MemoryCategory::Texture,
+        MemoryCategory::BindGroup,
+    ];
+
+    pub fn from_buffer_usage(usage: wgpu::BufferUsages) -> Self {
+        if usage.intersects(wgpu::BufferUsages::MAP_READ | wgpu::BufferUsages::MAP_WRITE) {
+            MemoryCategory::StagingBuffer
+        } else if usage.contains(wgpu::BufferUsages::INDEX) {
+            MemoryCategory::IndexBuffer
+        } else if usage.contains(wgpu::BufferUsages::VERTEX) {
+            MemoryCategory::VertexBuffer
+        } else if usage.contains(wgpu::BufferUsages::UNIFORM) {
+            MemoryCategory::UniformBuffer
+        } else if usage.contains(wgpu::BufferUsages::STORAGE) {
+            MemoryCategory::StorageBuffer
+        } else {
+            MemoryCategory::OtherBuffer
+        }
+    }
+}
+
+// Snapshot of a tracked allocation, frames are counted by `end_frame`
+#[derive(Clone, Debug)]
+pub struct AllocationInfo {
+    pub id: u64,
+    pub label: String,
+    pub category: MemoryCategory,
+    pub size: u64,
+    // Code that registered the allocation, the oxyde helper or the app code calling `MemoryAllocation::new`
+    pub location: &'static Location<'static>,
+    pub created_frame: u64,
+    // Last frame of `MemoryAllocation::mark_used`, None if never used or when the usage isn't tracked
+    pub last_used_frame: Option<u64>,
+    pub tracks_usage: bool,
+}
+
+impl AllocationInfo {
+    // Frames since the allocation was registered
+    pub fn age(&self) -> u64 { current_frame().saturating_sub(self.created_frame) }
+}
+
+struct RegistryEntry {
+    label: String,
+    category: MemoryCategory,
+    size: u64,
+    location: &'static Location<'static>,
+    created_frame: u64,
+    usage: Arc<AllocationUsage>,
+}
+
+impl RegistryEntry {
+    fn info(&self, id: u64) -> AllocationInfo {
+        let last_used_frame = self.usage.last_used_frame.load(Ordering::Relaxed);
+        AllocationInfo {
+            id,
+            label: self.label.clone(),
+            category: self.category,
+            size: self.size,
+            location: self.location,
+            created_frame: self.created_frame,
+            last_used_frame: (last_used_frame != NEVER_USED).then_some(last_used_frame),
+            tracks_usage: self.usage.tracked.load(Ordering::Relaxed),
+        }
+    }
+}
+
+// Shared by the allocation and its registry entry so that marking it used doesn't lock the registry
+#[derive(Debug)]
+struct AllocationUsage {
+    tracked: AtomicBool,
+    last_used_frame: AtomicU64,
+}
+
+const NEVER_USED: u64 = u64::MAX;
+
+struct Registry {
+    next_id: u64,
+    allocations: BTreeMap<u64, RegistryEntry>,
+    leak_check: LeakCheck,
+}
+
+static REGISTRY: Mutex<Registry> = Mutex::new(Registry {
+    next_id: 0,
+    allocations: BTreeMap::new(),
+    leak_check: LeakCheck {
+        label_counts: Vec::new(),
+        warnings: Vec::new(),
+    },
+});
+
+static CURRENT_FRAME: AtomicU64 = AtomicU64::new(0);
+
+// Registration of a GPU allocation in the global memory tracker, removed when dropped.
+// Oxyde helpers keep one next to each buffer/texture they create, apps can do the same for their own resources.
+#[derive(Debug)]
+pub struct MemoryAllocation {
+    id: u64,
+    size: u64,
+    usage: Arc<AllocationUsage>,
+}
+
+impl MemoryAllocation {
+    #[track_caller]
+    pub fn new(category: MemoryCategory, size: u64, label: Option<&str>) -> Self {
+        let usage = Arc::new(AllocationUsage {
+            tracked: false.into(),
+            last_used_frame: NEVER_USED.into(),
+        });
+        let mut registry = REGISTRY.lock().unwrap();
+        let id = registry.next_id;
+        registry.next_id += 1;
+        registry.allocations.insert(
+            id,
+            RegistryEntry {
+                label: label.unwrap_or("unknown").to_string(),
+                category,
+                size,
+                location: Location::caller(),
+                created_frame: current_frame(),
+                usage: usage.clone(),
+            },
+        );
+        Self { id, size, usage }
+    }
+
+    #[track_caller]
+    pub fn for_buffer(buffer: &wgpu::Buffer, label: Option<&str>) -> Self {
+        Self::new(MemoryCategory::from_buffer_usage(buffer.usage()), buffer.size(), label)
+    }
+
+    #[track_caller]
+    pub fn for_texture(texture: &wgpu::Texture, label: Option<&str>) -> Self {
+        Self::new(MemoryCategory::Texture, texture_size_estimate(texture), label)
+    }
+
+    #[track_caller]
+    pub fn for_bind_group(label: Option<&str>) -> Self { Self::new(MemoryCategory::BindGroup, 0, label).with_usage_tracking() }
+
+    // Report the allocation in `resource_warnings` when it isn't used for `LeakCheck::UNUSED_FRAMES` frames, the
+    // owner calls `mark_used` when the resource is bound or written
+    pub fn with_usage_tracking(self) -> Self {
+        self.usage.tracked.store(true, Ordering::Relaxed);
+        self
+    }
+
+    #[inline]
+    pub fn mark_used(&self) { self.usage.last_used_frame.store(current_frame(), Ordering::Relaxed); }
+
+    #[inline]
+    pub fn id(&self) -> u64 { self.id }
+
+    #[inline]
+    pub fn size(&self) -> u64 { self.size }
+}
+
+impl Drop for MemoryAllocation {
+    fn drop(&mut self) {
+        if let Ok(mut registry) = REGISTRY.lock() {
+            registry.allocations.remove(&self.id);
+        }
+    }
+}
+
+pub fn current_frame() -> u64 { CURRENT_FRAME.load(Ordering::Relaxed) }
+
+// Possible misuse of the tracked resources, see `resource_warnings`
+#[derive(Clone, Debug)]
+pub enum ResourceWarning {
+    // Allocation with usage tracking not used for `LeakCheck::UNUSED_FRAMES` frames
+    Unused(AllocationInfo),
+    // Allocations of a label whose count kept growing over the last `LeakCheck::GROWTH_CHECKS` checks, never freed
+    Growing {
+        label: String,
+        category: MemoryCategory,
+        count: usize,
+        size: u64,
+    },
+}
+
+impl std::fmt::Display for ResourceWarning {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Unused(allocation) => write!(
+                f,
+                "{:?} \"{}\" ({}) never used for {} frames",
+                allocation.category,
+                allocation.label,
+                allocation.location,
+                allocation.age()
+            ),
+            Self::Growing { label, category, count, size } => {
+                write!(f, "{:?} \"{}\" growing without being freed: {} allocations, {} bytes", category, label, count, size)
+            },
+        }
+    }
+}
+
+// Counts of the allocations per label at the last checks, for the growth detection
+struct LeakCheck {
+    label_counts: Vec<HashMap<(String, MemoryCategory), usize>>,
+    warnings: Vec<ResourceWarning>,
+}
+
+impl LeakCheck {
+    const CHECK_INTERVAL: u64 = 300;
+    const GROWTH_CHECKS: usize = 4;
+    const UNUSED_FRAMES: u64 = 600;
+
+    fn check(&mut self, allocations: &BTreeMap<u64, RegistryEntry>) {
+        let mut label_counts: HashMap<(String, MemoryCategory), usize> = HashMap::new();
+        for entry in allocations.values() {
+            *label_counts.entry((entry.label.clone(), entry.category)).or_default() += 1;
+        }
+        if self.label_counts.len() == Self::GROWTH_CHECKS {
+            self.label_counts.remove(0);
+        }
+        self.label_counts.push(label_counts);
+
+        #[cfg(feature = "log")]
+        let previous_warnings = std::mem::take(&mut self.warnings);
+        self.warnings.clear();
+        let last_counts = self.label_counts.last().unwrap();
+        if self.label_counts.len() == Self::GROWTH_CHECKS {
+            for ((label, category), &count) in last_counts {
+                let growing = self
+                    .label_counts
+                    .windows(2)
+                    .all(|counts| counts[1].get(&(label.clone(), *category)) > counts[0].get(&(label.clone(), *category)));
+                if growing {
+                    let size = allocations
+                        .values()
+                        .filter(|entry| entry.label == *label && entry.category == *category)
+                        .map(|entry| entry.size)
+                        .sum();
+                    self.warnings.push(ResourceWarning::Growing {
+                        label: label.clone(),
+                        category: *category,
+                        count,
+                        size,
+                    });
+                }
+            }
+        }
+        let frame = current_frame();
+        for (&id, entry) in allocations {
+            let info = entry.info(id);
+            let last_used_frame = info.last_used_frame.unwrap_or(info.created_frame);
+            if info.tracks_usage && frame.saturating_sub(last_used_frame) >= Self::UNUSED_FRAMES {
+                self.warnings.push(ResourceWarning::Unused(info));
+            }
+        }
+
+        // Only the new warnings are logged
+        #[cfg(feature = "log")]
+        for warning in &self.warnings {
+            let is_new = !previous_warnings.iter().any(|previous| match (previous, warning) {
+                (ResourceWarning::Unused(previous), ResourceWarning::Unused(allocation)) => previous.id == allocation.id,
+                (ResourceWarning::Growing { label: previous, .. }, ResourceWarning::Growing { label, .. }) => previous == label,
+                _ => false,
+            });
+            if is_new {
+                log::warn!("{}", warning);
+            }
+        }
+    }
+}
+
+// Count a frame, called once per frame by the application. The leak checks run every `LeakCheck::CHECK_INTERVAL` frames.
+pub fn end_frame() {
+    let frame = CURRENT_FRAME.fetch_add(1, Ordering::Relaxed) + 1;
+    if frame % LeakCheck::CHECK_INTERVAL == 0 {
+        let mut registry = REGISTRY.lock().unwrap();
+        let Registry { allocations, leak_check, .. } = &mut *registry;
+        leak_check.check(allocations);
+    }
+}
+
+// Resources not used for a long time or leaked at the last check, also logged as warnings once found
+pub fn resource_warnings() -> Vec<ResourceWarning> { REGISTRY.lock().unwrap().leak_check.warnings.clone() }
+
+// Estimation of the memory used by a texture with all its mips and samples (drivers may add padding and compression)
+pub fn texture_size_estimate(texture: &wgpu::Texture) -> u64 {
+    let format = texture.format();
+    let (block_width, block_height) = format.block_dimensions();
+    // Combined depth stencil formats have no copy size, assume 4 bytes per texel
+    let block_size = format.block_copy_size(None).unwrap_or(4) as u64;
+    let size = texture.size();
+
+    (0..texture.mip_level_count())
+        .map(|mip_level| {
+            let mip_size = size.mip_level_size(mip_level, texture.dimension());
+            let block_count = mip_size.width.div_ceil(block_width) as u64 * mip_size.height.div_ceil(block_height) as u64;
+            block_count * mip_size.depth_or_array_layers as u64 * block_size
+        })
+        .sum::<u64>()
+        * texture.sample_count() as u64
+}
+
+#[derive(Clone, Copy, Debug)]
+pub struct CategoryUsage {
+    pub category: MemoryCategory,
+    pub size: u64,
+    pub count: usize,
+}
+
+#[derive(Clone, Debug)]
+pub struct MemoryReport {
+    // Usage of every category, in the order of `MemoryCategory::ALL`
+    pub categories: Vec<CategoryUsage>,
+    pub total_size: u64,
+    pub total_count: usize,
+}
+
+pub fn memory_report() -> MemoryReport {
+    let registry = REGISTRY.lock().unwrap();
+    let categories: Vec<CategoryUsage> = MemoryCategory::ALL
+        .into_iter()
+        .map(|category| {
+            let allocations = registry.allocations.values().filter(|entry| entry.category == category);
+            CategoryUsage {
+                category,
+                size: allocations.clone().map(|allocation| allocation.size).sum(),
+                count: allocations.count(),
+            }
+        })
+        .collect();
+
+    MemoryReport {
+        total_size: categories.iter().map(|usage| usage.size).sum(),
+        total_count: categories.iter().map(|usage| usage.count).sum(),
+        categories,
+    }
+}
+
+// Current tracked allocations, biggest first
+pub fn tracked_allocations() -> Vec<AllocationInfo> { filtered_allocations(&AllocationFilter::default()) }
+
+// Criteria of `filtered_allocations`, all the allocations match the default filter
+#[derive(Clone, Debug, Default)]
+pub struct AllocationFilter {
+    // Case insensitive part of the label or of the creation site
+    pub text: String,
+    pub category: Option<MemoryCategory>,
+    // Allocations registered at least this many frames ago
+    pub min_age: u64,
+    // Allocations with usage tracking not used for this many frames
+    pub unused_for: Option<u64>,
+}
+
+// Current tracked allocations matching the filter, biggest first
+pub fn filtered_allocations(filter: &AllocationFilter) -> Vec<AllocationInfo> {
+    let text = filter.text.to_lowercase();
+    let frame = current_frame();
+    let mut allocations: Vec<AllocationInfo> = REGISTRY
+        .lock()
+        .unwrap()
+        .allocations
+        .iter()
+        .map(|(&id, entry)| entry.info(id))
+        .filter(|allocation| {
+            (text.is_empty() || allocation.label.to_lowercase().contains(&text) || allocation.location.file().to_lowercase().contains(&text))
+                && filter.category.is_none_or(|category| allocation.category == category)
+                && allocation.age() >= filter.min_age
+                && filter.unused_for.is_none_or(|unused_for| {
+                    allocation.tracks_usage && frame.saturating_sub(allocation.last_used_frame.unwrap_or(allocation.created_frame)) >= unused_for
+                })
+        })
+        .collect();
+    allocations.sort_by_key(|allocation| std::cmp::Reverse(allocation.size));
+    allocations
+}
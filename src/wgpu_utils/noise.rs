@@ -0,0 +1,281 @@
+// Hash functions on integers (PCG, Jarzynski and Olano "Hash Functions for GPU Rendering") and on floats, to [0, 1)
+const HASH_WGSL: &str = r#"#define_import_path oxyde::noise::hash
+
+fn pcg(v: u32) -> u32 {
+    let state = v * 747796405u + 2891336453u;
+    let word = ((state >> ((state >> 28u) + 4u)) ^ state) * 277803737u;
+    return (word >> 22u) ^ word;
+}
+
+fn pcg2d(v_in: vec2<u32>) -> vec2<u32> {
+    var v = v_in * 1664525u + 1013904223u;
+    v.x += v.y * 1664525u;
+    v.y += v.x * 1664525u;
+    v = v ^ (v >> vec2<u32>(16u));
+    v.x += v.y * 1664525u;
+    v.y += v.x * 1664525u;
+    v = v ^ (v >> vec2<u32>(16u));
+    return v;
+}
+
+fn pcg3d(v_in: vec3<u32>) -> vec3<u32> {
+    var v = v_in * 1664525u + 1013904223u;
+    v.x += v.y * v.z;
+    v.y += v.z * v.x;
+    v.z += v.x * v.y;
+    v = v ^ (v >> vec3<u32>(16u));
+    v.x += v.y * v.z;
+    v.y += v.z * v.x;
+    v.z += v.x * v.y;
+    return v;
+}
+
+// Uniform float in [0, 1) from the 24 high bits
+fn to_unit_float(h: u32) -> f32 {
+    return f32(h >> 8u) * (1.0 / 16777216.0);
+}
+
+fn hash11(p: f32) -> f32 {
+    return to_unit_float(pcg(bitcast<u32>(p)));
+}
+
+fn hash21(p: vec2<f32>) -> f32 {
+    return to_unit_float(pcg2d(bitcast<vec2<u32>>(p)).x);
+}
+
+fn hash31(p: vec3<f32>) -> f32 {
+    return to_unit_float(pcg3d(bitcast<vec3<u32>>(p)).x);
+}
+
+fn hash22(p: vec2<f32>) -> vec2<f32> {
+    let h = pcg2d(bitcast<vec2<u32>>(p));
+    return vec2<f32>(to_unit_float(h.x), to_unit_float(h.y));
+}
+
+fn hash33(p: vec3<f32>) -> vec3<f32> {
+    let h = pcg3d(bitcast<vec3<u32>>(p));
+    return vec3<f32>(to_unit_float(h.x), to_unit_float(h.y), to_unit_float(h.z));
+}
+
+// Per lattice cell hashes, used by the noises
+fn hash_cell2(cell: vec2<i32>) -> u32 {
+    return pcg2d(bitcast<vec2<u32>>(cell)).x;
+}
+
+fn hash_cell3(cell: vec3<i32>) -> vec3<u32> {
+    return pcg3d(bitcast<vec3<u32>>(cell));
+}
+
+// Uniformly distributed unit gradients
+fn gradient2(cell: vec2<i32>) -> vec2<f32> {
+    let angle = to_unit_float(hash_cell2(cell)) * 6.28318530718;
+    return vec2<f32>(cos(angle), sin(angle));
+}
+
+fn gradient3(cell: vec3<i32>) -> vec3<f32> {
+    let h = hash_cell3(cell);
+    let z = to_unit_float(h.x) * 2.0 - 1.0;
+    let angle = to_unit_float(h.y) * 6.28318530718;
+    let radius = sqrt(1.0 - z * z);
+    return vec3<f32>(radius * cos(angle), radius * sin(angle), z);
+}
+"#;
+
+// Value noise in [0, 1] with quintic interpolation
+const VALUE_WGSL: &str = r#"#define_import_path oxyde::noise::value
+
+#import oxyde::noise::hash::{hash_cell2, hash_cell3, to_unit_float}
+
+fn value_noise_2d(p: vec2<f32>) -> f32 {
+    let cell = vec2<i32>(floor(p));
+    let f = fract(p);
+    let u = f * f * f * (f * (f * 6.0 - 15.0) + 10.0);
+    let a = to_unit_float(hash_cell2(cell));
+    let b = to_unit_float(hash_cell2(cell + vec2<i32>(1, 0)));
+    let c = to_unit_float(hash_cell2(cell + vec2<i32>(0, 1)));
+    let d = to_unit_float(hash_cell2(cell + vec2<i32>(1, 1)));
+    return mix(mix(a, b, u.x), mix(c, d, u.x), u.y);
+}
+
+fn value_noise_3d(p: vec3<f32>) -> f32 {
+    let cell = vec3<i32>(floor(p));
+    let f = fract(p);
+    let u = f * f * f * (f * (f * 6.0 - 15.0) + 10.0);
+    let a = to_unit_float(hash_cell3(cell).x);
+    let b = to_unit_float(hash_cell3(cell + vec3<i32>(1, 0, 0)).x);
+    let c = to_unit_float(hash_cell3(cell + vec3<i32>(0, 1, 0)).x);
+    let d = to_unit_float(hash_cell3(cell + vec3<i32>(1, 1, 0)).x);
+    let e = to_unit_float(hash_cell3(cell + vec3<i32>(0, 0, 1)).x);
+    let f1 = to_unit_float(hash_cell3(cell + vec3<i32>(1, 0, 1)).x);
+    let g = to_unit_float(hash_cell3(cell + vec3<i32>(0, 1, 1)).x);
+    let h = to_unit_float(hash_cell3(cell + vec3<i32>(1, 1, 1)).x);
+    return mix(mix(mix(a, b, u.x), mix(c, d, u.x), u.y), mix(mix(e, f1, u.x), mix(g, h, u.x), u.y), u.z);
+}
+"#;
+
+// Perlin (gradient) noise in about [-1, 1] with quintic interpolation
+const PERLIN_WGSL: &str = r#"#define_import_path oxyde::noise::perlin
+
+#import oxyde::noise::hash::{gradient2, gradient3}
+
+fn perlin_noise_2d(p: vec2<f32>) -> f32 {
+    let cell = vec2<i32>(floor(p));
+    let f = fract(p);
+    let u = f * f * f * (f * (f * 6.0 - 15.0) + 10.0);
+    let a = dot(gradient2(cell), f);
+    let b = dot(gradient2(cell + vec2<i32>(1, 0)), f - vec2<f32>(1.0, 0.0));
+    let c = dot(gradient2(cell + vec2<i32>(0, 1)), f - vec2<f32>(0.0, 1.0));
+    let d = dot(gradient2(cell + vec2<i32>(1, 1)), f - vec2<f32>(1.0, 1.0));
+    return mix(mix(a, b, u.x), mix(c, d, u.x), u.y) * 1.41421356;
+}
+
+fn perlin_noise_3d(p: vec3<f32>) -> f32 {
+    let cell = vec3<i32>(floor(p));
+    let f = fract(p);
+    let u = f * f * f * (f * (f * 6.0 - 15.0) + 10.0);
+    let a = dot(gradient3(cell), f);
+    let b = dot(gradient3(cell + vec3<i32>(1, 0, 0)), f - vec3<f32>(1.0, 0.0, 0.0));
+    let c = dot(gradient3(cell + vec3<i32>(0, 1, 0)), f - vec3<f32>(0.0, 1.0, 0.0));
+    let d = dot(gradient3(cell + vec3<i32>(1, 1, 0)), f - vec3<f32>(1.0, 1.0, 0.0));
+    let e = dot(gradient3(cell + vec3<i32>(0, 0, 1)), f - vec3<f32>(0.0, 0.0, 1.0));
+    let f1 = dot(gradient3(cell + vec3<i32>(1, 0, 1)), f - vec3<f32>(1.0, 0.0, 1.0));
+    let g = dot(gradient3(cell + vec3<i32>(0, 1, 1)), f - vec3<f32>(0.0, 1.0, 1.0));
+    let h = dot(gradient3(cell + vec3<i32>(1, 1, 1)), f - vec3<f32>(1.0, 1.0, 1.0));
+    return mix(mix(mix(a, b, u.x), mix(c, d, u.x), u.y), mix(mix(e, f1, u.x), mix(g, h, u.x), u.y), u.z) * 1.15470054;
+}
+"#;
+
+// Simplex noise in about [-1, 1] (Gustavson "Simplex noise demystified"), cheaper than Perlin in 3D and without its axis artifacts
+const SIMPLEX_WGSL: &str = r#"#define_import_path oxyde::noise::simplex
+
+#import oxyde::noise::hash::{gradient2, gradient3}
+
+fn simplex_noise_2d(p: vec2<f32>) -> f32 {
+    // Skewing factors (sqrt(3) - 1) / 2 and (3 - sqrt(3)) / 6
+    let skewed_cell = floor(p + dot(p, vec2<f32>(0.36602540378)));
+    let x0 = p - skewed_cell + dot(skewed_cell, vec2<f32>(0.2113248654));
+    let i1 = select(vec2<f32>(0.0, 1.0), vec2<f32>(1.0, 0.0), x0.x > x0.y);
+    let x1 = x0 - i1 + 0.2113248654;
+    let x2 = x0 - 1.0 + 2.0 * 0.2113248654;
+
+    let cell = vec2<i32>(skewed_cell);
+    let t = max(vec3<f32>(0.5) - vec3<f32>(dot(x0, x0), dot(x1, x1), dot(x2, x2)), vec3<f32>(0.0));
+    let t4 = t * t * t * t;
+    let contributions = vec3<f32>(
+        dot(gradient2(cell), x0),
+        dot(gradient2(cell + vec2<i32>(i1)), x1),
+        dot(gradient2(cell + vec2<i32>(1, 1)), x2),
+    );
+    return dot(t4, contributions) * 99.2;
+}
+
+fn simplex_noise_3d(p: vec3<f32>) -> f32 {
+    // Skewing factors 1 / 3 and 1 / 6, kernel radius of 0.5 rather than the 0.6 of the reference to keep the derivatives continuous
+    let skewed_cell = floor(p + dot(p, vec3<f32>(1.0 / 3.0)));
+    let x0 = p - skewed_cell + dot(skewed_cell, vec3<f32>(1.0 / 6.0));
+    let e = step(x0.yzx, x0.xyz);
+    let i1 = e * (1.0 - e.zxy);
+    let i2 = 1.0 - e.zxy * (1.0 - e);
+    let x1 = x0 - i1 + 1.0 / 6.0;
+    let x2 = x0 - i2 + 2.0 / 6.0;
+    let x3 = x0 - 0.5;
+
+    let cell = vec3<i32>(skewed_cell);
+    let t = max(vec4<f32>(0.5) - vec4<f32>(dot(x0, x0), dot(x1, x1), dot(x2, x2), dot(x3, x3)), vec4<f32>(0.0));
+    let t4 = t * t * t * t;
+    let contributions = vec4<f32>(
+        dot(gradient3(cell), x0),
+        dot(gradient3(cell + vec3<i32>(i1)), x1),
+        dot(gradient3(cell + vec3<i32>(i2)), x2),
+        dot(gradient3(cell + vec3<i32>(1, 1, 1)), x3),
+    );
+    return dot(t4, contributions) * 105.0;
+}
+"#;
+
+// Fractal brownian motion: `octaves` layers of simplex noise, each with `lacunarity` times the frequency and `gain` times the
+// amplitude of the previous one (typically 2.0 and 0.5), normalized to about [-1, 1]
+const FBM_WGSL: &str = r#"#define_import_path oxyde::noise::fbm
+
+#import oxyde::noise::simplex::{simplex_noise_2d, simplex_noise_3d}
+
+fn fbm_2d(p: vec2<f32>, octaves: u32, lacunarity: f32, gain: f32) -> f32 {
+    var sum = 0.0;
+    var amplitude = 1.0;
+    var frequency = 1.0;
+    var amplitude_sum = 0.0;
+    for (var octave = 0u; octave < octaves; octave++) {
+        sum += amplitude * simplex_noise_2d(p * frequency);
+        amplitude_sum += amplitude;
+        amplitude *= gain;
+        frequency *= lacunarity;
+    }
+    return sum / max(amplitude_sum, 1e-6);
+}
+
+fn fbm_3d(p: vec3<f32>, octaves: u32, lacunarity: f32, gain: f32) -> f32 {
+    var sum = 0.0;
+    var amplitude = 1.0;
+    var frequency = 1.0;
+    var amplitude_sum = 0.0;
+    for (var octave = 0u; octave < octaves; octave++) {
+        sum += amplitude * simplex_noise_3d(p * frequency);
+        amplitude_sum += amplitude;
+        amplitude *= gain;
+        frequency *= lacunarity;
+    }
+    return sum / max(amplitude_sum, 1e-6);
+}
+"#;
+
+// Divergence free velocity fields from simplex noise potentials (Bridson et al. "Curl-noise for procedural fluid flow"),
+// derivatives by central differences
+const CURL_WGSL: &str = r#"#define_import_path oxyde::noise::curl
+
+#import oxyde::noise::simplex::{simplex_noise_2d, simplex_noise_3d}
+
+const CURL_EPSILON: f32 = 1e-3;
+
+// Rotated gradient of a scalar potential
+fn curl_noise_2d(p: vec2<f32>) -> vec2<f32> {
+    let dx = vec2<f32>(CURL_EPSILON, 0.0);
+    let dy = vec2<f32>(0.0, CURL_EPSILON);
+    let gradient_x = simplex_noise_2d(p + dx) - simplex_noise_2d(p - dx);
+    let gradient_y = simplex_noise_2d(p + dy) - simplex_noise_2d(p - dy);
+    return vec2<f32>(gradient_y, -gradient_x) / (2.0 * CURL_EPSILON);
+}
+
+// Vector potential made of three decorrelated (offset) simplex noises
+fn curl_potential(p: vec3<f32>) -> vec3<f32> {
+    return vec3<f32>(
+        simplex_noise_3d(p),
+        simplex_noise_3d(p + vec3<f32>(31.416, -47.853, 12.679)),
+        simplex_noise_3d(p + vec3<f32>(-233.145, -113.408, -185.31)),
+    );
+}
+
+fn curl_noise_3d(p: vec3<f32>) -> vec3<f32> {
+    let dx = vec3<f32>(CURL_EPSILON, 0.0, 0.0);
+    let dy = vec3<f32>(0.0, CURL_EPSILON, 0.0);
+    let dz = vec3<f32>(0.0, 0.0, CURL_EPSILON);
+    let derivative_x = curl_potential(p + dx) - curl_potential(p - dx);
+    let derivative_y = curl_potential(p + dy) - curl_potential(p - dy);
+    let derivative_z = curl_potential(p + dz) - curl_potential(p - dz);
+    return vec3<f32>(
+        derivative_y.z - derivative_z.y,
+        derivative_z.x - derivative_x.z,
+        derivative_x.y - derivative_y.x,
+    ) / (2.0 * CURL_EPSILON);
+}
+"#;
+
+// (import path, source) of the noise modules in dependency order, added by `ShaderComposer::with_noise`:
+// `#import oxyde::noise::simplex::simplex_noise_3d`, `#import oxyde::noise::fbm::fbm_2d`...
+pub const NOISE_MODULES: [(&str, &str); 6] = [
+    ("oxyde::noise::hash", HASH_WGSL),
+    ("oxyde::noise::value", VALUE_WGSL),
+    ("oxyde::noise::perlin", PERLIN_WGSL),
+    ("oxyde::noise::simplex", SIMPLEX_WGSL),
+    ("oxyde::noise::fbm", FBM_WGSL),
+    ("oxyde::noise::curl", CURL_WGSL),
+];
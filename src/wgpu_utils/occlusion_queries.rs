@@ -0,0 +1,189 @@
+use std::{cell::RefCell, collections::HashMap, hash::Hash};
+
+use super::{
+    buffers::{map_async, MapFuture},
+    child_label,
+    memory_tracker::MemoryAllocation,
+};
+
+const QUERY_SIZE: wgpu::BufferAddress = std::mem::size_of::<u64>() as wgpu::BufferAddress;
+
+enum ReadbackSlotState<K> {
+    Free,
+    // Results of the frames in resolve order copied, mapped once the frame is submitted
+    Copied(u64, Vec<K>),
+    Mapping(u64, Vec<K>, MapFuture),
+}
+
+struct ReadbackSlot<K> {
+    buffer: wgpu::Buffer,
+    state: ReadbackSlotState<K>,
+    _memory: MemoryAllocation,
+}
+
+// Occlusion queries of objects identified by keys (e.g. object IDs): the draws of an object are surrounded by `begin` and
+// `end` in a render pass using `query_set`, the number of samples passing the depth test of each object is resolved
+// and read back asynchronously, a frame or two later.
+// Usage per frame: `begin`/`end` around the draws, `encode_resolve` after the pass, `after_submit` once the frame is
+// submitted then `poll` to update the results.
+pub struct OcclusionQueries<K: Eq + Hash = u32> {
+    query_set: wgpu::QuerySet,
+    capacity: u32,
+    resolve_buffer: wgpu::Buffer,
+    // Keys of the queries of the frame, by query index. A RefCell as the render pass borrows the query set.
+    frame_keys: RefCell<Vec<K>>,
+    slots: Vec<ReadbackSlot<K>>,
+    results: HashMap<K, u64>,
+    resolve_count: u64,
+    label: String,
+    _memory: MemoryAllocation,
+}
+
+impl<K: Eq + Hash> OcclusionQueries<K> {
+    const SLOT_COUNT: usize = 3;
+
+    // `capacity` is the maximum number of queries per frame
+    pub fn new(device: &wgpu::Device, capacity: u32, label: Option<&str>) -> Self {
+        let label = format!("OcclusionQueries: {}", label.unwrap_or("unknown"));
+        let resolve_label = child_label(Some(&label), "resolve");
+        let query_set = device.create_query_set(&wgpu::QuerySetDescriptor {
+            label: Some(&label),
+            ty: wgpu::QueryType::Occlusion,
+            count: capacity,
+        });
+        let resolve_buffer = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some(&resolve_label),
+            size: capacity as wgpu::BufferAddress * QUERY_SIZE,
+            usage: wgpu::BufferUsages::QUERY_RESOLVE | wgpu::BufferUsages::COPY_SRC,
+            mapped_at_creation: false,
+        });
+        let memory = MemoryAllocation::for_buffer(&resolve_buffer, Some(&resolve_label));
+
+        Self {
+            query_set,
+            capacity,
+            resolve_buffer,
+            frame_keys: RefCell::new(Vec::new()),
+            slots: Vec::new(),
+            results: HashMap::new(),
+            resolve_count: 0,
+            label,
+            _memory: memory,
+        }
+    }
+
+    // Query set of the `RenderPassDescriptor::occlusion_query_set` of the passes with queries
+    pub fn query_set(&self) -> &wgpu::QuerySet { &self.query_set }
+
+    pub fn capacity(&self) -> u32 { self.capacity }
+
+    // Start the query of `key`, the draws until `end` are counted. Returns false (and nothing must be ended) when all the
+    // queries of the frame are used.
+    pub fn begin(&self, render_pass: &mut wgpu::RenderPass<'_>, key: K) -> bool {
+        let mut frame_keys = self.frame_keys.borrow_mut();
+        if frame_keys.len() as u32 >= self.capacity {
+            return false;
+        }
+        render_pass.begin_occlusion_query(frame_keys.len() as u32);
+        frame_keys.push(key);
+        true
+    }
+
+    pub fn end(&self, render_pass: &mut wgpu::RenderPass<'_>) { render_pass.end_occlusion_query(); }
+
+    // Resolve the queries of the frame and copy them for the readback. When all the readbacks are in use the results of the
+    // frame are dropped.
+    pub fn encode_resolve(&mut self, device: &wgpu::Device, encoder: &mut wgpu::CommandEncoder) {
+        let keys = std::mem::take(self.frame_keys.get_mut());
+        if keys.is_empty() {
+            return;
+        }
+        let slot_index = match self.slots.iter().position(|slot| matches!(slot.state, ReadbackSlotState::Free)) {
+            Some(slot_index) => slot_index,
+            None if self.slots.len() < Self::SLOT_COUNT => {
+                let readback_label = child_label(Some(&self.label), &format!("readback {}", self.slots.len()));
+                let buffer = device.create_buffer(&wgpu::BufferDescriptor {
+                    label: Some(&readback_label),
+                    size: self.capacity as wgpu::BufferAddress * QUERY_SIZE,
+                    usage: wgpu::BufferUsages::COPY_DST | wgpu::BufferUsages::MAP_READ,
+                    mapped_at_creation: false,
+                });
+                let memory = MemoryAllocation::for_buffer(&buffer, Some(&readback_label));
+                self.slots.push(ReadbackSlot {
+                    buffer,
+                    state: ReadbackSlotState::Free,
+                    _memory: memory,
+                });
+                self.slots.len() - 1
+            },
+            None => return,
+        };
+
+        let query_count = keys.len() as u32;
+        encoder.resolve_query_set(&self.query_set, 0..query_count, &self.resolve_buffer, 0);
+        let slot = &mut self.slots[slot_index];
+        encoder.copy_buffer_to_buffer(&self.resolve_buffer, 0, &slot.buffer, 0, query_count as wgpu::BufferAddress * QUERY_SIZE);
+        slot.state = ReadbackSlotState::Copied(self.resolve_count, keys);
+        self.resolve_count += 1;
+    }
+
+    pub fn after_submit(&mut self) {
+        for slot in &mut self.slots {
+            slot.state = match std::mem::replace(&mut slot.state, ReadbackSlotState::Free) {
+                ReadbackSlotState::Copied(index, keys) => {
+                    let size = keys.len() as wgpu::BufferAddress * QUERY_SIZE;
+                    ReadbackSlotState::Mapping(index, keys, map_async(slot.buffer.slice(..size), wgpu::MapMode::Read))
+                },
+                state => state,
+            };
+        }
+    }
+
+    // Update the results with the resolved readbacks, without blocking. Returns true if results were updated.
+    pub fn poll(&mut self, device: &wgpu::Device) -> bool {
+        if !self.slots.iter().any(|slot| matches!(slot.state, ReadbackSlotState::Mapping(..))) {
+            return false;
+        }
+        device.poll(wgpu::Maintain::Poll);
+
+        let mut resolved = Vec::new();
+        for slot in &mut self.slots {
+            let ReadbackSlotState::Mapping(_, _, map_future) = &slot.state else {
+                continue;
+            };
+            let Some(map_result) = map_future.try_take_result() else {
+                continue;
+            };
+            let ReadbackSlotState::Mapping(index, keys, _) = std::mem::replace(&mut slot.state, ReadbackSlotState::Free) else {
+                unreachable!()
+            };
+            if map_result.is_err() {
+                continue;
+            }
+
+            let size = keys.len() as wgpu::BufferAddress * QUERY_SIZE;
+            let samples: Vec<u64> = bytemuck::pod_collect_to_vec(&slot.buffer.slice(..size).get_mapped_range());
+            slot.buffer.unmap();
+            resolved.push((index, keys, samples));
+        }
+
+        // The latest frames override the results of the older ones
+        resolved.sort_by_key(|(index, ..)| *index);
+        let updated = !resolved.is_empty();
+        for (_, keys, samples) in resolved {
+            self.results.extend(keys.into_iter().zip(samples));
+        }
+        updated
+    }
+
+    // Samples that passed the depth test in the latest resolved query of `key` (only 0 or 1 with the GL backend)
+    pub fn samples_passed(&self, key: &K) -> Option<u64> { self.results.get(key).copied() }
+
+    // None until a query of `key` is resolved
+    pub fn is_visible(&self, key: &K) -> Option<bool> { self.samples_passed(key).map(|samples| samples > 0) }
+
+    pub fn results(&self) -> &HashMap<K, u64> { &self.results }
+
+    // Forget the results, e.g. when the objects are reloaded
+    pub fn clear_results(&mut self) { self.results.clear(); }
+}
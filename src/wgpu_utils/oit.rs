@@ -0,0 +1,270 @@
+use super::{
+    binding_builder::{BindGroupBuilder, BindGroupLayoutBuilder, BindGroupLayoutWithDesc},
+    child_label,
+    memory_tracker::MemoryAllocation,
+    DepthConvention,
+    DepthPreset,
+};
+
+// WGSL of the transparent draws: fragment shaders return `oit_output(color, position.z)` (straight alpha color)
+// instead of their color, written to the accumulation and revealage targets
+pub const OIT_WGSL: &str = "struct OitOutput {
+    @location(0) accumulation: vec4<f32>,
+    @location(1) revealage: f32,
+}
+
+// Weight favoring the fragments close to the camera (McGuire and Bavoil, equation 10 with the 0 to 1 depth range)
+fn oit_weight(alpha: f32, depth: f32) -> f32 {
+    return alpha * clamp(0.03 / (1e-5 + pow(depth * 0.5, 4.0)), 1e-2, 3e3);
+}
+
+fn oit_output(color: vec4<f32>, depth: f32) -> OitOutput {
+    let weight = oit_weight(color.a, depth);
+    var output: OitOutput;
+    output.accumulation = vec4<f32>(color.rgb * color.a, color.a) * weight;
+    output.revealage = color.a;
+    return output;
+}
+";
+
+const COMPOSITE_WGSL: &str = "@group(0) @binding(0) var accumulation_texture: texture_2d<f32>;
+@group(0) @binding(1) var revealage_texture: texture_2d<f32>;
+
+@vertex
+fn vs_main(@builtin(vertex_index) vertex_index: u32) -> @builtin(position) vec4<f32> {
+    let uv = vec2<f32>(f32((vertex_index << 1u) & 2u), f32(vertex_index & 2u));
+    return vec4<f32>(uv * 2.0 - 1.0, 0.0, 1.0);
+}
+
+@fragment
+fn fs_main(@builtin(position) position: vec4<f32>) -> @location(0) vec4<f32> {
+    let texel = vec2<i32>(position.xy);
+    let revealage = textureLoad(revealage_texture, texel, 0).r;
+    if revealage >= 1.0 {
+        discard;
+    }
+    let accumulation = textureLoad(accumulation_texture, texel, 0);
+    let average_color = accumulation.rgb / max(accumulation.a, 1e-5);
+    return vec4<f32>(average_color, 1.0 - revealage);
+}
+";
+
+// Weighted blended order independent transparency: transparent draws are accumulated in any order in the
+// accumulation and revealage targets, then composited over the opaque result.
+// Transparent pipelines use `color_targets` and `depth_stencil` with a fragment shader using `OIT_WGSL`,
+// each frame the transparent draws are recorded in `begin_transparent_pass` after the opaque pass, then `encode_composite`.
+pub struct WeightedBlendedOit {
+    accumulation_view: wgpu::TextureView,
+    revealage_view: wgpu::TextureView,
+    composite_bind_group_layout: BindGroupLayoutWithDesc,
+    composite_bind_group: wgpu::BindGroup,
+    composite_pipeline: wgpu::RenderPipeline,
+    _memory: [MemoryAllocation; 2],
+}
+
+impl WeightedBlendedOit {
+    pub const ACCUMULATION_FORMAT: wgpu::TextureFormat = wgpu::TextureFormat::Rgba16Float;
+    pub const REVEALAGE_FORMAT: wgpu::TextureFormat = wgpu::TextureFormat::R8Unorm;
+
+    // `target_format` is the format of the opaque result the transparency is composited on
+    pub fn new(device: &wgpu::Device, target_format: wgpu::TextureFormat, width: u32, height: u32) -> Self {
+        let texture_binding = wgpu::BindingType::Texture {
+            sample_type: wgpu::TextureSampleType::Float { filterable: false },
+            view_dimension: wgpu::TextureViewDimension::D2,
+            multisampled: false,
+        };
+        let composite_bind_group_layout = BindGroupLayoutBuilder::new()
+            .add_binding_fragment(texture_binding)
+            .add_binding_fragment(texture_binding)
+            .create(device, Some("WeightedBlendedOit: composite"));
+
+        let shader_module = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: Some("WeightedBlendedOit: composite"),
+            source: wgpu::ShaderSource::Wgsl(COMPOSITE_WGSL.into()),
+        });
+        let pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("WeightedBlendedOit: composite"),
+            bind_group_layouts: &[&composite_bind_group_layout.layout],
+            push_constant_ranges: &[],
+        });
+        let composite_pipeline = device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+            label: Some("WeightedBlendedOit: composite"),
+            layout: Some(&pipeline_layout),
+            vertex: wgpu::VertexState {
+                module: &shader_module,
+                entry_point: "vs_main",
+                buffers: &[],
+            },
+            fragment: Some(wgpu::FragmentState {
+                module: &shader_module,
+                entry_point: "fs_main",
+                targets: &[Some(wgpu::ColorTargetState {
+                    format: target_format,
+                    blend: Some(wgpu::BlendState::ALPHA_BLENDING),
+                    write_mask: wgpu::ColorWrites::ALL,
+                })],
+            }),
+            primitive: wgpu::PrimitiveState::default(),
+            depth_stencil: None,
+            multisample: wgpu::MultisampleState::default(),
+            multiview: None,
+        });
+
+        let (accumulation_view, revealage_view, composite_bind_group, memory) =
+            Self::create_targets(device, &composite_bind_group_layout, width, height);
+
+        Self {
+            accumulation_view,
+            revealage_view,
+            composite_bind_group_layout,
+            composite_bind_group,
+            composite_pipeline,
+            _memory: memory,
+        }
+    }
+
+    fn create_targets(
+        device: &wgpu::Device,
+        composite_bind_group_layout: &BindGroupLayoutWithDesc,
+        width: u32,
+        height: u32,
+    ) -> (wgpu::TextureView, wgpu::TextureView, wgpu::BindGroup, [MemoryAllocation; 2]) {
+        let create_target = |format: wgpu::TextureFormat, label: &str| {
+            let texture = device.create_texture(&wgpu::TextureDescriptor {
+                label: Some(label),
+                size: wgpu::Extent3d {
+                    width: width.max(1),
+                    height: height.max(1),
+                    depth_or_array_layers: 1,
+                },
+                mip_level_count: 1,
+                sample_count: 1,
+                dimension: wgpu::TextureDimension::D2,
+                format,
+                usage: wgpu::TextureUsages::RENDER_ATTACHMENT | wgpu::TextureUsages::TEXTURE_BINDING,
+                view_formats: &[],
+            });
+            let memory = MemoryAllocation::for_texture(&texture, Some(label));
+            (
+                texture.create_view(&wgpu::TextureViewDescriptor {
+                    label: Some(&child_label(Some(label), "view")),
+                    ..Default::default()
+                }),
+                memory,
+            )
+        };
+        let (accumulation_view, accumulation_memory) = create_target(Self::ACCUMULATION_FORMAT, "WeightedBlendedOit: accumulation");
+        let (revealage_view, revealage_memory) = create_target(Self::REVEALAGE_FORMAT, "WeightedBlendedOit: revealage");
+        let composite_bind_group = BindGroupBuilder::new(composite_bind_group_layout)
+            .texture(&accumulation_view)
+            .texture(&revealage_view)
+            .create(device, Some("WeightedBlendedOit: composite"));
+        (accumulation_view, revealage_view, composite_bind_group, [accumulation_memory, revealage_memory])
+    }
+
+    pub fn resize(&mut self, device: &wgpu::Device, width: u32, height: u32) {
+        (self.accumulation_view, self.revealage_view, self.composite_bind_group, self._memory) =
+            Self::create_targets(device, &self.composite_bind_group_layout, width, height);
+    }
+
+    // Color targets of the transparent pipelines: additive accumulation and multiplicative revealage
+    pub fn color_targets() -> [Option<wgpu::ColorTargetState>; 2] {
+        [
+            Some(wgpu::ColorTargetState {
+                format: Self::ACCUMULATION_FORMAT,
+                blend: Some(wgpu::BlendState {
+                    color: wgpu::BlendComponent {
+                        src_factor: wgpu::BlendFactor::One,
+                        dst_factor: wgpu::BlendFactor::One,
+                        operation: wgpu::BlendOperation::Add,
+                    },
+                    alpha: wgpu::BlendComponent {
+                        src_factor: wgpu::BlendFactor::One,
+                        dst_factor: wgpu::BlendFactor::One,
+                        operation: wgpu::BlendOperation::Add,
+                    },
+                }),
+                write_mask: wgpu::ColorWrites::ALL,
+            }),
+            Some(wgpu::ColorTargetState {
+                format: Self::REVEALAGE_FORMAT,
+                blend: Some(wgpu::BlendState {
+                    color: wgpu::BlendComponent {
+                        src_factor: wgpu::BlendFactor::Zero,
+                        dst_factor: wgpu::BlendFactor::OneMinusSrc,
+                        operation: wgpu::BlendOperation::Add,
+                    },
+                    alpha: wgpu::BlendComponent::REPLACE,
+                }),
+                write_mask: wgpu::ColorWrites::RED,
+            }),
+        ]
+    }
+
+    // Depth state of the transparent pipelines: tested against the opaque depth, not written. `depth_convention` is the one
+    // of the projection the opaque depth was rendered with.
+    pub fn depth_stencil(depth_format: wgpu::TextureFormat, depth_convention: DepthConvention) -> wgpu::DepthStencilState {
+        DepthPreset::TestOnly.depth_stencil(depth_format, depth_convention)
+    }
+
+    // Pass of the transparent draws, recorded in any order. `depth_view` is the depth of the opaque pass,
+    // None to draw without depth test (pipelines without depth state).
+    pub fn begin_transparent_pass<'a>(
+        &'a self,
+        encoder: &'a mut wgpu::CommandEncoder,
+        depth_view: Option<&'a wgpu::TextureView>,
+    ) -> wgpu::RenderPass<'a> {
+        encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+            label: Some("WeightedBlendedOit: transparent"),
+            color_attachments: &[
+                Some(wgpu::RenderPassColorAttachment {
+                    view: &self.accumulation_view,
+                    resolve_target: None,
+                    ops: wgpu::Operations {
+                        load: wgpu::LoadOp::Clear(wgpu::Color::TRANSPARENT),
+                        store: wgpu::StoreOp::Store,
+                    },
+                }),
+                Some(wgpu::RenderPassColorAttachment {
+                    view: &self.revealage_view,
+                    resolve_target: None,
+                    ops: wgpu::Operations {
+                        load: wgpu::LoadOp::Clear(wgpu::Color::WHITE),
+                        store: wgpu::StoreOp::Store,
+                    },
+                }),
+            ],
+            depth_stencil_attachment: depth_view.map(|depth_view| wgpu::RenderPassDepthStencilAttachment {
+                view: depth_view,
+                depth_ops: Some(wgpu::Operations {
+                    load: wgpu::LoadOp::Load,
+                    store: wgpu::StoreOp::Store,
+                }),
+                stencil_ops: None,
+            }),
+            timestamp_writes: None,
+            occlusion_query_set: None,
+        })
+    }
+
+    // Blend the accumulated transparency over `target_view`, after the transparent pass
+    pub fn encode_composite(&self, encoder: &mut wgpu::CommandEncoder, target_view: &wgpu::TextureView) {
+        let mut composite_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+            label: Some("WeightedBlendedOit: composite"),
+            color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+                view: target_view,
+                resolve_target: None,
+                ops: wgpu::Operations {
+                    load: wgpu::LoadOp::Load,
+                    store: wgpu::StoreOp::Store,
+                },
+            })],
+            depth_stencil_attachment: None,
+            timestamp_writes: None,
+            occlusion_query_set: None,
+        });
+        composite_pass.set_pipeline(&self.composite_pipeline);
+        composite_pass.set_bind_group(0, &self.composite_bind_group, &[]);
+        composite_pass.draw(0..3, 0..1);
+    }
+}
@@ -0,0 +1,636 @@
+use std::sync::Arc;
+
+use anyhow::Result;
+use wgpu::util::DeviceExt;
+
+use super::{
+    binding_builder::{BindGroupBuilder, BindGroupLayoutBuilder, BindGroupLayoutWithDesc},
+    child_label,
+    memory_tracker::MemoryAllocation,
+    uniform_buffer::UniformBuffer,
+    BlendPreset,
+    DepthConvention,
+    DepthPreset,
+    ShaderComposer,
+};
+
+pub const PBR_BRDF_IMPORT_PATH: &str = "oxyde::pbr::brdf";
+pub const PBR_LIGHTING_IMPORT_PATH: &str = "oxyde::pbr::lighting";
+pub const PBR_MATERIAL_IMPORT_PATH: &str = "oxyde::pbr::material";
+
+// Cook-Torrance terms of the metallic roughness model (GGX distribution, height correlated Smith visibility, Schlick fresnel)
+pub const PBR_BRDF_WGSL: &str = r#"#define_import_path oxyde::pbr::brdf
+
+const PI: f32 = 3.14159265359;
+
+fn distribution_ggx(n_dot_h: f32, roughness: f32) -> f32 {
+    let a2 = roughness * roughness * roughness * roughness;
+    let d = n_dot_h * n_dot_h * (a2 - 1.0) + 1.0;
+    return a2 / (PI * d * d);
+}
+
+// Includes the 1 / (4 n_dot_v n_dot_l) factor of the specular term
+fn visibility_smith_ggx(n_dot_v: f32, n_dot_l: f32, roughness: f32) -> f32 {
+    let a2 = roughness * roughness * roughness * roughness;
+    let ggx_v = n_dot_l * sqrt(n_dot_v * n_dot_v * (1.0 - a2) + a2);
+    let ggx_l = n_dot_v * sqrt(n_dot_l * n_dot_l * (1.0 - a2) + a2);
+    return 0.5 / max(ggx_v + ggx_l, 1e-5);
+}
+
+fn fresnel_schlick(cos_theta: f32, f0: vec3<f32>) -> vec3<f32> {
+    return f0 + (1.0 - f0) * pow(clamp(1.0 - cos_theta, 0.0, 1.0), 5.0);
+}
+
+// Fresnel averaged over the hemisphere for ambient lighting, rough surfaces reflect less at grazing angles
+fn fresnel_schlick_roughness(cos_theta: f32, f0: vec3<f32>, roughness: f32) -> vec3<f32> {
+    return f0 + (max(vec3<f32>(1.0 - roughness), f0) - f0) * pow(clamp(1.0 - cos_theta, 0.0, 1.0), 5.0);
+}
+
+// Reflectance at normal incidence: 4% for dielectrics, the base color for metals
+fn specular_f0(base_color: vec3<f32>, metallic: f32) -> vec3<f32> {
+    return mix(vec3<f32>(0.04), base_color, metallic);
+}
+"#;
+
+// Light contributions for a shaded surface, all vectors in the same (world) space and normalized
+pub const PBR_LIGHTING_WGSL: &str = r#"#define_import_path oxyde::pbr::lighting
+
+#import oxyde::pbr::brdf::{PI, distribution_ggx, visibility_smith_ggx, fresnel_schlick, fresnel_schlick_roughness, specular_f0}
+
+struct PbrSurface {
+    base_color: vec3<f32>,
+    metallic: f32,
+    roughness: f32,
+    occlusion: f32,
+    emissive: vec3<f32>,
+    normal: vec3<f32>,
+    // From the surface to the camera
+    view_direction: vec3<f32>,
+}
+
+// Radiance reflected toward the camera for an incoming `radiance` from `light_direction` (from the surface to the light)
+fn light_contribution(surface: PbrSurface, light_direction: vec3<f32>, radiance: vec3<f32>) -> vec3<f32> {
+    let n_dot_l = dot(surface.normal, light_direction);
+    if n_dot_l <= 0.0 {
+        return vec3<f32>(0.0);
+    }
+    let half_vector = normalize(surface.view_direction + light_direction);
+    let n_dot_v = max(dot(surface.normal, surface.view_direction), 1e-4);
+    let n_dot_h = max(dot(surface.normal, half_vector), 0.0);
+    let v_dot_h = max(dot(surface.view_direction, half_vector), 0.0);
+    // Perfectly smooth surfaces would give an infinitely thin highlight
+    let roughness = max(surface.roughness, 0.045);
+
+    let fresnel = fresnel_schlick(v_dot_h, specular_f0(surface.base_color, surface.metallic));
+    let specular = distribution_ggx(n_dot_h, roughness) * visibility_smith_ggx(n_dot_v, n_dot_l, roughness) * fresnel;
+    let diffuse = (1.0 - fresnel) * (1.0 - surface.metallic) * surface.base_color / PI;
+    return (diffuse + specular) * radiance * n_dot_l;
+}
+
+// `direction` is the direction the light travels in
+fn directional_light(surface: PbrSurface, direction: vec3<f32>, color: vec3<f32>, intensity: f32) -> vec3<f32> {
+    return light_contribution(surface, -normalize(direction), color * intensity);
+}
+
+// Inverse square falloff, smoothly reaching 0 at `range` (no limit when range <= 0) as recommended by KHR_lights_punctual
+fn point_light(surface: PbrSurface, position: vec3<f32>, light_position: vec3<f32>, color: vec3<f32>, intensity: f32, range: f32) -> vec3<f32> {
+    let to_light = light_position - position;
+    let distance_squared = max(dot(to_light, to_light), 1e-4);
+    var attenuation = 1.0 / distance_squared;
+    if range > 0.0 {
+        let window = clamp(1.0 - pow(distance_squared / (range * range), 2.0), 0.0, 1.0);
+        attenuation *= window * window;
+    }
+    return light_contribution(surface, to_light * inverseSqrt(distance_squared), color * intensity * attenuation);
+}
+
+// Uniform ambient lighting (no image based lighting), attenuated by the occlusion
+fn ambient_light(surface: PbrSurface, color: vec3<f32>) -> vec3<f32> {
+    let n_dot_v = max(dot(surface.normal, surface.view_direction), 1e-4);
+    let fresnel = fresnel_schlick_roughness(n_dot_v, specular_f0(surface.base_color, surface.metallic), surface.roughness);
+    let diffuse = (1.0 - fresnel) * (1.0 - surface.metallic) * surface.base_color;
+    return (diffuse + fresnel) * color * surface.occlusion;
+}
+"#;
+
+// Material uniforms matching `PbrMaterialUniforms` and the normal mapping
+pub const PBR_MATERIAL_WGSL: &str = r#"#define_import_path oxyde::pbr::material
+
+const PBR_TEXTURE_BASE_COLOR: u32 = 1u;
+const PBR_TEXTURE_METALLIC_ROUGHNESS: u32 = 2u;
+const PBR_TEXTURE_NORMAL: u32 = 4u;
+const PBR_TEXTURE_OCCLUSION: u32 = 8u;
+const PBR_TEXTURE_EMISSIVE: u32 = 16u;
+
+struct PbrMaterial {
+    base_color_factor: vec4<f32>,
+    emissive_factor: vec3<f32>,
+    metallic_factor: f32,
+    roughness_factor: f32,
+    normal_scale: f32,
+    occlusion_strength: f32,
+    // Alpha mask threshold, negative when the material is not masked
+    alpha_cutoff: f32,
+    texture_flags: u32,
+}
+
+fn has_texture(material: PbrMaterial, texture: u32) -> bool {
+    return (material.texture_flags & texture) != 0u;
+}
+
+// Tangent space normal map sample (0 to 1 range) to the space of `normal` and `tangent` (w is the bitangent sign)
+fn perturb_normal(normal: vec3<f32>, tangent: vec4<f32>, normal_sample: vec3<f32>, normal_scale: f32) -> vec3<f32> {
+    let orthogonal_tangent = normalize(tangent.xyz - normal * dot(normal, tangent.xyz));
+    let bitangent = cross(normal, orthogonal_tangent) * tangent.w;
+    let tangent_normal = (normal_sample * 2.0 - 1.0) * vec3<f32>(normal_scale, normal_scale, 1.0);
+    return normalize(mat3x3<f32>(orthogonal_tangent, bitangent, normal) * tangent_normal);
+}
+"#;
+
+const PBR_FORWARD_WGSL: &str = r#"#import oxyde::pbr::material::{PbrMaterial, has_texture, perturb_normal, PBR_TEXTURE_NORMAL}
+#import oxyde::pbr::lighting::{PbrSurface, directional_light, ambient_light}
+
+struct PbrScene {
+    view_proj: mat4x4<f32>,
+    camera_position: vec4<f32>,
+    light_direction: vec4<f32>,
+    light_color: vec4<f32>,
+    ambient_color: vec4<f32>,
+}
+
+@group(0) @binding(0) var<uniform> scene: PbrScene;
+
+@group(1) @binding(0) var<uniform> material: PbrMaterial;
+@group(1) @binding(1) var material_sampler: sampler;
+@group(1) @binding(2) var base_color_texture: texture_2d<f32>;
+@group(1) @binding(3) var metallic_roughness_texture: texture_2d<f32>;
+@group(1) @binding(4) var normal_texture: texture_2d<f32>;
+@group(1) @binding(5) var occlusion_texture: texture_2d<f32>;
+@group(1) @binding(6) var emissive_texture: texture_2d<f32>;
+
+struct VertexInput {
+    @location(0) position: vec3<f32>,
+    @location(1) normal: vec3<f32>,
+    @location(2) tangent: vec4<f32>,
+    @location(3) uv: vec2<f32>,
+    @location(4) model_0: vec4<f32>,
+    @location(5) model_1: vec4<f32>,
+    @location(6) model_2: vec4<f32>,
+    @location(7) model_3: vec4<f32>,
+}
+
+struct VertexOutput {
+    @builtin(position) clip_position: vec4<f32>,
+    @location(0) world_position: vec3<f32>,
+    @location(1) normal: vec3<f32>,
+    @location(2) tangent: vec4<f32>,
+    @location(3) uv: vec2<f32>,
+}
+
+@vertex
+fn vs_main(input: VertexInput) -> VertexOutput {
+    let model = mat4x4<f32>(input.model_0, input.model_1, input.model_2, input.model_3);
+    let world_position = model * vec4<f32>(input.position, 1.0);
+    // Normals are transformed by the model matrix, valid for uniform scales only
+    let normal_matrix = mat3x3<f32>(model[0].xyz, model[1].xyz, model[2].xyz);
+    var output: VertexOutput;
+    output.clip_position = scene.view_proj * world_position;
+    output.world_position = world_position.xyz;
+    output.normal = normalize(normal_matrix * input.normal);
+    output.tangent = vec4<f32>(normal_matrix * input.tangent.xyz, input.tangent.w);
+    output.uv = input.uv;
+    return output;
+}
+
+@fragment
+fn fs_main(input: VertexOutput, @builtin(front_facing) front_facing: bool) -> @location(0) vec4<f32> {
+    let base_color = material.base_color_factor * textureSample(base_color_texture, material_sampler, input.uv);
+    if base_color.a < material.alpha_cutoff {
+        discard;
+    }
+    // glTF packs the roughness in green and the metalness in blue
+    let metallic_roughness = textureSample(metallic_roughness_texture, material_sampler, input.uv);
+    let occlusion = textureSample(occlusion_texture, material_sampler, input.uv).r;
+    let emissive = textureSample(emissive_texture, material_sampler, input.uv).rgb;
+
+    var normal = normalize(input.normal);
+    if !front_facing {
+        normal = -normal;
+    }
+    if has_texture(material, PBR_TEXTURE_NORMAL) {
+        let normal_sample = textureSample(normal_texture, material_sampler, input.uv).rgb;
+        normal = perturb_normal(normal, input.tangent, normal_sample, material.normal_scale);
+    }
+
+    var surface: PbrSurface;
+    surface.base_color = base_color.rgb;
+    surface.metallic = material.metallic_factor * metallic_roughness.b;
+    surface.roughness = material.roughness_factor * metallic_roughness.g;
+    surface.occlusion = mix(1.0, occlusion, material.occlusion_strength);
+    surface.emissive = material.emissive_factor * emissive;
+    surface.normal = normal;
+    surface.view_direction = normalize(scene.camera_position.xyz - input.world_position);
+
+    let color = directional_light(surface, scene.light_direction.xyz, scene.light_color.rgb, scene.light_direction.w)
+        + ambient_light(surface, scene.ambient_color.rgb)
+        + surface.emissive;
+    return vec4<f32>(color, base_color.a);
+}
+"#;
+
+// Vertex of the forward pipeline, the tangent w is the bitangent sign (glTF convention)
+#[repr(C)]
+#[derive(Clone, Copy, Default, Debug, bytemuck::Pod, bytemuck::Zeroable)]
+pub struct PbrVertex {
+    pub position: [f32; 3],
+    pub normal: [f32; 3],
+    pub tangent: [f32; 4],
+    pub uv: [f32; 2],
+}
+
+impl PbrVertex {
+    const ATTRIBUTES: [wgpu::VertexAttribute; 4] = wgpu::vertex_attr_array![0 => Float32x3, 1 => Float32x3, 2 => Float32x4, 3 => Float32x2];
+
+    pub fn layout() -> wgpu::VertexBufferLayout<'static> {
+        wgpu::VertexBufferLayout {
+            array_stride: std::mem::size_of::<Self>() as wgpu::BufferAddress,
+            step_mode: wgpu::VertexStepMode::Vertex,
+            attributes: &Self::ATTRIBUTES,
+        }
+    }
+
+    // Fill the tangents from the positions, normals and uvs (see `math::generate_tangents`), for meshes without tangents
+    #[cfg(feature = "math")]
+    pub fn generate_tangents(vertices: &mut [PbrVertex], indices: Option<&[u32]>) {
+        let positions: Vec<[f32; 3]> = vertices.iter().map(|vertex| vertex.position).collect();
+        let normals: Vec<[f32; 3]> = vertices.iter().map(|vertex| vertex.normal).collect();
+        let uvs: Vec<[f32; 2]> = vertices.iter().map(|vertex| vertex.uv).collect();
+        let tangents = crate::math::generate_tangents(&positions, &normals, &uvs, indices);
+        for (vertex, tangent) in vertices.iter_mut().zip(tangents) {
+            vertex.tangent = tangent;
+        }
+    }
+}
+
+// Per instance model matrix of the forward pipeline (second vertex buffer)
+#[repr(C)]
+#[derive(Clone, Copy, Debug, bytemuck::Pod, bytemuck::Zeroable)]
+pub struct PbrInstance {
+    pub model: [[f32; 4]; 4],
+}
+
+impl Default for PbrInstance {
+    fn default() -> Self {
+        Self {
+            model: [[1.0, 0.0, 0.0, 0.0], [0.0, 1.0, 0.0, 0.0], [0.0, 0.0, 1.0, 0.0], [0.0, 0.0, 0.0, 1.0]],
+        }
+    }
+}
+
+impl PbrInstance {
+    const ATTRIBUTES: [wgpu::VertexAttribute; 4] = wgpu::vertex_attr_array![4 => Float32x4, 5 => Float32x4, 6 => Float32x4, 7 => Float32x4];
+
+    pub fn layout() -> wgpu::VertexBufferLayout<'static> {
+        wgpu::VertexBufferLayout {
+            array_stride: std::mem::size_of::<Self>() as wgpu::BufferAddress,
+            step_mode: wgpu::VertexStepMode::Instance,
+            attributes: &Self::ATTRIBUTES,
+        }
+    }
+}
+
+// Camera and lights of the forward pipeline: a directional light and a uniform ambient
+#[repr(C)]
+#[derive(Clone, Copy, Default, Debug, bytemuck::Pod, bytemuck::Zeroable)]
+pub struct PbrSceneUniforms {
+    pub view_proj: [[f32; 4]; 4],
+    // w unused
+    pub camera_position: [f32; 4],
+    // Direction the light travels in, w is the intensity
+    pub light_direction: [f32; 4],
+    // w unused
+    pub light_color: [f32; 4],
+    // w unused
+    pub ambient_color: [f32; 4],
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum PbrTextureSlot {
+    // sRGB color and alpha
+    BaseColor,
+    // Roughness in green, metalness in blue
+    MetallicRoughness,
+    // Tangent space normal map, requires vertex tangents
+    Normal,
+    // Occlusion in red
+    Occlusion,
+    // sRGB emissive color
+    Emissive,
+}
+
+impl PbrTextureSlot {
+    pub const ALL: [PbrTextureSlot; 5] = [
+        PbrTextureSlot::BaseColor,
+        PbrTextureSlot::MetallicRoughness,
+        PbrTextureSlot::Normal,
+        PbrTextureSlot::Occlusion,
+        PbrTextureSlot::Emissive,
+    ];
+
+    // Bit of the slot in `PbrMaterialUniforms::texture_flags`
+    pub fn flag(&self) -> u32 { 1 << *self as u32 }
+}
+
+// Matching WGSL struct of `PBR_MATERIAL_WGSL`
+#[repr(C)]
+#[derive(Clone, Copy, Default, Debug, PartialEq, bytemuck::Pod, bytemuck::Zeroable)]
+pub struct PbrMaterialUniforms {
+    pub base_color_factor: [f32; 4],
+    pub emissive_factor: [f32; 3],
+    pub metallic_factor: f32,
+    pub roughness_factor: f32,
+    pub normal_scale: f32,
+    pub occlusion_strength: f32,
+    pub alpha_cutoff: f32,
+    pub texture_flags: u32,
+    pub _padding: [u32; 3],
+}
+
+// Metallic roughness material (glTF 2.0 model): factors multiplied with the optional textures, missing textures are neutral.
+// Textures are shared (e.g. between the materials of a glTF file), all sampled with the same sampler.
+#[derive(Clone, Debug)]
+pub struct PbrMaterial {
+    pub base_color_factor: [f32; 4],
+    pub metallic_factor: f32,
+    pub roughness_factor: f32,
+    pub emissive_factor: [f32; 3],
+    pub normal_scale: f32,
+    pub occlusion_strength: f32,
+    // Fragments with a lower alpha are discarded, None for opaque or blended materials
+    pub alpha_cutoff: Option<f32>,
+    pub base_color_texture: Option<Arc<wgpu::TextureView>>,
+    pub metallic_roughness_texture: Option<Arc<wgpu::TextureView>>,
+    pub normal_texture: Option<Arc<wgpu::TextureView>>,
+    pub occlusion_texture: Option<Arc<wgpu::TextureView>>,
+    pub emissive_texture: Option<Arc<wgpu::TextureView>>,
+}
+
+// Default values of the glTF specification
+impl Default for PbrMaterial {
+    fn default() -> Self {
+        Self {
+            base_color_factor: [1.0; 4],
+            metallic_factor: 1.0,
+            roughness_factor: 1.0,
+            emissive_factor: [0.0; 3],
+            normal_scale: 1.0,
+            occlusion_strength: 1.0,
+            alpha_cutoff: None,
+            base_color_texture: None,
+            metallic_roughness_texture: None,
+            normal_texture: None,
+            occlusion_texture: None,
+            emissive_texture: None,
+        }
+    }
+}
+
+impl PbrMaterial {
+    pub fn texture(&self, slot: PbrTextureSlot) -> Option<&Arc<wgpu::TextureView>> {
+        match slot {
+            PbrTextureSlot::BaseColor => self.base_color_texture.as_ref(),
+            PbrTextureSlot::MetallicRoughness => self.metallic_roughness_texture.as_ref(),
+            PbrTextureSlot::Normal => self.normal_texture.as_ref(),
+            PbrTextureSlot::Occlusion => self.occlusion_texture.as_ref(),
+            PbrTextureSlot::Emissive => self.emissive_texture.as_ref(),
+        }
+    }
+
+    pub fn with_texture(mut self, slot: PbrTextureSlot, texture: Arc<wgpu::TextureView>) -> Self {
+        let texture = Some(texture);
+        match slot {
+            PbrTextureSlot::BaseColor => self.base_color_texture = texture,
+            PbrTextureSlot::MetallicRoughness => self.metallic_roughness_texture = texture,
+            PbrTextureSlot::Normal => self.normal_texture = texture,
+            PbrTextureSlot::Occlusion => self.occlusion_texture = texture,
+            PbrTextureSlot::Emissive => self.emissive_texture = texture,
+        }
+        self
+    }
+
+    pub fn uniforms(&self) -> PbrMaterialUniforms {
+        PbrMaterialUniforms {
+            base_color_factor: self.base_color_factor,
+            emissive_factor: self.emissive_factor,
+            metallic_factor: self.metallic_factor,
+            roughness_factor: self.roughness_factor,
+            normal_scale: self.normal_scale,
+            occlusion_strength: self.occlusion_strength,
+            alpha_cutoff: self.alpha_cutoff.unwrap_or(-1.0),
+            texture_flags: PbrTextureSlot::ALL
+                .iter()
+                .filter(|slot| self.texture(**slot).is_some())
+                .fold(0, |flags, slot| flags | slot.flag()),
+            _padding: [0; 3],
+        }
+    }
+}
+
+// Material uploaded for the forward pipeline (group 1), see `PbrForwardPipeline::create_material`
+pub struct PbrMaterialBindGroup {
+    uniforms: UniformBuffer<PbrMaterialUniforms>,
+    bind_group: wgpu::BindGroup,
+}
+
+impl PbrMaterialBindGroup {
+    // Update the factors, the textures are those of the creation
+    pub fn update(&mut self, queue: &wgpu::Queue, material: &PbrMaterial) { self.uniforms.update_content(queue, material.uniforms()); }
+
+    pub fn bind_group(&self) -> &wgpu::BindGroup { &self.bind_group }
+}
+
+// Camera and lights uploaded for the forward pipeline (group 0), see `PbrForwardPipeline::create_scene`
+pub struct PbrScene {
+    uniforms: UniformBuffer<PbrSceneUniforms>,
+    bind_group: wgpu::BindGroup,
+}
+
+impl PbrScene {
+    pub fn update(&mut self, queue: &wgpu::Queue, uniforms: PbrSceneUniforms) { self.uniforms.update_content(queue, uniforms); }
+
+    pub fn bind_group(&self) -> &wgpu::BindGroup { &self.bind_group }
+}
+
+// Default forward PBR pipeline: `PbrVertex` and `PbrInstance` vertex buffers, scene bind group 0 and material bind group 1.
+// Outputs linear color, to render to an sRGB target.
+pub struct PbrForwardPipeline {
+    scene_bind_group_layout: BindGroupLayoutWithDesc,
+    material_bind_group_layout: BindGroupLayoutWithDesc,
+    pipeline: wgpu::RenderPipeline,
+    sampler: wgpu::Sampler,
+    // Neutral textures of the missing material slots: white and flat normal
+    white_texture: wgpu::TextureView,
+    flat_normal_texture: wgpu::TextureView,
+    _memory: [MemoryAllocation; 2],
+}
+
+impl PbrForwardPipeline {
+    // `depth_format` with a depth test following the `depth_convention` of the camera projection, back faces are culled
+    // unless `double_sided`
+    pub fn new(
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        color_format: wgpu::TextureFormat,
+        depth_format: Option<wgpu::TextureFormat>,
+        depth_convention: DepthConvention,
+        double_sided: bool,
+    ) -> Result<Self> {
+        let uniform_binding = wgpu::BindingType::Buffer {
+            ty: wgpu::BufferBindingType::Uniform,
+            has_dynamic_offset: false,
+            min_binding_size: None,
+        };
+        let texture_binding = wgpu::BindingType::Texture {
+            sample_type: wgpu::TextureSampleType::Float { filterable: true },
+            view_dimension: wgpu::TextureViewDimension::D2,
+            multisampled: false,
+        };
+        let scene_bind_group_layout = BindGroupLayoutBuilder::new()
+            .add_binding_rendering(uniform_binding)
+            .create(device, Some("PbrForwardPipeline: scene"));
+        let material_bind_group_layout = PbrTextureSlot::ALL
+            .iter()
+            .fold(
+                BindGroupLayoutBuilder::new()
+                    .add_binding_fragment(uniform_binding)
+                    .add_binding_fragment(wgpu::BindingType::Sampler(wgpu::SamplerBindingType::Filtering)),
+                |builder, _| builder.add_binding_fragment(texture_binding),
+            )
+            .create(device, Some("PbrForwardPipeline: material"));
+
+        let module = ShaderComposer::new(PBR_FORWARD_WGSL, Some("oxyde::pbr::forward")).with_pbr()?.build()?;
+        let shader_module = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: Some("PbrForwardPipeline"),
+            source: wgpu::ShaderSource::Naga(std::borrow::Cow::Owned(module)),
+        });
+        let pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("PbrForwardPipeline"),
+            bind_group_layouts: &[&scene_bind_group_layout.layout, &material_bind_group_layout.layout],
+            push_constant_ranges: &[],
+        });
+        let pipeline = device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+            label: Some("PbrForwardPipeline"),
+            layout: Some(&pipeline_layout),
+            vertex: wgpu::VertexState {
+                module: &shader_module,
+                entry_point: "vs_main",
+                buffers: &[PbrVertex::layout(), PbrInstance::layout()],
+            },
+            fragment: Some(wgpu::FragmentState {
+                module: &shader_module,
+                entry_point: "fs_main",
+                targets: &[Some(BlendPreset::AlphaBlend.color_target(color_format))],
+            }),
+            primitive: wgpu::PrimitiveState {
+                cull_mode: (!double_sided).then_some(wgpu::Face::Back),
+                ..Default::default()
+            },
+            depth_stencil: depth_format.map(|format| DepthPreset::TestWrite.depth_stencil(format, depth_convention)),
+            multisample: wgpu::MultisampleState::default(),
+            multiview: None,
+        });
+
+        let sampler = device.create_sampler(&wgpu::SamplerDescriptor {
+            label: Some("PbrForwardPipeline"),
+            address_mode_u: wgpu::AddressMode::Repeat,
+            address_mode_v: wgpu::AddressMode::Repeat,
+            mag_filter: wgpu::FilterMode::Linear,
+            min_filter: wgpu::FilterMode::Linear,
+            mipmap_filter: wgpu::FilterMode::Linear,
+            ..Default::default()
+        });
+
+        let create_texel_texture = |texel: [u8; 4], label: &str| {
+            let texture = device.create_texture_with_data(
+                queue,
+                &wgpu::TextureDescriptor {
+                    label: Some(label),
+                    size: wgpu::Extent3d {
+                        width: 1,
+                        height: 1,
+                        depth_or_array_layers: 1,
+                    },
+                    mip_level_count: 1,
+                    sample_count: 1,
+                    dimension: wgpu::TextureDimension::D2,
+                    format: wgpu::TextureFormat::Rgba8Unorm,
+                    usage: wgpu::TextureUsages::TEXTURE_BINDING,
+                    view_formats: &[],
+                },
+                wgpu::util::TextureDataOrder::LayerMajor,
+                &texel,
+            );
+            let memory = MemoryAllocation::for_texture(&texture, Some(label));
+            (
+                texture.create_view(&wgpu::TextureViewDescriptor {
+                    label: Some(&child_label(Some(label), "view")),
+                    ..Default::default()
+                }),
+                memory,
+            )
+        };
+        let (white_texture, white_memory) = create_texel_texture([255; 4], "PbrForwardPipeline: white");
+        let (flat_normal_texture, flat_normal_memory) = create_texel_texture([128, 128, 255, 255], "PbrForwardPipeline: flat normal");
+
+        Ok(Self {
+            scene_bind_group_layout,
+            material_bind_group_layout,
+            pipeline,
+            sampler,
+            white_texture,
+            flat_normal_texture,
+            _memory: [white_memory, flat_normal_memory],
+        })
+    }
+
+    pub fn pipeline(&self) -> &wgpu::RenderPipeline { &self.pipeline }
+
+    pub fn scene_bind_group_layout(&self) -> &BindGroupLayoutWithDesc { &self.scene_bind_group_layout }
+
+    pub fn material_bind_group_layout(&self) -> &BindGroupLayoutWithDesc { &self.material_bind_group_layout }
+
+    pub fn create_scene(&self, device: &wgpu::Device) -> PbrScene {
+        let uniforms = UniformBuffer::new(device);
+        let bind_group = BindGroupBuilder::new(&self.scene_bind_group_layout)
+            .resource(uniforms.binding_resource())
+            .create(device, Some("PbrForwardPipeline: scene"));
+        PbrScene { uniforms, bind_group }
+    }
+
+    pub fn create_material(&self, device: &wgpu::Device, material: &PbrMaterial) -> PbrMaterialBindGroup {
+        let uniforms = UniformBuffer::new_with_data(device, &material.uniforms());
+        let bind_group = PbrTextureSlot::ALL
+            .iter()
+            .fold(
+                BindGroupBuilder::new(&self.material_bind_group_layout)
+                    .resource(uniforms.binding_resource())
+                    .sampler(&self.sampler),
+                |builder, slot| {
+                    let default_texture = match slot {
+                        PbrTextureSlot::Normal => &self.flat_normal_texture,
+                        _ => &self.white_texture,
+                    };
+                    builder.texture(material.texture(*slot).map_or(default_texture, |texture| texture.as_ref()))
+                },
+            )
+            .create(device, Some("PbrForwardPipeline: material"));
+        PbrMaterialBindGroup { uniforms, bind_group }
+    }
+
+    // Set the pipeline and the bind groups, the vertex and instance buffers are left to the caller
+    pub fn bind<'a>(&'a self, render_pass: &mut wgpu::RenderPass<'a>, scene: &'a PbrScene, material: &'a PbrMaterialBindGroup) {
+        render_pass.set_pipeline(&self.pipeline);
+        render_pass.set_bind_group(0, &scene.bind_group, &[]);
+        render_pass.set_bind_group(1, &material.bind_group, &[]);
+    }
+}
@@ -0,0 +1,213 @@
+use super::{buffers::MapFuture, memory_tracker::MemoryAllocation, TextureReadback};
+
+// Object of the picked pixel (or of the closest one in the picking region), None when there is only background
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct PickResult {
+    pub position: (u32, u32),
+    pub object_id: Option<u32>,
+}
+
+#[derive(Clone, Copy)]
+struct PendingPick {
+    position: (u32, u32),
+    // Top left corner of the copied region
+    origin: (u32, u32),
+    // Order of the requests, only the latest resolved pick is returned
+    index: u64,
+}
+
+enum PickSlotState {
+    Free,
+    // Region copy encoded, mapped once the frame is submitted
+    Copied(PendingPick),
+    Mapping(PendingPick, MapFuture),
+}
+
+struct PickSlot {
+    readback: TextureReadback,
+    state: PickSlotState,
+}
+
+// Mouse picking through an object ID buffer: objects are rendered with their ID to the R32Uint `view` (0 is no object),
+// a small region around the requested position is copied and mapped asynchronously, resolved a frame or two later.
+// Usage per frame: render the IDs (see `color_attachment`), `encode_readback` after the ID pass,
+// `after_submit` once the frame is submitted then `poll` for the results.
+pub struct ObjectPicking {
+    texture: wgpu::Texture,
+    view: wgpu::TextureView,
+    // Half size of the read region, the closest object of the region is picked to make thin objects easier to select
+    region_radius: u32,
+    slots: Vec<PickSlot>,
+    request: Option<(u32, u32)>,
+    pick_count: u64,
+    _memory: MemoryAllocation,
+}
+
+impl ObjectPicking {
+    pub const FORMAT: wgpu::TextureFormat = wgpu::TextureFormat::R32Uint;
+    pub const NO_OBJECT: u32 = 0;
+    const SLOT_COUNT: usize = 3;
+
+    pub fn new(device: &wgpu::Device, width: u32, height: u32, region_radius: u32) -> Self {
+        let (texture, view, memory) = Self::create_texture(device, width, height);
+        Self {
+            texture,
+            view,
+            region_radius,
+            slots: Vec::new(),
+            request: None,
+            pick_count: 0,
+            _memory: memory,
+        }
+    }
+
+    fn create_texture(device: &wgpu::Device, width: u32, height: u32) -> (wgpu::Texture, wgpu::TextureView, MemoryAllocation) {
+        let texture = device.create_texture(&wgpu::TextureDescriptor {
+            label: Some("ObjectPicking: ids"),
+            size: wgpu::Extent3d {
+                width: width.max(1),
+                height: height.max(1),
+                depth_or_array_layers: 1,
+            },
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: wgpu::TextureDimension::D2,
+            format: Self::FORMAT,
+            usage: wgpu::TextureUsages::RENDER_ATTACHMENT | wgpu::TextureUsages::COPY_SRC,
+            view_formats: &[],
+        });
+        let view = texture.create_view(&wgpu::TextureViewDescriptor {
+            label: Some("ObjectPicking: ids view"),
+            ..Default::default()
+        });
+        let memory = MemoryAllocation::for_texture(&texture, Some("ObjectPicking: ids"));
+        (texture, view, memory)
+    }
+
+    // Recreate the ID target, pending readbacks of the previous size are still resolved
+    pub fn resize(&mut self, device: &wgpu::Device, width: u32, height: u32) {
+        (self.texture, self.view, self._memory) = Self::create_texture(device, width, height);
+    }
+
+    pub fn view(&self) -> &wgpu::TextureView { &self.view }
+
+    pub fn size(&self) -> (u32, u32) { (self.texture.width(), self.texture.height()) }
+
+    // Attachment of the ID pass, cleared to `NO_OBJECT`
+    pub fn color_attachment(&self) -> wgpu::RenderPassColorAttachment<'_> {
+        wgpu::RenderPassColorAttachment {
+            view: &self.view,
+            resolve_target: None,
+            ops: wgpu::Operations {
+                load: wgpu::LoadOp::Clear(wgpu::Color::TRANSPARENT),
+                store: wgpu::StoreOp::Store,
+            },
+        }
+    }
+
+    // Pick at a position in pixels of the ID target, replacing the request not encoded yet
+    pub fn request_pick(&mut self, x: u32, y: u32) { self.request = Some((x, y)); }
+
+    pub fn is_pending(&self) -> bool { self.request.is_some() || self.slots.iter().any(|slot| !matches!(slot.state, PickSlotState::Free)) }
+
+    // Copy the region around the requested position, the request waits for the next frame when all the slots are in use
+    pub fn encode_readback(&mut self, device: &wgpu::Device, encoder: &mut wgpu::CommandEncoder) {
+        let Some((x, y)) = self.request else {
+            return;
+        };
+        let (width, height) = self.size();
+        if x >= width || y >= height {
+            self.request = None;
+            return;
+        }
+
+        let region_size = ((self.region_radius * 2 + 1).min(width), (self.region_radius * 2 + 1).min(height));
+        // Free readbacks of another region size (the target is smaller than the region) are recreated
+        self.slots
+            .retain(|slot| !matches!(slot.state, PickSlotState::Free) || (slot.readback.width(), slot.readback.height()) == region_size);
+        let slot_index = match self.slots.iter().position(|slot| matches!(slot.state, PickSlotState::Free)) {
+            Some(slot_index) => slot_index,
+            None if self.slots.len() < Self::SLOT_COUNT => {
+                let readback = TextureReadback::new(device, Self::FORMAT, region_size.0, region_size.1, Some("ObjectPicking"))
+                    .expect("R32Uint can be read back");
+                self.slots.push(PickSlot { readback, state: PickSlotState::Free });
+                self.slots.len() - 1
+            },
+            None => return,
+        };
+
+        // The region is moved inside the target near its borders
+        let origin = (
+            x.saturating_sub(self.region_radius).min(width - region_size.0),
+            y.saturating_sub(self.region_radius).min(height - region_size.1),
+        );
+        let slot = &mut self.slots[slot_index];
+        slot.readback.encode_copy_from(
+            encoder,
+            wgpu::ImageCopyTexture {
+                texture: &self.texture,
+                mip_level: 0,
+                origin: wgpu::Origin3d { x: origin.0, y: origin.1, z: 0 },
+                aspect: wgpu::TextureAspect::All,
+            },
+        );
+        slot.state = PickSlotState::Copied(PendingPick {
+            position: (x, y),
+            origin,
+            index: self.pick_count,
+        });
+        self.pick_count += 1;
+        self.request = None;
+    }
+
+    pub fn after_submit(&mut self) {
+        for slot in &mut self.slots {
+            if let PickSlotState::Copied(pending_pick) = slot.state {
+                slot.state = PickSlotState::Mapping(pending_pick, slot.readback.map());
+            }
+        }
+    }
+
+    // Latest resolved pick if any, without blocking
+    pub fn poll(&mut self, device: &wgpu::Device) -> Option<PickResult> {
+        if !self.slots.iter().any(|slot| matches!(slot.state, PickSlotState::Mapping(..))) {
+            return None;
+        }
+        device.poll(wgpu::Maintain::Poll);
+
+        let region_radius = self.region_radius;
+        let mut latest: Option<(u64, PickResult)> = None;
+        for slot in &mut self.slots {
+            let PickSlotState::Mapping(pending_pick, map_future) = &slot.state else {
+                continue;
+            };
+            let Some(map_result) = map_future.try_take_result() else {
+                continue;
+            };
+            let PendingPick { position, origin, index } = *pending_pick;
+            slot.state = PickSlotState::Free;
+            if map_result.is_err() {
+                continue;
+            }
+
+            let ids: Vec<u32> = slot.readback.read();
+            let region_width = slot.readback.width();
+            let object_id = ids
+                .iter()
+                .enumerate()
+                .filter(|(_, id)| **id != Self::NO_OBJECT)
+                .map(|(index, id)| {
+                    let pixel_x = origin.0 + index as u32 % region_width;
+                    let pixel_y = origin.1 + index as u32 / region_width;
+                    (pixel_x.abs_diff(position.0).pow(2) + pixel_y.abs_diff(position.1).pow(2), *id)
+                })
+                .filter(|(distance_squared, _)| *distance_squared <= region_radius.pow(2))
+                .min_by_key(|(distance_squared, _)| *distance_squared)
+                .map(|(_, id)| id);
+            if latest.is_none_or(|(latest_index, _)| index > latest_index) {
+                latest = Some((index, PickResult { position, object_id }));
+            }
+        }
+        latest.map(|(_, result)| result)
+    }
+}
@@ -1,7 +1,33 @@
-use super::binding_builder::{BindGroupBuilder, BindGroupLayoutBuilder, BindGroupLayoutWithDesc};
+use super::{
+    binding_builder::{BindGroupBuilder, BindGroupLayoutBuilder, BindGroupLayoutWithDesc},
+    child_label,
+    memory_tracker::MemoryAllocation,
+    variant_label,
+};
 
 use wgpu::BindGroupLayout;
 
+// Storage access of the bindings created by a PingPongBuffer
+#[derive(Clone, Copy, Debug)]
+pub struct PingPongBufferAccess {
+    // Source (first) binding of the ping pong bind group
+    pub source_read_only: bool,
+    // Target (second) binding of the ping pong bind group
+    pub target_read_only: bool,
+    // Binding of the single buffer bind groups
+    pub single_buffer_read_only: bool,
+}
+
+impl Default for PingPongBufferAccess {
+    fn default() -> Self {
+        Self {
+            source_read_only: true,
+            target_read_only: false,
+            single_buffer_read_only: true,
+        }
+    }
+}
+
 pub struct PingPongBuffer {
     ping_buffer: wgpu::Buffer,
     pong_buffer: wgpu::Buffer,
@@ -12,47 +38,33 @@ pub struct PingPongBuffer {
     ping_bind_group: wgpu::BindGroup,
     pong_bind_group: wgpu::BindGroup,
     state: bool,
+    _memory: [MemoryAllocation; 2],
 }
 
 impl PingPongBuffer {
+    pub fn builder<'a>(size: wgpu::BufferAddress) -> PingPongBufferBuilder<'a> { PingPongBufferBuilder::new(size) }
+
     pub fn from_buffer_descriptor(
         device: &wgpu::Device,
         descriptor: &wgpu::BufferDescriptor,
         single_buffer_visibility: wgpu::ShaderStages,
         ping_pong_buffer_visibility: wgpu::ShaderStages,
     ) -> Self {
-        // TODO: add suffix to label on descriptor using method map_label
-        let ping_buffer = device.create_buffer(descriptor);
-        let pong_buffer = device.create_buffer(descriptor);
+        let ping_label = variant_label(descriptor.label, "ping");
+        let pong_label = variant_label(descriptor.label, "pong");
+        let ping_buffer = device.create_buffer(&descriptor.map_label(|_| Some(ping_label.as_str())));
+        let pong_buffer = device.create_buffer(&descriptor.map_label(|_| Some(pong_label.as_str())));
 
-        let (
-            ping_pong_bind_group_layout_builder_descriptor,
-            ping_pong_bind_group,
-            pong_ping_bind_group,
-            single_buffer_bind_group_layout_builder_descriptor,
-            ping_bind_group,
-            pong_bind_group,
-        ) = Self::create_layout_and_bind_group(
+        Self::from_buffers(
             device,
-            &ping_buffer,
-            &pong_buffer,
+            ping_buffer,
+            pong_buffer,
             single_buffer_visibility,
             ping_pong_buffer_visibility,
+            PingPongBufferAccess::default(),
             descriptor.label,
             descriptor.size,
-        );
-
-        Self {
-            ping_buffer,
-            pong_buffer,
-            ping_pong_bind_group_layout_builder_descriptor,
-            ping_pong_bind_group,
-            pong_ping_bind_group,
-            single_buffer_bind_group_layout_builder_descriptor,
-            ping_bind_group,
-            pong_bind_group,
-            state: false,
-        }
+        )
     }
 
     pub fn from_buffer_init_descriptor(
@@ -61,9 +73,36 @@ impl PingPongBuffer {
         single_buffer_visibility: wgpu::ShaderStages,
         ping_pong_buffer_visibility: wgpu::ShaderStages,
     ) -> Self {
-        let ping_buffer = wgpu::util::DeviceExt::create_buffer_init(device, descriptor);
-        let pong_buffer = wgpu::util::DeviceExt::create_buffer_init(device, descriptor);
+        let ping_label = variant_label(descriptor.label, "ping");
+        let pong_label = variant_label(descriptor.label, "pong");
+        let ping_buffer =
+            wgpu::util::DeviceExt::create_buffer_init(device, &wgpu::util::BufferInitDescriptor { label: Some(&ping_label), ..*descriptor });
+        let pong_buffer =
+            wgpu::util::DeviceExt::create_buffer_init(device, &wgpu::util::BufferInitDescriptor { label: Some(&pong_label), ..*descriptor });
 
+        Self::from_buffers(
+            device,
+            ping_buffer,
+            pong_buffer,
+            single_buffer_visibility,
+            ping_pong_buffer_visibility,
+            PingPongBufferAccess::default(),
+            descriptor.label,
+            descriptor.contents.len() as u64,
+        )
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    fn from_buffers(
+        device: &wgpu::Device,
+        ping_buffer: wgpu::Buffer,
+        pong_buffer: wgpu::Buffer,
+        single_buffer_visibility: wgpu::ShaderStages,
+        ping_pong_buffer_visibility: wgpu::ShaderStages,
+        access: PingPongBufferAccess,
+        label: Option<&str>,
+        size: u64,
+    ) -> Self {
         let (
             ping_pong_bind_group_layout_builder_descriptor,
             ping_pong_bind_group,
@@ -71,16 +110,22 @@ impl PingPongBuffer {
             single_buffer_bind_group_layout_builder_descriptor,
             ping_bind_group,
             pong_bind_group,
-        ) = Self::create_layout_and_bind_group(
+        ) = Self::create_layout_and_bind_group_with_access(
             device,
             &ping_buffer,
             &pong_buffer,
             single_buffer_visibility,
             ping_pong_buffer_visibility,
-            descriptor.label,
-            descriptor.contents.len() as u64,
+            access,
+            label,
+            size,
         );
 
+        let memory = [
+            MemoryAllocation::for_buffer(&ping_buffer, Some(&variant_label(Some(label.unwrap_or("PingPongBuffer")), "ping"))),
+            MemoryAllocation::for_buffer(&pong_buffer, Some(&variant_label(Some(label.unwrap_or("PingPongBuffer")), "pong"))),
+        ];
+
         Self {
             ping_buffer,
             pong_buffer,
@@ -91,6 +136,7 @@ impl PingPongBuffer {
             ping_bind_group,
             pong_bind_group,
             state: false,
+            _memory: memory,
         }
     }
 
@@ -110,13 +156,41 @@ impl PingPongBuffer {
         wgpu::BindGroup,
         wgpu::BindGroup,
     ) {
-        let label = label.unwrap_or("unknown");
+        Self::create_layout_and_bind_group_with_access(
+            device,
+            ping_buffer,
+            pong_buffer,
+            single_buffer_visibility,
+            ping_pong_buffer_visibility,
+            PingPongBufferAccess::default(),
+            label,
+            size,
+        )
+    }
 
+    #[allow(clippy::too_many_arguments)]
+    pub fn create_layout_and_bind_group_with_access(
+        device: &wgpu::Device,
+        ping_buffer: &wgpu::Buffer,
+        pong_buffer: &wgpu::Buffer,
+        single_buffer_visibility: wgpu::ShaderStages,
+        ping_pong_buffer_visibility: wgpu::ShaderStages,
+        access: PingPongBufferAccess,
+        label: Option<&str>,
+        size: u64,
+    ) -> (
+        BindGroupLayoutWithDesc,
+        wgpu::BindGroup,
+        wgpu::BindGroup,
+        BindGroupLayoutWithDesc,
+        wgpu::BindGroup,
+        wgpu::BindGroup,
+    ) {
         let ping_pong_bind_group_layout_builder_descriptor = BindGroupLayoutBuilder::new()
             .add_binding(
                 ping_pong_buffer_visibility,
                 wgpu::BindingType::Buffer {
-                    ty: wgpu::BufferBindingType::Storage { read_only: true },
+                    ty: wgpu::BufferBindingType::Storage { read_only: access.source_read_only },
                     has_dynamic_offset: false,
                     min_binding_size: wgpu::BufferSize::new(size),
                 },
@@ -124,41 +198,43 @@ impl PingPongBuffer {
             .add_binding(
                 ping_pong_buffer_visibility,
                 wgpu::BindingType::Buffer {
-                    ty: wgpu::BufferBindingType::Storage { read_only: false },
+                    ty: wgpu::BufferBindingType::Storage { read_only: access.target_read_only },
                     has_dynamic_offset: false,
                     min_binding_size: wgpu::BufferSize::new(size),
                 },
             )
-            .create(device, Some(format!("{} ping_pong_bind_group_layout", label).as_str()));
+            .create(device, Some(&child_label(label, "ping_pong_bind_group_layout")));
 
         let ping_pong_bind_group = BindGroupBuilder::new(&ping_pong_bind_group_layout_builder_descriptor)
             .resource(ping_buffer.as_entire_binding())
             .resource(pong_buffer.as_entire_binding())
-            .create(device, Some(format!("{} ping_pong_bind_group", label).as_str()));
+            .create(device, Some(&child_label(label, "ping_pong_bind_group")));
 
         let pong_ping_bind_group = BindGroupBuilder::new(&ping_pong_bind_group_layout_builder_descriptor)
             .resource(pong_buffer.as_entire_binding())
             .resource(ping_buffer.as_entire_binding())
-            .create(device, Some(format!("{} pong_ping_bind_group", label).as_str()));
+            .create(device, Some(&child_label(label, "pong_ping_bind_group")));
 
         let single_buffer_bind_group_layout_builder_descriptor = BindGroupLayoutBuilder::new()
             .add_binding(
                 single_buffer_visibility,
                 wgpu::BindingType::Buffer {
-                    ty: wgpu::BufferBindingType::Storage { read_only: true },
+                    ty: wgpu::BufferBindingType::Storage {
+                        read_only: access.single_buffer_read_only,
+                    },
                     has_dynamic_offset: false,
                     min_binding_size: wgpu::BufferSize::new(size),
                 },
             )
-            .create(device, Some(format!("{} buffer_bind_group_layout", label).as_str()));
+            .create(device, Some(&child_label(label, "buffer_bind_group_layout")));
 
         let ping_bind_group = BindGroupBuilder::new(&single_buffer_bind_group_layout_builder_descriptor)
             .resource(ping_buffer.as_entire_binding())
-            .create(device, Some(format!("{} ping_bind_group", label).as_str()));
+            .create(device, Some(&child_label(label, "ping_bind_group")));
 
         let pong_bind_group = BindGroupBuilder::new(&single_buffer_bind_group_layout_builder_descriptor)
             .resource(pong_buffer.as_entire_binding())
-            .create(device, Some(format!("{} pong_bind_group", label).as_str()));
+            .create(device, Some(&child_label(label, "pong_bind_group")));
 
         (
             ping_pong_bind_group_layout_builder_descriptor,
@@ -214,3 +290,110 @@ impl PingPongBuffer {
     pub fn get_ping_pong_bind_group_layout(&self) -> &BindGroupLayout { &self.ping_pong_bind_group_layout_builder_descriptor.layout }
     pub fn get_buffer_bind_group_layout(&self) -> &BindGroupLayout { &self.single_buffer_bind_group_layout_builder_descriptor.layout }
 }
+
+// Builder for PingPongBuffer allowing distinct initial contents, extra usages and per side binding access
+pub struct PingPongBufferBuilder<'a> {
+    label: Option<&'a str>,
+    size: wgpu::BufferAddress,
+    ping_contents: Option<&'a [u8]>,
+    pong_contents: Option<&'a [u8]>,
+    usage: wgpu::BufferUsages,
+    single_buffer_visibility: wgpu::ShaderStages,
+    ping_pong_buffer_visibility: wgpu::ShaderStages,
+    access: PingPongBufferAccess,
+}
+
+impl<'a> PingPongBufferBuilder<'a> {
+    pub fn new(size: wgpu::BufferAddress) -> Self {
+        Self {
+            label: None,
+            size,
+            ping_contents: None,
+            pong_contents: None,
+            usage: wgpu::BufferUsages::STORAGE,
+            single_buffer_visibility: wgpu::ShaderStages::all(),
+            ping_pong_buffer_visibility: wgpu::ShaderStages::COMPUTE,
+            access: PingPongBufferAccess::default(),
+        }
+    }
+
+    pub fn label(mut self, label: &'a str) -> Self {
+        self.label = Some(label);
+        self
+    }
+
+    // Same initial contents for both buffers
+    pub fn contents(self, contents: &'a [u8]) -> Self { self.ping_contents(contents).pong_contents(contents) }
+
+    pub fn ping_contents(mut self, contents: &'a [u8]) -> Self {
+        assert!(contents.len() as wgpu::BufferAddress <= self.size, "ping contents are bigger than the buffer size");
+        self.ping_contents = Some(contents);
+        self
+    }
+
+    pub fn pong_contents(mut self, contents: &'a [u8]) -> Self {
+        assert!(contents.len() as wgpu::BufferAddress <= self.size, "pong contents are bigger than the buffer size");
+        self.pong_contents = Some(contents);
+        self
+    }
+
+    // Usages added to the STORAGE one (COPY_SRC, COPY_DST, VERTEX, INDIRECT...)
+    pub fn usages(mut self, usage: wgpu::BufferUsages) -> Self {
+        self.usage |= usage;
+        self
+    }
+
+    pub fn visibility(mut self, single_buffer_visibility: wgpu::ShaderStages, ping_pong_buffer_visibility: wgpu::ShaderStages) -> Self {
+        self.single_buffer_visibility = single_buffer_visibility;
+        self.ping_pong_buffer_visibility = ping_pong_buffer_visibility;
+        self
+    }
+
+    pub fn source_read_only(mut self, read_only: bool) -> Self {
+        self.access.source_read_only = read_only;
+        self
+    }
+
+    pub fn target_read_only(mut self, read_only: bool) -> Self {
+        self.access.target_read_only = read_only;
+        self
+    }
+
+    pub fn single_buffer_read_only(mut self, read_only: bool) -> Self {
+        self.access.single_buffer_read_only = read_only;
+        self
+    }
+
+    pub fn build(self, device: &wgpu::Device) -> PingPongBuffer {
+        let ping_buffer = self.create_buffer(device, self.ping_contents, &variant_label(self.label, "ping"));
+        let pong_buffer = self.create_buffer(device, self.pong_contents, &variant_label(self.label, "pong"));
+
+        PingPongBuffer::from_buffers(
+            device,
+            ping_buffer,
+            pong_buffer,
+            self.single_buffer_visibility,
+            self.ping_pong_buffer_visibility,
+            self.access,
+            self.label,
+            self.size,
+        )
+    }
+
+    fn create_buffer(&self, device: &wgpu::Device, contents: Option<&[u8]>, label: &str) -> wgpu::Buffer {
+        let buffer = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some(label),
+            // Mapped buffers size must be a multiple of COPY_BUFFER_ALIGNMENT
+            size: wgpu::util::align_to(self.size, wgpu::COPY_BUFFER_ALIGNMENT),
+            usage: self.usage,
+            mapped_at_creation: contents.is_some(),
+        });
+
+        if let Some(contents) = contents {
+            buffer.slice(..).get_mapped_range_mut()[..contents.len()].copy_from_slice(contents);
+            buffer.unmap();
+        }
+
+        buffer
+    }
+}
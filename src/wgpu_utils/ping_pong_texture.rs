@@ -1,9 +1,82 @@
-use super::binding_builder::{BindGroupBuilder, BindGroupLayoutBuilder, BindGroupLayoutWithDesc};
+use super::{
+    binding_builder::{BindGroupBuilder, BindGroupLayoutBuilder, BindGroupLayoutWithDesc},
+    child_label,
+    memory_tracker::MemoryAllocation,
+    variant_label,
+};
+
+// How a texture of the PingPongTexture is bound in the generated bind groups
+#[derive(Clone, Copy, Debug)]
+pub enum PingPongTextureBinding {
+    Sampled(wgpu::TextureSampleType),
+    // The texture descriptor usage must contain STORAGE_BINDING
+    Storage {
+        access: wgpu::StorageTextureAccess,
+        format: wgpu::TextureFormat,
+    },
+}
+
+impl PingPongTextureBinding {
+    fn binding_type(&self, view_dimension: wgpu::TextureViewDimension) -> wgpu::BindingType {
+        match *self {
+            PingPongTextureBinding::Sampled(sample_type) => wgpu::BindingType::Texture {
+                multisampled: false,
+                view_dimension,
+                sample_type,
+            },
+            PingPongTextureBinding::Storage { access, format } => wgpu::BindingType::StorageTexture { access, format, view_dimension },
+        }
+    }
+}
+
+// Layout of the bind groups generated by a PingPongTexture
+// Each bind group contains the source texture, then the target texture if any, then the sampler if any
+#[derive(Clone, Copy, Debug)]
+pub struct PingPongTextureLayout {
+    pub visibility: wgpu::ShaderStages,
+    pub source: PingPongTextureBinding,
+    // Bind the other texture as well (e.g. as a write only storage texture for compute based simulations)
+    pub target: Option<PingPongTextureBinding>,
+    pub sampler: Option<wgpu::SamplerBindingType>,
+}
+
+impl Default for PingPongTextureLayout {
+    fn default() -> Self {
+        Self {
+            visibility: wgpu::ShaderStages::FRAGMENT,
+            source: PingPongTextureBinding::Sampled(wgpu::TextureSampleType::Float { filterable: true }),
+            target: None,
+            sampler: Some(wgpu::SamplerBindingType::Filtering),
+        }
+    }
+}
+
+impl PingPongTextureLayout {
+    // Compute layout reading the source texture without sampler and writing the target as a storage texture
+    pub fn compute(target_format: wgpu::TextureFormat) -> Self {
+        Self {
+            visibility: wgpu::ShaderStages::COMPUTE,
+            source: PingPongTextureBinding::Sampled(wgpu::TextureSampleType::Float { filterable: false }),
+            target: Some(PingPongTextureBinding::Storage {
+                access: wgpu::StorageTextureAccess::WriteOnly,
+                format: target_format,
+            }),
+            sampler: None,
+        }
+    }
+}
 
 pub struct PingPongTexture {
     label: Option<&'static str>,
+    // Descriptor used to (re)create the textures, its view formats are stored aside to own them
+    descriptor: wgpu::TextureDescriptor<'static>,
+    view_formats: Vec<wgpu::TextureFormat>,
+    texture_ping: wgpu::Texture,
+    texture_pong: wgpu::Texture,
     view_ping: wgpu::TextureView,
     view_pong: wgpu::TextureView,
+    _memory: [MemoryAllocation; 2],
+    layout: PingPongTextureLayout,
     pub bind_group_layout: BindGroupLayoutWithDesc,
     pub state: bool,
 }
@@ -14,43 +87,190 @@ impl PingPongTexture {
         descriptor: &wgpu::TextureDescriptor,
         label: Option<&'static str>, // Optional debug label. This will show up in graphics debuggers for easy identification.
     ) -> Result<Self, wgpu::Error> {
-        let texture_ping = device.create_texture(descriptor);
-        let texture_pong = device.create_texture(descriptor);
-        let view_ping = texture_ping.create_view(&wgpu::TextureViewDescriptor::default());
-        let view_pong = texture_pong.create_view(&wgpu::TextureViewDescriptor::default());
+        Self::from_descriptor_with_layout(device, descriptor, PingPongTextureLayout::default(), label)
+    }
 
-        let bind_group_layout = BindGroupLayoutBuilder::new()
-            .add_binding_fragment(wgpu::BindingType::Texture {
-                multisampled: false,
-                view_dimension: wgpu::TextureViewDimension::D2,
-                sample_type: wgpu::TextureSampleType::Float { filterable: true },
-            })
-            .add_binding_fragment(wgpu::BindingType::Sampler(wgpu::SamplerBindingType::Filtering))
-            .create(device, label);
+    pub fn from_descriptor_with_layout(
+        device: &wgpu::Device,
+        descriptor: &wgpu::TextureDescriptor,
+        layout: PingPongTextureLayout,
+        label: Option<&'static str>,
+    ) -> Result<Self, wgpu::Error> {
+        let view_formats = descriptor.view_formats.to_vec();
+        let descriptor = wgpu::TextureDescriptor {
+            label,
+            size: descriptor.size,
+            mip_level_count: descriptor.mip_level_count,
+            sample_count: descriptor.sample_count,
+            dimension: descriptor.dimension,
+            format: descriptor.format,
+            usage: descriptor.usage,
+            view_formats: &[],
+        };
+        let (texture_ping, texture_pong) = Self::create_textures(device, &descriptor, &view_formats);
+        let view_dimension = Self::view_dimension_from_descriptor(&descriptor);
+        let view_ping = Self::create_default_view(&texture_ping, view_dimension, label, "ping");
+        let view_pong = Self::create_default_view(&texture_pong, view_dimension, label, "pong");
+        let memory = Self::track_memory(&texture_ping, &texture_pong, label);
+
+        let mut bind_group_layout_builder = BindGroupLayoutBuilder::new().add_binding(layout.visibility, layout.source.binding_type(view_dimension));
+        if let Some(target) = layout.target {
+            bind_group_layout_builder = bind_group_layout_builder.add_binding(layout.visibility, target.binding_type(view_dimension));
+        }
+        if let Some(sampler) = layout.sampler {
+            bind_group_layout_builder = bind_group_layout_builder.add_binding(layout.visibility, wgpu::BindingType::Sampler(sampler));
+        }
+        let bind_group_layout = bind_group_layout_builder.create(device, label);
 
         Ok(Self {
             label,
+            descriptor,
+            view_formats,
+            texture_ping,
+            texture_pong,
             view_ping,
             view_pong,
+            _memory: memory,
+            layout,
             bind_group_layout,
             state: false,
         })
     }
 
+    fn create_textures(
+        device: &wgpu::Device,
+        descriptor: &wgpu::TextureDescriptor<'static>,
+        view_formats: &[wgpu::TextureFormat],
+    ) -> (wgpu::Texture, wgpu::Texture) {
+        let create_texture = |variant: &str| {
+            device.create_texture(&wgpu::TextureDescriptor {
+                label: Some(&variant_label(descriptor.label, variant)),
+                view_formats,
+                ..descriptor.clone()
+            })
+        };
+        (create_texture("ping"), create_texture("pong"))
+    }
+
+    fn track_memory(texture_ping: &wgpu::Texture, texture_pong: &wgpu::Texture, label: Option<&str>) -> [MemoryAllocation; 2] {
+        [
+            MemoryAllocation::for_texture(texture_ping, Some(&variant_label(Some(label.unwrap_or("PingPongTexture")), "ping"))),
+            MemoryAllocation::for_texture(texture_pong, Some(&variant_label(Some(label.unwrap_or("PingPongTexture")), "pong"))),
+        ]
+    }
+
+    fn create_default_view(texture: &wgpu::Texture, dimension: wgpu::TextureViewDimension, label: Option<&str>, variant: &str) -> wgpu::TextureView {
+        texture.create_view(&wgpu::TextureViewDescriptor {
+            label: Some(&variant_label(label, variant)),
+            dimension: Some(dimension),
+            ..Default::default()
+        })
+    }
+
+    // 2D textures with several layers are viewed (and bound) as 2D arrays
+    fn view_dimension_from_descriptor(descriptor: &wgpu::TextureDescriptor) -> wgpu::TextureViewDimension {
+        match descriptor.dimension {
+            wgpu::TextureDimension::D1 => wgpu::TextureViewDimension::D1,
+            wgpu::TextureDimension::D2 if descriptor.size.depth_or_array_layers > 1 => wgpu::TextureViewDimension::D2Array,
+            wgpu::TextureDimension::D2 => wgpu::TextureViewDimension::D2,
+            wgpu::TextureDimension::D3 => wgpu::TextureViewDimension::D3,
+        }
+    }
+
+    // Recreate both textures with the new size, their content is lost.
+    // Returns true if the textures were recreated, in which case bind groups previously created reference the old textures
+    // and must be created again with `create_bind_groups`.
+    pub fn resize(&mut self, device: &wgpu::Device, size: wgpu::Extent3d) -> bool {
+        if size == self.descriptor.size {
+            return false;
+        }
+
+        // The view dimension must not change as it is part of the bind group layout
+        assert_eq!(
+            Self::view_dimension_from_descriptor(&wgpu::TextureDescriptor { size, ..self.descriptor.clone() }),
+            self.view_dimension(),
+            "PingPongTexture can't be resized to a size changing its view dimension"
+        );
+
+        self.descriptor.size = size;
+        (self.texture_ping, self.texture_pong) = Self::create_textures(device, &self.descriptor, &self.view_formats);
+        self.view_ping = Self::create_default_view(&self.texture_ping, self.view_dimension(), self.label, "ping");
+        self.view_pong = Self::create_default_view(&self.texture_pong, self.view_dimension(), self.label, "pong");
+        self._memory = Self::track_memory(&self.texture_ping, &self.texture_pong, self.label);
+        true
+    }
+
+    pub fn view_dimension(&self) -> wgpu::TextureViewDimension { Self::view_dimension_from_descriptor(&self.descriptor) }
+
+    // Number of layers of 2D array textures (1 for other dimensions, 3D textures depth is not made of layers)
+    pub fn array_layer_count(&self) -> u32 { self.descriptor.array_layer_count() }
+
+    // Create 2D views on a single layer of ping and pong, e.g. to use a layer of an array texture as a render target.
+    // Slices of 3D textures can't be viewed on their own, they must be accessed through the 3D views.
+    pub fn create_layer_views(&self, layer: u32) -> (wgpu::TextureView, wgpu::TextureView) {
+        assert!(
+            layer < self.array_layer_count(),
+            "Layer {} out of the {} layers of the PingPongTexture",
+            layer,
+            self.array_layer_count()
+        );
+
+        let create_layer_view = |texture: &wgpu::Texture, suffix: &str| {
+            texture.create_view(&wgpu::TextureViewDescriptor {
+                label: Some(&child_label(Some(&variant_label(self.label, suffix)), &format!("layer {}", layer))),
+                dimension: Some(wgpu::TextureViewDimension::D2),
+                base_array_layer: layer,
+                array_layer_count: Some(1),
+                ..Default::default()
+            })
+        };
+
+        (create_layer_view(&self.texture_ping, "ping"), create_layer_view(&self.texture_pong, "pong"))
+    }
+
+    pub fn size(&self) -> wgpu::Extent3d { self.descriptor.size }
+
+    pub fn format(&self) -> wgpu::TextureFormat { self.descriptor.format }
+
+    pub fn layout(&self) -> &PingPongTextureLayout { &self.layout }
+
     pub fn create_binding_group(&self, device: &wgpu::Device, sampler: &wgpu::Sampler) -> (wgpu::BindGroup, wgpu::BindGroup) {
-        let bind_group_ping = BindGroupBuilder::new(&self.bind_group_layout)
-            .texture(&self.view_ping)
-            .sampler(sampler)
-            .create(device, Some(format!("{}[ping]", self.label.unwrap_or("unknown")).as_str()));
+        self.create_bind_groups(device, Some(sampler))
+    }
 
-        let bind_group_pong = BindGroupBuilder::new(&self.bind_group_layout)
-            .texture(&self.view_pong)
-            .sampler(sampler)
-            .create(device, Some(format!("{}[pong]", self.label.unwrap_or("unknown")).as_str()));
+    // Create the bind groups reading ping and pong respectively (and writing the other one if the layout has a target binding)
+    // A sampler must be given if and only if the layout has a sampler binding
+    pub fn create_bind_groups(&self, device: &wgpu::Device, sampler: Option<&wgpu::Sampler>) -> (wgpu::BindGroup, wgpu::BindGroup) {
+        assert_eq!(
+            sampler.is_some(),
+            self.layout.sampler.is_some(),
+            "The sampler must match the sampler binding of the PingPongTexture layout"
+        );
+
+        let bind_group_ping = self.create_bind_group(device, &self.view_ping, &self.view_pong, sampler, "ping");
+        let bind_group_pong = self.create_bind_group(device, &self.view_pong, &self.view_ping, sampler, "pong");
 
         (bind_group_ping, bind_group_pong)
     }
 
+    fn create_bind_group(
+        &self,
+        device: &wgpu::Device,
+        source_view: &wgpu::TextureView,
+        target_view: &wgpu::TextureView,
+        sampler: Option<&wgpu::Sampler>,
+        suffix: &str,
+    ) -> wgpu::BindGroup {
+        let mut bind_group_builder = BindGroupBuilder::new(&self.bind_group_layout).texture(source_view);
+        if self.layout.target.is_some() {
+            bind_group_builder = bind_group_builder.texture(target_view);
+        }
+        if let Some(sampler) = sampler {
+            bind_group_builder = bind_group_builder.sampler(sampler);
+        }
+        bind_group_builder.create(device, Some(&variant_label(self.label, suffix)))
+    }
+
     pub fn toogle_state(&mut self) { self.state = !self.state; }
 
     pub fn get_target_texture_view(&self) -> &wgpu::TextureView {
@@ -68,4 +288,33 @@ impl PingPongTexture {
             &self.view_pong
         }
     }
+
+    // Underlying (ping, pong) textures
+    pub fn textures(&self) -> (&wgpu::Texture, &wgpu::Texture) { (&self.texture_ping, &self.texture_pong) }
+
+    pub fn get_target_texture(&self) -> &wgpu::Texture {
+        if self.state {
+            &self.texture_ping
+        } else {
+            &self.texture_pong
+        }
+    }
+
+    pub fn get_rendered_texture(&self) -> &wgpu::Texture {
+        if !self.state {
+            &self.texture_ping
+        } else {
+            &self.texture_pong
+        }
+    }
+
+    // Custom views (specific mip, layer or view format) of the current target and rendered textures
+    // As the state changes, they must be created again to follow the current target/rendered textures
+    pub fn create_target_view(&self, descriptor: &wgpu::TextureViewDescriptor) -> wgpu::TextureView {
+        self.get_target_texture().create_view(descriptor)
+    }
+
+    pub fn create_rendered_view(&self, descriptor: &wgpu::TextureViewDescriptor) -> wgpu::TextureView {
+        self.get_rendered_texture().create_view(descriptor)
+    }
 }
@@ -0,0 +1,153 @@
+use std::{path::PathBuf, sync::Arc};
+
+use anyhow::Result;
+
+use super::{AssetLoader, LoadHandle};
+
+// WGSL source parsed and validated by naga, the error being formatted with the faulty source lines. Usable off the main
+// thread, unlike the device error scopes.
+#[cfg(feature = "naga")]
+pub fn parse_wgsl(source: &str) -> Result<wgpu::naga::Module> {
+    use wgpu::naga::{front::wgsl, valid};
+    let module = wgsl::parse_str(source).map_err(|error| anyhow::anyhow!(error.emit_to_string(source)))?;
+    valid::Validator::new(valid::ValidationFlags::all(), valid::Capabilities::all())
+        .validate(&module)
+        .map_err(|error| anyhow::anyhow!(error.emit_to_string(source)))?;
+    Ok(module)
+}
+
+// Shaders compiled and pipelines created on the asset loader workers, so a pipeline variant needed for the first time
+// doesn't stall the frames while it is compiled. The returned LoadHandle is polled (`is_loading`, `take`) to show a
+// loading state meanwhile, it is filled once `AssetLoader::process_completed` runs.
+// On the web the device can't be used from another thread: the compilation still runs on a worker but the resources
+// are created on the main loop, by `process_completed`.
+// The validation errors of the resources created on a worker reach the uncaptured error handler, error scopes being
+// shared by all the threads using the device.
+#[derive(Clone)]
+pub struct PipelineCompiler {
+    device: Arc<wgpu::Device>,
+}
+
+impl PipelineCompiler {
+    pub fn new(device: Arc<wgpu::Device>) -> Self { Self { device } }
+
+    // True if the resources are created on the workers, false if on the main loop
+    pub fn is_threaded() -> bool { !cfg!(target_arch = "wasm32") }
+
+    // Run `compile` (CPU only, e.g. a shader compilation) on a worker then `create` with the device, on the same worker
+    // when the backend allows it
+    pub fn compile<C, T>(
+        &self,
+        asset_loader: &AssetLoader,
+        label: impl Into<String>,
+        compile: impl FnOnce() -> Result<C> + Send + 'static,
+        create: impl FnOnce(&wgpu::Device, C) -> Result<T> + Send + 'static,
+    ) -> LoadHandle<T>
+    where
+        C: Send + 'static,
+        T: Send + 'static,
+    {
+        let device = self.device.clone();
+        if Self::is_threaded() {
+            asset_loader.load(label, move || create(&device, compile()?), |created, _, _| Ok(created))
+        } else {
+            asset_loader.load(label, compile, move |compiled, _, _| create(&device, compiled))
+        }
+    }
+
+    // Create a resource (e.g. a pipeline from shader modules already compiled) with the device, on a worker when the
+    // backend allows it
+    pub fn create<T: Send + 'static>(
+        &self,
+        asset_loader: &AssetLoader,
+        label: impl Into<String>,
+        create: impl FnOnce(&wgpu::Device) -> Result<T> + Send + 'static,
+    ) -> LoadHandle<T> {
+        self.compile(asset_loader, label, || Ok(()), move |device, ()| create(device))
+    }
+
+    // WGSL shader parsed and validated by naga on a worker, the error being formatted with the faulty source lines
+    #[cfg(feature = "naga")]
+    pub fn compile_wgsl(&self, asset_loader: &AssetLoader, label: impl Into<String>, source: impl Into<String>) -> LoadHandle<wgpu::ShaderModule> {
+        let label = label.into();
+        let module_label = label.clone();
+        let source = source.into();
+        self.compile(
+            asset_loader,
+            label,
+            move || parse_wgsl(&source),
+            move |device, module| {
+                super::record_shader(module_label.as_str());
+                Ok(device.create_shader_module(wgpu::ShaderModuleDescriptor {
+                    label: Some(&module_label),
+                    source: wgpu::ShaderSource::Naga(std::borrow::Cow::Owned(module)),
+                }))
+            },
+        )
+    }
+
+    // Shader composed (imports and defines resolved by naga_oil) on a worker
+    #[cfg(feature = "naga")]
+    pub fn compose(&self, asset_loader: &AssetLoader, label: impl Into<String>, mut composer: super::ShaderComposer) -> LoadHandle<wgpu::ShaderModule> {
+        let label = label.into();
+        let module_label = label.clone();
+        self.compile(
+            asset_loader,
+            label,
+            move || composer.build_ref().map_err(|error| anyhow::anyhow!(composer.emit_error(&error))),
+            move |device, module| {
+                Ok(device.create_shader_module(wgpu::ShaderModuleDescriptor {
+                    label: Some(&module_label),
+                    source: wgpu::ShaderSource::Naga(std::borrow::Cow::Owned(module)),
+                }))
+            },
+        )
+    }
+
+    // GLSL shader compiled to SPIR-V by shaderc, see `shaders_glsl::load_glsl_shader_module_from_path`
+    #[cfg(feature = "glsl")]
+    pub fn compile_glsl(&self, asset_loader: &AssetLoader, path: impl Into<PathBuf>, entry_point_name: &'static str) -> LoadHandle<wgpu::ShaderModule> {
+        let path = path.into();
+        let label = path.display().to_string();
+        self.create(asset_loader, label, move |device| {
+            Ok(super::shaders_glsl::load_glsl_shader_module_from_path(device, &path, entry_point_name)?.module)
+        })
+    }
+
+    // WGSL shader file read and compiled on a worker
+    pub fn load_wgsl(&self, asset_loader: &AssetLoader, path: impl Into<PathBuf>) -> LoadHandle<wgpu::ShaderModule> {
+        let path = path.into();
+        let label = path.display().to_string();
+        let module_label = label.clone();
+        self.compile(
+            asset_loader,
+            label,
+            move || Ok(std::fs::read_to_string(&path)?),
+            move |device, source| {
+                super::record_shader(module_label.as_str());
+                Ok(device.create_shader_module(wgpu::ShaderModuleDescriptor {
+                    label: Some(&module_label),
+                    source: wgpu::ShaderSource::Wgsl(source.into()),
+                }))
+            },
+        )
+    }
+
+    pub fn create_render_pipeline(
+        &self,
+        asset_loader: &AssetLoader,
+        label: impl Into<String>,
+        create: impl FnOnce(&wgpu::Device) -> wgpu::RenderPipeline + Send + 'static,
+    ) -> LoadHandle<wgpu::RenderPipeline> {
+        self.create(asset_loader, label, move |device| Ok(create(device)))
+    }
+
+    pub fn create_compute_pipeline(
+        &self,
+        asset_loader: &AssetLoader,
+        label: impl Into<String>,
+        create: impl FnOnce(&wgpu::Device) -> wgpu::ComputePipeline + Send + 'static,
+    ) -> LoadHandle<wgpu::ComputePipeline> {
+        self.create(asset_loader, label, move |device| Ok(create(device)))
+    }
+}
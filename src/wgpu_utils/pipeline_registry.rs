@@ -0,0 +1,283 @@
+use std::{
+    any::Any,
+    collections::HashMap,
+    future::Future,
+    path::{Path, PathBuf},
+    sync::Arc,
+    task::Poll,
+    time::{Duration, Instant, SystemTime},
+};
+
+use anyhow::{anyhow, Context, Result};
+
+use super::{AssetLoader, LoadHandle};
+
+// Compile the shaders of a pipeline from the sources of its shader files (in the order they were registered), without
+// the device
+type CompileFn = dyn Fn(&[String]) -> Result<Box<dyn Any + Send>> + Send + Sync;
+
+// Create a pipeline from its compiled shaders
+type CreateFn<P> = dyn Fn(&wgpu::Device, Box<dyn Any + Send>) -> Result<P> + Send + Sync;
+
+enum PipelineBuild {
+    Render(Arc<CreateFn<wgpu::RenderPipeline>>),
+    Compute(Arc<CreateFn<wgpu::ComputePipeline>>),
+}
+
+enum Pipeline {
+    Render(wgpu::RenderPipeline),
+    Compute(wgpu::ComputePipeline),
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub struct RenderPipelineId(usize);
+
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub struct ComputePipelineId(usize);
+
+// Outcome of a rebuild completed during `PipelineRegistry::poll`
+#[derive(Debug)]
+pub struct PipelineReload {
+    pub label: String,
+    // None if the new pipeline replaced the previous one, else the failure, the previous pipeline being kept
+    pub error: Option<String>,
+}
+
+struct PipelineEntry {
+    label: String,
+    sources: Vec<PathBuf>,
+    compile: Arc<CompileFn>,
+    build: PipelineBuild,
+    pipeline: Pipeline,
+    rebuild: Option<LoadHandle<Pipeline>>,
+    // Failure of the last rebuild, cleared by the next successful one
+    error: Option<String>,
+}
+
+fn modified_time(path: &Path) -> Option<SystemTime> { std::fs::metadata(path).and_then(|metadata| metadata.modified()).ok() }
+
+fn read_sources(sources: &[PathBuf]) -> Result<Vec<String>> {
+    sources
+        .iter()
+        .map(|path| std::fs::read_to_string(path).with_context(|| format!("Failed to read shader {}", path.display())))
+        .collect()
+}
+
+fn compile_shaders(label: &str, compile: &CompileFn, sources: &[PathBuf]) -> Result<Box<dyn Any + Send>> {
+    compile(&read_sources(sources)?).with_context(|| format!("Failed to compile the shaders of pipeline \"{}\"", label))
+}
+
+// Create the pipeline on the main thread in a validation scope, so an invalid shader or pipeline is returned as an error
+// instead of reaching the uncaptured error handler. The scope is resolved right away on native, on the web its error
+// can't be waited for.
+fn build_pipeline_scoped(device: &wgpu::Device, label: &str, build: &PipelineBuild, compiled: Box<dyn Any + Send>) -> Result<Pipeline> {
+    device.push_error_scope(wgpu::ErrorFilter::Validation);
+    let pipeline = match build {
+        PipelineBuild::Render(create) => create(device, compiled).map(Pipeline::Render),
+        PipelineBuild::Compute(create) => create(device, compiled).map(Pipeline::Compute),
+    }
+    .with_context(|| format!("Failed to build pipeline \"{}\"", label));
+    let mut scope_error = std::pin::pin!(device.pop_error_scope());
+    let mut context = std::task::Context::from_waker(std::task::Waker::noop());
+    if let Poll::Ready(Some(error)) = scope_error.as_mut().poll(&mut context) {
+        return Err(anyhow!("{}", error).context(format!("Failed to build pipeline \"{}\"", label)));
+    }
+    pipeline
+}
+
+// Pipelines owned by the registry and rebuilt when one of their shader files changes. The files modification times are
+// checked by `poll` (at most once per check interval), the affected pipelines are rebuilt in the background and swapped
+// by `poll` once completed, between two frames.
+// A pipeline is built in two steps: `compile` turns the shader sources into shaders without the device (e.g. naga modules
+// validated by `parse_wgsl` or composed by a `ShaderComposer`), on the asset loader workers, then `create` creates the
+// pipeline from them on the main loop, in a validation scope (error scopes being shared by all the threads using the
+// device, they can't capture the errors of a worker).
+// A rebuild failing at either step keeps the previous pipeline, its error is reported by `poll` and kept until the next
+// success. Pipelines are keyed by their label and shader files, registering the same pipeline twice returns the existing one.
+pub struct PipelineRegistry {
+    entries: Vec<PipelineEntry>,
+    keys: HashMap<(String, Vec<PathBuf>), usize>,
+    modified_times: HashMap<PathBuf, Option<SystemTime>>,
+    check_interval: Duration,
+    last_check: Instant,
+}
+
+impl Default for PipelineRegistry {
+    fn default() -> Self { Self::new() }
+}
+
+impl PipelineRegistry {
+    pub fn new() -> Self {
+        Self {
+            entries: Vec::new(),
+            keys: HashMap::new(),
+            modified_times: HashMap::new(),
+            check_interval: Duration::from_millis(250),
+            last_check: Instant::now(),
+        }
+    }
+
+    // Minimum time between two checks of the shader files by `poll`
+    pub fn with_check_interval(mut self, check_interval: Duration) -> Self {
+        self.check_interval = check_interval;
+        self
+    }
+
+    fn add(&mut self, device: &wgpu::Device, label: &str, sources: Vec<PathBuf>, compile: Arc<CompileFn>, build: PipelineBuild) -> Result<usize> {
+        let key = (label.to_string(), sources);
+        if let Some(index) = self.keys.get(&key) {
+            return Ok(*index);
+        }
+        let (label, sources) = key;
+
+        for path in &sources {
+            self.modified_times.entry(path.clone()).or_insert_with(|| modified_time(path));
+        }
+        let pipeline = build_pipeline_scoped(device, &label, &build, compile_shaders(&label, &*compile, &sources)?)?;
+
+        let index = self.entries.len();
+        self.keys.insert((label.clone(), sources.clone()), index);
+        self.entries.push(PipelineEntry {
+            label,
+            sources,
+            compile,
+            build,
+            pipeline,
+            rebuild: None,
+            error: None,
+        });
+        Ok(index)
+    }
+
+    fn erase_compile<C: Send + 'static>(compile: impl Fn(&[String]) -> Result<C> + Send + Sync + 'static) -> Arc<CompileFn> {
+        Arc::new(move |sources| compile(sources).map(|compiled| Box::new(compiled) as Box<dyn Any + Send>))
+    }
+
+    fn erase_create<C: 'static, P>(create: impl Fn(&wgpu::Device, C) -> Result<P> + Send + Sync + 'static) -> Arc<CreateFn<P>> {
+        Arc::new(move |device, compiled| create(device, *compiled.downcast::<C>().expect("shaders compiled for another pipeline")))
+    }
+
+    // Register a pipeline built right away from the content of the `sources` files (shaders compiled by `compile` then
+    // pipeline created by `create`), and again when they change
+    pub fn add_render_pipeline<C: Send + 'static>(
+        &mut self,
+        device: &wgpu::Device,
+        label: &str,
+        sources: impl IntoIterator<Item = impl Into<PathBuf>>,
+        compile: impl Fn(&[String]) -> Result<C> + Send + Sync + 'static,
+        create: impl Fn(&wgpu::Device, C) -> Result<wgpu::RenderPipeline> + Send + Sync + 'static,
+    ) -> Result<RenderPipelineId> {
+        let sources = sources.into_iter().map(Into::into).collect();
+        let build = PipelineBuild::Render(Self::erase_create(create));
+        self.add(device, label, sources, Self::erase_compile(compile), build).map(RenderPipelineId)
+    }
+
+    pub fn add_compute_pipeline<C: Send + 'static>(
+        &mut self,
+        device: &wgpu::Device,
+        label: &str,
+        sources: impl IntoIterator<Item = impl Into<PathBuf>>,
+        compile: impl Fn(&[String]) -> Result<C> + Send + Sync + 'static,
+        create: impl Fn(&wgpu::Device, C) -> Result<wgpu::ComputePipeline> + Send + Sync + 'static,
+    ) -> Result<ComputePipelineId> {
+        let sources = sources.into_iter().map(Into::into).collect();
+        let build = PipelineBuild::Compute(Self::erase_create(create));
+        self.add(device, label, sources, Self::erase_compile(compile), build).map(ComputePipelineId)
+    }
+
+    pub fn render_pipeline(&self, id: RenderPipelineId) -> &wgpu::RenderPipeline {
+        match &self.entries[id.0].pipeline {
+            Pipeline::Render(pipeline) => pipeline,
+            Pipeline::Compute(_) => unreachable!("RenderPipelineId of a compute pipeline"),
+        }
+    }
+
+    pub fn compute_pipeline(&self, id: ComputePipelineId) -> &wgpu::ComputePipeline {
+        match &self.entries[id.0].pipeline {
+            Pipeline::Compute(pipeline) => pipeline,
+            Pipeline::Render(_) => unreachable!("ComputePipelineId of a render pipeline"),
+        }
+    }
+
+    fn request_rebuild(asset_loader: &AssetLoader, entry: &mut PipelineEntry) {
+        let sources = entry.sources.clone();
+        let compile_label = entry.label.clone();
+        let create_label = entry.label.clone();
+        let compile = entry.compile.clone();
+        let build = match &entry.build {
+            PipelineBuild::Render(create) => PipelineBuild::Render(create.clone()),
+            PipelineBuild::Compute(create) => PipelineBuild::Compute(create.clone()),
+        };
+        // A rebuild still in flight is replaced, its result being outdated
+        entry.rebuild = Some(asset_loader.load(
+            format!("Pipeline: {}", entry.label),
+            move || compile_shaders(&compile_label, &*compile, &sources),
+            move |compiled, device, _| build_pipeline_scoped(device, &create_label, &build, compiled),
+        ));
+    }
+
+    // Rebuild all the pipelines, e.g. after a change of the shader defines their compile functions read
+    pub fn rebuild_all(&mut self, asset_loader: &AssetLoader) {
+        for entry in &mut self.entries {
+            Self::request_rebuild(asset_loader, entry);
+        }
+    }
+
+    // Swap the pipelines rebuilt by the asset loader since the last call, then request the rebuild of the pipelines whose
+    // shader files changed. To call once per frame, after `AssetLoader::process_completed`.
+    pub fn poll(&mut self, asset_loader: &AssetLoader) -> Vec<PipelineReload> {
+        let mut reloads = Vec::new();
+        for entry in &mut self.entries {
+            let Some(result) = entry.rebuild.as_ref().and_then(LoadHandle::take) else {
+                continue;
+            };
+            entry.rebuild = None;
+            match result {
+                Ok(pipeline) => {
+                    entry.pipeline = pipeline;
+                    entry.error = None;
+                },
+                Err(error) => entry.error = Some(format!("{:#}", error)),
+            }
+            reloads.push(PipelineReload {
+                label: entry.label.clone(),
+                error: entry.error.clone(),
+            });
+        }
+
+        if self.last_check.elapsed() >= self.check_interval {
+            self.last_check = Instant::now();
+            let mut changed_files = Vec::new();
+            for (path, previous_modified) in &mut self.modified_times {
+                let modified = modified_time(path);
+                if modified.is_some() && modified != *previous_modified {
+                    *previous_modified = modified;
+                    changed_files.push(path.clone());
+                }
+            }
+            if !changed_files.is_empty() {
+                for entry in &mut self.entries {
+                    if entry.sources.iter().any(|path| changed_files.contains(path)) {
+                        Self::request_rebuild(asset_loader, entry);
+                    }
+                }
+            }
+        }
+
+        reloads
+    }
+
+    // Pipelines whose last rebuild failed, with the error
+    pub fn errors(&self) -> impl Iterator<Item = (&str, &str)> {
+        self.entries
+            .iter()
+            .filter_map(|entry| entry.error.as_deref().map(|error| (entry.label.as_str(), error)))
+    }
+
+    pub fn is_rebuilding(&self) -> bool { self.entries.iter().any(|entry| entry.rebuild.is_some()) }
+
+    #[inline]
+    pub fn len(&self) -> usize { self.entries.len() }
+    #[inline]
+    pub fn is_empty(&self) -> bool { self.entries.is_empty() }
+}
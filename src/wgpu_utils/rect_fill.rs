@@ -0,0 +1,90 @@
+const RECT_FILL_WGSL: &str = r#"
+@vertex
+fn vs_main(@builtin(vertex_index) vertex_index: u32) -> @builtin(position) vec4<f32> {
+    let uv = vec2<f32>(f32((vertex_index << 1u) & 2u), f32(vertex_index & 2u));
+    return vec4<f32>(uv * 2.0 - 1.0, 0.0, 1.0);
+}
+
+// Replaced by the blend constant
+@fragment
+fn fs_main() -> @location(0) vec4<f32> {
+    return vec4<f32>(1.0);
+}
+"#;
+
+// Fill of rects of a target with a color, without clearing the rest of it (e.g. the bars around a letterboxed viewport,
+// see `math::Letterbox::bars`). Each rect is a scissored fullscreen triangle with the color as blend constant, there
+// are no buffers to update.
+pub struct RectFill {
+    format: wgpu::TextureFormat,
+    pipeline: wgpu::RenderPipeline,
+}
+
+impl RectFill {
+    pub fn new(device: &wgpu::Device, format: wgpu::TextureFormat) -> Self {
+        let shader_module = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: Some("RectFill"),
+            source: wgpu::ShaderSource::Wgsl(RECT_FILL_WGSL.into()),
+        });
+        let constant = wgpu::BlendComponent {
+            src_factor: wgpu::BlendFactor::Constant,
+            dst_factor: wgpu::BlendFactor::Zero,
+            operation: wgpu::BlendOperation::Add,
+        };
+        let pipeline = device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+            label: Some("RectFill"),
+            layout: None,
+            vertex: wgpu::VertexState {
+                module: &shader_module,
+                entry_point: "vs_main",
+                buffers: &[],
+            },
+            fragment: Some(wgpu::FragmentState {
+                module: &shader_module,
+                entry_point: "fs_main",
+                targets: &[Some(wgpu::ColorTargetState {
+                    format,
+                    blend: Some(wgpu::BlendState { color: constant, alpha: constant }),
+                    write_mask: wgpu::ColorWrites::ALL,
+                })],
+            }),
+            primitive: wgpu::PrimitiveState::default(),
+            depth_stencil: None,
+            multisample: wgpu::MultisampleState::default(),
+            multiview: None,
+        });
+        Self { format, pipeline }
+    }
+
+    pub fn format(&self) -> wgpu::TextureFormat { self.format }
+
+    // Fill the `[x, y, width, height]` rects of `target_view` (in pixels, inside the target) with `color`, in the color
+    // space of the target format. Empty rects are skipped.
+    pub fn encode(&self, encoder: &mut wgpu::CommandEncoder, target_view: &wgpu::TextureView, rects: &[[u32; 4]], color: wgpu::Color) {
+        if rects.iter().all(|[_, _, width, height]| *width == 0 || *height == 0) {
+            return;
+        }
+        let mut render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+            label: Some("RectFill"),
+            color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+                view: target_view,
+                resolve_target: None,
+                ops: wgpu::Operations {
+                    load: wgpu::LoadOp::Load,
+                    store: wgpu::StoreOp::Store,
+                },
+            })],
+            depth_stencil_attachment: None,
+            timestamp_writes: None,
+            occlusion_query_set: None,
+        });
+        render_pass.set_pipeline(&self.pipeline);
+        render_pass.set_blend_constant(color);
+        for &[x, y, width, height] in rects {
+            if width > 0 && height > 0 {
+                render_pass.set_scissor_rect(x, y, width, height);
+                render_pass.draw(0..3, 0..1);
+            }
+        }
+    }
+}
@@ -7,6 +7,7 @@ pub enum RenderHandleError {
     SurfaceCreationError(wgpu::CreateSurfaceError),
     SurfaceTextureFormatRgbaBgraError,
     SurfaceSizeError(u32, u32),
+    NoAdapterMatchingSelector,
 }
 
 impl std::fmt::Display for RenderHandleError {
@@ -25,20 +26,45 @@ impl std::fmt::Display for RenderHandleError {
             RenderHandleError::SurfaceSizeError(width, height) => {
                 write!(f, "Surface size error: {}x{}. Width and height must be greater than 0", width, height)
             }
+            RenderHandleError::NoAdapterMatchingSelector => write!(f, "No compatible adapter matches the adapter selector"),
         }
     }
 }
 
 impl std::error::Error for RenderHandleError {}
 
+// Adapter choice among the adapters of the instance backends (compatible with the surface if any), overriding the power preference
+pub enum AdapterSelector {
+    // Index in the compatible adapters enumeration order
+    Index(usize),
+    // Case insensitive substring of the adapter name
+    NameContains(String),
+    DeviceType(wgpu::DeviceType),
+    Custom(Box<dyn Fn(&wgpu::AdapterInfo) -> bool>),
+}
+
+impl AdapterSelector {
+    pub fn matches(&self, index: usize, adapter_info: &wgpu::AdapterInfo) -> bool {
+        match self {
+            AdapterSelector::Index(selected_index) => index == *selected_index,
+            AdapterSelector::NameContains(name) => adapter_info.name.to_lowercase().contains(&name.to_lowercase()),
+            AdapterSelector::DeviceType(device_type) => adapter_info.device_type == *device_type,
+            AdapterSelector::Custom(predicate) => predicate(adapter_info),
+        }
+    }
+}
+
 pub struct RenderInstance {
     instance: wgpu::Instance,
+    backends: wgpu::Backends,
+    adapter_selector: Option<AdapterSelector>,
     pub devices: Vec<DeviceHandle>,
 }
 
 pub struct DeviceHandle {
     adapter: wgpu::Adapter,
-    pub device: wgpu::Device,
+    // Shared with the worker threads creating resources in the background (see `PipelineCompiler`)
+    pub device: std::sync::Arc<wgpu::Device>,
     pub queue: wgpu::Queue,
 }
 
@@ -50,17 +76,51 @@ pub struct SurfaceHandle<'s> {
 
 impl RenderInstance {
     pub fn new(backends: Option<wgpu::Backends>, flags: Option<wgpu::InstanceFlags>) -> Self {
+        let backends = backends.unwrap_or(wgpu::util::backend_bits_from_env().unwrap_or(wgpu::Backends::PRIMARY));
         let instance = wgpu::Instance::new(wgpu::InstanceDescriptor {
-            backends: backends.unwrap_or(wgpu::util::backend_bits_from_env().unwrap_or(wgpu::Backends::PRIMARY)),
-            flags: flags.unwrap_or(wgpu::InstanceFlags::default()),
+            backends,
+            flags: flags.unwrap_or_default(),
             ..Default::default()
         });
         Self {
             instance,
+            backends,
+            adapter_selector: None,
             devices: Vec::new(),
         }
     }
 
+    // Selector used for the next created devices, the WGPU_ADAPTER_NAME environment variable still takes precedence
+    pub fn set_adapter_selector(&mut self, adapter_selector: Option<AdapterSelector>) {
+        self.adapter_selector = adapter_selector;
+    }
+
+    // Adapters of the instance backends, in the order used by `AdapterSelector::Index`
+    pub fn adapters_info(&self, compatible_surface: Option<&wgpu::Surface<'_>>) -> Vec<wgpu::AdapterInfo> {
+        self.compatible_adapters(compatible_surface).iter().map(|adapter| adapter.get_info()).collect()
+    }
+
+    fn compatible_adapters(&self, compatible_surface: Option<&wgpu::Surface<'_>>) -> Vec<wgpu::Adapter> {
+        self.instance
+            .enumerate_adapters(self.backends)
+            .into_iter()
+            .filter(|adapter| compatible_surface.is_none_or(|surface| adapter.is_surface_supported(surface)))
+            .collect()
+    }
+
+    fn select_adapter(&self, adapter_selector: &AdapterSelector, compatible_surface: Option<&wgpu::Surface<'_>>) -> Result<wgpu::Adapter, RenderHandleError> {
+        self.compatible_adapters(compatible_surface)
+            .into_iter()
+            .enumerate()
+            .find(|(index, adapter)| adapter_selector.matches(*index, &adapter.get_info()))
+            .map(|(_, adapter)| adapter)
+            .ok_or(RenderHandleError::NoAdapterMatchingSelector)
+    }
+
+    pub fn instance(&self) -> &wgpu::Instance {
+        &self.instance
+    }
+
     // Return the index of a device that is compatible with the given surface
     // If no compatible device is found, create a new device and return its index
     pub async fn device(&mut self, compatible_surface: Option<&wgpu::Surface<'_>>, power_preference: Option<wgpu::PowerPreference>) -> Result<usize, RenderHandleError> {
@@ -74,7 +134,7 @@ impl RenderInstance {
             None => (!self.devices.is_empty()).then_some(0),
         };
         
-        return match compatible_device_index {
+        match compatible_device_index {
             Some(index) => Ok(index),
             None => self.new_device(compatible_surface, power_preference).await,
         }
@@ -85,39 +145,39 @@ impl RenderInstance {
         let adapter = match wgpu::util::initialize_adapter_from_env(&self.instance, compatible_surface) {
             // TODO: add condition to check if the adapter is compatible required power preference as well if provided
             Some(a) => Some(a),
-            None => {
-                self.instance
-                    .request_adapter(&wgpu::RequestAdapterOptions {
-                        power_preference: power_preference.unwrap_or(wgpu::util::power_preference_from_env().unwrap_or_default()),
-                        force_fallback_adapter: false,
-                        compatible_surface,
-                    })
-                    .await
-            }
+            None => match &self.adapter_selector {
+                Some(adapter_selector) => Some(self.select_adapter(adapter_selector, compatible_surface)?),
+                None => {
+                    self.instance
+                        .request_adapter(&wgpu::RequestAdapterOptions {
+                            power_preference: power_preference.unwrap_or(wgpu::util::power_preference_from_env().unwrap_or_default()),
+                            force_fallback_adapter: false,
+                            compatible_surface,
+                        })
+                        .await
+                }
+            },
         }.ok_or(RenderHandleError::AdapterRequestError)?;
 
         let features = adapter.features();
         let limits = wgpu::Limits::default();
-        #[allow(unused_mut)]
-        let mut maybe_features = wgpu::Features::CLEAR_TEXTURE;
-        #[cfg(feature = "wgpu-profiler")]
-        {
-            maybe_features |= wgpu_profiler::GpuProfiler::ALL_WGPU_TIMER_FEATURES;
-        };
+        // Optional features requested when supported by the adapter (POLYGON_MODE_LINE for the wireframe debug view, the
+        // timestamps for the `GpuTimer` scopes)
+        let maybe_features = wgpu::Features::CLEAR_TEXTURE | wgpu::Features::POLYGON_MODE_LINE | super::GpuTimer::required_features();
         let (device, queue) = adapter
             .request_device(
                 &wgpu::DeviceDescriptor {
-                    label: None,
+                    label: Some(&format!("Device: {}", adapter.get_info().name)),
                     required_features: features & maybe_features,
                     required_limits: limits,
                 },
                 None,
             )
             .await
-            .map_err(|e| RenderHandleError::NoCompatibleDevice(e))?;
+            .map_err(RenderHandleError::NoCompatibleDevice)?;
         self.devices.push(DeviceHandle {
             adapter,
-            device,
+            device: std::sync::Arc::new(device),
             queue,
         });
         Ok(self.devices.len() - 1)
@@ -135,7 +195,7 @@ impl RenderInstance {
             if width == 0 || height == 0 {
                 return Err(RenderHandleError::SurfaceSizeError(width, height));
             }
-            let surface = self.instance.create_surface(window.into()).map_err(|e| RenderHandleError::SurfaceCreationError(e))?;
+            let surface = self.instance.create_surface(window.into()).map_err(RenderHandleError::SurfaceCreationError)?;
 
             let device_handle_id: usize = self.device(Some(&surface), power_preference).await?;
     
@@ -146,16 +206,29 @@ impl RenderInstance {
                 .into_iter()
                 .find(|it| matches!(it, wgpu::TextureFormat::Rgba8Unorm | wgpu::TextureFormat::Bgra8Unorm))
                 .ok_or(RenderHandleError::SurfaceTextureFormatRgbaBgraError)?;
+
+            // The sRGB counterpart of the format is allowed as view format, when the adapter supports surface view formats
+            let srgb_format = format.add_srgb_suffix();
+            let view_formats = if srgb_format != format
+                && device_handle.adapter.get_downlevel_capabilities().flags.contains(wgpu::DownlevelFlags::SURFACE_VIEW_FORMATS)
+            {
+                vec![srgb_format]
+            } else {
+                vec![]
+            };
             
+            // Surface textures can be copied from (e.g. for screenshots) when supported
+            let usage = wgpu::TextureUsages::RENDER_ATTACHMENT | (capabilities.usages & wgpu::TextureUsages::COPY_SRC);
+
             let config = wgpu::SurfaceConfiguration {
-                usage: wgpu::TextureUsages::RENDER_ATTACHMENT,
+                usage,
                 format,
                 width,
                 height,
                 present_mode,
                 desired_maximum_frame_latency: 2,
                 alpha_mode: wgpu::CompositeAlphaMode::Auto,
-                view_formats: vec![],
+                view_formats,
             };
             let mut surface_handle = SurfaceHandle {
                 surface,
@@ -172,6 +245,12 @@ impl RenderInstance {
         }
 }
 
+impl DeviceHandle {
+    pub fn adapter(&self) -> &wgpu::Adapter {
+        &self.adapter
+    }
+}
+
 impl SurfaceHandle<'_> {
     pub fn resize(&mut self, device: &wgpu::Device, width: u32, height: u32) -> Result<(), RenderHandleError> {
         if width == 0 || height == 0 {
@@ -192,12 +271,53 @@ impl SurfaceHandle<'_> {
         self.configure(device);
     }
 
+    // Use an alpha mode compositing the surface with what is behind the window, returns false if none is supported
+    pub fn enable_transparency(&mut self, device_handle: &DeviceHandle) -> bool {
+        let alpha_modes = self.surface.get_capabilities(&device_handle.adapter).alpha_modes;
+        let transparent_alpha_mode = [
+            wgpu::CompositeAlphaMode::PreMultiplied,
+            wgpu::CompositeAlphaMode::PostMultiplied,
+            wgpu::CompositeAlphaMode::Inherit,
+        ]
+        .into_iter()
+        .find(|alpha_mode| alpha_modes.contains(alpha_mode));
+        match transparent_alpha_mode {
+            Some(alpha_mode) => {
+                self.config.alpha_mode = alpha_mode;
+                self.configure(&device_handle.device);
+                true
+            }
+            None => false,
+        }
+    }
+
     pub fn format(&self) -> wgpu::TextureFormat {
         self.config.format
     }
 
+    // Surface textures can be the source of copies (e.g. `TextureReadback::encode_copy`)
+    pub fn supports_copy(&self) -> bool {
+        self.config.usage.contains(wgpu::TextureUsages::COPY_SRC)
+    }
+
     pub fn get_current_texture(&self) -> Result<wgpu::SurfaceTexture, wgpu::SurfaceError> {
         self.surface.get_current_texture()
     }
+
+    // sRGB format usable to view the surface textures, None if the adapter doesn't support surface view formats
+    pub fn srgb_format(&self) -> Option<wgpu::TextureFormat> {
+        let srgb_format = self.config.format.add_srgb_suffix();
+        (srgb_format == self.config.format || self.config.view_formats.contains(&srgb_format)).then_some(srgb_format)
+    }
+
+    // View of a surface texture in the sRGB format, writes are then gamma encoded while the base format
+    // (which the egui path expects) stays linear. Falls back to the default view when not supported.
+    pub fn srgb_view(&self, surface_texture: &wgpu::SurfaceTexture) -> wgpu::TextureView {
+        surface_texture.texture.create_view(&wgpu::TextureViewDescriptor {
+            label: Some("Surface sRGB view"),
+            format: self.srgb_format(),
+            ..Default::default()
+        })
+    }
 }
 
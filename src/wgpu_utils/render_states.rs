@@ -0,0 +1,164 @@
+// Named blend and depth states for the pipeline descriptors, e.g.
+// `targets: &[Some(BlendPreset::AlphaBlend.color_target(format))]` and
+// `depth_stencil: Some(DepthPreset::TestWrite.depth_stencil(depth_format, DepthConvention::Standard))`
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum BlendPreset {
+    // Replace the target
+    #[default]
+    Opaque,
+    // Straight (not premultiplied) alpha output over the target
+    AlphaBlend,
+    // Color already multiplied by its alpha over the target
+    Premultiplied,
+    // Color weighted by its alpha added to the target, the target alpha is kept (e.g. particles, light accumulation)
+    Additive,
+    // Target color multiplied by the color, the target alpha is kept (e.g. decals darkening the scene)
+    Multiply,
+}
+
+impl BlendPreset {
+    pub fn blend_state(self) -> Option<wgpu::BlendState> {
+        let keep_alpha = wgpu::BlendComponent {
+            src_factor: wgpu::BlendFactor::Zero,
+            dst_factor: wgpu::BlendFactor::One,
+            operation: wgpu::BlendOperation::Add,
+        };
+        match self {
+            Self::Opaque => None,
+            Self::AlphaBlend => Some(wgpu::BlendState::ALPHA_BLENDING),
+            Self::Premultiplied => Some(wgpu::BlendState::PREMULTIPLIED_ALPHA_BLENDING),
+            Self::Additive => Some(wgpu::BlendState {
+                color: wgpu::BlendComponent {
+                    src_factor: wgpu::BlendFactor::SrcAlpha,
+                    dst_factor: wgpu::BlendFactor::One,
+                    operation: wgpu::BlendOperation::Add,
+                },
+                alpha: keep_alpha,
+            }),
+            Self::Multiply => Some(wgpu::BlendState {
+                color: wgpu::BlendComponent {
+                    src_factor: wgpu::BlendFactor::Dst,
+                    dst_factor: wgpu::BlendFactor::Zero,
+                    operation: wgpu::BlendOperation::Add,
+                },
+                alpha: keep_alpha,
+            }),
+        }
+    }
+
+    pub fn color_target(self, format: wgpu::TextureFormat) -> wgpu::ColorTargetState {
+        wgpu::ColorTargetState {
+            format,
+            blend: self.blend_state(),
+            write_mask: wgpu::ColorWrites::ALL,
+        }
+    }
+}
+
+// Depth values of the near and far planes: 0 to 1 (standard) or 1 to 0 (reversed, better precision far from the camera
+// with a floating point depth format)
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum DepthConvention {
+    #[default]
+    Standard,
+    Reversed,
+}
+
+impl DepthConvention {
+    // Depth of the near plane
+    pub fn near_depth(self) -> f32 {
+        match self {
+            Self::Standard => 0.0,
+            Self::Reversed => 1.0,
+        }
+    }
+
+    // Depth of the far plane, which the depth targets are cleared to
+    pub fn far_depth(self) -> f32 { 1.0 - self.near_depth() }
+
+    // Operations of a depth attachment cleared to the far plane
+    pub fn clear_operations(self) -> wgpu::Operations<f32> {
+        wgpu::Operations {
+            load: wgpu::LoadOp::Clear(self.far_depth()),
+            store: wgpu::StoreOp::Store,
+        }
+    }
+
+    // Passing the fragments closer to the camera
+    pub fn closer_compare(self) -> wgpu::CompareFunction {
+        match self {
+            Self::Standard => wgpu::CompareFunction::Less,
+            Self::Reversed => wgpu::CompareFunction::Greater,
+        }
+    }
+
+    // Passing the fragments closer to the camera or at the same depth
+    pub fn closer_or_equal_compare(self) -> wgpu::CompareFunction {
+        match self {
+            Self::Standard => wgpu::CompareFunction::LessEqual,
+            Self::Reversed => wgpu::CompareFunction::GreaterEqual,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum DepthPreset {
+    // Opaque geometry: keep the closest fragments and write their depth
+    #[default]
+    TestWrite,
+    // Transparent geometry and overlays: tested against the scene without occluding each other
+    TestOnly,
+    // Shading pass after a depth pre-pass (see `DepthPrepass`): only the fragments of the pre-pass depth
+    Equal,
+    // Write the depth without testing (e.g. a fullscreen pass restoring a depth)
+    WriteOnly,
+    // Depth attachment ignored
+    Disabled,
+}
+
+impl DepthPreset {
+    pub fn depth_stencil(self, format: wgpu::TextureFormat, convention: DepthConvention) -> wgpu::DepthStencilState {
+        let (depth_compare, depth_write_enabled) = match self {
+            Self::TestWrite => (convention.closer_compare(), true),
+            Self::TestOnly => (convention.closer_or_equal_compare(), false),
+            Self::Equal => (wgpu::CompareFunction::Equal, false),
+            Self::WriteOnly => (wgpu::CompareFunction::Always, true),
+            Self::Disabled => (wgpu::CompareFunction::Always, false),
+        };
+        wgpu::DepthStencilState {
+            format,
+            depth_write_enabled,
+            depth_compare,
+            stencil: wgpu::StencilState::default(),
+            bias: wgpu::DepthBiasState::default(),
+        }
+    }
+}
+
+pub const DEPTH_IMPORT_PATH: &str = "oxyde::depth";
+
+// View distance of a depth value for a perspective projection, as a composable module
+// (`#import oxyde::depth::linear_depth_reversed_infinite`, see `ShaderComposer::with_depth`) or to paste in a shader.
+// The far plane variants match `math::perspective` with the same depth convention.
+pub const DEPTH_WGSL: &str = r#"#define_import_path oxyde::depth
+
+fn linear_depth(depth: f32, near: f32, far: f32) -> f32 {
+    return near * far / (far - depth * (far - near));
+}
+
+fn linear_depth_infinite(depth: f32, near: f32) -> f32 {
+    return near / max(1.0 - depth, 1e-7);
+}
+
+fn linear_depth_reversed(depth: f32, near: f32, far: f32) -> f32 {
+    return linear_depth(1.0 - depth, near, far);
+}
+
+fn linear_depth_reversed_infinite(depth: f32, near: f32) -> f32 {
+    return near / max(depth, 1e-7);
+}
+"#;
@@ -0,0 +1,282 @@
+use std::collections::HashMap;
+
+use super::{
+    binding_builder::{BindGroupBuilder, BindGroupLayoutBuilder, BindGroupLayoutWithDesc},
+    child_label,
+    memory_tracker::MemoryAllocation,
+};
+
+// Size of a render target, surface based sizes follow the surface when `RenderTargets::resize` is called
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum RenderTargetSize {
+    Fixed(u32, u32),
+    // Same size as the surface
+    Surface,
+    // Surface size scaled independently on each axis (e.g. (0.5, 0.5) for half resolution buffers)
+    SurfaceRelative(f32, f32),
+    // Surface size divided by an integer factor, rounded up so no pixel of the surface is missed
+    SurfaceFraction(u32),
+}
+
+impl RenderTargetSize {
+    pub fn resolve(&self, surface_width: u32, surface_height: u32) -> (u32, u32) {
+        let (width, height) = match *self {
+            RenderTargetSize::Fixed(width, height) => (width, height),
+            RenderTargetSize::Surface => (surface_width, surface_height),
+            RenderTargetSize::SurfaceRelative(scale_x, scale_y) =>
+                ((surface_width as f32 * scale_x).round() as u32, (surface_height as f32 * scale_y).round() as u32),
+            RenderTargetSize::SurfaceFraction(divisor) => (surface_width.div_ceil(divisor.max(1)), surface_height.div_ceil(divisor.max(1))),
+        };
+        (width.max(1), height.max(1))
+    }
+
+    pub fn depends_on_surface(&self) -> bool { !matches!(self, RenderTargetSize::Fixed(..)) }
+}
+
+#[derive(Clone, Copy, Debug)]
+pub struct RenderTargetDescriptor {
+    pub format: wgpu::TextureFormat,
+    pub size: RenderTargetSize,
+    pub usage: wgpu::TextureUsages,
+    pub sample_count: u32,
+    // Stages of the generated bind group (texture and sampler), only created with the TEXTURE_BINDING usage
+    pub visibility: wgpu::ShaderStages,
+}
+
+impl RenderTargetDescriptor {
+    // Target rendered to then sampled in a later pass
+    pub fn color(format: wgpu::TextureFormat, size: RenderTargetSize) -> Self {
+        Self {
+            format,
+            size,
+            usage: wgpu::TextureUsages::RENDER_ATTACHMENT | wgpu::TextureUsages::TEXTURE_BINDING,
+            sample_count: 1,
+            visibility: wgpu::ShaderStages::FRAGMENT,
+        }
+    }
+
+    pub fn depth(format: wgpu::TextureFormat, size: RenderTargetSize) -> Self {
+        Self {
+            usage: wgpu::TextureUsages::RENDER_ATTACHMENT,
+            ..Self::color(format, size)
+        }
+    }
+
+    // Motion vectors target (two 16 bits floats), see `VELOCITY_WGSL`
+    pub fn velocity(size: RenderTargetSize) -> Self { Self::color(wgpu::TextureFormat::Rg16Float, size) }
+
+    pub fn with_usage(mut self, usage: wgpu::TextureUsages) -> Self {
+        self.usage = usage;
+        self
+    }
+
+    pub fn with_sample_count(mut self, sample_count: u32) -> Self {
+        self.sample_count = sample_count;
+        self
+    }
+
+    pub fn with_visibility(mut self, visibility: wgpu::ShaderStages) -> Self {
+        self.visibility = visibility;
+        self
+    }
+}
+
+pub struct RenderTarget {
+    descriptor: RenderTargetDescriptor,
+    texture: wgpu::Texture,
+    view: wgpu::TextureView,
+    // The layout doesn't change on resize so pipelines created with it stay valid, the bind group is recreated
+    bind_group_layout: Option<BindGroupLayoutWithDesc>,
+    bind_group: Option<wgpu::BindGroup>,
+    _memory: MemoryAllocation,
+}
+
+impl RenderTarget {
+    pub fn descriptor(&self) -> &RenderTargetDescriptor { &self.descriptor }
+
+    pub fn texture(&self) -> &wgpu::Texture { &self.texture }
+
+    pub fn view(&self) -> &wgpu::TextureView { &self.view }
+
+    pub fn bind_group_layout(&self) -> Option<&BindGroupLayoutWithDesc> { self.bind_group_layout.as_ref() }
+
+    pub fn bind_group(&self) -> Option<&wgpu::BindGroup> { self.bind_group.as_ref() }
+
+    pub fn size(&self) -> (u32, u32) { (self.texture.width(), self.texture.height()) }
+}
+
+// Named offscreen targets, recreated when the surface is resized according to their size policy
+pub struct RenderTargets {
+    surface_size: (u32, u32),
+    filtering_sampler: wgpu::Sampler,
+    non_filtering_sampler: wgpu::Sampler,
+    targets: HashMap<String, RenderTarget>,
+}
+
+impl RenderTargets {
+    // Name of the target declared by `declare_velocity`
+    pub const VELOCITY: &'static str = "velocity";
+
+    pub fn new(device: &wgpu::Device, surface_width: u32, surface_height: u32) -> Self {
+        let create_sampler = |filter_mode: wgpu::FilterMode, label: &str| {
+            device.create_sampler(&wgpu::SamplerDescriptor {
+                label: Some(label),
+                mag_filter: filter_mode,
+                min_filter: filter_mode,
+                ..Default::default()
+            })
+        };
+        Self {
+            surface_size: (surface_width, surface_height),
+            filtering_sampler: create_sampler(wgpu::FilterMode::Linear, "RenderTargets: filtering sampler"),
+            non_filtering_sampler: create_sampler(wgpu::FilterMode::Nearest, "RenderTargets: non filtering sampler"),
+            targets: HashMap::new(),
+        }
+    }
+
+    // Create a target, replacing the one with the same name if any
+    pub fn declare(&mut self, device: &wgpu::Device, name: &str, descriptor: RenderTargetDescriptor) -> &RenderTarget {
+        let bind_group_layout = descriptor
+            .usage
+            .contains(wgpu::TextureUsages::TEXTURE_BINDING)
+            .then(|| self.create_bind_group_layout(device, name, &descriptor));
+        let target = self.create_target(device, name, descriptor, bind_group_layout);
+        self.targets.insert(name.to_string(), target);
+        &self.targets[name]
+    }
+
+    // Surface sized motion vectors target named `RenderTargets::VELOCITY`, cleared to zero (no motion) before rendering
+    pub fn declare_velocity(&mut self, device: &wgpu::Device) -> &RenderTarget {
+        self.declare(device, Self::VELOCITY, RenderTargetDescriptor::velocity(RenderTargetSize::Surface))
+    }
+
+    pub fn remove(&mut self, name: &str) -> Option<RenderTarget> { self.targets.remove(name) }
+
+    // Recreate the targets depending on the surface size, returns the names of the recreated targets
+    pub fn resize(&mut self, device: &wgpu::Device, surface_width: u32, surface_height: u32) -> Vec<String> {
+        self.surface_size = (surface_width, surface_height);
+        let names: Vec<String> = self
+            .targets
+            .iter()
+            .filter(|(_, target)| target.size() != target.descriptor.size.resolve(surface_width, surface_height))
+            .map(|(name, _)| name.clone())
+            .collect();
+        for name in &names {
+            let target = self.targets.remove(name).unwrap();
+            let target = self.create_target(device, name, target.descriptor, target.bind_group_layout);
+            self.targets.insert(name.clone(), target);
+        }
+        names
+    }
+
+    pub fn surface_size(&self) -> (u32, u32) { self.surface_size }
+
+    pub fn get(&self, name: &str) -> Option<&RenderTarget> { self.targets.get(name) }
+
+    pub fn names(&self) -> impl Iterator<Item = &str> { self.targets.keys().map(String::as_str) }
+
+    // Panics if the target is not declared
+    pub fn target(&self, name: &str) -> &RenderTarget { self.get(name).unwrap_or_else(|| panic!("Unknown render target \"{}\"", name)) }
+
+    pub fn view(&self, name: &str) -> &wgpu::TextureView { self.target(name).view() }
+
+    // Panics if the target is not declared or has no TEXTURE_BINDING usage
+    pub fn bind_group(&self, name: &str) -> &wgpu::BindGroup {
+        self.target(name)
+            .bind_group()
+            .unwrap_or_else(|| panic!("Render target \"{}\" has no TEXTURE_BINDING usage", name))
+    }
+
+    pub fn bind_group_layout(&self, name: &str) -> &BindGroupLayoutWithDesc {
+        self.target(name)
+            .bind_group_layout()
+            .unwrap_or_else(|| panic!("Render target \"{}\" has no TEXTURE_BINDING usage", name))
+    }
+
+    fn sample_type(descriptor: &RenderTargetDescriptor) -> wgpu::TextureSampleType {
+        let aspect = descriptor.format.has_depth_aspect().then_some(wgpu::TextureAspect::DepthOnly);
+        match descriptor.format.sample_type(aspect, None) {
+            // Multisampled textures can't be filtered
+            Some(wgpu::TextureSampleType::Float { .. }) if descriptor.sample_count > 1 => wgpu::TextureSampleType::Float { filterable: false },
+            Some(sample_type) => sample_type,
+            None => panic!("Render target format {:?} can't be sampled", descriptor.format),
+        }
+    }
+
+    fn create_bind_group_layout(&self, device: &wgpu::Device, name: &str, descriptor: &RenderTargetDescriptor) -> BindGroupLayoutWithDesc {
+        let sample_type = Self::sample_type(descriptor);
+        let multisampled = descriptor.sample_count > 1;
+        let mut builder = BindGroupLayoutBuilder::new().add_binding(
+            descriptor.visibility,
+            wgpu::BindingType::Texture {
+                sample_type,
+                view_dimension: wgpu::TextureViewDimension::D2,
+                multisampled,
+            },
+        );
+        // Multisampled textures are loaded, not sampled
+        if !multisampled {
+            let sampler_type = match sample_type {
+                wgpu::TextureSampleType::Float { filterable: true } => wgpu::SamplerBindingType::Filtering,
+                _ => wgpu::SamplerBindingType::NonFiltering,
+            };
+            builder = builder.add_binding(descriptor.visibility, wgpu::BindingType::Sampler(sampler_type));
+        }
+        builder.create(device, Some(format!("RenderTarget: {}", name).as_str()))
+    }
+
+    fn create_target(
+        &self,
+        device: &wgpu::Device,
+        name: &str,
+        descriptor: RenderTargetDescriptor,
+        bind_group_layout: Option<BindGroupLayoutWithDesc>,
+    ) -> RenderTarget {
+        let label = format!("RenderTarget: {}", name);
+        let (width, height) = descriptor.size.resolve(self.surface_size.0, self.surface_size.1);
+        let texture = device.create_texture(&wgpu::TextureDescriptor {
+            label: Some(&label),
+            size: wgpu::Extent3d { width, height, depth_or_array_layers: 1 },
+            mip_level_count: 1,
+            sample_count: descriptor.sample_count,
+            dimension: wgpu::TextureDimension::D2,
+            format: descriptor.format,
+            usage: descriptor.usage,
+            view_formats: &[],
+        });
+        let view = texture.create_view(&wgpu::TextureViewDescriptor {
+            label: Some(&child_label(Some(&label), "view")),
+            ..Default::default()
+        });
+
+        let bind_group = bind_group_layout.as_ref().map(|bind_group_layout| {
+            // Combined depth stencil formats are bound through their depth aspect
+            let binding_view = (descriptor.format.is_combined_depth_stencil_format()).then(|| {
+                texture.create_view(&wgpu::TextureViewDescriptor {
+                    label: Some(&child_label(Some(&label), "depth binding view")),
+                    aspect: wgpu::TextureAspect::DepthOnly,
+                    ..Default::default()
+                })
+            });
+            let mut builder = BindGroupBuilder::new(bind_group_layout).texture(binding_view.as_ref().unwrap_or(&view));
+            if descriptor.sample_count == 1 {
+                builder = builder.sampler(match Self::sample_type(&descriptor) {
+                    wgpu::TextureSampleType::Float { filterable: true } => &self.filtering_sampler,
+                    _ => &self.non_filtering_sampler,
+                });
+            }
+            builder.create(device, Some(&label))
+        });
+
+        let memory = MemoryAllocation::for_texture(&texture, Some(&label));
+
+        RenderTarget {
+            descriptor,
+            texture,
+            view,
+            bind_group_layout,
+            bind_group,
+            _memory: memory,
+        }
+    }
+}
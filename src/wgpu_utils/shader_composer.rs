@@ -40,6 +40,8 @@ pub struct ShaderComposer {
     defines: HashMap<String, compose::ShaderDefValue>,
 }
 
+// ComposerError is the (large) naga_oil error type, kept as is for callers to inspect
+#[allow(clippy::result_large_err)]
 impl ShaderComposer {
     pub fn new(source: &'static str, name: Option<&'static str>) -> Self {
         Self {
@@ -73,6 +75,70 @@ impl ShaderComposer {
             .map(|_| ())
     }
 
+    // Add the module of the built-in FrameUniforms struct, imported with `#import oxyde::frame_uniforms::FrameUniforms`
+    pub fn with_frame_uniforms(mut self) -> Result<Self, ComposerError> {
+        self.add_module(super::FRAME_UNIFORMS_IMPORT_PATH, super::FRAME_UNIFORMS_WGSL)?;
+        Ok(self)
+    }
+
+    // Add the module of the `Exposure` struct written by `AutoExposure`, imported with `#import oxyde::exposure::Exposure`
+    pub fn with_exposure(mut self) -> Result<Self, ComposerError> {
+        self.add_module(super::EXPOSURE_IMPORT_PATH, super::EXPOSURE_WGSL)?;
+        Ok(self)
+    }
+
+    // Add the motion vector helpers, imported with `#import oxyde::velocity::{MotionUniforms, velocity_from_clip}`
+    pub fn with_velocity(mut self) -> Result<Self, ComposerError> {
+        self.add_module(super::VELOCITY_IMPORT_PATH, super::VELOCITY_WGSL)?;
+        Ok(self)
+    }
+
+    // Add the depth linearization helpers, imported with `#import oxyde::depth::{linear_depth, linear_depth_reversed_infinite}`
+    pub fn with_depth(mut self) -> Result<Self, ComposerError> {
+        self.add_module(super::DEPTH_IMPORT_PATH, super::DEPTH_WGSL)?;
+        Ok(self)
+    }
+
+    // Add the common modules: `oxyde::{version, color, tonemapping, random, math, fullscreen}`
+    pub fn with_common(mut self) -> Result<Self, ComposerError> {
+        for (import_path, source) in super::COMMON_MODULES {
+            self.add_module(import_path, source)?;
+        }
+        Ok(self)
+    }
+
+    // Add the noise modules: `oxyde::noise::{hash, value, perlin, simplex, fbm, curl}`
+    pub fn with_noise(mut self) -> Result<Self, ComposerError> {
+        for (import_path, source) in super::NOISE_MODULES {
+            self.add_module(import_path, source)?;
+        }
+        Ok(self)
+    }
+
+    // Add the PBR modules: `oxyde::pbr::brdf`, `oxyde::pbr::lighting` and `oxyde::pbr::material`
+    pub fn with_pbr(mut self) -> Result<Self, ComposerError> {
+        self.add_module(super::PBR_BRDF_IMPORT_PATH, super::PBR_BRDF_WGSL)?;
+        self.add_module(super::PBR_LIGHTING_IMPORT_PATH, super::PBR_LIGHTING_WGSL)?;
+        self.add_module(super::PBR_MATERIAL_IMPORT_PATH, super::PBR_MATERIAL_WGSL)?;
+        Ok(self)
+    }
+
+    // Add the module generated for a `impl_wgsl_struct!` struct, imported with `#import <IMPORT_PATH>::<NAME>`, and the
+    // modules of the structs it contains. Modules already added are kept.
+    pub fn with_wgsl_struct<T: super::wgsl_struct::WgslStruct>(mut self) -> Result<Self, ComposerError> {
+        self.add_wgsl_struct::<T>()?;
+        Ok(self)
+    }
+
+    pub fn add_wgsl_struct<T: super::wgsl_struct::WgslStruct>(&mut self) -> Result<(), ComposerError> {
+        for (import_path, source) in T::wgsl_modules() {
+            if !self.composer.contains_module(import_path) {
+                self.add_module(import_path, &source)?;
+            }
+        }
+        Ok(())
+    }
+
     pub fn with_shader_define(mut self, name: &str, value: ShaderDefValue) -> Self {
         self.add_shader_define(name, value);
         self
@@ -82,7 +148,11 @@ impl ShaderComposer {
 
     pub fn add_shader_define(&mut self, name: &str, value: ShaderDefValue) { self.defines.insert(name.to_string(), value.into()); }
 
+    // Error of `build` or `build_ref` formatted with the faulty source lines of the composed modules
+    pub fn emit_error(&self, error: &ComposerError) -> String { error.emit_to_string(&self.composer) }
+
     pub fn build_ref(&mut self) -> Result<wgpu::naga::Module, ComposerError> {
+        super::record_shader(self.name.unwrap_or("unknown"));
         self.composer.make_naga_module(NagaModuleDescriptor {
             source: self.source,
             file_path: self.name.unwrap_or("unknown"),
@@ -92,6 +162,7 @@ impl ShaderComposer {
     }
 
     pub fn build(mut self) -> Result<wgpu::naga::Module, ComposerError> {
+        super::record_shader(self.name.unwrap_or("unknown"));
         self.composer.make_naga_module(NagaModuleDescriptor {
             source: self.source,
             file_path: self.name.unwrap_or("unknown"),
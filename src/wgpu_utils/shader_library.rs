@@ -0,0 +1,244 @@
+// Version of the crate the modules are shipped with, to check shaders against the Rust side they were written for
+const VERSION_WGSL: &str = concat!(
+    "#define_import_path oxyde::version\n\n",
+    "const OXYDE_VERSION_MAJOR: u32 = ",
+    env!("CARGO_PKG_VERSION_MAJOR"),
+    "u;\nconst OXYDE_VERSION_MINOR: u32 = ",
+    env!("CARGO_PKG_VERSION_MINOR"),
+    "u;\nconst OXYDE_VERSION_PATCH: u32 = ",
+    env!("CARGO_PKG_VERSION_PATCH"),
+    "u;\n"
+);
+
+// sRGB transfer functions, HSV conversions (Sam Hocevar's branchless version) and luminance
+const COLOR_WGSL: &str = r#"#define_import_path oxyde::color
+
+fn srgb_to_linear(color: vec3<f32>) -> vec3<f32> {
+    let low = color / 12.92;
+    let high = pow((color + 0.055) / 1.055, vec3<f32>(2.4));
+    return select(high, low, color <= vec3<f32>(0.04045));
+}
+
+fn linear_to_srgb(color: vec3<f32>) -> vec3<f32> {
+    let low = color * 12.92;
+    let high = 1.055 * pow(color, vec3<f32>(1.0 / 2.4)) - 0.055;
+    return select(high, low, color <= vec3<f32>(0.0031308));
+}
+
+// Hue, saturation and value all in [0, 1]
+fn rgb_to_hsv(color: vec3<f32>) -> vec3<f32> {
+    let k = vec4<f32>(0.0, -1.0 / 3.0, 2.0 / 3.0, -1.0);
+    let p = select(vec4<f32>(color.gb, k.xy), vec4<f32>(color.bg, k.wz), color.g < color.b);
+    let q = select(vec4<f32>(color.r, p.yzx), vec4<f32>(p.xyw, color.r), color.r < p.x);
+    let d = q.x - min(q.w, q.y);
+    let e = 1.0e-10;
+    return vec3<f32>(abs(q.z + (q.w - q.y) / (6.0 * d + e)), d / (q.x + e), q.x);
+}
+
+fn hsv_to_rgb(hsv: vec3<f32>) -> vec3<f32> {
+    let p = abs(fract(hsv.xxx + vec3<f32>(1.0, 2.0 / 3.0, 1.0 / 3.0)) * 6.0 - 3.0);
+    return hsv.z * mix(vec3<f32>(1.0), clamp(p - 1.0, vec3<f32>(0.0), vec3<f32>(1.0)), hsv.y);
+}
+
+// Relative luminance of a linear Rec. 709 (sRGB primaries) color
+fn luminance(color: vec3<f32>) -> f32 {
+    return dot(color, vec3<f32>(0.2126, 0.7152, 0.0722));
+}
+"#;
+
+// Tonemapping operators from linear HDR to [0, 1] linear colors, `tonemap` selects one from a `Tonemapping` value
+const TONEMAPPING_WGSL: &str = r#"#define_import_path oxyde::tonemapping
+
+#import oxyde::color::luminance
+
+const TONEMAPPING_NONE: u32 = 0u;
+const TONEMAPPING_REINHARD: u32 = 1u;
+const TONEMAPPING_REINHARD_LUMINANCE: u32 = 2u;
+const TONEMAPPING_ACES: u32 = 3u;
+const TONEMAPPING_UNCHARTED2: u32 = 4u;
+
+fn tonemap_reinhard(color: vec3<f32>) -> vec3<f32> {
+    return color / (1.0 + color);
+}
+
+// Applied on the luminance to keep the hue and saturation
+fn tonemap_reinhard_luminance(color: vec3<f32>) -> vec3<f32> {
+    let color_luminance = luminance(color);
+    return color * (1.0 / (1.0 + color_luminance));
+}
+
+// Krzysztof Narkowicz fit of the ACES filmic curve
+fn tonemap_aces(color: vec3<f32>) -> vec3<f32> {
+    let x = color * 0.6;
+    return clamp((x * (2.51 * x + 0.03)) / (x * (2.43 * x + 0.59) + 0.14), vec3<f32>(0.0), vec3<f32>(1.0));
+}
+
+fn uncharted2_curve(x: vec3<f32>) -> vec3<f32> {
+    let a = 0.15;
+    let b = 0.50;
+    let c = 0.10;
+    let d = 0.20;
+    let e = 0.02;
+    let f = 0.30;
+    return ((x * (a * x + c * b) + d * e) / (x * (a * x + b) + d * f)) - e / f;
+}
+
+// John Hable filmic curve, white point at 11.2
+fn tonemap_uncharted2(color: vec3<f32>) -> vec3<f32> {
+    let exposure_bias = 2.0;
+    return uncharted2_curve(color * exposure_bias) / uncharted2_curve(vec3<f32>(11.2));
+}
+
+fn tonemap(color: vec3<f32>, tonemapping: u32) -> vec3<f32> {
+    switch tonemapping {
+        case 1u: { return tonemap_reinhard(color); }
+        case 2u: { return tonemap_reinhard_luminance(color); }
+        case 3u: { return tonemap_aces(color); }
+        case 4u: { return tonemap_uncharted2(color); }
+        default: { return clamp(color, vec3<f32>(0.0), vec3<f32>(1.0)); }
+    }
+}
+
+// Scale by 2^exposure_value
+fn apply_exposure(color: vec3<f32>, exposure_value: f32) -> vec3<f32> {
+    return color * exp2(exposure_value);
+}
+"#;
+
+// PCG random number generator with a u32 state, e.g. seeded per pixel and frame:
+// `var rng = rng_seed_pixel(pixel, frame_uniforms.frame_index); let value = rng_float(&rng);`
+const RANDOM_WGSL: &str = r#"#define_import_path oxyde::random
+
+fn rng_seed(seed: u32) -> u32 {
+    return rng_hash(seed + 0x9e3779b9u);
+}
+
+fn rng_seed_pixel(pixel: vec2<u32>, frame_index: u32) -> u32 {
+    return rng_hash(rng_hash(rng_hash(pixel.x) ^ pixel.y) ^ frame_index);
+}
+
+fn rng_hash(v: u32) -> u32 {
+    let state = v * 747796405u + 2891336453u;
+    let word = ((state >> ((state >> 28u) + 4u)) ^ state) * 277803737u;
+    return (word >> 22u) ^ word;
+}
+
+fn rng_next(state: ptr<function, u32>) -> u32 {
+    *state = *state * 747796405u + 2891336453u;
+    let word = ((*state >> ((*state >> 28u) + 4u)) ^ *state) * 277803737u;
+    return (word >> 22u) ^ word;
+}
+
+// Uniform in [0, 1)
+fn rng_float(state: ptr<function, u32>) -> f32 {
+    return f32(rng_next(state) >> 8u) * (1.0 / 16777216.0);
+}
+
+fn rng_range(state: ptr<function, u32>, min_value: f32, max_value: f32) -> f32 {
+    return mix(min_value, max_value, rng_float(state));
+}
+
+// Uniform on the unit sphere
+fn rng_unit_vector(state: ptr<function, u32>) -> vec3<f32> {
+    let z = rng_float(state) * 2.0 - 1.0;
+    let angle = rng_float(state) * 6.28318530718;
+    let radius = sqrt(1.0 - z * z);
+    return vec3<f32>(radius * cos(angle), radius * sin(angle), z);
+}
+"#;
+
+// Quaternions as vec4 (xyz imaginary, w real, as glam) and matrix helpers
+const MATH_WGSL: &str = r#"#define_import_path oxyde::math
+
+const PI: f32 = 3.14159265359;
+const TAU: f32 = 6.28318530718;
+
+fn quat_from_axis_angle(axis: vec3<f32>, angle: f32) -> vec4<f32> {
+    return vec4<f32>(axis * sin(angle * 0.5), cos(angle * 0.5));
+}
+
+fn quat_mul(a: vec4<f32>, b: vec4<f32>) -> vec4<f32> {
+    return vec4<f32>(a.w * b.xyz + b.w * a.xyz + cross(a.xyz, b.xyz), a.w * b.w - dot(a.xyz, b.xyz));
+}
+
+fn quat_conjugate(q: vec4<f32>) -> vec4<f32> {
+    return vec4<f32>(-q.xyz, q.w);
+}
+
+// Rotation of `v` by the unit quaternion `q`
+fn quat_rotate(q: vec4<f32>, v: vec3<f32>) -> vec3<f32> {
+    let t = 2.0 * cross(q.xyz, v);
+    return v + q.w * t + cross(q.xyz, t);
+}
+
+fn quat_to_mat3(q: vec4<f32>) -> mat3x3<f32> {
+    return mat3x3<f32>(quat_rotate(q, vec3<f32>(1.0, 0.0, 0.0)), quat_rotate(q, vec3<f32>(0.0, 1.0, 0.0)), quat_rotate(q, vec3<f32>(0.0, 0.0, 1.0)));
+}
+
+fn mat4_to_mat3(m: mat4x4<f32>) -> mat3x3<f32> {
+    return mat3x3<f32>(m[0].xyz, m[1].xyz, m[2].xyz);
+}
+
+fn inverse_mat3(m: mat3x3<f32>) -> mat3x3<f32> {
+    let row_0 = cross(m[1], m[2]);
+    let row_1 = cross(m[2], m[0]);
+    let row_2 = cross(m[0], m[1]);
+    return transpose(mat3x3<f32>(row_0, row_1, row_2)) * (1.0 / dot(m[0], row_0));
+}
+
+// Matrix transforming the normals of a model matrix, valid with non uniform scales
+fn normal_matrix(model: mat4x4<f32>) -> mat3x3<f32> {
+    return transpose(inverse_mat3(mat4_to_mat3(model)));
+}
+"#;
+
+// Vertex stage drawing a triangle covering the screen with `draw(0..3, 0..1)`, uv origin at the top left:
+// `@vertex fn vs_main(@builtin(vertex_index) vertex_index: u32) -> FullscreenVertexOutput { return fullscreen_vertex(vertex_index); }`
+const FULLSCREEN_WGSL: &str = r#"#define_import_path oxyde::fullscreen
+
+struct FullscreenVertexOutput {
+    @builtin(position) position: vec4<f32>,
+    @location(0) uv: vec2<f32>,
+}
+
+fn fullscreen_vertex(vertex_index: u32) -> FullscreenVertexOutput {
+    let uv = vec2<f32>(f32((vertex_index << 1u) & 2u), f32(vertex_index & 2u));
+    var output: FullscreenVertexOutput;
+    output.position = vec4<f32>(uv.x * 2.0 - 1.0, 1.0 - uv.y * 2.0, 0.0, 1.0);
+    output.uv = uv;
+    return output;
+}
+"#;
+
+// Tonemapping operators of the `oxyde::tonemapping` module, passed to `tonemap` as `Tonemapping as u32`
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize), serde(rename_all = "snake_case"))]
+pub enum Tonemapping {
+    // Clamped to [0, 1]
+    None = 0,
+    Reinhard = 1,
+    ReinhardLuminance = 2,
+    #[default]
+    Aces = 3,
+    Uncharted2 = 4,
+}
+
+impl Tonemapping {
+    pub const ALL: [Tonemapping; 5] = [
+        Tonemapping::None,
+        Tonemapping::Reinhard,
+        Tonemapping::ReinhardLuminance,
+        Tonemapping::Aces,
+        Tonemapping::Uncharted2,
+    ];
+}
+
+// (import path, source) of the common modules in dependency order, added by `ShaderComposer::with_common`
+pub const COMMON_MODULES: [(&str, &str); 6] = [
+    ("oxyde::version", VERSION_WGSL),
+    ("oxyde::color", COLOR_WGSL),
+    ("oxyde::tonemapping", TONEMAPPING_WGSL),
+    ("oxyde::random", RANDOM_WGSL),
+    ("oxyde::math", MATH_WGSL),
+    ("oxyde::fullscreen", FULLSCREEN_WGSL),
+];
@@ -83,6 +83,7 @@ pub fn load_glsl_shader_module_from_path(device: &wgpu::Device, path: &Path, ent
 
     let label = Some(path.file_name().unwrap().to_str().unwrap());
 
+    super::record_shader(path.display().to_string());
     let module = device.create_shader_module(wgpu::ShaderModuleDescriptor {
         label,
         source: wgpu::util::make_spirv(compilation_artifact.as_binary_u8()),
@@ -163,6 +164,7 @@ pub fn load_glsl_shader_module_from_string(
         warn!("warnings when compiling:\n{}", compilation_artifact.get_warning_messages());
     }
 
+    super::record_shader(label.unwrap_or("unknown"));
     let module = device.create_shader_module(wgpu::ShaderModuleDescriptor {
         label,
         source: wgpu::util::make_spirv(compilation_artifact.as_binary_u8()),
@@ -0,0 +1,166 @@
+use std::sync::{mpsc, Arc};
+
+use wgpu::{util::align_to, Buffer, BufferAddress, BufferDescriptor, BufferSize, BufferUsages, BufferViewMut, CommandEncoder, Device, MapMode, Queue};
+
+use super::{child_label, memory_tracker::MemoryAllocation};
+
+// Chunk of mappable memory shared by several uploads during a frame
+struct Chunk {
+    buffer: Arc<Buffer>,
+    size: BufferAddress,
+    offset: BufferAddress,
+    _memory: MemoryAllocation,
+}
+
+impl Chunk {
+    fn can_allocate(&self, size: BufferSize) -> bool { self.offset + size.get() <= self.size }
+}
+
+// Staging belt handing out chunks of mappable memory for many small per-frame uploads (uniforms, instance data...)
+// instead of issuing one `queue.write_buffer` per upload.
+// Usage per frame: `write_buffer` as many times as needed, `finish` before submitting the encoder and `recall` right after the submit.
+// Chunks are recycled once the queue reports the submitted work as done (`on_submitted_work_done`) and they are mapped again.
+pub struct StagingBelt {
+    chunk_size: BufferAddress,
+    label: Option<&'static str>,
+    // Chunks currently mapped and used for uploads of the current frame
+    active_chunks: Vec<Chunk>,
+    // Chunks unmapped and referenced by copy commands that are not submitted yet
+    closed_chunks: Vec<Chunk>,
+    // Mapped chunks ready to be reused
+    free_chunks: Vec<Chunk>,
+    submitted_sender: mpsc::Sender<Vec<Chunk>>,
+    submitted_receiver: mpsc::Receiver<Vec<Chunk>>,
+    free_sender: mpsc::Sender<Chunk>,
+    free_receiver: mpsc::Receiver<Chunk>,
+    chunk_count: usize,
+}
+
+impl StagingBelt {
+    // chunk_size is the default size of the staging buffers, uploads bigger than it get a dedicated chunk
+    pub fn new(chunk_size: BufferAddress, label: Option<&'static str>) -> Self {
+        let (submitted_sender, submitted_receiver) = mpsc::channel();
+        let (free_sender, free_receiver) = mpsc::channel();
+        Self {
+            chunk_size,
+            label,
+            active_chunks: Vec::new(),
+            closed_chunks: Vec::new(),
+            free_chunks: Vec::new(),
+            submitted_sender,
+            submitted_receiver,
+            free_sender,
+            free_receiver,
+            chunk_count: 0,
+        }
+    }
+
+    // Record a copy of `size` bytes into `target` at `offset` and return the mapped view to fill with the data.
+    // `size` and `offset` must be multiples of `wgpu::COPY_BUFFER_ALIGNMENT`.
+    pub fn write_buffer(
+        &mut self,
+        encoder: &mut CommandEncoder,
+        target: &Buffer,
+        offset: BufferAddress,
+        size: BufferSize,
+        device: &Device,
+    ) -> BufferViewMut<'_> {
+        self.receive_chunks();
+
+        let mut chunk = if let Some(index) = self.active_chunks.iter().position(|chunk| chunk.can_allocate(size)) {
+            self.active_chunks.swap_remove(index)
+        } else if let Some(index) = self.free_chunks.iter().position(|chunk| chunk.can_allocate(size)) {
+            self.free_chunks.swap_remove(index)
+        } else {
+            self.create_chunk(device, size)
+        };
+
+        let chunk_offset = chunk.offset;
+        chunk.offset = align_to(chunk_offset + size.get(), wgpu::MAP_ALIGNMENT);
+
+        encoder.copy_buffer_to_buffer(&chunk.buffer, chunk_offset, target, offset, size.get());
+
+        self.active_chunks.push(chunk);
+        self.active_chunks
+            .last()
+            .unwrap()
+            .buffer
+            .slice(chunk_offset..chunk_offset + size.get())
+            .get_mapped_range_mut()
+    }
+
+    // Convenient helper copying `data` directly, empty data records nothing
+    pub fn write(&mut self, encoder: &mut CommandEncoder, target: &Buffer, offset: BufferAddress, data: &[u8], device: &Device) {
+        if let Some(size) = BufferSize::new(data.len() as BufferAddress) {
+            self.write_buffer(encoder, target, offset, size, device).copy_from_slice(data);
+        }
+    }
+
+    // Unmap the chunks used this frame, must be called before submitting the encoders given to `write_buffer`
+    pub fn finish(&mut self) {
+        for chunk in self.active_chunks.drain(..) {
+            chunk.buffer.unmap();
+            self.closed_chunks.push(chunk);
+        }
+    }
+
+    // Give back the chunks closed by `finish` once the GPU is done with the work submitted so far,
+    // must be called after the submission of the encoders given to `write_buffer`
+    pub fn recall(&mut self, queue: &Queue) {
+        self.receive_chunks();
+
+        if self.closed_chunks.is_empty() {
+            return;
+        }
+
+        let chunks = std::mem::take(&mut self.closed_chunks);
+        let sender = self.submitted_sender.clone();
+        queue.on_submitted_work_done(move || {
+            let _ = sender.send(chunks);
+        });
+    }
+
+    #[inline]
+    pub fn chunk_count(&self) -> usize { self.chunk_count }
+    #[inline]
+    pub fn free_chunk_count(&self) -> usize { self.free_chunks.len() }
+
+    fn create_chunk(&mut self, device: &Device, size: BufferSize) -> Chunk {
+        let size = align_to(self.chunk_size.max(size.get()), wgpu::MAP_ALIGNMENT);
+        self.chunk_count += 1;
+        let label = child_label(Some(self.label.unwrap_or("StagingBelt")), &format!("chunk {}", self.chunk_count));
+        let buffer = device.create_buffer(&BufferDescriptor {
+            label: Some(label.as_str()),
+            size,
+            usage: BufferUsages::MAP_WRITE | BufferUsages::COPY_SRC,
+            mapped_at_creation: true,
+        });
+        Chunk {
+            _memory: MemoryAllocation::for_buffer(&buffer, Some(label.as_str())),
+            buffer: Arc::new(buffer),
+            size,
+            offset: 0,
+        }
+    }
+
+    // Map again chunks whose work is done and collect chunks whose mapping completed
+    fn receive_chunks(&mut self) {
+        for chunks in self.submitted_receiver.try_iter() {
+            for chunk in chunks {
+                let sender = self.free_sender.clone();
+                let buffer = chunk.buffer.clone();
+                buffer.slice(..).map_async(MapMode::Write, move |result| {
+                    // A chunk that failed to map is dropped, a new one will be created when needed
+                    if result.is_ok() {
+                        let _ = sender.send(chunk);
+                    }
+                });
+            }
+        }
+
+        for mut chunk in self.free_receiver.try_iter() {
+            chunk.offset = 0;
+            self.free_chunks.push(chunk);
+        }
+    }
+}
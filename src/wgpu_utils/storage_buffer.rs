@@ -0,0 +1,118 @@
+use encase::{internal::ReadFrom, internal::WriteInto, ShaderType};
+
+use super::{
+    binding_builder::{BindGroupBuilder, BindGroupLayoutBuilder, BindGroupLayoutWithDesc},
+    memory_tracker::MemoryAllocation,
+};
+
+// Storage buffer holding a `ShaderType` content serialized following the WGSL std430-like layout rules
+// The content may end with a runtime sized array (e.g. a `Vec`), the buffer is reallocated when its size changes
+pub struct StorageBufferWrapper<Content> {
+    content: Content,
+    buffer: wgpu::Buffer,
+    previous_content: Vec<u8>,
+    bind_group_layout_with_desc: BindGroupLayoutWithDesc,
+    bind_group: wgpu::BindGroup,
+    _memory: MemoryAllocation,
+}
+
+impl<Content: ShaderType + WriteInto> StorageBufferWrapper<Content> {
+    fn name() -> &'static str {
+        let type_name = std::any::type_name::<Content>();
+        let type_name = &type_name[..type_name.find('<').unwrap_or(type_name.len())];
+        &type_name[type_name.rfind(':').map_or(0, |pos| pos + 1)..]
+    }
+
+    fn serialize(content: &Content) -> Vec<u8> {
+        let mut bytes = encase::StorageBuffer::new(Vec::with_capacity(content.size().get() as usize));
+        bytes.write(content).expect("storage content serialization into a Vec can't fail");
+        bytes.into_inner()
+    }
+
+    fn create_buffer(device: &wgpu::Device, contents: &[u8]) -> (wgpu::Buffer, MemoryAllocation) {
+        let label = format!("StorageBuffer: {}", Self::name());
+        let buffer = super::buffers::create_buffer_from_content(
+            device,
+            wgpu::BufferUsages::STORAGE | wgpu::BufferUsages::COPY_DST | wgpu::BufferUsages::COPY_SRC,
+            Some(&label),
+            Some(contents),
+        );
+        let memory = MemoryAllocation::for_buffer(&buffer, Some(&label));
+        (buffer, memory)
+    }
+
+    fn create_bind_group(device: &wgpu::Device, layout: &BindGroupLayoutWithDesc, buffer: &wgpu::Buffer) -> wgpu::BindGroup {
+        BindGroupBuilder::new(layout)
+            .resource(buffer.as_entire_binding())
+            .create(device, Some(&format!("BindGroup: {}", Self::name())))
+    }
+
+    pub fn new(device: &wgpu::Device, content: Content, visibility: wgpu::ShaderStages, read_only: bool) -> Self {
+        let previous_content = Self::serialize(&content);
+        let (buffer, memory) = Self::create_buffer(device, &previous_content);
+
+        let bind_group_layout_with_desc = BindGroupLayoutBuilder::new()
+            .add_binding(
+                visibility,
+                wgpu::BindingType::Buffer {
+                    ty: wgpu::BufferBindingType::Storage { read_only },
+                    has_dynamic_offset: false,
+                    min_binding_size: Some(Content::min_size()),
+                },
+            )
+            .create(device, Some(&format!("BindGroupLayout: {}", Self::name())));
+
+        let bind_group = Self::create_bind_group(device, &bind_group_layout_with_desc, &buffer);
+
+        StorageBufferWrapper {
+            content,
+            buffer,
+            previous_content,
+            bind_group_layout_with_desc,
+            bind_group,
+            _memory: memory,
+        }
+    }
+
+    // Uploads the content if it changed, returns true if the buffer had to be reallocated (and the bind group recreated).
+    // The buffer is reallocated whenever the content size changes (e.g. a runtime sized array growing or shrinking), the
+    // bind group binding the whole buffer so `arrayLength` matches the content.
+    pub fn update_content(&mut self, device: &wgpu::Device, queue: &wgpu::Queue) -> bool {
+        let new_content = Self::serialize(&self.content);
+        if self.previous_content == new_content {
+            return false;
+        }
+
+        let reallocated = new_content.len() as u64 != self.buffer.size();
+        if reallocated {
+            let (buffer, memory) = Self::create_buffer(device, &new_content);
+            self.bind_group = Self::create_bind_group(device, &self.bind_group_layout_with_desc, &buffer);
+            self.buffer = buffer;
+            self._memory = memory;
+        } else {
+            queue.write_buffer(&self.buffer, 0, &new_content);
+        }
+        self.previous_content = new_content;
+        reallocated
+    }
+
+    pub fn content_mut(&mut self) -> &mut Content { &mut self.content }
+    pub fn content(&self) -> &Content { &self.content }
+
+    pub fn buffer(&self) -> &wgpu::Buffer { &self.buffer }
+
+    pub fn binding_resource(&self) -> wgpu::BindingResource<'_> { self.buffer.as_entire_binding() }
+
+    pub fn bind_group(&self) -> &wgpu::BindGroup { &self.bind_group }
+
+    pub fn layout(&self) -> &wgpu::BindGroupLayout { &self.bind_group_layout_with_desc.layout }
+}
+
+impl<Content: ShaderType + WriteInto + ReadFrom> StorageBufferWrapper<Content> {
+    // Deserializes bytes read back from the buffer (e.g. through a mapped staging buffer) into the content
+    pub fn read_content(&mut self, bytes: &[u8]) -> encase::internal::Result<()> {
+        encase::StorageBuffer::new(bytes).read(&mut self.content)?;
+        self.previous_content = Self::serialize(&self.content);
+        Ok(())
+    }
+}
@@ -0,0 +1,143 @@
+use super::{child_label, TextureBuilder, TextureResource};
+
+// Texture written by compute passes through a storage binding then read by the next passes through a sampled binding.
+// Storage bindings see a single mip, so the storage view is on mip 0 while the sampled view covers all the mips.
+pub struct StorageTexture {
+    builder: TextureBuilder,
+    access: wgpu::StorageTextureAccess,
+    resource: TextureResource,
+    storage_view: wgpu::TextureView,
+}
+
+impl StorageTexture {
+    // 2D texture, `format` must support storage bindings (e.g. Rgba16Float, Rgba32Float, R32Float, Rgba8Unorm)
+    pub fn new(
+        device: &wgpu::Device,
+        format: wgpu::TextureFormat,
+        width: u32,
+        height: u32,
+        access: wgpu::StorageTextureAccess,
+        label: Option<&str>,
+    ) -> Self {
+        let builder = TextureBuilder::storage(format, width, height).with_label(format!("StorageTexture: {}", label.unwrap_or("unknown")));
+        Self::from_builder(device, builder, access)
+    }
+
+    // Array, 3D or mipmapped storage textures, the storage and sampled usages are added to the builder ones
+    pub fn from_builder(device: &wgpu::Device, builder: TextureBuilder, access: wgpu::StorageTextureAccess) -> Self {
+        let builder = builder.with_usage(wgpu::TextureUsages::STORAGE_BINDING | wgpu::TextureUsages::TEXTURE_BINDING);
+        let resource = builder.build(device);
+        let storage_view = Self::create_storage_view(&resource, 0);
+        Self { builder, access, resource, storage_view }
+    }
+
+    fn create_storage_view(resource: &TextureResource, mip_level: u32) -> wgpu::TextureView {
+        resource.texture().create_view(&wgpu::TextureViewDescriptor {
+            label: Some(&child_label(Some(resource.label()), &format!("storage mip {}", mip_level))),
+            dimension: Some(Self::storage_view_dimension(resource.view_dimension())),
+            base_mip_level: mip_level,
+            mip_level_count: Some(1),
+            ..Default::default()
+        })
+    }
+
+    // Cube textures are written as 2D arrays
+    fn storage_view_dimension(view_dimension: wgpu::TextureViewDimension) -> wgpu::TextureViewDimension {
+        match view_dimension {
+            wgpu::TextureViewDimension::Cube | wgpu::TextureViewDimension::CubeArray => wgpu::TextureViewDimension::D2Array,
+            view_dimension => view_dimension,
+        }
+    }
+
+    // Recreate the texture with a new 2D size, its content is lost.
+    // Returns true if the texture was recreated, in which case the bind groups using it must be created again.
+    pub fn resize(&mut self, device: &wgpu::Device, width: u32, height: u32) -> bool {
+        let size = self.resource.size();
+        if (size.width, size.height) == (width, height) {
+            return false;
+        }
+        let descriptor = self.builder.descriptor();
+        let mut builder = TextureBuilder::new(descriptor.format, width, height)
+            .with_usage(descriptor.usage)
+            .with_mip_level_count(descriptor.mip_level_count);
+        builder = match descriptor.dimension {
+            wgpu::TextureDimension::D3 => builder.with_depth(size.depth_or_array_layers),
+            _ => builder.with_array_layer_count(size.depth_or_array_layers),
+        };
+        if let Some(label) = descriptor.label {
+            builder = builder.with_label(label);
+        }
+        *self = Self::from_builder(device, builder, self.access);
+        true
+    }
+
+    pub fn texture(&self) -> &wgpu::Texture { self.resource.texture() }
+
+    pub fn format(&self) -> wgpu::TextureFormat { self.resource.format() }
+
+    pub fn size(&self) -> wgpu::Extent3d { self.resource.size() }
+
+    pub fn access(&self) -> wgpu::StorageTextureAccess { self.access }
+
+    // View of mip 0 for the storage binding
+    pub fn storage_view(&self) -> &wgpu::TextureView { &self.storage_view }
+
+    // Storage view of another mip, e.g. to write a mip chain level by level
+    pub fn create_storage_mip_view(&self, mip_level: u32) -> wgpu::TextureView { Self::create_storage_view(&self.resource, mip_level) }
+
+    // View of all the mips for the sampled binding
+    pub fn sampled_view(&self) -> &wgpu::TextureView { self.resource.view() }
+
+    // Layout entry types, e.g. `BindGroupLayoutBuilder::new().add_binding_compute(output.storage_binding_type())`
+    pub fn storage_binding_type(&self) -> wgpu::BindingType {
+        wgpu::BindingType::StorageTexture {
+            access: self.access,
+            format: self.format(),
+            view_dimension: Self::storage_view_dimension(self.resource.view_dimension()),
+        }
+    }
+
+    // Float formats that can't be filtered (e.g. Rgba32Float) are bound as non filterable
+    pub fn sampled_binding_type(&self) -> wgpu::BindingType { self.resource.sampled_binding_type() }
+
+    // WGSL type of the storage binding, e.g. `texture_storage_2d<rgba16float, write>`
+    pub fn storage_wgsl_type(&self) -> String {
+        let dimension = match Self::storage_view_dimension(self.resource.view_dimension()) {
+            wgpu::TextureViewDimension::D1 => "1d",
+            wgpu::TextureViewDimension::D2Array => "2d_array",
+            wgpu::TextureViewDimension::D3 => "3d",
+            _ => "2d",
+        };
+        let access = match self.access {
+            wgpu::StorageTextureAccess::ReadOnly => "read",
+            wgpu::StorageTextureAccess::WriteOnly => "write",
+            wgpu::StorageTextureAccess::ReadWrite => "read_write",
+        };
+        format!("texture_storage_{}<{}, {}>", dimension, wgsl_storage_format(self.format()), access)
+    }
+}
+
+// WGSL texel format of the storage formats
+fn wgsl_storage_format(format: wgpu::TextureFormat) -> &'static str {
+    use wgpu::TextureFormat as Format;
+    match format {
+        Format::Rgba8Unorm => "rgba8unorm",
+        Format::Rgba8Snorm => "rgba8snorm",
+        Format::Rgba8Uint => "rgba8uint",
+        Format::Rgba8Sint => "rgba8sint",
+        Format::Rgba16Uint => "rgba16uint",
+        Format::Rgba16Sint => "rgba16sint",
+        Format::Rgba16Float => "rgba16float",
+        Format::R32Uint => "r32uint",
+        Format::R32Sint => "r32sint",
+        Format::R32Float => "r32float",
+        Format::Rg32Uint => "rg32uint",
+        Format::Rg32Sint => "rg32sint",
+        Format::Rg32Float => "rg32float",
+        Format::Rgba32Uint => "rgba32uint",
+        Format::Rgba32Sint => "rgba32sint",
+        Format::Rgba32Float => "rgba32float",
+        Format::Bgra8Unorm => "bgra8unorm",
+        format => panic!("{:?} is not a storage texture format", format),
+    }
+}
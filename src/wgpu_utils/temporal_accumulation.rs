@@ -0,0 +1,218 @@
+use super::{
+    binding_builder::{BindGroupBuilder, BindGroupLayoutBuilder, BindGroupLayoutWithDesc},
+    uniform_buffer::UniformBuffer,
+    PingPongTexture,
+    PingPongTextureLayout,
+};
+
+const WORKGROUP_SIZE: u32 = 8;
+
+#[repr(C)]
+#[derive(Clone, Copy, Default, Debug, PartialEq, bytemuck::Pod, bytemuck::Zeroable)]
+struct AccumulationParams {
+    sample_count: u32,
+    _padding: [u32; 3],
+}
+
+// Running average of the frames: sample n + 1 is weighted 1 / (n + 1) so every sample contributes the same
+const ACCUMULATION_WGSL: &str = r#"
+struct AccumulationParams {
+    sample_count: u32,
+    _padding_0: u32,
+    _padding_1: u32,
+    _padding_2: u32,
+}
+
+@group(0) @binding(0) var history: texture_2d<f32>;
+@group(0) @binding(1) var accumulated: texture_storage_2d<{format}, write>;
+@group(1) @binding(0) var current: texture_2d<f32>;
+@group(1) @binding(1) var<uniform> params: AccumulationParams;
+
+@compute @workgroup_size({workgroup_size}, {workgroup_size})
+fn main(@builtin(global_invocation_id) id: vec3<u32>) {
+    if any(id.xy >= textureDimensions(accumulated)) {
+        return;
+    }
+    let sample = textureLoad(current, id.xy, 0);
+    // The history is ignored for the first sample, it may hold anything after a reset
+    var result = sample;
+    if params.sample_count > 0u {
+        result = mix(textureLoad(history, id.xy, 0), sample, 1.0 / f32(params.sample_count + 1u));
+    }
+    textureStore(accumulated, id.xy, result);
+}
+"#;
+
+// Progressive accumulation of frames (e.g. of a path tracer) into a history `PingPongTexture`.
+// Each `encode` averages the current frame into the history, until `max_samples` are accumulated.
+// The average restarts after `reset`, e.g. when the camera moves (see `reset_if_changed`).
+pub struct TemporalAccumulation {
+    history: PingPongTexture,
+    history_bind_groups: (wgpu::BindGroup, wgpu::BindGroup),
+    current_bind_group_layout: BindGroupLayoutWithDesc,
+    params: UniformBuffer<AccumulationParams>,
+    pipeline: wgpu::ComputePipeline,
+    sample_count: u32,
+    max_samples: Option<u32>,
+    // Bytes of the last state given to `reset_if_changed`
+    tracked_state: Vec<u8>,
+}
+
+impl TemporalAccumulation {
+    // `format` is the accumulation format, Rgba32Float (recommended for long accumulations) or Rgba16Float
+    pub fn new(device: &wgpu::Device, format: wgpu::TextureFormat, width: u32, height: u32, max_samples: Option<u32>) -> Self {
+        let format_wgsl = match format {
+            wgpu::TextureFormat::Rgba32Float => "rgba32float",
+            wgpu::TextureFormat::Rgba16Float => "rgba16float",
+            format => panic!("{:?} can't be used for the accumulation, use Rgba32Float or Rgba16Float", format),
+        };
+
+        let history = PingPongTexture::from_descriptor_with_layout(
+            device,
+            &wgpu::TextureDescriptor {
+                label: Some("TemporalAccumulation: history"),
+                size: wgpu::Extent3d { width, height, depth_or_array_layers: 1 },
+                mip_level_count: 1,
+                sample_count: 1,
+                dimension: wgpu::TextureDimension::D2,
+                format,
+                usage: wgpu::TextureUsages::TEXTURE_BINDING | wgpu::TextureUsages::STORAGE_BINDING | wgpu::TextureUsages::COPY_SRC,
+                view_formats: &[],
+            },
+            PingPongTextureLayout::compute(format),
+            Some("TemporalAccumulation: history"),
+        )
+        .expect("Accumulation history creation");
+        let history_bind_groups = history.create_bind_groups(device, None);
+
+        let current_bind_group_layout = BindGroupLayoutBuilder::new()
+            .add_binding_compute(wgpu::BindingType::Texture {
+                sample_type: wgpu::TextureSampleType::Float { filterable: false },
+                view_dimension: wgpu::TextureViewDimension::D2,
+                multisampled: false,
+            })
+            .add_binding_compute(wgpu::BindingType::Buffer {
+                ty: wgpu::BufferBindingType::Uniform,
+                has_dynamic_offset: false,
+                min_binding_size: None,
+            })
+            .create(device, Some("TemporalAccumulation: current"));
+
+        let shader_module = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: Some("TemporalAccumulation"),
+            source: wgpu::ShaderSource::Wgsl(
+                ACCUMULATION_WGSL
+                    .replace("{format}", format_wgsl)
+                    .replace("{workgroup_size}", &WORKGROUP_SIZE.to_string())
+                    .into(),
+            ),
+        });
+        let pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("TemporalAccumulation"),
+            bind_group_layouts: &[&history.bind_group_layout.layout, &current_bind_group_layout.layout],
+            push_constant_ranges: &[],
+        });
+        let pipeline = device.create_compute_pipeline(&wgpu::ComputePipelineDescriptor {
+            label: Some("TemporalAccumulation"),
+            layout: Some(&pipeline_layout),
+            module: &shader_module,
+            entry_point: "main",
+        });
+
+        Self {
+            history,
+            history_bind_groups,
+            current_bind_group_layout,
+            params: UniformBuffer::new(device),
+            pipeline,
+            sample_count: 0,
+            max_samples,
+            tracked_state: Vec::new(),
+        }
+    }
+
+    // Resize the history, which restarts the accumulation
+    pub fn resize(&mut self, device: &wgpu::Device, width: u32, height: u32) {
+        let size = wgpu::Extent3d { width, height, depth_or_array_layers: 1 };
+        if self.history.resize(device, size) {
+            self.history_bind_groups = self.history.create_bind_groups(device, None);
+            self.reset();
+        }
+    }
+
+    pub fn reset(&mut self) { self.sample_count = 0; }
+
+    // Reset when `state` (e.g. the camera view projection matrix) differs from the one of the previous call.
+    // Returns true if the accumulation was reset.
+    pub fn reset_if_changed<T: bytemuck::Pod>(&mut self, state: &T) -> bool {
+        let bytes = bytemuck::bytes_of(state);
+        if self.tracked_state == bytes {
+            return false;
+        }
+        self.tracked_state = bytes.to_vec();
+        self.reset();
+        true
+    }
+
+    pub fn sample_count(&self) -> u32 { self.sample_count }
+
+    pub fn max_samples(&self) -> Option<u32> { self.max_samples }
+
+    pub fn set_max_samples(&mut self, max_samples: Option<u32>) { self.max_samples = max_samples; }
+
+    pub fn is_converged(&self) -> bool { self.max_samples.is_some_and(|max_samples| self.sample_count >= max_samples) }
+
+    // Accumulate `current_view` (any float texture of the history size), nothing is done once converged.
+    // Returns true if the frame was accumulated. The sample count is written with the queue, so frames are accumulated
+    // at most once per submit.
+    pub fn encode(
+        &mut self,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        encoder: &mut wgpu::CommandEncoder,
+        current_view: &wgpu::TextureView,
+    ) -> bool {
+        if self.is_converged() {
+            return false;
+        }
+
+        self.params.update_content(
+            queue,
+            AccumulationParams {
+                sample_count: self.sample_count,
+                _padding: [0; 3],
+            },
+        );
+        let current_bind_group = BindGroupBuilder::new(&self.current_bind_group_layout)
+            .texture(current_view)
+            .resource(self.params.binding_resource())
+            .create(device, Some("TemporalAccumulation: current"));
+        // The bind group of the rendered texture reads it and writes the target one
+        let history_bind_group = if self.history.state {
+            &self.history_bind_groups.1
+        } else {
+            &self.history_bind_groups.0
+        };
+        let size = self.history.size();
+        {
+            let mut compute_pass = encoder.begin_compute_pass(&wgpu::ComputePassDescriptor {
+                label: Some("TemporalAccumulation"),
+                timestamp_writes: None,
+            });
+            compute_pass.set_pipeline(&self.pipeline);
+            compute_pass.set_bind_group(0, history_bind_group, &[]);
+            compute_pass.set_bind_group(1, &current_bind_group, &[]);
+            compute_pass.dispatch_workgroups(size.width.div_ceil(WORKGROUP_SIZE), size.height.div_ceil(WORKGROUP_SIZE), 1);
+        }
+        self.history.toogle_state();
+        self.sample_count += 1;
+        true
+    }
+
+    // Accumulated result, valid once a frame was accumulated since the last reset
+    pub fn result_view(&self) -> &wgpu::TextureView { self.history.get_rendered_texture_view() }
+
+    pub fn result_texture(&self) -> &wgpu::Texture { self.history.get_rendered_texture() }
+
+    pub fn history(&self) -> &PingPongTexture { &self.history }
+}
@@ -0,0 +1,126 @@
+use super::{child_label, TextureBuilder, TextureResource};
+
+// 2D texture array whose layers are uploaded one by one (e.g. the albedo maps of all the materials), bound once as a
+// `texture_2d_array` and indexed by layer in the shaders instead of binding a texture per material.
+// All the layers share the size and format of the array.
+pub struct TextureArray {
+    resource: TextureResource,
+}
+
+impl TextureArray {
+    pub fn new(device: &wgpu::Device, format: wgpu::TextureFormat, width: u32, height: u32, layer_count: u32, label: Option<&str>) -> Self {
+        Self::from_builder(
+            device,
+            TextureBuilder::texture_array(format, width, height, layer_count).with_label(format!("TextureArray: {}", label.unwrap_or("unknown"))),
+        )
+    }
+
+    // Array with mips or other usages, from a `TextureBuilder::texture_array` preset
+    pub fn from_builder(device: &wgpu::Device, builder: TextureBuilder) -> Self {
+        let resource = builder.with_usage(wgpu::TextureUsages::COPY_DST).build(device);
+        assert_eq!(
+            resource.view_dimension(),
+            wgpu::TextureViewDimension::D2Array,
+            "TextureArray needs a builder from the TextureBuilder::texture_array preset"
+        );
+        Self { resource }
+    }
+
+    pub fn texture(&self) -> &wgpu::Texture { self.resource.texture() }
+
+    // View of all the layers
+    pub fn view(&self) -> &wgpu::TextureView { self.resource.view() }
+
+    pub fn format(&self) -> wgpu::TextureFormat { self.resource.format() }
+
+    pub fn size(&self) -> wgpu::Extent3d { self.resource.size() }
+
+    pub fn layer_count(&self) -> u32 { self.resource.size().depth_or_array_layers }
+
+    pub fn mip_level_count(&self) -> u32 { self.resource.texture().mip_level_count() }
+
+    // Layout entry type, see also `BindGroupLayoutBuilder::add_texture_2d_array`
+    pub fn binding_type(&self) -> wgpu::BindingType { self.resource.sampled_binding_type() }
+
+    pub fn binding_resource(&self) -> wgpu::BindingResource<'_> { self.resource.binding_resource() }
+
+    // Write a mip of a layer, `data` holds its tightly packed rows (of blocks for compressed formats)
+    pub fn upload_layer_mip(&self, queue: &wgpu::Queue, layer: u32, mip_level: u32, data: &[u8]) {
+        assert!(layer < self.layer_count(), "Layer {} out of the {} layers of the array", layer, self.layer_count());
+        let format = self.format();
+        let size = self.size().mip_level_size(mip_level, wgpu::TextureDimension::D2);
+        let (block_width, block_height) = format.block_dimensions();
+        let block_size = format.block_copy_size(None).unwrap_or_else(|| panic!("{:?} can't be uploaded", format));
+        let bytes_per_row = size.width.div_ceil(block_width) * block_size;
+        let rows = size.height.div_ceil(block_height);
+        assert_eq!(
+            data.len(),
+            (bytes_per_row * rows) as usize,
+            "Data size of layer {} mip {} doesn't match the array format and size",
+            layer,
+            mip_level
+        );
+
+        queue.write_texture(
+            wgpu::ImageCopyTexture {
+                texture: self.texture(),
+                mip_level,
+                origin: wgpu::Origin3d { x: 0, y: 0, z: layer },
+                aspect: wgpu::TextureAspect::All,
+            },
+            data,
+            wgpu::ImageDataLayout {
+                offset: 0,
+                bytes_per_row: Some(bytes_per_row),
+                rows_per_image: Some(rows),
+            },
+            wgpu::Extent3d {
+                depth_or_array_layers: 1,
+                ..size.physical_size(format)
+            },
+        );
+    }
+
+    pub fn upload_layer(&self, queue: &wgpu::Queue, layer: u32, data: &[u8]) { self.upload_layer_mip(queue, layer, 0, data); }
+
+    // Upload an image of the array size to a layer of a Rgba8Unorm or Rgba8UnormSrgb array
+    #[cfg(feature = "image")]
+    pub fn upload_layer_image(&self, queue: &wgpu::Queue, layer: u32, image: &image::DynamicImage) {
+        assert!(
+            matches!(self.format(), wgpu::TextureFormat::Rgba8Unorm | wgpu::TextureFormat::Rgba8UnormSrgb),
+            "Images can only be uploaded to Rgba8Unorm or Rgba8UnormSrgb arrays"
+        );
+        let size = self.size();
+        assert_eq!(
+            (image.width(), image.height()),
+            (size.width, size.height),
+            "Image size doesn't match the array size"
+        );
+        self.upload_layer(queue, layer, &image.to_rgba8());
+    }
+
+    // 2D view of a layer (all its mips), e.g. to render to it or preview it
+    pub fn create_layer_view(&self, layer: u32) -> wgpu::TextureView {
+        self.texture().create_view(&wgpu::TextureViewDescriptor {
+            label: Some(&child_label(Some(self.resource.label()), &format!("layer {}", layer))),
+            dimension: Some(wgpu::TextureViewDimension::D2),
+            base_array_layer: layer,
+            array_layer_count: Some(1),
+            ..Default::default()
+        })
+    }
+
+    // Array view of `layer_count` layers from `base_layer`, to bind a subset of the array
+    pub fn create_layers_view(&self, base_layer: u32, layer_count: u32) -> wgpu::TextureView {
+        self.texture().create_view(&wgpu::TextureViewDescriptor {
+            label: Some(&child_label(
+                Some(self.resource.label()),
+                &format!("layers {}..{}", base_layer, base_layer + layer_count),
+            )),
+            dimension: Some(wgpu::TextureViewDimension::D2Array),
+            base_array_layer: base_layer,
+            array_layer_count: Some(layer_count),
+            ..Default::default()
+        })
+    }
+}
@@ -0,0 +1,233 @@
+use super::{child_label, memory_tracker::MemoryAllocation};
+
+// Builder of a texture with its default view, the presets set the usages their use requires and the `with_` methods add the
+// usages they imply (e.g. RENDER_ATTACHMENT for multisampling)
+#[derive(Clone, Debug)]
+pub struct TextureBuilder {
+    label: Option<String>,
+    size: wgpu::Extent3d,
+    mip_level_count: u32,
+    sample_count: u32,
+    dimension: wgpu::TextureDimension,
+    format: wgpu::TextureFormat,
+    usage: wgpu::TextureUsages,
+    view_formats: Vec<wgpu::TextureFormat>,
+    cube: bool,
+    array: bool,
+}
+
+impl TextureBuilder {
+    pub fn new(format: wgpu::TextureFormat, width: u32, height: u32) -> Self {
+        Self {
+            label: None,
+            size: wgpu::Extent3d { width, height, depth_or_array_layers: 1 },
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: wgpu::TextureDimension::D2,
+            format,
+            usage: wgpu::TextureUsages::empty(),
+            view_formats: Vec::new(),
+            cube: false,
+            array: false,
+        }
+    }
+
+    // Rendered to then sampled
+    pub fn color_target(format: wgpu::TextureFormat, width: u32, height: u32) -> Self {
+        Self::new(format, width, height).with_usage(wgpu::TextureUsages::RENDER_ATTACHMENT | wgpu::TextureUsages::TEXTURE_BINDING)
+    }
+
+    // Add TEXTURE_BINDING to sample it (e.g. for shadow maps)
+    pub fn depth_target(format: wgpu::TextureFormat, width: u32, height: u32) -> Self {
+        Self::new(format, width, height).with_usage(wgpu::TextureUsages::RENDER_ATTACHMENT)
+    }
+
+    // Written by compute passes then sampled by the next ones
+    pub fn storage(format: wgpu::TextureFormat, width: u32, height: u32) -> Self {
+        Self::new(format, width, height).with_usage(wgpu::TextureUsages::STORAGE_BINDING | wgpu::TextureUsages::TEXTURE_BINDING)
+    }
+
+    // Six square faces viewed as a cube, filled by copies or rendering to the layer views
+    pub fn cubemap(format: wgpu::TextureFormat, face_size: u32) -> Self {
+        let mut builder = Self::new(format, face_size, face_size)
+            .with_array_layer_count(6)
+            .with_usage(wgpu::TextureUsages::TEXTURE_BINDING | wgpu::TextureUsages::COPY_DST);
+        builder.cube = true;
+        builder
+    }
+
+    // Layers sampled through a single 2D array binding (viewed as an array even with a single layer), filled by copies
+    pub fn texture_array(format: wgpu::TextureFormat, width: u32, height: u32, layer_count: u32) -> Self {
+        let mut builder = Self::new(format, width, height)
+            .with_array_layer_count(layer_count)
+            .with_usage(wgpu::TextureUsages::TEXTURE_BINDING | wgpu::TextureUsages::COPY_DST);
+        builder.array = true;
+        builder
+    }
+
+    // Sampled texture with all its mips, uploaded then its mips generated by rendering or copies
+    pub fn mipmapped(format: wgpu::TextureFormat, width: u32, height: u32) -> Self {
+        Self::new(format, width, height)
+            .with_full_mip_chain()
+            .with_usage(wgpu::TextureUsages::TEXTURE_BINDING | wgpu::TextureUsages::COPY_DST | wgpu::TextureUsages::RENDER_ATTACHMENT)
+    }
+
+    pub fn with_label(mut self, label: impl Into<String>) -> Self {
+        self.label = Some(label.into());
+        self
+    }
+
+    // Usages added to the ones of the preset
+    pub fn with_usage(mut self, usage: wgpu::TextureUsages) -> Self {
+        self.usage |= usage;
+        self
+    }
+
+    pub fn with_mip_level_count(mut self, mip_level_count: u32) -> Self {
+        self.mip_level_count = mip_level_count.clamp(1, self.size.max_mips(self.dimension));
+        self
+    }
+
+    pub fn with_full_mip_chain(mut self) -> Self {
+        self.mip_level_count = self.size.max_mips(self.dimension);
+        self
+    }
+
+    // Multisampled textures can only be render attachments
+    pub fn with_sample_count(mut self, sample_count: u32) -> Self {
+        self.sample_count = sample_count;
+        if sample_count > 1 {
+            self.usage |= wgpu::TextureUsages::RENDER_ATTACHMENT;
+        }
+        self
+    }
+
+    // 2D array texture, viewed as a 2D array when there are several layers
+    pub fn with_array_layer_count(mut self, layer_count: u32) -> Self {
+        self.size.depth_or_array_layers = layer_count.max(1);
+        self
+    }
+
+    pub fn with_depth(mut self, depth: u32) -> Self {
+        self.dimension = wgpu::TextureDimension::D3;
+        self.size.depth_or_array_layers = depth.max(1);
+        self
+    }
+
+    // Other format the views can use (e.g. the sRGB variant of the format)
+    pub fn with_view_format(mut self, format: wgpu::TextureFormat) -> Self {
+        self.view_formats.push(format);
+        self
+    }
+
+    pub fn view_dimension(&self) -> wgpu::TextureViewDimension {
+        match self.dimension {
+            wgpu::TextureDimension::D1 => wgpu::TextureViewDimension::D1,
+            wgpu::TextureDimension::D2 if self.cube && self.size.depth_or_array_layers > 6 => wgpu::TextureViewDimension::CubeArray,
+            wgpu::TextureDimension::D2 if self.cube => wgpu::TextureViewDimension::Cube,
+            wgpu::TextureDimension::D2 if self.array || self.size.depth_or_array_layers > 1 => wgpu::TextureViewDimension::D2Array,
+            wgpu::TextureDimension::D2 => wgpu::TextureViewDimension::D2,
+            wgpu::TextureDimension::D3 => wgpu::TextureViewDimension::D3,
+        }
+    }
+
+    fn label(&self) -> &str { self.label.as_deref().unwrap_or("TextureBuilder") }
+
+    pub fn descriptor(&self) -> wgpu::TextureDescriptor<'_> {
+        wgpu::TextureDescriptor {
+            label: Some(self.label()),
+            size: self.size,
+            mip_level_count: self.mip_level_count,
+            sample_count: self.sample_count,
+            dimension: self.dimension,
+            format: self.format,
+            usage: self.usage,
+            view_formats: &self.view_formats,
+        }
+    }
+
+    pub fn build(&self, device: &wgpu::Device) -> TextureResource {
+        let texture = device.create_texture(&self.descriptor());
+        let view = texture.create_view(&wgpu::TextureViewDescriptor {
+            label: Some(&child_label(Some(self.label()), "view")),
+            dimension: Some(self.view_dimension()),
+            ..Default::default()
+        });
+        let memory = MemoryAllocation::for_texture(&texture, Some(self.label()));
+
+        TextureResource {
+            label: self.label().to_string(),
+            texture,
+            view,
+            view_dimension: self.view_dimension(),
+            _memory: memory,
+        }
+    }
+}
+
+// Texture created by a `TextureBuilder` with its default view (all mips and layers)
+pub struct TextureResource {
+    label: String,
+    texture: wgpu::Texture,
+    view: wgpu::TextureView,
+    view_dimension: wgpu::TextureViewDimension,
+    _memory: MemoryAllocation,
+}
+
+impl TextureResource {
+    // Label of the texture, the parent label of the views created from it
+    pub fn label(&self) -> &str { &self.label }
+
+    pub fn texture(&self) -> &wgpu::Texture { &self.texture }
+
+    pub fn view(&self) -> &wgpu::TextureView { &self.view }
+
+    pub fn view_dimension(&self) -> wgpu::TextureViewDimension { self.view_dimension }
+
+    pub fn format(&self) -> wgpu::TextureFormat { self.texture.format() }
+
+    pub fn size(&self) -> wgpu::Extent3d { self.texture.size() }
+
+    // Layout entry type of the texture sampled, depth formats are depth textures (combined depth stencil formats are bound
+    // through a DepthOnly view)
+    pub fn sampled_binding_type(&self) -> wgpu::BindingType {
+        let format = self.texture.format();
+        let aspect = format.has_depth_aspect().then_some(wgpu::TextureAspect::DepthOnly);
+        let multisampled = self.texture.sample_count() > 1;
+        let sample_type = match format.sample_type(aspect, None) {
+            // Multisampled textures can't be filtered
+            Some(wgpu::TextureSampleType::Float { .. }) if multisampled => wgpu::TextureSampleType::Float { filterable: false },
+            Some(sample_type) => sample_type,
+            None => panic!("Texture format {:?} can't be sampled", format),
+        };
+        wgpu::BindingType::Texture {
+            sample_type,
+            view_dimension: self.view_dimension,
+            multisampled,
+        }
+    }
+
+    pub fn storage_binding_type(&self, access: wgpu::StorageTextureAccess) -> wgpu::BindingType {
+        wgpu::BindingType::StorageTexture {
+            access,
+            format: self.texture.format(),
+            view_dimension: self.view_dimension,
+        }
+    }
+
+    // Bind group resource of the default view
+    pub fn binding_resource(&self) -> wgpu::BindingResource<'_> { wgpu::BindingResource::TextureView(&self.view) }
+
+    // 2D view of a single layer and mip, e.g. to render to a cubemap face or a mip level
+    pub fn create_layer_view(&self, layer: u32, mip_level: u32) -> wgpu::TextureView {
+        self.texture.create_view(&wgpu::TextureViewDescriptor {
+            label: Some(&child_label(Some(&self.label), &format!("layer {} mip {}", layer, mip_level))),
+            dimension: Some(wgpu::TextureViewDimension::D2),
+            base_mip_level: mip_level,
+            mip_level_count: Some(1),
+            base_array_layer: layer,
+            array_layer_count: Some(1),
+            ..Default::default()
+        })
+    }
+}
@@ -0,0 +1,265 @@
+use anyhow::{anyhow, Result};
+
+use super::{
+    buffers::{map_async, MapFuture},
+    memory_tracker::MemoryAllocation,
+};
+#[cfg(feature = "image")]
+use crate::srgb::{linear_to_srgb, srgb_to_linear};
+
+// Read back a texture (mip level and layer) to the CPU through a buffer, handling the 256 bytes alignment of rows
+// Usage: `encode_copy` then submit the encoder, `map` (and poll the device) then `read_bytes`/`read`/`read_image`
+pub struct TextureReadback {
+    buffer: wgpu::Buffer,
+    format: wgpu::TextureFormat,
+    width: u32,
+    height: u32,
+    unpadded_bytes_per_row: u32,
+    padded_bytes_per_row: u32,
+    _memory: MemoryAllocation,
+}
+
+impl TextureReadback {
+    pub fn new(device: &wgpu::Device, format: wgpu::TextureFormat, width: u32, height: u32, label: Option<&str>) -> Result<Self> {
+        let bytes_per_texel = match format.block_copy_size(None) {
+            Some(size) if format.block_dimensions() == (1, 1) => size,
+            _ => return Err(anyhow!("Texture format {:?} can't be read back", format)),
+        };
+
+        let unpadded_bytes_per_row = width * bytes_per_texel;
+        let padded_bytes_per_row = wgpu::util::align_to(unpadded_bytes_per_row, wgpu::COPY_BYTES_PER_ROW_ALIGNMENT);
+
+        let buffer = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some(format!("TextureReadback: {}", label.unwrap_or("unknown")).as_str()),
+            size: (padded_bytes_per_row * height) as wgpu::BufferAddress,
+            usage: wgpu::BufferUsages::COPY_DST | wgpu::BufferUsages::MAP_READ,
+            mapped_at_creation: false,
+        });
+
+        let memory = MemoryAllocation::for_buffer(&buffer, label);
+
+        Ok(Self {
+            buffer,
+            format,
+            width,
+            height,
+            unpadded_bytes_per_row,
+            padded_bytes_per_row,
+            _memory: memory,
+        })
+    }
+
+    // Readback matching the first mip level of the texture
+    pub fn for_texture(device: &wgpu::Device, texture: &wgpu::Texture, label: Option<&str>) -> Result<Self> {
+        Self::new(device, texture.format(), texture.width(), texture.height(), label)
+    }
+
+    // Copy the first mip level and layer of the texture, which must have the COPY_SRC usage
+    pub fn encode_copy(&self, encoder: &mut wgpu::CommandEncoder, texture: &wgpu::Texture) {
+        self.encode_copy_from(encoder, texture.as_image_copy());
+    }
+
+    // Copy a region (mip level, layer and origin) of a texture, the region has the readback size
+    pub fn encode_copy_from(&self, encoder: &mut wgpu::CommandEncoder, source: wgpu::ImageCopyTexture) {
+        encoder.copy_texture_to_buffer(
+            source,
+            wgpu::ImageCopyBuffer {
+                buffer: &self.buffer,
+                layout: wgpu::ImageDataLayout {
+                    offset: 0,
+                    bytes_per_row: Some(self.padded_bytes_per_row),
+                    rows_per_image: Some(self.height),
+                },
+            },
+            wgpu::Extent3d {
+                width: self.width,
+                height: self.height,
+                depth_or_array_layers: 1,
+            },
+        );
+    }
+
+    // Request the mapping of the buffer once the copy is submitted, the device must be polled for it to complete
+    pub fn map(&self) -> MapFuture { map_async(self.buffer.slice(..), wgpu::MapMode::Read) }
+
+    // Texels bytes without row padding, the buffer must be mapped and is unmapped afterwards
+    pub fn read_bytes(&self) -> Vec<u8> {
+        let mut bytes = Vec::with_capacity((self.unpadded_bytes_per_row * self.height) as usize);
+        {
+            let mapped_range = self.buffer.slice(..).get_mapped_range();
+            for padded_row in mapped_range.chunks_exact(self.padded_bytes_per_row as usize) {
+                bytes.extend_from_slice(&padded_row[..self.unpadded_bytes_per_row as usize]);
+            }
+        }
+        self.buffer.unmap();
+        bytes
+    }
+
+    // Texels as `T` (e.g. `[u8; 4]` for Rgba8 formats or `f32` for R32Float), row after row
+    pub fn read<T: bytemuck::Pod>(&self) -> Vec<T> { bytemuck::pod_collect_to_vec(&self.read_bytes()) }
+
+    // Texels split in `height` rows of `width` texels, `T` being a whole texel (e.g. `[f32; 4]` for Rgba32Float)
+    pub fn read_rows<T: bytemuck::Pod>(&self) -> Vec<Vec<T>> {
+        let texels = self.read::<T>();
+        debug_assert_eq!(texels.len(), (self.width * self.height) as usize, "read_rows expects one T per texel");
+        texels.chunks_exact(self.width as usize).map(<[T]>::to_vec).collect()
+    }
+
+    pub async fn read_async<T: bytemuck::Pod>(&self) -> Result<Vec<T>, wgpu::BufferAsyncError> {
+        self.map().await?;
+        Ok(self.read())
+    }
+
+    #[cfg(feature = "image")]
+    pub fn read_image(&self) -> Result<image::DynamicImage> { texels_to_image(self.format, self.width, self.height, self.read_bytes()) }
+
+    #[cfg(feature = "image")]
+    pub async fn read_image_async(&self) -> Result<image::DynamicImage> {
+        self.map().await?;
+        self.read_image()
+    }
+
+    #[inline]
+    pub fn format(&self) -> wgpu::TextureFormat { self.format }
+    #[inline]
+    pub fn width(&self) -> u32 { self.width }
+    #[inline]
+    pub fn height(&self) -> u32 { self.height }
+}
+
+#[cfg(feature = "image")]
+pub fn texels_to_image(format: wgpu::TextureFormat, width: u32, height: u32, bytes: Vec<u8>) -> Result<image::DynamicImage> {
+    use image::DynamicImage;
+    use wgpu::TextureFormat;
+
+    let invalid_size = || anyhow!("Texels size doesn't match a {}x{} {:?} image", width, height, format);
+
+    let image = match format {
+        TextureFormat::Rgba8Unorm | TextureFormat::Rgba8UnormSrgb =>
+            DynamicImage::ImageRgba8(image::RgbaImage::from_raw(width, height, bytes).ok_or_else(invalid_size)?),
+        TextureFormat::Bgra8Unorm | TextureFormat::Bgra8UnormSrgb => {
+            let mut bytes = bytes;
+            bytes.chunks_exact_mut(4).for_each(|texel| texel.swap(0, 2));
+            DynamicImage::ImageRgba8(image::RgbaImage::from_raw(width, height, bytes).ok_or_else(invalid_size)?)
+        },
+        TextureFormat::R8Unorm => DynamicImage::ImageLuma8(image::GrayImage::from_raw(width, height, bytes).ok_or_else(invalid_size)?),
+        TextureFormat::Rg8Unorm => DynamicImage::ImageLumaA8(image::GrayAlphaImage::from_raw(width, height, bytes).ok_or_else(invalid_size)?),
+        TextureFormat::R16Unorm =>
+            DynamicImage::ImageLuma16(image::ImageBuffer::from_raw(width, height, bytemuck::pod_collect_to_vec(&bytes)).ok_or_else(invalid_size)?),
+        TextureFormat::Rgba16Unorm =>
+            DynamicImage::ImageRgba16(image::ImageBuffer::from_raw(width, height, bytemuck::pod_collect_to_vec(&bytes)).ok_or_else(invalid_size)?),
+        TextureFormat::R16Float | TextureFormat::Rg16Float | TextureFormat::Rgba16Float => {
+            let values: Vec<f32> = bytemuck::pod_collect_to_vec::<u8, u16>(&bytes).into_iter().map(f16_to_f32).collect();
+            DynamicImage::ImageRgba32F(
+                image::ImageBuffer::from_raw(width, height, expand_to_rgba(&values, format.components())).ok_or_else(invalid_size)?,
+            )
+        },
+        TextureFormat::R32Float | TextureFormat::Rg32Float | TextureFormat::Rgba32Float => {
+            let values: Vec<f32> = bytemuck::pod_collect_to_vec(&bytes);
+            DynamicImage::ImageRgba32F(
+                image::ImageBuffer::from_raw(width, height, expand_to_rgba(&values, format.components())).ok_or_else(invalid_size)?,
+            )
+        },
+        _ => return Err(anyhow!("Texture format {:?} can't be converted to an image", format)),
+    };
+
+    Ok(image)
+}
+
+#[cfg(feature = "image")]
+impl TextureReadback {
+    // Save the texels as a PNG, float (linear HDR) formats are clamped and converted to 8 bits sRGB
+    pub fn save_png(&self, path: impl AsRef<std::path::Path>) -> Result<()> {
+        let image = self.read_image()?;
+        let image = if is_float_format(self.format) {
+            let mut rgba = image.into_rgba32f();
+            rgba.pixels_mut()
+                .for_each(|pixel| pixel.0[0..3].iter_mut().for_each(|value| *value = linear_to_srgb(value.clamp(0.0, 1.0))));
+            image::DynamicImage::ImageRgba32F(rgba).into_rgba8().into()
+        } else {
+            image
+        };
+        image.save_with_format(path, image::ImageFormat::Png)?;
+        Ok(())
+    }
+
+    // Save the texels as a 32 bits float EXR, sRGB formats are converted to linear values
+    pub fn save_exr(&self, path: impl AsRef<std::path::Path>) -> Result<()> {
+        let mut rgba = self.read_image()?.into_rgba32f();
+        if self.format.is_srgb() {
+            rgba.pixels_mut()
+                .for_each(|pixel| pixel.0[0..3].iter_mut().for_each(|value| *value = srgb_to_linear(*value)));
+        }
+        image::DynamicImage::ImageRgba32F(rgba).save_with_format(path, image::ImageFormat::OpenExr)?;
+        Ok(())
+    }
+}
+
+// Blocking helpers reading back the first mip level of a texture (with the COPY_SRC usage) to save it, meant for debugging dumps
+#[cfg(feature = "image")]
+pub fn save_texture_to_png(device: &wgpu::Device, queue: &wgpu::Queue, texture: &wgpu::Texture, path: impl AsRef<std::path::Path>) -> Result<()> {
+    read_back_blocking(device, queue, texture)?.save_png(path)
+}
+
+#[cfg(feature = "image")]
+pub fn save_texture_to_exr(device: &wgpu::Device, queue: &wgpu::Queue, texture: &wgpu::Texture, path: impl AsRef<std::path::Path>) -> Result<()> {
+    read_back_blocking(device, queue, texture)?.save_exr(path)
+}
+
+#[cfg(feature = "image")]
+fn read_back_blocking(device: &wgpu::Device, queue: &wgpu::Queue, texture: &wgpu::Texture) -> Result<TextureReadback> {
+    let readback = TextureReadback::for_texture(device, texture, Some("save texture"))?;
+    let mut encoder = device.create_command_encoder(&wgpu::CommandEncoderDescriptor { label: Some("Save texture encoder") });
+    readback.encode_copy(&mut encoder, texture);
+    queue.submit(Some(encoder.finish()));
+
+    let map_future = readback.map();
+    device.poll(wgpu::Maintain::Wait);
+    map_future
+        .try_take_result()
+        .ok_or_else(|| anyhow!("Texture readback mapping did not complete"))??;
+
+    Ok(readback)
+}
+
+#[cfg(feature = "image")]
+fn is_float_format(format: wgpu::TextureFormat) -> bool {
+    use wgpu::TextureFormat;
+    matches!(
+        format,
+        TextureFormat::R16Float
+            | TextureFormat::Rg16Float
+            | TextureFormat::Rgba16Float
+            | TextureFormat::R32Float
+            | TextureFormat::Rg32Float
+            | TextureFormat::Rgba32Float
+    )
+}
+
+// Single channel values are shown as grey levels, two channels values fill red and green
+#[cfg(feature = "image")]
+fn expand_to_rgba(values: &[f32], components: u8) -> Vec<f32> {
+    match components {
+        1 => values.iter().flat_map(|&value| [value, value, value, 1.0]).collect(),
+        2 => values.chunks_exact(2).flat_map(|texel| [texel[0], texel[1], 0.0, 1.0]).collect(),
+        _ => values.to_vec(),
+    }
+}
+
+#[cfg(feature = "image")]
+fn f16_to_f32(bits: u16) -> f32 {
+    let sign = if bits & 0x8000 != 0 { -1.0 } else { 1.0 };
+    let exponent = ((bits >> 10) & 0x1f) as u32;
+    let mantissa = (bits & 0x3ff) as u32;
+
+    let value = match exponent {
+        // Zero and subnormal numbers
+        0 => mantissa as f32 * 2f32.powi(-24),
+        31 if mantissa == 0 => f32::INFINITY,
+        31 => f32::NAN,
+        // Rebias the exponent from 15 to 127
+        _ => f32::from_bits(((exponent + 112) << 23) | (mantissa << 13)),
+    };
+
+    sign * value
+}
@@ -0,0 +1,195 @@
+use wgpu::util::DeviceExt;
+
+use super::{
+    binding_builder::{BindGroupBuilder, BindGroupLayoutBuilder, BindGroupLayoutWithDesc},
+    memory_tracker::MemoryAllocation,
+    uniform_buffer::UniformBuffer,
+    AutoExposure,
+    Exposure,
+    ShaderComposer,
+    Tonemapping,
+};
+
+const TONEMAPPING_PASS_WGSL: &str = r#"
+#import oxyde::fullscreen::{FullscreenVertexOutput, fullscreen_vertex}
+#import oxyde::tonemapping::{tonemap, apply_exposure}
+#import oxyde::color::linear_to_srgb
+#import oxyde::exposure::Exposure
+
+struct TonemappingParams {
+    tonemapping: u32,
+    exposure_compensation: f32,
+    encode_srgb: u32,
+    _padding: u32,
+}
+
+@group(0) @binding(0) var hdr: texture_2d<f32>;
+@group(0) @binding(1) var<uniform> params: TonemappingParams;
+@group(0) @binding(2) var<uniform> exposure: Exposure;
+
+@vertex
+fn vs_main(@builtin(vertex_index) vertex_index: u32) -> FullscreenVertexOutput {
+    return fullscreen_vertex(vertex_index);
+}
+
+@fragment
+fn fs_main(in: FullscreenVertexOutput) -> @location(0) vec4<f32> {
+    let color = textureLoad(hdr, vec2<i32>(in.position.xy), 0);
+    var mapped = tonemap(apply_exposure(color.rgb, exposure.exposure_value + params.exposure_compensation), params.tonemapping);
+    if params.encode_srgb != 0u {
+        mapped = linear_to_srgb(mapped);
+    }
+    return vec4<f32>(mapped, color.a);
+}
+"#;
+
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct TonemappingSettings {
+    pub tonemapping: Tonemapping,
+    // EV added to the exposure, the only exposure without `AutoExposure`
+    pub exposure_compensation: f32,
+}
+
+#[repr(C)]
+#[derive(Clone, Copy, Default, Debug, PartialEq, bytemuck::Pod, bytemuck::Zeroable)]
+struct TonemappingParams {
+    tonemapping: u32,
+    exposure_compensation: f32,
+    encode_srgb: u32,
+    _padding: u32,
+}
+
+// Fullscreen pass applying the exposure and a tonemapping operator to an HDR texture of the size of the target.
+// Non sRGB target formats get the sRGB transfer function applied in the shader.
+pub struct TonemappingPass {
+    settings: TonemappingSettings,
+    encode_srgb: bool,
+    params: UniformBuffer<TonemappingParams>,
+    // Exposure of 0 EV used without `AutoExposure`
+    fixed_exposure: wgpu::Buffer,
+    bind_group_layout: BindGroupLayoutWithDesc,
+    pipeline: wgpu::RenderPipeline,
+    _memory: MemoryAllocation,
+}
+
+impl TonemappingPass {
+    pub fn new(device: &wgpu::Device, target_format: wgpu::TextureFormat, settings: TonemappingSettings) -> anyhow::Result<Self> {
+        let uniform_binding = wgpu::BindingType::Buffer {
+            ty: wgpu::BufferBindingType::Uniform,
+            has_dynamic_offset: false,
+            min_binding_size: None,
+        };
+        let bind_group_layout = BindGroupLayoutBuilder::new()
+            .add_binding_fragment(wgpu::BindingType::Texture {
+                sample_type: wgpu::TextureSampleType::Float { filterable: false },
+                view_dimension: wgpu::TextureViewDimension::D2,
+                multisampled: false,
+            })
+            .add_binding_fragment(uniform_binding)
+            .add_binding_fragment(uniform_binding)
+            .create(device, Some("TonemappingPass"));
+
+        let module = ShaderComposer::new(TONEMAPPING_PASS_WGSL, Some("oxyde::tonemapping_pass"))
+            .with_common()?
+            .with_exposure()?
+            .build()?;
+        let shader_module = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: Some("TonemappingPass"),
+            source: wgpu::ShaderSource::Naga(std::borrow::Cow::Owned(module)),
+        });
+        let pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("TonemappingPass"),
+            bind_group_layouts: &[&bind_group_layout.layout],
+            push_constant_ranges: &[],
+        });
+        let pipeline = device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+            label: Some("TonemappingPass"),
+            layout: Some(&pipeline_layout),
+            vertex: wgpu::VertexState {
+                module: &shader_module,
+                entry_point: "vs_main",
+                buffers: &[],
+            },
+            fragment: Some(wgpu::FragmentState {
+                module: &shader_module,
+                entry_point: "fs_main",
+                targets: &[Some(target_format.into())],
+            }),
+            primitive: wgpu::PrimitiveState::default(),
+            depth_stencil: None,
+            multisample: wgpu::MultisampleState::default(),
+            multiview: None,
+        });
+
+        let fixed_exposure = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("TonemappingPass: fixed exposure"),
+            contents: bytemuck::bytes_of(&Exposure::default()),
+            usage: wgpu::BufferUsages::UNIFORM,
+        });
+        let memory = MemoryAllocation::for_buffer(&fixed_exposure, Some("TonemappingPass: fixed exposure"));
+
+        let encode_srgb = !target_format.is_srgb();
+        let params = UniformBuffer::new_with_data(device, &Self::params(&settings, encode_srgb));
+
+        Ok(Self {
+            settings,
+            encode_srgb,
+            params,
+            fixed_exposure,
+            bind_group_layout,
+            pipeline,
+            _memory: memory,
+        })
+    }
+
+    fn params(settings: &TonemappingSettings, encode_srgb: bool) -> TonemappingParams {
+        TonemappingParams {
+            tonemapping: settings.tonemapping as u32,
+            exposure_compensation: settings.exposure_compensation,
+            encode_srgb: encode_srgb as u32,
+            _padding: 0,
+        }
+    }
+
+    pub fn settings(&self) -> &TonemappingSettings { &self.settings }
+
+    pub fn set_settings(&mut self, queue: &wgpu::Queue, settings: TonemappingSettings) {
+        self.settings = settings;
+        self.params.update_content(queue, Self::params(&settings, self.encode_srgb));
+    }
+
+    // Draw `hdr_view` (any float format) into `target_view`, with the exposure of `auto_exposure` if any
+    pub fn encode(
+        &self,
+        device: &wgpu::Device,
+        encoder: &mut wgpu::CommandEncoder,
+        hdr_view: &wgpu::TextureView,
+        target_view: &wgpu::TextureView,
+        auto_exposure: Option<&AutoExposure>,
+    ) {
+        let exposure = auto_exposure.map_or_else(|| self.fixed_exposure.as_entire_binding(), AutoExposure::exposure_binding_resource);
+        let bind_group = BindGroupBuilder::new(&self.bind_group_layout)
+            .texture(hdr_view)
+            .resource(self.params.binding_resource())
+            .resource(exposure)
+            .create(device, Some("TonemappingPass"));
+        let mut render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+            label: Some("TonemappingPass"),
+            color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+                view: target_view,
+                resolve_target: None,
+                ops: wgpu::Operations {
+                    load: wgpu::LoadOp::Clear(wgpu::Color::BLACK),
+                    store: wgpu::StoreOp::Store,
+                },
+            })],
+            depth_stencil_attachment: None,
+            timestamp_writes: None,
+            occlusion_query_set: None,
+        });
+        render_pass.set_pipeline(&self.pipeline);
+        render_pass.set_bind_group(0, &bind_group, &[]);
+        render_pass.draw(0..3, 0..1);
+    }
+}
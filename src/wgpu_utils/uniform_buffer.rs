@@ -1,55 +1,46 @@
 // good wrapper taken from Wumpf in his project blub (https://github.com/Wumpf/blub)
 use std::marker::PhantomData;
 
+use super::memory_tracker::MemoryAllocation;
+
 pub struct UniformBuffer<Content> {
     buffer: wgpu::Buffer,
     content_type: PhantomData<Content>,
     previous_content: Vec<u8>,
+    _memory: MemoryAllocation,
 }
 
-impl<Content: bytemuck::Pod> UniformBuffer<Content> {
+impl<Content> UniformBuffer<Content> {
     fn name() -> &'static str {
         let type_name = std::any::type_name::<Content>();
-        let pos = type_name.rfind(':').unwrap();
-        &type_name[(pos + 1)..]
+        let pos = type_name.rfind(':').map_or(0, |pos| pos + 1);
+        &type_name[pos..]
     }
 
-    pub fn new(device: &wgpu::Device) -> Self {
+    fn create(device: &wgpu::Device, size: u64, initial_content: Option<&[u8]>) -> Self {
         let buffer = device.create_buffer(&wgpu::BufferDescriptor {
             label: Some(&format!("UniformBuffer: {}", Self::name())),
-            size: std::mem::size_of::<Content>() as u64,
+            size,
             usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
-            mapped_at_creation: false,
+            mapped_at_creation: initial_content.is_some(),
         });
 
-        UniformBuffer {
-            buffer,
-            content_type: PhantomData,
-            previous_content: Vec::new(),
+        if let Some(initial_content) = initial_content {
+            buffer.slice(..).get_mapped_range_mut().clone_from_slice(initial_content);
+            buffer.unmap();
         }
-    }
-
-    pub fn new_with_data(device: &wgpu::Device, initial_content: &Content) -> Self {
-        let buffer = device.create_buffer(&wgpu::BufferDescriptor {
-            label: Some(&format!("UniformBuffer: {}", Self::name())),
-            size: std::mem::size_of::<Content>() as u64,
-            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
-            mapped_at_creation: true,
-        });
 
-        let mapped_memory = buffer.slice(..);
-        mapped_memory.get_mapped_range_mut().clone_from_slice(bytemuck::bytes_of(initial_content));
-        buffer.unmap();
+        let memory = MemoryAllocation::for_buffer(&buffer, Some(&format!("UniformBuffer: {}", Self::name())));
 
         UniformBuffer {
             buffer,
             content_type: PhantomData,
-            previous_content: bytemuck::bytes_of(initial_content).to_vec(),
+            previous_content: initial_content.map(<[u8]>::to_vec).unwrap_or_default(),
+            _memory: memory,
         }
     }
 
-    pub fn update_content(&mut self, queue: &wgpu::Queue, content: Content) {
-        let new_content = bytemuck::bytes_of(&content);
+    fn write_bytes(&mut self, queue: &wgpu::Queue, new_content: &[u8]) {
         if self.previous_content == new_content {
             return;
         }
@@ -58,9 +49,42 @@ impl<Content: bytemuck::Pod> UniformBuffer<Content> {
         self.previous_content = new_content.to_vec();
     }
 
+    pub fn binding_resource(&self) -> wgpu::BindingResource<'_> { self.buffer.as_entire_binding() }
+}
+
+impl<Content: bytemuck::Pod> UniformBuffer<Content> {
+    pub fn new(device: &wgpu::Device) -> Self { Self::create(device, std::mem::size_of::<Content>() as u64, None) }
+
+    pub fn new_with_data(device: &wgpu::Device, initial_content: &Content) -> Self {
+        Self::create(device, std::mem::size_of::<Content>() as u64, Some(bytemuck::bytes_of(initial_content)))
+    }
+
+    pub fn update_content(&mut self, queue: &wgpu::Queue, content: Content) { self.write_bytes(queue, bytemuck::bytes_of(&content)); }
+
     pub fn force_update_content(&self, queue: &wgpu::Queue, content: Content) { queue.write_buffer(&self.buffer, 0, bytemuck::bytes_of(&content)); }
+}
+
+// `ShaderType` contents are serialized following the WGSL layout rules (vec3 alignment, struct and array padding)
+// instead of their Rust memory layout, so they don't need to be `Pod` nor manually padded
+#[cfg(feature = "encase")]
+impl<Content: encase::ShaderType + encase::ShaderSize + encase::internal::WriteInto> UniformBuffer<Content> {
+    fn encase_bytes(content: &Content) -> Vec<u8> {
+        let mut bytes = encase::UniformBuffer::new(Vec::with_capacity(Content::SHADER_SIZE.get() as usize));
+        bytes.write(content).expect("uniform content serialization into a Vec can't fail");
+        bytes.into_inner()
+    }
+
+    pub fn new_encased(device: &wgpu::Device) -> Self { Self::create(device, Content::SHADER_SIZE.get(), None) }
+
+    pub fn new_encased_with_data(device: &wgpu::Device, initial_content: &Content) -> Self {
+        Self::create(device, Content::SHADER_SIZE.get(), Some(&Self::encase_bytes(initial_content)))
+    }
+
+    pub fn update_encased_content(&mut self, queue: &wgpu::Queue, content: &Content) { self.write_bytes(queue, &Self::encase_bytes(content)); }
 
-    pub fn binding_resource(&self) -> wgpu::BindingResource { self.buffer.as_entire_binding() }
+    pub fn force_update_encased_content(&self, queue: &wgpu::Queue, content: &Content) {
+        queue.write_buffer(&self.buffer, 0, &Self::encase_bytes(content));
+    }
 }
 
 pub struct UniformBufferWrapper<Content> {
@@ -70,17 +94,21 @@ pub struct UniformBufferWrapper<Content> {
     bind_group: wgpu::BindGroup,
 }
 
-impl<Content: bytemuck::Pod> UniformBufferWrapper<Content> {
-    pub fn new(device: &wgpu::Device, content: Content, visibility: wgpu::ShaderStages) -> Self {
-        let uniform_buffer = UniformBuffer::new_with_data(device, &content);
-
+impl<Content> UniformBufferWrapper<Content> {
+    fn from_uniform_buffer(
+        device: &wgpu::Device,
+        content: Content,
+        uniform_buffer: UniformBuffer<Content>,
+        min_binding_size: u64,
+        visibility: wgpu::ShaderStages,
+    ) -> Self {
         let bind_group_layout_with_desc = super::binding_builder::BindGroupLayoutBuilder::new()
             .add_binding(
                 visibility,
                 wgpu::BindingType::Buffer {
                     ty: wgpu::BufferBindingType::Uniform,
                     has_dynamic_offset: false,
-                    min_binding_size: wgpu::BufferSize::new(std::mem::size_of::<Content>() as _),
+                    min_binding_size: wgpu::BufferSize::new(min_binding_size),
                 },
             )
             .create(device, Some(&format!("BindGroupLayout: {}", UniformBuffer::<Content>::name())));
@@ -97,10 +125,6 @@ impl<Content: bytemuck::Pod> UniformBufferWrapper<Content> {
         }
     }
 
-    pub fn update_content(&mut self, queue: &wgpu::Queue) { self.uniform_buffer.update_content(queue, self.content); }
-
-    pub fn force_update_content(&self, queue: &wgpu::Queue) { self.uniform_buffer.force_update_content(queue, self.content); }
-
     pub fn content_mut(&mut self) -> &mut Content { &mut self.content }
     pub fn content(&self) -> &Content { &self.content }
 
@@ -108,3 +132,32 @@ impl<Content: bytemuck::Pod> UniformBufferWrapper<Content> {
 
     pub fn layout(&self) -> &wgpu::BindGroupLayout { &self.bind_group_layout_with_desc.layout }
 }
+
+impl<Content: bytemuck::Pod> UniformBufferWrapper<Content> {
+    pub fn new(device: &wgpu::Device, content: Content, visibility: wgpu::ShaderStages) -> Self {
+        let uniform_buffer = UniformBuffer::new_with_data(device, &content);
+        Self::from_uniform_buffer(device, content, uniform_buffer, std::mem::size_of::<Content>() as u64, visibility)
+    }
+
+    pub fn update_content(&mut self, queue: &wgpu::Queue) { self.uniform_buffer.update_content(queue, self.content); }
+
+    pub fn force_update_content(&self, queue: &wgpu::Queue) { self.uniform_buffer.force_update_content(queue, self.content); }
+}
+
+#[cfg(feature = "encase")]
+impl<Content: encase::ShaderType + encase::ShaderSize + encase::internal::WriteInto> UniformBufferWrapper<Content> {
+    pub fn new_encased(device: &wgpu::Device, content: Content, visibility: wgpu::ShaderStages) -> Self {
+        let uniform_buffer = UniformBuffer::new_encased_with_data(device, &content);
+        Self::from_uniform_buffer(device, content, uniform_buffer, Content::SHADER_SIZE.get(), visibility)
+    }
+
+    pub fn update_encased_content(&mut self, queue: &wgpu::Queue) { self.uniform_buffer.update_encased_content(queue, &self.content); }
+
+    pub fn force_update_encased_content(&self, queue: &wgpu::Queue) { self.uniform_buffer.force_update_encased_content(queue, &self.content); }
+}
+
+#[cfg(feature = "egui")]
+impl<Content: crate::inspect::Inspect> UniformBufferWrapper<Content> {
+    // Widgets editing the content, a change is uploaded by the next `update_content`. Returns true if the content changed.
+    pub fn inspect_ui(&mut self, ui: &mut egui::Ui) -> bool { self.content.inspect(ui, UniformBuffer::<Content>::name()) }
+}
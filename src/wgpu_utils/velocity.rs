@@ -0,0 +1,56 @@
+// Unjittered view projection matrices of the current and previous frames, to compute motion vectors
+#[repr(C)]
+#[derive(Clone, Copy, Debug, PartialEq, bytemuck::Pod, bytemuck::Zeroable)]
+pub struct MotionUniforms {
+    pub view_projection: [[f32; 4]; 4],
+    pub previous_view_projection: [[f32; 4]; 4],
+}
+
+impl Default for MotionUniforms {
+    fn default() -> Self {
+        let identity = [[1.0, 0.0, 0.0, 0.0], [0.0, 1.0, 0.0, 0.0], [0.0, 0.0, 1.0, 0.0], [0.0, 0.0, 0.0, 1.0]];
+        Self {
+            view_projection: identity,
+            previous_view_projection: identity,
+        }
+    }
+}
+
+pub const VELOCITY_IMPORT_PATH: &str = "oxyde::velocity";
+
+// Motion vectors written to a velocity target (see `RenderTargetDescriptor::velocity`) and read back for TAA or motion blur.
+// The vertex stage outputs the clip positions of the current and previous frames (previous model matrix and
+// `previous_view_projection`), interpolated then compared per fragment with `velocity_from_clip`.
+// Velocities are in uv units per frame (x right, y down), from the previous position to the current one.
+pub const VELOCITY_WGSL: &str = r#"#define_import_path oxyde::velocity
+
+struct MotionUniforms {
+    view_projection: mat4x4<f32>,
+    previous_view_projection: mat4x4<f32>,
+}
+
+// Clip positions must be unjittered so the jitter doesn't show as motion
+fn velocity_from_clip(clip_position: vec4<f32>, previous_clip_position: vec4<f32>) -> vec2<f32> {
+    let ndc = clip_position.xy / clip_position.w;
+    let previous_ndc = previous_clip_position.xy / previous_clip_position.w;
+    return (ndc - previous_ndc) * vec2<f32>(0.5, -0.5);
+}
+
+// Velocity of a world position that was at `previous_world_position` on the previous frame
+fn velocity_from_world(world_position: vec3<f32>, previous_world_position: vec3<f32>, motion: MotionUniforms) -> vec2<f32> {
+    return velocity_from_clip(
+        motion.view_projection * vec4<f32>(world_position, 1.0),
+        motion.previous_view_projection * vec4<f32>(previous_world_position, 1.0)
+    );
+}
+
+// Velocity of a static point only moving with the camera, e.g. reconstructed from the depth buffer
+fn camera_velocity(world_position: vec3<f32>, motion: MotionUniforms) -> vec2<f32> {
+    return velocity_from_world(world_position, world_position, motion);
+}
+
+// Where the content at `uv` was on the previous frame, to sample a history texture
+fn reproject_uv(uv: vec2<f32>, velocity: vec2<f32>) -> vec2<f32> {
+    return uv - velocity;
+}
+"#;
@@ -0,0 +1,325 @@
+use super::{
+    binding_builder::{BindGroupBuilder, BindGroupLayoutBuilder, BindGroupLayoutWithDesc},
+    uniform_buffer::UniformBuffer,
+    TextureBuilder,
+    TextureResource,
+};
+
+// 3D texture of a volumetric simulation or field (e.g. smoke density, SDF), uploaded from the CPU or written by compute
+// passes through a storage binding when created with the STORAGE_BINDING usage
+pub struct VolumeTexture {
+    resource: TextureResource,
+}
+
+impl VolumeTexture {
+    // `usage` is added to TEXTURE_BINDING and COPY_DST, e.g. STORAGE_BINDING to write it from compute passes
+    pub fn new(device: &wgpu::Device, format: wgpu::TextureFormat, size: wgpu::Extent3d, usage: wgpu::TextureUsages, label: Option<&str>) -> Self {
+        let resource = TextureBuilder::new(format, size.width, size.height)
+            .with_depth(size.depth_or_array_layers)
+            .with_usage(wgpu::TextureUsages::TEXTURE_BINDING | wgpu::TextureUsages::COPY_DST | usage)
+            .with_label(format!("VolumeTexture: {}", label.unwrap_or("unknown")))
+            .build(device);
+        Self { resource }
+    }
+
+    pub fn texture(&self) -> &wgpu::Texture { self.resource.texture() }
+
+    pub fn view(&self) -> &wgpu::TextureView { self.resource.view() }
+
+    pub fn format(&self) -> wgpu::TextureFormat { self.resource.format() }
+
+    pub fn size(&self) -> wgpu::Extent3d { self.resource.size() }
+
+    pub fn sampled_binding_type(&self) -> wgpu::BindingType { self.resource.sampled_binding_type() }
+
+    // `texture_storage_3d` binding, the view can be bound directly as the volume has a single mip
+    pub fn storage_binding_type(&self, access: wgpu::StorageTextureAccess) -> wgpu::BindingType { self.resource.storage_binding_type(access) }
+
+    pub fn binding_resource(&self) -> wgpu::BindingResource<'_> { self.resource.binding_resource() }
+
+    // Workgroups covering the volume with one invocation per voxel
+    pub fn workgroup_count(&self, workgroup_size: [u32; 3]) -> [u32; 3] {
+        let size = self.size();
+        [
+            size.width.div_ceil(workgroup_size[0]),
+            size.height.div_ceil(workgroup_size[1]),
+            size.depth_or_array_layers.div_ceil(workgroup_size[2]),
+        ]
+    }
+
+    fn bytes_per_row(&self) -> u32 {
+        let format = self.format();
+        let block_size = format.block_copy_size(None).unwrap_or_else(|| panic!("{:?} can't be uploaded", format));
+        self.size().width * block_size
+    }
+
+    // Write the whole volume, `data` holds the tightly packed rows of the slices from z = 0
+    pub fn upload(&self, queue: &wgpu::Queue, data: &[u8]) {
+        let size = self.size();
+        self.write(queue, 0, size.depth_or_array_layers, data);
+    }
+
+    // Write the slice `z`, e.g. to stream a volume too large to be held at once on the CPU
+    pub fn upload_slice(&self, queue: &wgpu::Queue, z: u32, data: &[u8]) {
+        assert!(z < self.size().depth_or_array_layers, "Slice {} out of the volume", z);
+        self.write(queue, z, 1, data);
+    }
+
+    fn write(&self, queue: &wgpu::Queue, z: u32, depth: u32, data: &[u8]) {
+        let size = self.size();
+        let bytes_per_row = self.bytes_per_row();
+        assert_eq!(
+            data.len(),
+            (bytes_per_row * size.height * depth) as usize,
+            "Data size doesn't match the volume format and size"
+        );
+        queue.write_texture(
+            wgpu::ImageCopyTexture {
+                texture: self.texture(),
+                mip_level: 0,
+                origin: wgpu::Origin3d { x: 0, y: 0, z },
+                aspect: wgpu::TextureAspect::All,
+            },
+            data,
+            wgpu::ImageDataLayout {
+                offset: 0,
+                bytes_per_row: Some(bytes_per_row),
+                rows_per_image: Some(size.height),
+            },
+            wgpu::Extent3d { depth_or_array_layers: depth, ..size },
+        );
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum VolumeSliceAxis {
+    X,
+    Y,
+    #[default]
+    Z,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum VolumeSliceMode {
+    #[default]
+    Rgb,
+    Red,
+    Green,
+    Blue,
+    Alpha,
+    // Red channel as a signed distance: outside in orange, inside in blue, with isolines and the surface in white
+    SignedDistance,
+}
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct VolumeSliceSettings {
+    pub axis: VolumeSliceAxis,
+    // Position of the slice along the axis, from 0 to 1
+    pub position: f32,
+    pub mode: VolumeSliceMode,
+    // Values mapped to 0 and 1 (for signed distances, the distance of the darkest color and the spacing of the isolines)
+    pub range: [f32; 2],
+}
+
+impl Default for VolumeSliceSettings {
+    fn default() -> Self {
+        Self {
+            axis: VolumeSliceAxis::Z,
+            position: 0.5,
+            mode: VolumeSliceMode::Rgb,
+            range: [0.0, 1.0],
+        }
+    }
+}
+
+#[repr(C)]
+#[derive(Clone, Copy, Default, Debug, PartialEq, bytemuck::Pod, bytemuck::Zeroable)]
+struct VolumeSliceParams {
+    axis: u32,
+    position: f32,
+    mode: u32,
+    _padding: u32,
+    range: [f32; 2],
+    _padding_range: [f32; 2],
+}
+
+// Voxels are loaded without filtering so any float volume (e.g. R32Float SDFs) can be displayed
+const VOLUME_SLICE_WGSL: &str = r#"
+struct VolumeSliceParams {
+    axis: u32,
+    position: f32,
+    mode: u32,
+    _padding: u32,
+    range: vec2<f32>,
+    _padding_range: vec2<f32>,
+}
+
+struct VertexOutput {
+    @builtin(position) position: vec4<f32>,
+    @location(0) uv: vec2<f32>,
+}
+
+@group(0) @binding(0) var volume: texture_3d<f32>;
+@group(0) @binding(1) var<uniform> params: VolumeSliceParams;
+
+@vertex
+fn vs_main(@builtin(vertex_index) vertex_index: u32) -> VertexOutput {
+    let uv = vec2<f32>(f32((vertex_index << 1u) & 2u), f32(vertex_index & 2u));
+    var output: VertexOutput;
+    output.position = vec4<f32>(uv.x * 2.0 - 1.0, 1.0 - uv.y * 2.0, 0.0, 1.0);
+    output.uv = uv;
+    return output;
+}
+
+@fragment
+fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
+    // The slice is seen along the axis: x and y for Z, z and y for X, x and z for Y
+    var coordinates: vec3<f32>;
+    switch params.axis {
+        case 0u: { coordinates = vec3<f32>(params.position, in.uv.y, in.uv.x); }
+        case 1u: { coordinates = vec3<f32>(in.uv.x, params.position, in.uv.y); }
+        default: { coordinates = vec3<f32>(in.uv, params.position); }
+    }
+    let size = vec3<i32>(textureDimensions(volume));
+    let voxel = clamp(vec3<i32>(coordinates * vec3<f32>(size)), vec3<i32>(0), size - 1);
+    let value = textureLoad(volume, voxel, 0);
+
+    let extent = max(params.range.y - params.range.x, 1e-6);
+    let normalized = (value - params.range.x) / extent;
+    switch params.mode {
+        case 1u: { return vec4<f32>(vec3<f32>(normalized.r), 1.0); }
+        case 2u: { return vec4<f32>(vec3<f32>(normalized.g), 1.0); }
+        case 3u: { return vec4<f32>(vec3<f32>(normalized.b), 1.0); }
+        case 4u: { return vec4<f32>(vec3<f32>(normalized.a), 1.0); }
+        case 5u: {
+            let distance = value.r;
+            var color = select(vec3<f32>(0.25, 0.55, 0.95), vec3<f32>(0.95, 0.6, 0.25), distance > 0.0);
+            color *= 1.0 - 0.8 * clamp(abs(distance) / extent, 0.0, 1.0);
+            // Isolines every tenth of the range
+            let isoline = abs(fract(distance / extent * 10.0 + 0.5) - 0.5) / max(fwidth(distance / extent * 10.0), 1e-6);
+            color *= mix(0.7, 1.0, clamp(isoline, 0.0, 1.0));
+            let surface = abs(distance) / max(fwidth(distance), 1e-6);
+            color = mix(vec3<f32>(1.0), color, clamp(surface - 0.5, 0.0, 1.0));
+            return vec4<f32>(color, 1.0);
+        }
+        default: { return vec4<f32>(clamp(normalized.rgb, vec3<f32>(0.0), vec3<f32>(1.0)), 1.0); }
+    }
+}
+"#;
+
+// Debug view of an axis aligned slice of a `VolumeTexture` (any float format), drawn over a region of a target
+pub struct VolumeSliceRenderer {
+    settings: VolumeSliceSettings,
+    params: UniformBuffer<VolumeSliceParams>,
+    bind_group_layout: BindGroupLayoutWithDesc,
+    pipeline: wgpu::RenderPipeline,
+}
+
+impl VolumeSliceRenderer {
+    pub fn new(device: &wgpu::Device, target_format: wgpu::TextureFormat, settings: VolumeSliceSettings) -> Self {
+        let bind_group_layout = BindGroupLayoutBuilder::new()
+            .add_binding_fragment(wgpu::BindingType::Texture {
+                sample_type: wgpu::TextureSampleType::Float { filterable: false },
+                view_dimension: wgpu::TextureViewDimension::D3,
+                multisampled: false,
+            })
+            .add_binding_fragment(wgpu::BindingType::Buffer {
+                ty: wgpu::BufferBindingType::Uniform,
+                has_dynamic_offset: false,
+                min_binding_size: None,
+            })
+            .create(device, Some("VolumeSliceRenderer"));
+
+        let shader_module = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: Some("VolumeSliceRenderer"),
+            source: wgpu::ShaderSource::Wgsl(VOLUME_SLICE_WGSL.into()),
+        });
+        let pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("VolumeSliceRenderer"),
+            bind_group_layouts: &[&bind_group_layout.layout],
+            push_constant_ranges: &[],
+        });
+        let pipeline = device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+            label: Some("VolumeSliceRenderer"),
+            layout: Some(&pipeline_layout),
+            vertex: wgpu::VertexState {
+                module: &shader_module,
+                entry_point: "vs_main",
+                buffers: &[],
+            },
+            fragment: Some(wgpu::FragmentState {
+                module: &shader_module,
+                entry_point: "fs_main",
+                targets: &[Some(target_format.into())],
+            }),
+            primitive: wgpu::PrimitiveState::default(),
+            depth_stencil: None,
+            multisample: wgpu::MultisampleState::default(),
+            multiview: None,
+        });
+
+        Self {
+            settings,
+            params: UniformBuffer::new_with_data(device, &Self::params(&settings)),
+            bind_group_layout,
+            pipeline,
+        }
+    }
+
+    fn params(settings: &VolumeSliceSettings) -> VolumeSliceParams {
+        VolumeSliceParams {
+            axis: settings.axis as u32,
+            position: settings.position.clamp(0.0, 1.0),
+            mode: settings.mode as u32,
+            _padding: 0,
+            range: settings.range,
+            _padding_range: [0.0; 2],
+        }
+    }
+
+    pub fn settings(&self) -> &VolumeSliceSettings { &self.settings }
+
+    pub fn set_settings(&mut self, queue: &wgpu::Queue, settings: VolumeSliceSettings) {
+        self.settings = settings;
+        self.params.update_content(queue, Self::params(&settings));
+    }
+
+    // Draw the slice of `volume_view` over `target_view`, in the `viewport` region (x, y, width, height in pixels) or the
+    // whole target. The rest of the target is kept.
+    pub fn encode(
+        &self,
+        device: &wgpu::Device,
+        encoder: &mut wgpu::CommandEncoder,
+        volume_view: &wgpu::TextureView,
+        target_view: &wgpu::TextureView,
+        viewport: Option<[f32; 4]>,
+    ) {
+        let bind_group = BindGroupBuilder::new(&self.bind_group_layout)
+            .texture(volume_view)
+            .resource(self.params.binding_resource())
+            .create(device, Some("VolumeSliceRenderer"));
+        let mut render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+            label: Some("VolumeSliceRenderer"),
+            color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+                view: target_view,
+                resolve_target: None,
+                ops: wgpu::Operations {
+                    load: wgpu::LoadOp::Load,
+                    store: wgpu::StoreOp::Store,
+                },
+            })],
+            depth_stencil_attachment: None,
+            timestamp_writes: None,
+            occlusion_query_set: None,
+        });
+        if let Some([x, y, width, height]) = viewport {
+            render_pass.set_viewport(x, y, width, height, 0.0, 1.0);
+        }
+        render_pass.set_pipeline(&self.pipeline);
+        render_pass.set_bind_group(0, &bind_group, &[]);
+        render_pass.draw(0..3, 0..1);
+    }
+}
@@ -0,0 +1,230 @@
+// WGSL struct definitions generated from Rust structs, so a uniform/storage layout is only written once
+// `impl_wgsl_struct!("my_app::light", Light { position: glam::Vec3, intensity: f32 });`
+// The Rust field offsets and size are checked at compile time against the WGSL layout rules (vec3 aligned on 16 bytes,
+// struct size rounded to its alignment...), a padding field has to be added where they differ.
+// The uniform address space also requires 16 bytes aligned array strides and nested structs, checked by naga when the
+// shader is created.
+
+// Host shareable WGSL type with its alignment and size following the WGSL memory layout rules
+pub trait WgslType {
+    const ALIGN: u64;
+    const SIZE: u64;
+
+    fn wgsl_type() -> String;
+
+    // `#import` of the module declaring this type (e.g. `my_app::light::Light`), None for builtin types
+    fn wgsl_import() -> Option<String> { None }
+
+    // Composable modules (import path, source) this type needs, dependencies first
+    fn collect_modules(_modules: &mut Vec<(&'static str, String)>) {}
+}
+
+pub trait WgslStruct: WgslType {
+    const NAME: &'static str;
+    const IMPORT_PATH: &'static str;
+
+    // Field names and WGSL types, in declaration order
+    fn wgsl_fields() -> Vec<(&'static str, String)>;
+
+    // `struct Name { ... }` declaration, to paste in a shader
+    fn wgsl_definition() -> String {
+        let fields: String = Self::wgsl_fields()
+            .into_iter()
+            .map(|(name, ty)| format!("    {name}: {ty},\n"))
+            .collect();
+        format!("struct {} {{\n{fields}}}\n", Self::NAME)
+    }
+
+    // Composable module declaring the struct, imported with `#import <IMPORT_PATH>::<NAME>`
+    fn wgsl_module() -> String;
+
+    // Module of this struct preceded by the modules of the structs it contains, in registration order
+    fn wgsl_modules() -> Vec<(&'static str, String)> {
+        let mut modules = Vec::new();
+        Self::collect_modules(&mut modules);
+        modules
+    }
+}
+
+// Module source of a struct, importing the structs its fields are made of
+pub fn wgsl_struct_module(import_path: &str, imports: impl IntoIterator<Item = Option<String>>, definition: &str) -> String {
+    let mut imports: Vec<String> = imports.into_iter().flatten().collect();
+    imports.sort();
+    imports.dedup();
+
+    let mut source = format!("#define_import_path {import_path}\n\n");
+    if !imports.is_empty() {
+        for import in imports {
+            source += &format!("#import {import}\n");
+        }
+        source += "\n";
+    }
+    source + definition
+}
+
+// Pushes a module unless a module with the same import path was already collected
+pub fn push_module(modules: &mut Vec<(&'static str, String)>, import_path: &'static str, source: impl FnOnce() -> String) {
+    if !modules.iter().any(|(path, _)| *path == import_path) {
+        modules.push((import_path, source()));
+    }
+}
+
+macro_rules! impl_wgsl_builtin {
+    ($($type:ty => $wgsl:literal, $align:literal, $size:literal);* $(;)?) => {
+        $(impl WgslType for $type {
+            const ALIGN: u64 = $align;
+            const SIZE: u64 = $size;
+
+            fn wgsl_type() -> String { $wgsl.to_string() }
+        })*
+    };
+}
+
+impl_wgsl_builtin!(
+    f32 => "f32", 4, 4;
+    i32 => "i32", 4, 4;
+    u32 => "u32", 4, 4;
+);
+
+#[cfg(feature = "math")]
+impl_wgsl_builtin!(
+    glam::Vec2 => "vec2<f32>", 8, 8;
+    glam::Vec3 => "vec3<f32>", 16, 12;
+    glam::Vec3A => "vec3<f32>", 16, 12;
+    glam::Vec4 => "vec4<f32>", 16, 16;
+    glam::IVec2 => "vec2<i32>", 8, 8;
+    glam::IVec3 => "vec3<i32>", 16, 12;
+    glam::IVec4 => "vec4<i32>", 16, 16;
+    glam::UVec2 => "vec2<u32>", 8, 8;
+    glam::UVec3 => "vec3<u32>", 16, 12;
+    glam::UVec4 => "vec4<u32>", 16, 16;
+    glam::Mat2 => "mat2x2<f32>", 8, 16;
+    glam::Mat3A => "mat3x3<f32>", 16, 48;
+    glam::Mat4 => "mat4x4<f32>", 16, 64;
+);
+
+impl<T: WgslType, const N: usize> WgslType for [T; N] {
+    const ALIGN: u64 = T::ALIGN;
+    const SIZE: u64 = T::SIZE.next_multiple_of(T::ALIGN) * N as u64;
+
+    fn wgsl_type() -> String { format!("array<{}, {N}>", T::wgsl_type()) }
+
+    fn wgsl_import() -> Option<String> { T::wgsl_import() }
+
+    fn collect_modules(modules: &mut Vec<(&'static str, String)>) { T::collect_modules(modules) }
+}
+
+// Implement `WgslType` and `WgslStruct` for a struct, declared in the composable module `import_path`:
+// `impl_wgsl_struct!("my_app::light", Light { position: glam::Vec3, intensity: f32 });`
+// Every field has to be listed, with its type, in declaration order. A listed type differing from the field type,
+// or a field offset or struct size differing from the WGSL layout, is a compile error.
+#[macro_export]
+macro_rules! impl_wgsl_struct {
+    ($import_path:literal, $type:ident { $($field:ident: $field_type:ty),* $(,)? }) => {
+        impl $crate::wgpu_utils::wgsl_struct::WgslType for $type {
+            const ALIGN: u64 = {
+                let mut align = 1;
+                $(if <$field_type as $crate::wgpu_utils::wgsl_struct::WgslType>::ALIGN > align {
+                    align = <$field_type as $crate::wgpu_utils::wgsl_struct::WgslType>::ALIGN;
+                })*
+                align
+            };
+            const SIZE: u64 = {
+                let mut offset: u64 = 0;
+                $(offset = offset.next_multiple_of(<$field_type as $crate::wgpu_utils::wgsl_struct::WgslType>::ALIGN)
+                    + <$field_type as $crate::wgpu_utils::wgsl_struct::WgslType>::SIZE;)*
+                offset.next_multiple_of(<Self as $crate::wgpu_utils::wgsl_struct::WgslType>::ALIGN)
+            };
+
+            fn wgsl_type() -> String { stringify!($type).to_string() }
+
+            fn wgsl_import() -> Option<String> { Some(concat!($import_path, "::", stringify!($type)).to_string()) }
+
+            fn collect_modules(modules: &mut Vec<(&'static str, String)>) {
+                $(<$field_type as $crate::wgpu_utils::wgsl_struct::WgslType>::collect_modules(modules);)*
+                $crate::wgpu_utils::wgsl_struct::push_module(modules, $import_path, <Self as $crate::wgpu_utils::wgsl_struct::WgslStruct>::wgsl_module);
+            }
+        }
+
+        impl $crate::wgpu_utils::wgsl_struct::WgslStruct for $type {
+            const NAME: &'static str = stringify!($type);
+            const IMPORT_PATH: &'static str = $import_path;
+
+            fn wgsl_fields() -> Vec<(&'static str, String)> {
+                vec![$((stringify!($field), <$field_type as $crate::wgpu_utils::wgsl_struct::WgslType>::wgsl_type())),*]
+            }
+
+            fn wgsl_module() -> String {
+                $crate::wgpu_utils::wgsl_struct::wgsl_struct_module(
+                    $import_path,
+                    [$(<$field_type as $crate::wgpu_utils::wgsl_struct::WgslType>::wgsl_import()),*],
+                    &<Self as $crate::wgpu_utils::wgsl_struct::WgslStruct>::wgsl_definition(),
+                )
+            }
+        }
+
+        const _: () = {
+            $(let _: fn(&$type) -> &$field_type = |value| &value.$field;)*
+
+            let mut offset: u64 = 0;
+            $(
+                offset = offset.next_multiple_of(<$field_type as $crate::wgpu_utils::wgsl_struct::WgslType>::ALIGN);
+                assert!(
+                    ::core::mem::offset_of!($type, $field) as u64 == offset,
+                    concat!("`", stringify!($type), "::", stringify!($field), "` offset doesn't match its WGSL offset, add padding before it")
+                );
+                offset += <$field_type as $crate::wgpu_utils::wgsl_struct::WgslType>::SIZE;
+            )*
+            let _ = offset;
+            assert!(
+                ::core::mem::size_of::<$type>() as u64 == <$type as $crate::wgpu_utils::wgsl_struct::WgslType>::SIZE,
+                concat!("`", stringify!($type), "` size doesn't match its WGSL size, add padding at its end")
+            );
+        };
+    };
+}
+
+#[cfg(all(test, feature = "math"))]
+mod tests {
+    use super::*;
+
+    #[repr(C)]
+    struct Light {
+        position: glam::Vec3,
+        intensity: f32,
+        color: glam::Vec3,
+        _padding: f32,
+    }
+    crate::impl_wgsl_struct!("oxyde_test::light", Light { position: glam::Vec3, intensity: f32, color: glam::Vec3, _padding: f32 });
+
+    #[repr(C)]
+    struct Lights {
+        lights: [Light; 2],
+        count: u32,
+        _padding: [u32; 3],
+    }
+    crate::impl_wgsl_struct!("oxyde_test::lights", Lights { lights: [Light; 2], count: u32, _padding: [u32; 3] });
+
+    #[test]
+    fn layout_follows_the_wgsl_rules() {
+        assert_eq!((Light::ALIGN, Light::SIZE), (16, 32));
+        assert_eq!((Lights::ALIGN, Lights::SIZE), (16, 80));
+        assert_eq!(<[glam::Vec3; 3]>::SIZE, 48);
+    }
+
+    #[test]
+    fn definition_lists_the_fields() {
+        assert_eq!(
+            Light::wgsl_definition(),
+            "struct Light {\n    position: vec3<f32>,\n    intensity: f32,\n    color: vec3<f32>,\n    _padding: f32,\n}\n"
+        );
+        assert_eq!(Lights::wgsl_fields()[0], ("lights", "array<Light, 2>".to_string()));
+    }
+
+    #[test]
+    fn modules_import_their_dependencies() {
+        let modules = Lights::wgsl_modules();
+        assert_eq!(modules.iter().map(|(path, _)| *path).collect::<Vec<_>>(), ["oxyde_test::light", "oxyde_test::lights"]);
+        assert!(modules[1].1.starts_with("#define_import_path oxyde_test::lights\n\n#import oxyde_test::light::Light\n"));
+    }
+}